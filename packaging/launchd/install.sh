@@ -0,0 +1,47 @@
+#!/bin/sh
+# Install the indexer as a launchd agent for the current user.
+#
+#   cargo build --release
+#   packaging/launchd/install.sh subscribe --slots
+#
+# Arguments are passed to the client, endpoint and token are read from
+# $PREFIX/etc/solana-indexer/.env. PREFIX defaults to /usr/local.
+set -eu
+
+if [ "$#" -eq 0 ]; then
+    echo "usage: $0 <client arguments>, e.g. $0 subscribe --slots" >&2
+    exit 1
+fi
+
+PREFIX="${PREFIX:-/usr/local}"
+HERE="$(cd "$(dirname "$0")" && pwd)"
+ROOT="$(cd "$HERE/../.." && pwd)"
+LABEL="com.solana-indexer.client"
+PLIST="$HOME/Library/LaunchAgents/$LABEL.plist"
+
+install -d "$PREFIX/bin" "$PREFIX/libexec/solana-indexer" "$PREFIX/etc/solana-indexer" \
+    "$PREFIX/var/log/solana-indexer" "$PREFIX/var/run"
+install -m 755 "$ROOT/target/release/client" "$PREFIX/bin/solana-indexer"
+install -m 755 "$HERE/run.sh" "$PREFIX/libexec/solana-indexer/run.sh"
+if [ ! -f "$PREFIX/etc/solana-indexer/.env" ]; then
+    install -m 600 "$ROOT/.env" "$PREFIX/etc/solana-indexer/.env"
+fi
+
+ARGS=""
+for arg in "$@"; do
+    # awk expands the \n escapes when the value is passed with -v
+    ARGS="$ARGS        <string>$arg</string>\n"
+done
+
+mkdir -p "$(dirname "$PLIST")"
+awk -v prefix="$PREFIX" -v args="$ARGS" '
+    /@ARGS@/ { printf "%s", args; next }
+    { gsub("@PREFIX@", prefix); print }
+' "$HERE/$LABEL.plist" > "$PLIST"
+
+sed -e "s|@PREFIX@|$PREFIX|g" -e "s|@OWNER@|$(id -un):$(id -gn)|g" "$HERE/newsyslog.conf" \
+    | sudo tee /etc/newsyslog.d/solana-indexer.conf > /dev/null
+
+launchctl bootout "gui/$(id -u)/$LABEL" 2>/dev/null || true
+launchctl bootstrap "gui/$(id -u)" "$PLIST"
+echo "installed $LABEL, logs in $PREFIX/var/log/solana-indexer"
@@ -0,0 +1,6 @@
+#!/bin/sh
+# Started by launchd. Records the pid so newsyslog can signal the client after
+# rotating its logs, launchd then restarts it with fresh log files.
+PREFIX="$(cd "$(dirname "$0")/../.." && pwd)"
+echo $$ > "$PREFIX/var/run/solana-indexer.pid"
+exec "$PREFIX/bin/solana-indexer" "$@"
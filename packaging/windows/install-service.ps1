@@ -0,0 +1,31 @@
+<#
+.SYNOPSIS
+    Install the indexer as a Windows service using WinSW (https://github.com/winsw/winsw).
+
+.EXAMPLE
+    cargo build --release
+    .\packaging\windows\install-service.ps1 -WinSW C:\tools\WinSW-x64.exe -Arguments "subscribe --slots"
+#>
+param(
+    [Parameter(Mandatory = $true)] [string] $WinSW,
+    [Parameter(Mandatory = $true)] [string] $Arguments,
+    [string] $InstallDir = "$env:ProgramFiles\solana-indexer"
+)
+
+$ErrorActionPreference = "Stop"
+$root = Resolve-Path "$PSScriptRoot\..\.."
+
+New-Item -ItemType Directory -Force -Path "$InstallDir\logs" | Out-Null
+Copy-Item "$root\target\release\client.exe" "$InstallDir\solana-indexer.exe" -Force
+Copy-Item $WinSW "$InstallDir\solana-indexer-service.exe" -Force
+if (-not (Test-Path "$InstallDir\.env")) {
+    Copy-Item "$root\.env" "$InstallDir\.env"
+}
+
+$escaped = [System.Security.SecurityElement]::Escape($Arguments)
+(Get-Content "$PSScriptRoot\solana-indexer.xml") -replace "@ARGS@", $escaped |
+    Set-Content "$InstallDir\solana-indexer-service.xml"
+
+& "$InstallDir\solana-indexer-service.exe" install
+& "$InstallDir\solana-indexer-service.exe" start
+Write-Host "installed solana-indexer service, logs in $InstallDir\logs"
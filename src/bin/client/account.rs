@@ -0,0 +1,72 @@
+//! `get-account`: fetch one account and print it decoded, to sanity check filters and decoders.
+
+use {
+    crate::{DecodedUpdate, create_pretty_account, decode_update, rpc},
+    anyhow::Context,
+    futures::stream::StreamExt,
+    serde_json::{Value, json},
+    std::{collections::HashMap, time::Instant},
+    yellowstone_grpc_client::{GeyserGrpcClient, Interceptor},
+    yellowstone_grpc_proto::prelude::{
+        CommitmentLevel, SubscribeRequest, SubscribeRequestFilterAccounts,
+        subscribe_update::UpdateOneof,
+    },
+};
+
+/// Current state of the account over RPC
+pub async fn fetch(
+    rpc: &rpc::RpcClient,
+    pubkey: &str,
+    commitment: CommitmentLevel,
+) -> anyhow::Result<Value> {
+    let result = rpc
+        .call(
+            "getAccountInfo",
+            json!([pubkey, {
+                "encoding": "base64",
+                "commitment": commitment.as_str_name().to_lowercase(),
+            }]),
+        )
+        .await?;
+    anyhow::ensure!(!result["value"].is_null(), "account {pubkey} not found");
+    let account = create_pretty_account(rpc::parse_account(pubkey, &result["value"])?)?;
+    let mut value = serde_json::to_value(account)?;
+    value["slot"] = result["context"]["slot"].clone();
+    Ok(value)
+}
+
+/// Wait for the first streamed update of the account
+pub async fn first_update(
+    mut client: GeyserGrpcClient<impl Interceptor>,
+    pubkey: &str,
+    commitment: CommitmentLevel,
+) -> anyhow::Result<Value> {
+    let request = SubscribeRequest {
+        accounts: HashMap::from([(
+            "client".to_owned(),
+            SubscribeRequestFilterAccounts {
+                account: vec![pubkey.to_owned()],
+                ..Default::default()
+            },
+        )]),
+        commitment: Some(commitment as i32),
+        ..Default::default()
+    };
+    let (_subscribe_tx, mut stream) = client.subscribe_with_request(Some(request)).await?;
+    while let Some(message) = stream.next().await {
+        let message = message?;
+        if let Some(UpdateOneof::Account(_)) = message.update_oneof {
+            let DecodedUpdate { value, .. } = decode_update(message, Instant::now())?;
+            return Ok(value);
+        }
+    }
+    anyhow::bail!("stream closed before an update of {pubkey}")
+}
+
+pub fn print(value: &Value) -> anyhow::Result<()> {
+    println!(
+        "{}",
+        serde_json::to_string_pretty(value).context("failed to serialize account")?
+    );
+    Ok(())
+}
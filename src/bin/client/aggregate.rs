@@ -0,0 +1,182 @@
+//! `--aggregate <kind> --window 60s`: replace transactions by statistics over tumbling windows,
+//! for network observability without storing every transaction.
+//!
+//! - `program-stats`: per program invoked (top-level or through CPI), the transactions, the
+//!   failure rate, and the fees and compute units of those transactions, in a `programStats`
+//!   event per window. Fees and compute units are counted in full for every program a
+//!   transaction invokes.
+//! - `top-n`: `topN` leaderboards of the most written accounts, the hottest programs and the
+//!   largest SOL transfers over a sliding window, see [`crate::topn`].
+
+use {
+    crate::{instructions, topn},
+    clap::ValueEnum,
+    serde_json::{Value, json},
+    std::{
+        collections::{BTreeSet, HashMap},
+        time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+    },
+    tracing::debug,
+    yellowstone_grpc_proto::prelude::{SubscribeRequest, SubscribeRequestFilterTransactions},
+};
+
+/// Transactions filter added to the request when transactions aren't subscribed already
+pub const FILTER: &str = "aggregate";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Aggregation {
+    /// Per-program transactions, failure rate, fees and compute units (`programStats` events)
+    ProgramStats,
+    /// Leaderboards of the most active accounts, hottest programs and largest SOL transfers
+    /// over a sliding window, emitted as it slides (`topN` events, sized by --top)
+    TopN,
+}
+
+#[derive(Default)]
+struct ProgramStats {
+    transactions: u64,
+    failed: u64,
+    fee: u64,
+    compute_units: u64,
+}
+
+enum State {
+    ProgramStats(HashMap<String, ProgramStats>),
+    TopN(topn::TopN),
+}
+
+pub struct Aggregator {
+    /// How often an event is emitted
+    interval: Duration,
+    started_at: Instant,
+    started: SystemTime,
+    slots: Option<(u64, u64)>,
+    transactions: u64,
+    state: State,
+}
+
+impl Aggregator {
+    /// Aggregate the transactions of `request`, subscribing to them if needed
+    pub fn new(
+        aggregation: Aggregation,
+        window: Duration,
+        top: usize,
+        request: &mut SubscribeRequest,
+    ) -> Self {
+        if request.transactions.is_empty() {
+            request.transactions.insert(
+                FILTER.to_owned(),
+                SubscribeRequestFilterTransactions {
+                    vote: Some(false),
+                    ..Default::default()
+                },
+            );
+        }
+        let (interval, state) = match aggregation {
+            Aggregation::ProgramStats => (window, State::ProgramStats(HashMap::new())),
+            Aggregation::TopN => (window / topn::SLICES, State::TopN(topn::TopN::new(top))),
+        };
+        Self {
+            interval,
+            started_at: Instant::now(),
+            started: SystemTime::now(),
+            slots: None,
+            transactions: 0,
+            state,
+        }
+    }
+
+    /// Take an update, returns `true` for transactions, which aren't written
+    pub fn observe(&mut self, kind: &str, value: &Value) -> bool {
+        if kind != "transaction" {
+            return false;
+        }
+        if let Some(slot) = value["slot"].as_u64() {
+            self.slots = Some(match self.slots {
+                Some((first, last)) => (first.min(slot), last.max(slot)),
+                None => (slot, slot),
+            });
+        }
+        self.transactions += 1;
+        let programs = match &mut self.state {
+            State::ProgramStats(programs) => programs,
+            State::TopN(top_n) => {
+                top_n.observe(value);
+                return true;
+            }
+        };
+        let invoked = match instructions::parse_all(value) {
+            Ok(instructions) => instructions
+                .into_iter()
+                .map(|ix| ix.program_id)
+                .collect::<BTreeSet<_>>(),
+            Err(error) => {
+                debug!("failed to parse transaction instructions: {error:#}");
+                return true;
+            }
+        };
+        let meta = &value["tx"]["meta"];
+        let failed = !meta["err"].is_null();
+        let fee = meta["fee"].as_u64().unwrap_or_default();
+        let compute_units = meta["computeUnitsConsumed"].as_u64().unwrap_or_default();
+        for program in invoked {
+            let stats = programs.entry(program.to_string()).or_default();
+            stats.transactions += 1;
+            stats.failed += u64::from(failed);
+            stats.fee += fee;
+            stats.compute_units += compute_units;
+        }
+        true
+    }
+
+    /// The event of the window, or of the slice for sliding windows, if it elapsed
+    pub fn due(&mut self) -> Option<(&'static str, Value)> {
+        if self.started_at.elapsed() < self.interval {
+            return None;
+        }
+        let window_secs = self.started_at.elapsed().as_secs_f64();
+        let started = self.started;
+        let slots = self.slots.take();
+        let transactions = std::mem::take(&mut self.transactions);
+        self.started_at = Instant::now();
+        self.started = SystemTime::now();
+
+        let programs = match &mut self.state {
+            State::ProgramStats(programs) => programs,
+            State::TopN(top_n) => {
+                let mut leaderboard = top_n.rotate();
+                leaderboard["slot"] = json!(slots.map(|(_, last)| last));
+                return Some(("topN", leaderboard));
+            }
+        };
+        let mut programs = std::mem::take(programs).into_iter().collect::<Vec<_>>();
+        programs.sort_by(|(a_id, a), (b_id, b)| {
+            b.transactions.cmp(&a.transactions).then(a_id.cmp(b_id))
+        });
+        let programs = programs
+            .into_iter()
+            .map(|(program_id, stats)| {
+                json!({
+                    "programId": program_id,
+                    "transactions": stats.transactions,
+                    "failed": stats.failed,
+                    "failureRate": stats.failed as f64 / stats.transactions as f64,
+                    "feeLamports": stats.fee,
+                    "computeUnits": stats.compute_units,
+                    "computeUnitsAvg": stats.compute_units as f64 / stats.transactions as f64,
+                })
+            })
+            .collect::<Vec<_>>();
+        Some((
+            "programStats",
+            json!({
+                "windowStart": started.duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64,
+                "windowSecs": window_secs,
+                "slot": slots.map(|(_, last)| last),
+                "firstSlot": slots.map(|(first, _)| first),
+                "transactions": transactions,
+                "programs": programs,
+            }),
+        ))
+    }
+}
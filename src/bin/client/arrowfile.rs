@@ -0,0 +1,152 @@
+//! `--arrow-file`: write updates as Apache Arrow record batches to an IPC file, so DataFusion,
+//! Polars or pandas load the stream without parsing JSON lines.
+//!
+//! A row is an update with the columns every kind has: `kind`, `created_at`, `slot`, `key` (the
+//! pubkey of accounts, the signature of transactions, the blockhash of blocks), `filters`,
+//! `traceparent` and the `update` itself as the JSON `--out-file` writes. Rows are written in
+//! batches of `--arrow-batch-rows`; the file is readable once the subscription ends and its
+//! footer is written.
+
+use {
+    anyhow::Context,
+    arrow_array::{
+        ArrayRef, RecordBatch,
+        builder::{ListBuilder, StringBuilder, TimestampMillisecondBuilder, UInt64Builder},
+    },
+    arrow_ipc::writer::FileWriter,
+    arrow_schema::{DataType, Field, Schema, SchemaRef, TimeUnit},
+    serde_json::Value,
+    std::{
+        fs::File,
+        io::BufWriter,
+        path::Path,
+        sync::Arc,
+        time::{SystemTime, UNIX_EPOCH},
+    },
+};
+
+pub struct ArrowFile {
+    writer: FileWriter<BufWriter<File>>,
+    schema: SchemaRef,
+    batch_rows: usize,
+    rows: usize,
+    kind: StringBuilder,
+    created_at: TimestampMillisecondBuilder,
+    slot: UInt64Builder,
+    key: StringBuilder,
+    filters: ListBuilder<StringBuilder>,
+    traceparent: StringBuilder,
+    update: StringBuilder,
+}
+
+impl ArrowFile {
+    pub fn create(path: &Path, batch_rows: usize) -> anyhow::Result<Self> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("kind", DataType::Utf8, false),
+            Field::new(
+                "created_at",
+                DataType::Timestamp(TimeUnit::Millisecond, Some("UTC".into())),
+                false,
+            ),
+            Field::new("slot", DataType::UInt64, true),
+            Field::new("key", DataType::Utf8, true),
+            Field::new_list(
+                "filters",
+                Field::new_list_field(DataType::Utf8, true),
+                false,
+            ),
+            Field::new("traceparent", DataType::Utf8, true),
+            Field::new("update", DataType::Utf8, false),
+        ]));
+        let file =
+            File::create(path).with_context(|| format!("failed to create {}", path.display()))?;
+        let writer = FileWriter::try_new_buffered(file, &schema)
+            .with_context(|| format!("failed to write the Arrow schema to {}", path.display()))?;
+        Ok(Self {
+            writer,
+            schema,
+            batch_rows,
+            rows: 0,
+            kind: StringBuilder::new(),
+            created_at: TimestampMillisecondBuilder::new().with_timezone("UTC"),
+            slot: UInt64Builder::new(),
+            key: StringBuilder::new(),
+            filters: ListBuilder::new(StringBuilder::new()),
+            traceparent: StringBuilder::new(),
+            update: StringBuilder::new(),
+        })
+    }
+
+    pub fn write(
+        &mut self,
+        kind: &str,
+        created_at: SystemTime,
+        filters: &[String],
+        value: &Value,
+        traceparent: Option<&str>,
+    ) -> anyhow::Result<()> {
+        let key = match kind {
+            "account" => value.get("pubkey"),
+            "transaction" | "transactionStatus" => value.get("signature"),
+            "block" | "blockmeta" => value.get("blockhash"),
+            _ => None,
+        };
+        self.kind.append_value(kind);
+        self.created_at.append_value(
+            created_at
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_millis() as i64,
+        );
+        self.slot
+            .append_option(value.get("slot").and_then(Value::as_u64));
+        self.key.append_option(key.and_then(Value::as_str));
+        for filter in filters {
+            self.filters.values().append_value(filter);
+        }
+        self.filters.append(true);
+        self.traceparent.append_option(traceparent);
+        self.update.append_value(value.to_string());
+        self.rows += 1;
+        if self.rows >= self.batch_rows {
+            self.write_batch()?;
+        }
+        Ok(())
+    }
+
+    fn write_batch(&mut self) -> anyhow::Result<()> {
+        if self.rows == 0 {
+            return Ok(());
+        }
+        let columns: Vec<ArrayRef> = vec![
+            Arc::new(self.kind.finish()),
+            Arc::new(self.created_at.finish()),
+            Arc::new(self.slot.finish()),
+            Arc::new(self.key.finish()),
+            Arc::new(self.filters.finish()),
+            Arc::new(self.traceparent.finish()),
+            Arc::new(self.update.finish()),
+        ];
+        let batch = RecordBatch::try_new(Arc::clone(&self.schema), columns)?;
+        self.rows = 0;
+        self.writer
+            .write(&batch)
+            .context("failed to write an Arrow record batch")
+    }
+
+    /// Write the last rows and the footer
+    fn finish(&mut self) -> anyhow::Result<()> {
+        self.write_batch()?;
+        self.writer
+            .finish()
+            .context("failed to write the Arrow file footer")
+    }
+}
+
+impl Drop for ArrowFile {
+    fn drop(&mut self) {
+        if let Err(error) = self.finish() {
+            tracing::warn!("{error:#}");
+        }
+    }
+}
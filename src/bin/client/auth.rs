@@ -0,0 +1,86 @@
+//! Sending the access token: as the Yellowstone `x-token` header or as an
+//! `authorization: Bearer` header for providers behind OAuth-style gateways.
+
+use {
+    crate::AuthScheme,
+    tonic::{
+        Request, Status,
+        metadata::{AsciiMetadataValue, MetadataValue},
+        service::{Interceptor, interceptor::InterceptedService},
+    },
+    tonic_health::pb::health_client::HealthClient,
+    yellowstone_grpc_client::{GeyserGrpcBuilder, GeyserGrpcBuilderError, GeyserGrpcClient},
+    yellowstone_grpc_proto::prelude::geyser_client::GeyserClient,
+};
+
+#[derive(Debug, Clone)]
+pub struct AuthInterceptor {
+    header: &'static str,
+    token: Option<AsciiMetadataValue>,
+    x_request_snapshot: bool,
+}
+
+impl AuthInterceptor {
+    /// Send the builder's token with the given scheme
+    pub fn new(builder: &GeyserGrpcBuilder, scheme: AuthScheme) -> anyhow::Result<Self> {
+        let token = match (scheme, &builder.x_token) {
+            (AuthScheme::Bearer, Some(token)) => {
+                let token = token.to_str().map_err(|_| anyhow::anyhow!("invalid token"))?;
+                let token = format!("Bearer {token}")
+                    .try_into()
+                    .map_err(GeyserGrpcBuilderError::MetadataValueError)?;
+                Some(token)
+            }
+            (_, token) => token.clone(),
+        };
+        Ok(Self {
+            header: match scheme {
+                AuthScheme::XToken => "x-token",
+                AuthScheme::Bearer => "authorization",
+            },
+            token,
+            x_request_snapshot: builder.x_request_snapshot,
+        })
+    }
+}
+
+impl Interceptor for AuthInterceptor {
+    fn call(&mut self, mut request: Request<()>) -> Result<Request<()>, Status> {
+        if let Some(token) = self.token.clone() {
+            request.metadata_mut().insert(self.header, token);
+        }
+        if self.x_request_snapshot {
+            request
+                .metadata_mut()
+                .insert("x-request-snapshot", MetadataValue::from_static("true"));
+        }
+        Ok(request)
+    }
+}
+
+/// `GeyserGrpcBuilder::connect` with the token sent according to `scheme`
+pub async fn connect(
+    builder: GeyserGrpcBuilder,
+    scheme: AuthScheme,
+) -> anyhow::Result<GeyserGrpcClient<AuthInterceptor>> {
+    let interceptor = AuthInterceptor::new(&builder, scheme)?;
+    let channel = builder.endpoint.connect().await?;
+
+    let mut geyser = GeyserClient::with_interceptor(channel.clone(), interceptor.clone());
+    if let Some(encoding) = builder.send_compressed {
+        geyser = geyser.send_compressed(encoding);
+    }
+    if let Some(encoding) = builder.accept_compressed {
+        geyser = geyser.accept_compressed(encoding);
+    }
+    if let Some(limit) = builder.max_decoding_message_size {
+        geyser = geyser.max_decoding_message_size(limit);
+    }
+    if let Some(limit) = builder.max_encoding_message_size {
+        geyser = geyser.max_encoding_message_size(limit);
+    }
+    Ok(GeyserGrpcClient::new(
+        HealthClient::new(InterceptedService::new(channel, interceptor)),
+        geyser,
+    ))
+}
@@ -0,0 +1,272 @@
+//! `bench`: subscribe to several endpoints with identical filters and compare which one
+//! delivers each update first, and how far behind the others are.
+
+use {
+    crate::{
+        latency::{format_micros, percentile},
+        print_query_result, ActionBench, Args, StatsKind,
+    },
+    futures::{sink::SinkExt, stream::StreamExt},
+    tracing::{error, info},
+    std::{
+        collections::{HashMap, VecDeque},
+        time::{Duration, Instant},
+    },
+    tokio::sync::mpsc,
+    yellowstone_grpc_proto::prelude::{
+        subscribe_update::UpdateOneof, SubscribeRequest, SubscribeRequestPing,
+    },
+};
+
+/// Benchmark length without `--duration`
+const DEFAULT_DURATION: Duration = Duration::from_secs(60);
+
+/// Identity of an update shared by all endpoints: type, slot and signature / pubkey / index
+type UpdateKey = (StatsKind, u64, Vec<u8>);
+
+pub async fn run(args: &Args, bench: &ActionBench, request: SubscribeRequest) -> anyhow::Result<()> {
+    let mut endpoints = vec![(args.endpoint.clone(), None)];
+    for spec in &bench.compare {
+        endpoints.push(match spec.split_once(',') {
+            Some((endpoint, x_token)) => (endpoint.to_owned(), Some(x_token.to_owned())),
+            None => (spec.clone(), None),
+        });
+    }
+
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let mut tasks = Vec::with_capacity(endpoints.len());
+    for (index, (endpoint, x_token)) in endpoints.iter().enumerate() {
+        let mut args = args.clone();
+        args.endpoint = endpoint.clone();
+        // An endpoint's own token replaces `--x-token` and `--x-token-file`
+        if let Some(x_token) = x_token {
+            args.x_token = x_token.clone();
+            args.x_token_file = None;
+        }
+        let request = request.clone();
+        let tx = tx.clone();
+        tasks.push(tokio::spawn(async move {
+            if let Err(error) = stream_keys(&args, request, index, tx).await {
+                error!("{}: {error:#}", args.endpoint);
+            }
+        }));
+    }
+    drop(tx);
+
+    let mut report = BenchReport::new(
+        endpoints.into_iter().map(|(endpoint, _)| endpoint).collect(),
+        Duration::from_secs(bench.window_secs),
+    );
+    let deadline = tokio::time::sleep(bench.subscribe.duration.unwrap_or(DEFAULT_DURATION));
+    tokio::pin!(deadline);
+    let interval = Duration::from_secs(bench.report_interval_secs.max(1));
+    let mut reports = tokio::time::interval_at(tokio::time::Instant::now() + interval, interval);
+    loop {
+        tokio::select! {
+            _ = &mut deadline => break,
+            _ = reports.tick() => {
+                report.expire(Instant::now());
+                report.print();
+            }
+            event = rx.recv() => match event {
+                Some((index, key, received_at)) => report.record(index, key, received_at),
+                None => break,
+            },
+        }
+    }
+
+    for task in tasks {
+        task.abort();
+    }
+    report.expire_all();
+    report.print();
+    Ok(())
+}
+
+async fn stream_keys(
+    args: &Args,
+    request: SubscribeRequest,
+    index: usize,
+    tx: mpsc::UnboundedSender<(usize, UpdateKey, Instant)>,
+) -> anyhow::Result<()> {
+    let mut client = args.connect().await?;
+    let (mut subscribe_tx, mut stream) = client.subscribe_with_request(Some(request)).await?;
+    info!("{}: stream opened", args.endpoint);
+
+    while let Some(message) = stream.next().await {
+        let received_at = Instant::now();
+        match message?.update_oneof {
+            Some(UpdateOneof::Ping(_)) => {
+                subscribe_tx
+                    .send(SubscribeRequest {
+                        ping: Some(SubscribeRequestPing { id: 1 }),
+                        ..Default::default()
+                    })
+                    .await?;
+            }
+            Some(update) => {
+                if let Some(key) = update_key(&update)
+                    && tx.send((index, key, received_at)).is_err()
+                {
+                    break;
+                }
+            }
+            None => anyhow::bail!("update not found in the message"),
+        }
+    }
+    info!("{}: stream closed", args.endpoint);
+    Ok(())
+}
+
+fn update_key(update: &UpdateOneof) -> Option<UpdateKey> {
+    let (slot, id) = match update {
+        UpdateOneof::Account(msg) => {
+            let account = msg.account.as_ref()?;
+            let signature = account.txn_signature.as_deref().unwrap_or_default();
+            (msg.slot, [account.pubkey.as_slice(), signature].concat())
+        }
+        UpdateOneof::Slot(msg) => (msg.slot, msg.status.to_le_bytes().to_vec()),
+        UpdateOneof::Transaction(msg) => (msg.slot, msg.transaction.as_ref()?.signature.clone()),
+        UpdateOneof::TransactionStatus(msg) => (msg.slot, msg.signature.clone()),
+        UpdateOneof::Entry(msg) => (msg.slot, msg.index.to_le_bytes().to_vec()),
+        UpdateOneof::BlockMeta(msg) => (msg.slot, vec![]),
+        UpdateOneof::Block(msg) => (msg.slot, vec![]),
+        UpdateOneof::Ping(_) | UpdateOneof::Pong(_) => return None,
+    };
+    Some((StatsKind::from_update(update), slot, id))
+}
+
+#[derive(Default)]
+struct EndpointStats {
+    /// Unique updates received
+    received: u64,
+    /// Updates this endpoint delivered before every other one
+    first: u64,
+    /// Delay behind the first endpoint, in microseconds
+    behind: Vec<u64>,
+    /// Updates not received within the window
+    missed: u64,
+}
+
+struct BenchReport {
+    endpoints: Vec<String>,
+    stats: Vec<EndpointStats>,
+    window: Duration,
+    started_at: Instant,
+    updates: u64,
+    /// First receipt and per-endpoint receipt flags of updates still within the window
+    pending: HashMap<UpdateKey, (Instant, Vec<bool>)>,
+    order: VecDeque<(Instant, UpdateKey)>,
+}
+
+impl BenchReport {
+    fn new(endpoints: Vec<String>, window: Duration) -> Self {
+        Self {
+            stats: endpoints.iter().map(|_| EndpointStats::default()).collect(),
+            endpoints,
+            window,
+            started_at: Instant::now(),
+            updates: 0,
+            pending: HashMap::new(),
+            order: VecDeque::new(),
+        }
+    }
+
+    fn record(&mut self, index: usize, key: UpdateKey, received_at: Instant) {
+        match self.pending.get_mut(&key) {
+            Some((first_at, seen)) => {
+                // Same update delivered twice by one endpoint
+                if std::mem::replace(&mut seen[index], true) {
+                    return;
+                }
+                let stats = &mut self.stats[index];
+                stats.received += 1;
+                stats
+                    .behind
+                    .push(received_at.saturating_duration_since(*first_at).as_micros() as u64);
+            }
+            None => {
+                let stats = &mut self.stats[index];
+                stats.received += 1;
+                stats.first += 1;
+                self.updates += 1;
+
+                let mut seen = vec![false; self.endpoints.len()];
+                seen[index] = true;
+                self.order.push_back((received_at, key.clone()));
+                self.pending.insert(key, (received_at, seen));
+            }
+        }
+        self.expire(received_at);
+    }
+
+    /// Count endpoints which didn't deliver an update within the window as missing it
+    fn expire(&mut self, now: Instant) {
+        while let Some((first_at, _)) = self.order.front()
+            && now.saturating_duration_since(*first_at) > self.window
+        {
+            let (_, key) = self.order.pop_front().expect("front exists");
+            self.forget(&key);
+        }
+    }
+
+    fn expire_all(&mut self) {
+        while let Some((_, key)) = self.order.pop_front() {
+            self.forget(&key);
+        }
+    }
+
+    fn forget(&mut self, key: &UpdateKey) {
+        if let Some((_, seen)) = self.pending.remove(key) {
+            for (stats, seen) in self.stats.iter_mut().zip(seen) {
+                if !seen {
+                    stats.missed += 1;
+                }
+            }
+        }
+    }
+
+    fn print(&mut self) {
+        let updates = self.updates.max(1) as f64;
+        let data = self
+            .endpoints
+            .iter()
+            .zip(self.stats.iter_mut())
+            .enumerate()
+            .map(|(index, (endpoint, stats))| {
+                stats.behind.sort_unstable();
+                let behind = if stats.behind.is_empty() {
+                    "never behind".to_owned()
+                } else {
+                    format!(
+                        "behind on {}: p50 {}, p95 {}, p99 {}, max {}",
+                        stats.behind.len(),
+                        format_micros(percentile(&stats.behind, 0.50)),
+                        format_micros(percentile(&stats.behind, 0.95)),
+                        format_micros(percentile(&stats.behind, 0.99)),
+                        format_micros(stats.behind[stats.behind.len() - 1]),
+                    )
+                };
+                (
+                    format!("[{index}] {endpoint}"),
+                    format!(
+                        "received {}, first {} ({:.1}%), {}, missed {}",
+                        stats.received,
+                        stats.first,
+                        stats.first as f64 * 100.0 / updates,
+                        behind,
+                        stats.missed,
+                    ),
+                )
+            })
+            .collect::<Vec<_>>();
+        print_query_result(
+            &format!(
+                "Bench: {} unique updates in {:.1?}",
+                self.updates,
+                self.started_at.elapsed()
+            ),
+            &data,
+        );
+    }
+}
@@ -0,0 +1,156 @@
+//! `--block-chunk-size`: full blocks decoded and written a chunk at a time, bounding memory.
+//!
+//! A decoded block is a JSON tree several times the size of its protobuf, decoding a large
+//! block at once holds both. With a chunk size, the transactions, accounts and entries of a
+//! block are decoded and written as `blockChunk` updates of at most that many items, in block
+//! order, freeing each chunk before decoding the next. The `block` update follows without them
+//! (`executedTransactionCount`, `updatedAccountCount` and `entriesCount` still count them) and
+//! marks the block complete. Chunks go through the output like any update, `--commit-boundary`
+//! and `--out-file-commitment` hold them until their slot is ready though.
+//!
+//! With `--dead-letter-dir`, the encoding of the block is kept while its chunks are decoded, a
+//! chunk failing to decode is written as a dead letter of the whole block.
+
+use {
+    crate::{
+        DecodedUpdate, UpdateTimings, create_pretty_account, create_pretty_entry,
+        create_pretty_transaction, deadletter::DecodeFailure, decode_update, resources, schema,
+        tracecontext::UpdateTrace,
+    },
+    anyhow::Context,
+    serde::Serialize,
+    std::{
+        mem,
+        time::{Instant, SystemTime},
+    },
+    yellowstone_grpc_proto::{
+        prelude::{SubscribeUpdate, subscribe_update::UpdateOneof},
+        prost::Message,
+    },
+};
+
+type Decoded = Result<DecodedUpdate, DecodeFailure>;
+
+/// Decode a block update chunk by chunk, passing every chunk and then the block to `write`
+pub fn decode(
+    mut msg: SubscribeUpdate,
+    chunk_size: usize,
+    received_at: Instant,
+    trace_ids: bool,
+    keep_raw: bool,
+    mut write: impl FnMut(Decoded) -> anyhow::Result<()>,
+) -> anyhow::Result<()> {
+    // Decoding consumes the update, keep its encoding for a dead letter
+    let raw = keep_raw.then(|| msg.encode_to_vec());
+    let Some(UpdateOneof::Block(block)) = &mut msg.update_oneof else {
+        anyhow::bail!("not a block update");
+    };
+    let transactions = mem::take(&mut block.transactions);
+    let accounts = mem::take(&mut block.accounts);
+    let entries = mem::take(&mut block.entries);
+    let chunks = Chunks {
+        slot: block.slot,
+        blockhash: block.blockhash.clone(),
+        created_at: msg
+            .created_at
+            .context("no created_at in the message")?
+            .try_into()
+            .context("failed to parse created_at")?,
+        filters: msg.filters.clone(),
+        chunk_size,
+        received_at,
+        trace_ids,
+        raw,
+    };
+    chunks.write(
+        "transactions",
+        transactions,
+        create_pretty_transaction,
+        &mut write,
+    )?;
+    chunks.write("accounts", accounts, create_pretty_account, &mut write)?;
+    chunks.write("entries", entries, create_pretty_entry, &mut write)?;
+
+    let trace = trace_ids.then(UpdateTrace::new);
+    let entered = trace.as_ref().map(|trace| trace.span().enter());
+    let decoded = decode_update(msg, received_at);
+    drop(entered);
+    write(
+        decoded
+            .map(|decoded| DecodedUpdate { trace, ..decoded })
+            .map_err(|error| DecodeFailure {
+                error,
+                raw: chunks.raw,
+            }),
+    )
+}
+
+struct Chunks {
+    slot: u64,
+    blockhash: String,
+    created_at: SystemTime,
+    filters: Vec<String>,
+    chunk_size: usize,
+    received_at: Instant,
+    trace_ids: bool,
+    raw: Option<Vec<u8>>,
+}
+
+impl Chunks {
+    fn write<P, T: Serialize>(
+        &self,
+        part: &'static str,
+        items: Vec<P>,
+        pretty: fn(P) -> anyhow::Result<T>,
+        write: &mut impl FnMut(Decoded) -> anyhow::Result<()>,
+    ) -> anyhow::Result<()> {
+        let total = items.len();
+        let mut items = items.into_iter();
+        for offset in (0..total).step_by(self.chunk_size) {
+            let trace = self.trace_ids.then(UpdateTrace::new);
+            let entered = trace.as_ref().map(|trace| trace.span().enter());
+            let span = tracing::debug_span!("decode", kind = "blockChunk").entered();
+            let decode_started_at = Instant::now();
+            let cpu_started_at = resources::thread_cpu_time();
+            let value = items
+                .by_ref()
+                .take(self.chunk_size)
+                .map(pretty)
+                .collect::<anyhow::Result<Vec<_>>>()
+                .and_then(|items| {
+                    Ok(serde_json::to_value(schema::BlockChunk {
+                        slot: self.slot,
+                        blockhash: self.blockhash.clone(),
+                        part,
+                        offset,
+                        total,
+                        items,
+                    })?)
+                });
+            let timings = UpdateTimings {
+                received_at: self.received_at,
+                queued: decode_started_at.duration_since(self.received_at),
+                decode: decode_started_at.elapsed(),
+                decode_cpu: resources::thread_cpu_time().saturating_sub(cpu_started_at),
+            };
+            drop(span);
+            drop(entered);
+            write(
+                value
+                    .map(|value| DecodedUpdate {
+                        kind: "blockChunk",
+                        created_at: self.created_at,
+                        filters: self.filters.clone(),
+                        value,
+                        timings,
+                        trace,
+                    })
+                    .map_err(|error| DecodeFailure {
+                        error,
+                        raw: self.raw.clone(),
+                    }),
+            )?;
+        }
+        Ok(())
+    }
+}
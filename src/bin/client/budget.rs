@@ -0,0 +1,43 @@
+//! `--max-messages`, `--max-bytes` and `--duration`: end a subscription cleanly once a budget is
+//! spent, so capture jobs can be scripted deterministically.
+//!
+//! The budget is counted by the session, across reconnects, on the messages received before
+//! client side filters; pings and pongs aren't counted. Once it's spent the updates still being
+//! decoded are written, sinks are closed and the session summary is printed, and the client
+//! exits with 0.
+//!
+//! `--until-slot` ends the subscription the same way at the first update of a later slot, which
+//! is dropped, so with `--from-slot` it captures a slot window. Updates of a slot are sent
+//! together at `confirmed` and `finalized`; at `processed` slots overlap and updates of the last
+//! slots arriving after a later one are lost.
+
+use std::time::Duration;
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Budget {
+    pub max_messages: Option<u64>,
+    pub max_bytes: Option<u64>,
+    pub duration: Option<Duration>,
+    pub until_slot: Option<u64>,
+}
+
+impl Budget {
+    /// Why the budget is spent after `messages` of `bytes` in `elapsed`, `None` if it isn't
+    pub fn spent(&self, messages: u64, bytes: u64, elapsed: Duration) -> Option<String> {
+        if let Some(max) = self.max_messages.filter(|max| messages >= *max) {
+            return Some(format!("--max-messages {max} reached"));
+        }
+        if let Some(max) = self.max_bytes.filter(|max| bytes >= *max) {
+            return Some(format!("--max-bytes {max} reached"));
+        }
+        self.duration
+            .filter(|duration| elapsed >= *duration)
+            .map(|duration| format!("--duration {}s elapsed", duration.as_secs()))
+    }
+
+    /// Why an update of `slot` is past the budget, `None` if it isn't
+    pub fn past(&self, slot: Option<u64>) -> Option<String> {
+        let (until, slot) = self.until_slot.zip(slot)?;
+        (slot > until).then(|| format!("--until-slot {until} passed (slot {slot})"))
+    }
+}
@@ -0,0 +1,319 @@
+//! `--capture`: record the raw `SubscribeUpdate` frames of a subscription to a file, and the
+//! `inspect` and `replay` actions to read them back.
+//!
+//! A capture starts with [`MAGIC`], followed by records: a record type byte, the payload
+//! length as a little-endian u32, then the payload. Frame records hold a `SubscribeUpdate`
+//! exactly as received. Every [`INDEX_INTERVAL`] frames an index record lists the slots first
+//! seen since the previous index with the offset of their first frame, as pairs of
+//! little-endian u64, so a reader can seek to a slot by skipping over frame payloads.
+//! With `--encryption-key` frames are written as sealed frame records instead.
+
+use {
+    crate::{StatsKind, crypto::Cipher, decode_update, raw},
+    anyhow::Context,
+    serde_json::json,
+    std::{
+        fs::{File, OpenOptions},
+        io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write},
+        path::{Path, PathBuf},
+        time::{Instant, UNIX_EPOCH},
+    },
+    yellowstone_grpc_proto::{prelude::SubscribeUpdate, prost::Message},
+};
+
+pub const MAGIC: &[u8; 8] = b"GEYSCAP1";
+
+const RECORD_FRAME: u8 = 0;
+const RECORD_INDEX: u8 = 1;
+const RECORD_SEALED_FRAME: u8 = 2;
+/// Record type and payload length
+const HEADER_LEN: u64 = 5;
+
+/// Frames between index records
+const INDEX_INTERVAL: usize = 10_000;
+
+pub struct CaptureWriter {
+    path: PathBuf,
+    writer: BufWriter<File>,
+    offset: u64,
+    /// Highest slot written, frames of a higher slot start a new index entry
+    max_slot: Option<u64>,
+    index: Vec<(u64, u64)>,
+    frames: usize,
+    cipher: Option<Cipher>,
+}
+
+impl CaptureWriter {
+    /// Append to the capture at `path`, creating it if needed
+    pub fn open(path: &Path, cipher: Option<Cipher>) -> anyhow::Result<Self> {
+        let file = OpenOptions::new()
+            .create(true)
+            .read(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("failed to open {}", path.display()))?;
+        let offset = file.metadata()?.len();
+        let mut writer = BufWriter::new(file);
+        if offset == 0 {
+            writer.write_all(MAGIC)?;
+        } else {
+            check_magic(writer.get_mut(), path)?;
+        }
+        Ok(Self {
+            path: path.to_owned(),
+            writer,
+            offset: offset.max(MAGIC.len() as u64),
+            max_slot: None,
+            index: vec![],
+            frames: 0,
+            cipher,
+        })
+    }
+
+    pub fn write_frame(&mut self, frame: &[u8], slot: Option<u64>) -> anyhow::Result<()> {
+        if let Some(slot) = slot
+            && self.max_slot.is_none_or(|max_slot| slot > max_slot)
+        {
+            self.max_slot = Some(slot);
+            self.index.push((slot, self.offset));
+        }
+        match &self.cipher {
+            Some(cipher) => {
+                let sealed = cipher.seal(frame)?;
+                self.write_record(RECORD_SEALED_FRAME, &sealed)?;
+            }
+            None => self.write_record(RECORD_FRAME, frame)?,
+        }
+        self.frames += 1;
+        if self.frames >= INDEX_INTERVAL {
+            self.write_index()?;
+        }
+        Ok(())
+    }
+
+    /// Write the pending index entries and flush
+    fn write_index(&mut self) -> anyhow::Result<()> {
+        self.frames = 0;
+        if !self.index.is_empty() {
+            let payload = self
+                .index
+                .drain(..)
+                .flat_map(|(slot, offset)| [slot.to_le_bytes(), offset.to_le_bytes()])
+                .flatten()
+                .collect::<Vec<u8>>();
+            self.write_record(RECORD_INDEX, &payload)?;
+        }
+        self.writer
+            .flush()
+            .with_context(|| format!("failed to write {}", self.path.display()))
+    }
+
+    fn write_record(&mut self, kind: u8, payload: &[u8]) -> anyhow::Result<()> {
+        let len = u32::try_from(payload.len()).context("record too large")?;
+        self.writer
+            .write_all(&[kind])
+            .and_then(|()| self.writer.write_all(&len.to_le_bytes()))
+            .and_then(|()| self.writer.write_all(payload))
+            .with_context(|| format!("failed to write {}", self.path.display()))?;
+        self.offset += HEADER_LEN + u64::from(len);
+        Ok(())
+    }
+}
+
+impl Drop for CaptureWriter {
+    fn drop(&mut self) {
+        if let Err(error) = self.write_index() {
+            tracing::warn!("{error:#}");
+        }
+    }
+}
+
+fn check_magic(file: &mut File, path: &Path) -> anyhow::Result<()> {
+    let mut magic = [0; MAGIC.len()];
+    file.seek(SeekFrom::Start(0))?;
+    file.read_exact(&mut magic)
+        .ok()
+        .filter(|()| &magic == MAGIC)
+        .with_context(|| format!("{} is not a capture file", path.display()))
+}
+
+struct CaptureReader {
+    reader: BufReader<File>,
+    offset: u64,
+}
+
+impl CaptureReader {
+    fn open(path: &Path) -> anyhow::Result<Self> {
+        let mut file =
+            File::open(path).with_context(|| format!("failed to open {}", path.display()))?;
+        check_magic(&mut file, path)?;
+        Ok(Self {
+            reader: BufReader::new(file),
+            offset: MAGIC.len() as u64,
+        })
+    }
+
+    fn seek(&mut self, offset: u64) -> io::Result<()> {
+        self.reader.seek(SeekFrom::Start(offset))?;
+        self.offset = offset;
+        Ok(())
+    }
+
+    /// Offset and header of the next record, `None` at the end or at a truncated record
+    fn next_header(&mut self) -> io::Result<Option<(u64, u8, u32)>> {
+        let mut header = [0; HEADER_LEN as usize];
+        match self.reader.read_exact(&mut header) {
+            Ok(()) => {}
+            Err(error) if error.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+            Err(error) => return Err(error),
+        }
+        let offset = self.offset;
+        let len = u32::from_le_bytes(header[1..].try_into().expect("4 bytes"));
+        self.offset += HEADER_LEN + u64::from(len);
+        Ok(Some((offset, header[0], len)))
+    }
+
+    fn read_payload(&mut self, len: u32) -> io::Result<Option<Vec<u8>>> {
+        let mut payload = vec![0; len as usize];
+        match self.reader.read_exact(&mut payload) {
+            Ok(()) => Ok(Some(payload)),
+            Err(error) if error.kind() == io::ErrorKind::UnexpectedEof => Ok(None),
+            Err(error) => Err(error),
+        }
+    }
+
+    fn skip_payload(&mut self, len: u32) -> io::Result<()> {
+        self.reader.seek_relative(i64::from(len))
+    }
+
+    /// Offset to read from so no frame of `slot` or later is missed, found by reading the
+    /// index records only
+    fn find_slot(&mut self, slot: u64) -> io::Result<u64> {
+        let mut found = MAGIC.len() as u64;
+        while let Some((_, kind, len)) = self.next_header()? {
+            if kind != RECORD_INDEX {
+                self.skip_payload(len)?;
+                continue;
+            }
+            let Some(payload) = self.read_payload(len)? else {
+                break;
+            };
+            for entry in payload.chunks_exact(16) {
+                let entry_slot = u64::from_le_bytes(entry[..8].try_into().expect("8 bytes"));
+                if entry_slot > slot {
+                    return Ok(found);
+                }
+                found = u64::from_le_bytes(entry[8..].try_into().expect("8 bytes"));
+            }
+        }
+        Ok(found)
+    }
+}
+
+/// Whether a file starts with the capture [`MAGIC`]
+pub fn is_capture(path: &Path) -> bool {
+    File::open(path).is_ok_and(|mut file| check_magic(&mut file, path).is_ok())
+}
+
+/// Read the frames of a capture, from the first of `from_slot` when given, passing each with
+/// its offset to `frame` until it returns `false`
+pub fn read_frames(
+    path: &Path,
+    from_slot: Option<u64>,
+    cipher: Option<&Cipher>,
+    mut frame: impl FnMut(u64, Vec<u8>, raw::FrameFields) -> anyhow::Result<bool>,
+) -> anyhow::Result<()> {
+    let mut reader = CaptureReader::open(path)?;
+    if let Some(from_slot) = from_slot {
+        let offset = reader.find_slot(from_slot)?;
+        reader.seek(offset)?;
+    }
+
+    while let Some((offset, kind, len)) = reader.next_header()? {
+        if kind != RECORD_FRAME && kind != RECORD_SEALED_FRAME {
+            reader.skip_payload(len)?;
+            continue;
+        }
+        let Some(payload) = reader.read_payload(len)? else {
+            tracing::warn!("capture ends with a truncated frame at offset {offset}");
+            break;
+        };
+        let payload = match (kind, cipher) {
+            (RECORD_SEALED_FRAME, Some(cipher)) => cipher
+                .open(&payload)
+                .with_context(|| format!("frame at offset {offset}"))?,
+            (RECORD_SEALED_FRAME, None) => {
+                anyhow::bail!("capture is encrypted, pass --encryption-key")
+            }
+            _ => payload,
+        };
+        let fields = raw::parse_frame(&payload).unwrap_or_default();
+        if from_slot.is_some_and(|from_slot| fields.slot.is_none_or(|slot| slot < from_slot)) {
+            continue;
+        }
+        if !frame(offset, payload, fields)? {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// `inspect`: list the frames of a capture, or dump them decoded as JSON lines
+pub fn inspect(
+    path: &Path,
+    from_slot: Option<u64>,
+    limit: Option<usize>,
+    dump: bool,
+    cipher: Option<&Cipher>,
+) -> anyhow::Result<()> {
+    let mut frames = 0;
+    let mut slots = None::<(u64, u64)>;
+    read_frames(path, from_slot, cipher, |offset, frame, fields| {
+        if limit.is_some_and(|limit| frames >= limit) {
+            return Ok(false);
+        }
+        frames += 1;
+        if let Some(slot) = fields.slot {
+            slots = Some(slots.map_or((slot, slot), |(first, last)| {
+                (first.min(slot), last.max(slot))
+            }));
+        }
+
+        if dump {
+            let update = SubscribeUpdate::decode(frame.as_slice())
+                .with_context(|| format!("invalid frame at offset {offset}"))?;
+            let update = decode_update(update, Instant::now())?;
+            let created_at = update.created_at.duration_since(UNIX_EPOCH)?;
+            println!(
+                "{}",
+                json!({
+                    "kind": update.kind,
+                    "filters": update.filters,
+                    "createdAt": created_at.as_millis() as u64,
+                    "update": update.value,
+                })
+            );
+        } else {
+            let kind = fields.update.and_then(StatsKind::from_field_number);
+            let created_at = fields
+                .created_at
+                .map(|created_at| chrono::DateTime::<chrono::Utc>::from(created_at).to_rfc3339());
+            println!(
+                "{offset:>12}  slot {:>10}  {:<22} {:>9} bytes  {}",
+                fields.slot.map(|slot| slot.to_string()).unwrap_or_default(),
+                kind.map_or("unknown", StatsKind::label),
+                frame.len(),
+                created_at.unwrap_or_default(),
+            );
+        }
+        Ok(true)
+    })?;
+
+    if !dump {
+        match slots {
+            Some((first, last)) => println!("{frames} frames, slots {first}..={last}"),
+            None => println!("{frames} frames"),
+        }
+    }
+    Ok(())
+}
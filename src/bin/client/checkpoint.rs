@@ -0,0 +1,260 @@
+//! `--checkpoint-file`: persist how far the output got, so a restart resumes with `from_slot`
+//! at the last written slot and skips the updates of that slot which were written already.
+//!
+//! The checkpoint keeps the last slot written and the keys of the updates written in it (the
+//! intra-slot cursor). Updates are assumed to arrive in slot order, as with `confirmed` and
+//! `finalized` commitments; with `processed`, updates of an older slot arriving after a newer
+//! one can be written twice across a restart.
+//!
+//! With `--sink`, an update counts as written once every sink delivered it. Once a sink dropped
+//! an update or failed to deliver it the checkpoint stops moving, so a restart delivers from
+//! there again rather than skipping it.
+
+use {
+    anyhow::Context,
+    tracing::{info, warn},
+    serde_json::{Value, json},
+    std::{
+        collections::HashSet,
+        fs,
+        path::PathBuf,
+        time::{Duration, Instant},
+    },
+};
+
+/// How often the checkpoint is written while updates flow
+const SAVE_INTERVAL: Duration = Duration::from_secs(1);
+
+pub struct Checkpoint {
+    path: PathBuf,
+    slot: Option<u64>,
+    /// Keys of the updates written in `slot`
+    keys: HashSet<String>,
+    dirty: bool,
+    saved_at: Instant,
+}
+
+impl Checkpoint {
+    pub fn load(path: PathBuf) -> anyhow::Result<Self> {
+        let mut checkpoint = Self {
+            path,
+            slot: None,
+            keys: HashSet::new(),
+            dirty: false,
+            saved_at: Instant::now(),
+        };
+        let text = match fs::read_to_string(&checkpoint.path) {
+            Ok(text) => text,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(checkpoint),
+            Err(error) => {
+                return Err(error).with_context(|| {
+                    format!("failed to read checkpoint {}", checkpoint.path.display())
+                });
+            }
+        };
+        let value: Value = serde_json::from_str(&text)
+            .with_context(|| format!("invalid checkpoint {}", checkpoint.path.display()))?;
+        checkpoint.slot = value["slot"].as_u64();
+        checkpoint.keys = value["keys"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(Value::as_str)
+            .map(str::to_owned)
+            .collect();
+        if let Some(slot) = checkpoint.slot {
+            info!(
+                "checkpoint: resuming at slot {slot}, {} updates of it written already",
+                checkpoint.keys.len()
+            );
+        }
+        Ok(checkpoint)
+    }
+
+    /// Slot to resume the subscription from
+    pub const fn slot(&self) -> Option<u64> {
+        self.slot
+    }
+
+    /// Whether the update was written before, only known for updates in the checkpoint slot
+    pub fn contains(&self, position: &Position) -> bool {
+        Some(position.slot) == self.slot
+            && position.key.as_ref().is_some_and(|key| self.keys.contains(key))
+    }
+
+    /// Record a written update, the checkpoint is saved at most every [`SAVE_INTERVAL`]
+    pub fn record(&mut self, position: Position) {
+        if self.slot.is_some_and(|slot| position.slot < slot) {
+            return;
+        }
+        if self.slot != Some(position.slot) {
+            self.slot = Some(position.slot);
+            self.keys.clear();
+        }
+        if let Some(key) = position.key {
+            self.keys.insert(key);
+        }
+        self.dirty = true;
+        if self.saved_at.elapsed() >= SAVE_INTERVAL
+            && let Err(error) = self.save()
+        {
+            warn!("{error:#}");
+        }
+    }
+
+    /// Write the checkpoint if it changed, through a temporary file so a crash never leaves
+    /// a partial one
+    pub fn save(&mut self) -> anyhow::Result<()> {
+        self.saved_at = Instant::now();
+        if !self.dirty {
+            return Ok(());
+        }
+        let value = json!({
+            "slot": self.slot,
+            "cursor": self.keys.len(),
+            "keys": self.keys,
+        });
+        let tmp = self.path.with_extension("tmp");
+        fs::write(&tmp, serde_json::to_vec(&value)?)
+            .and_then(|()| fs::rename(&tmp, &self.path))
+            .with_context(|| format!("failed to write checkpoint {}", self.path.display()))?;
+        self.dirty = false;
+        Ok(())
+    }
+}
+
+/// Saved when the subscription ends, whichever way it ends
+impl Drop for Checkpoint {
+    fn drop(&mut self) {
+        if let Err(error) = self.save() {
+            warn!("{error:#}");
+        }
+    }
+}
+
+/// Slot of an update and its identity within the slot
+#[derive(PartialEq, Eq, Hash)]
+pub struct Position {
+    slot: u64,
+    key: Option<String>,
+}
+
+impl Position {
+    pub fn of(kind: &str, value: &Value) -> Option<Self> {
+        Some(Self {
+            slot: value.get("slot")?.as_u64()?,
+            key: key(kind, value),
+        })
+    }
+
+    pub const fn slot(&self) -> u64 {
+        self.slot
+    }
+
+    /// Whether the update has an identity within its slot
+    pub const fn is_keyed(&self) -> bool {
+        self.key.is_some()
+    }
+}
+
+/// Identity of an update within its slot, `None` for updates without one
+fn key(kind: &str, value: &Value) -> Option<String> {
+    let field = match kind {
+        "account" => "pubkey",
+        "transaction" | "transactionStatus" => "signature",
+        "slot" => "status",
+        "entry" => "index",
+        "block" | "blockmeta" => "blockhash",
+        "blockChunk" => {
+            let blockhash = value.get("blockhash")?.as_str()?;
+            let part = value.get("part")?.as_str()?;
+            let offset = value.get("offset")?.as_u64()?;
+            return Some(format!("{kind}:{blockhash}:{part}:{offset}"));
+        }
+        _ => return None,
+    };
+    let id = match value.get(field)? {
+        Value::String(id) => id.clone(),
+        id => id.to_string(),
+    };
+    // An account can change several times in a slot
+    let version = match kind {
+        "account" => value.get("writeVersion").and_then(Value::as_u64),
+        _ => None,
+    };
+    Some(match version {
+        Some(version) => format!("{kind}:{id}:{version}"),
+        None => format!("{kind}:{id}"),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A checkpoint path in the temporary directory, removed beforehand
+    fn path(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "checkpoint-test-{}-{name}.json",
+            std::process::id()
+        ));
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    fn position(kind: &str, slot: u64, signature: &str) -> Position {
+        Position::of(kind, &json!({"slot": slot, "signature": signature})).unwrap()
+    }
+
+    #[test]
+    fn missing() {
+        let checkpoint = Checkpoint::load(path("missing")).unwrap();
+        assert_eq!(checkpoint.slot(), None);
+        assert!(!checkpoint.contains(&position("transaction", 1, "a")));
+    }
+
+    #[test]
+    fn round_trip() {
+        let path = path("round-trip");
+        let mut checkpoint = Checkpoint::load(path.clone()).unwrap();
+        checkpoint.record(position("transaction", 10, "a"));
+        checkpoint.record(position("transaction", 11, "b"));
+        checkpoint.record(position("transaction", 11, "c"));
+        checkpoint.save().unwrap();
+
+        let checkpoint = Checkpoint::load(path.clone()).unwrap();
+        assert_eq!(checkpoint.slot(), Some(11));
+        assert!(checkpoint.contains(&position("transaction", 11, "b")));
+        assert!(checkpoint.contains(&position("transaction", 11, "c")));
+        assert!(!checkpoint.contains(&position("transaction", 11, "d")));
+        assert!(!checkpoint.contains(&position("transactionStatus", 11, "b")));
+        // Only the keys of the checkpoint slot are kept
+        assert!(!checkpoint.contains(&position("transaction", 10, "a")));
+        drop(checkpoint);
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn saved_on_drop() {
+        let path = path("drop");
+        let mut checkpoint = Checkpoint::load(path.clone()).unwrap();
+        checkpoint.record(position("transaction", 7, "a"));
+        // An older slot arriving late doesn't move the checkpoint back
+        checkpoint.record(position("transaction", 6, "b"));
+        drop(checkpoint);
+
+        let checkpoint = Checkpoint::load(path.clone()).unwrap();
+        assert_eq!(checkpoint.slot(), Some(7));
+        assert!(checkpoint.contains(&position("transaction", 7, "a")));
+        drop(checkpoint);
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn invalid() {
+        let path = path("invalid");
+        fs::write(&path, "{").unwrap();
+        assert!(Checkpoint::load(path.clone()).is_err());
+        fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,92 @@
+//! `--accounts-mode latest`: keep only the newest update of every account over tumbling windows
+//! of `--accounts-window`, cutting the writes of accounts changing many times a second for
+//! consumers which only need their current state.
+//!
+//! Updates are compared by slot then write version, so an older version arriving late never
+//! replaces a newer one. At the end of a window the accounts held are written in that order,
+//! after the updates of other kinds received meanwhile, also when the stream is quiet then.
+
+use {
+    crate::DecodedUpdate,
+    clap::ValueEnum,
+    serde_json::Value,
+    std::{
+        collections::HashMap,
+        time::{Duration, Instant},
+    },
+};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum AccountsMode {
+    /// Every account update
+    #[default]
+    All,
+    /// The newest update of every account per --accounts-window
+    Latest,
+}
+
+pub struct Compactor {
+    window: Duration,
+    started_at: Instant,
+    accounts: HashMap<String, DecodedUpdate>,
+}
+
+impl Compactor {
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            started_at: Instant::now(),
+            accounts: HashMap::new(),
+        }
+    }
+
+    pub const fn window(&self) -> Duration {
+        self.window
+    }
+
+    /// Hold an account update, returns the update if it isn't an account
+    pub fn push(&mut self, update: DecodedUpdate) -> Option<DecodedUpdate> {
+        let pubkey = match update.value.get("pubkey").and_then(Value::as_str) {
+            Some(pubkey) if update.kind == "account" => pubkey.to_owned(),
+            _ => return Some(update),
+        };
+        match self.accounts.get(&pubkey) {
+            Some(held) if version(held) >= version(&update) => {}
+            _ => {
+                self.accounts.insert(pubkey, update);
+            }
+        }
+        None
+    }
+
+    /// The accounts held once the window is over, in slot and write version order
+    pub fn due(&mut self) -> Vec<DecodedUpdate> {
+        if self.started_at.elapsed() < self.window {
+            return vec![];
+        }
+        self.started_at = Instant::now();
+        self.drain()
+    }
+
+    /// The accounts held, in slot and write version order
+    pub fn drain(&mut self) -> Vec<DecodedUpdate> {
+        let mut accounts = self
+            .accounts
+            .drain()
+            .map(|(_, update)| update)
+            .collect::<Vec<_>>();
+        accounts.sort_by_key(version);
+        accounts
+    }
+}
+
+fn version(update: &DecodedUpdate) -> (u64, u64) {
+    let field = |name| {
+        update
+            .value
+            .get(name)
+            .and_then(Value::as_u64)
+            .unwrap_or_default()
+    };
+    (field("slot"), field("writeVersion"))
+}
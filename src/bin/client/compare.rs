@@ -0,0 +1,360 @@
+//! `verify`: audit an endpoint by comparing a sample of streamed transactions and accounts with
+//! what a JSON-RPC node (`--rpc-url`) returns for them, reporting every mismatch.
+//!
+//! Checks run `--delay-secs` after an update is received, giving the RPC node time to catch up.
+//! A transaction is looked up by signature and its slot, status and fee compared; a transaction
+//! still unknown after a second attempt is missing. An account is fetched and compared with the
+//! latest version streamed for it; if the RPC node is behind that version the check is
+//! inconclusive. Transactions are fetched at `confirmed` at least, and with `processed` updates
+//! of abandoned forks show up as missing, so `--commitment confirmed` gives the cleanest audit.
+
+use {
+    crate::{ActionVerify, Args, print_query_result, rpc::RpcClient, secrets},
+    base64::{Engine, engine::general_purpose::STANDARD},
+    futures::{
+        sink::SinkExt,
+        stream::{FuturesUnordered, StreamExt},
+    },
+    serde_json::{Value, json},
+    solana_pubkey::Pubkey,
+    solana_signature::Signature,
+    std::{
+        collections::{BTreeMap, HashMap},
+        time::Duration,
+    },
+    tracing::{info, warn},
+    yellowstone_grpc_proto::prelude::{
+        CommitmentLevel, SubscribeRequest, SubscribeRequestAccountsDataSlice, SubscribeRequestPing,
+        subscribe_update::UpdateOneof,
+    },
+};
+
+/// Checks waiting for their delay or RPC response, updates aren't sampled beyond it
+const MAX_PENDING: usize = 256;
+
+enum Check {
+    Transaction {
+        signature: String,
+        slot: u64,
+        failed: bool,
+        fee: u64,
+        attempt: u32,
+    },
+    Account {
+        pubkey: String,
+        slot: u64,
+    },
+}
+
+/// Latest streamed version of an account being checked
+struct AccountVersion {
+    slot: u64,
+    lamports: u64,
+    owner: Vec<u8>,
+    executable: bool,
+    data: Vec<u8>,
+    /// Pending checks of the account, the version is forgotten with the last one
+    checks: usize,
+}
+
+pub async fn run(
+    args: &Args,
+    verify: &ActionVerify,
+    request: SubscribeRequest,
+) -> anyhow::Result<()> {
+    anyhow::ensure!(
+        !request.transactions.is_empty() || !request.accounts.is_empty(),
+        "verify compares transactions and accounts, subscribe to at least one of them"
+    );
+    anyhow::ensure!(
+        (0.0..=1.0).contains(&verify.sample_rate),
+        "--sample-rate must be between 0 and 1"
+    );
+    let rpc = RpcClient::new(secrets::resolve(&args.rpc_url).await?);
+    let commitment = args.get_commitment().unwrap_or_default();
+    let data_slices = request.accounts_data_slice.clone();
+    let delay = Duration::from_secs(verify.delay_secs);
+
+    let mut client = args.connect().await?;
+    let (mut subscribe_tx, mut stream) = client.subscribe_with_request(Some(request)).await?;
+    info!("stream opened, sampling {} checks", verify.checks);
+
+    let deadline = tokio::time::sleep(verify.subscribe.duration.unwrap_or(Duration::MAX));
+    tokio::pin!(deadline);
+    let mut pending = FuturesUnordered::new();
+    let mut accounts: HashMap<String, AccountVersion> = HashMap::new();
+    let mut sampled = 0;
+    let mut outcomes: BTreeMap<&'static str, u64> = BTreeMap::new();
+    let mut mismatches = 0;
+
+    let schedule = |check: Check, delay: Duration| {
+        let rpc = &rpc;
+        async move {
+            tokio::time::sleep(delay).await;
+            let response = match &check {
+                Check::Transaction { signature, .. } => {
+                    let commitment = commitment.max(CommitmentLevel::Confirmed);
+                    rpc.call(
+                        "getTransaction",
+                        json!([signature, {
+                            "encoding": "base64",
+                            "commitment": commitment_name(commitment),
+                            "maxSupportedTransactionVersion": 0,
+                        }]),
+                    )
+                    .await
+                }
+                Check::Account { pubkey, slot } => {
+                    rpc.call(
+                        "getAccountInfo",
+                        json!([pubkey, {
+                            "encoding": "base64",
+                            "commitment": commitment_name(commitment),
+                            "minContextSlot": slot,
+                        }]),
+                    )
+                    .await
+                }
+            };
+            (check, response)
+        }
+    };
+
+    loop {
+        if sampled >= verify.checks && pending.is_empty() {
+            break;
+        }
+        tokio::select! {
+            _ = &mut deadline => {
+                warn!("--duration reached with {} checks pending", pending.len());
+                break;
+            }
+            Some((check, response)) = pending.next(), if !pending.is_empty() => {
+                let (outcome, details) = match (&check, response) {
+                    (_, Err(error)) => ("rpcError", json!(format!("{error:#}"))),
+                    (Check::Transaction { attempt: 0, .. }, Ok(Value::Null)) => {
+                        let Check::Transaction { signature, slot, failed, fee, .. } = check else {
+                            unreachable!("matched a transaction check");
+                        };
+                        let retry = Check::Transaction { signature, slot, failed, fee, attempt: 1 };
+                        pending.push(schedule(retry, delay));
+                        continue;
+                    }
+                    (Check::Transaction { .. }, Ok(response)) => compare_transaction(&check, &response),
+                    (Check::Account { pubkey, .. }, Ok(response)) => {
+                        let version = accounts.get_mut(pubkey).expect("checked accounts are tracked");
+                        let result = compare_account(version, &response, &data_slices);
+                        version.checks -= 1;
+                        if version.checks == 0 {
+                            accounts.remove(pubkey);
+                        }
+                        result
+                    }
+                };
+                *outcomes.entry(outcome).or_default() += 1;
+                if !matches!(outcome, "ok" | "rpcBehind" | "rpcError") {
+                    mismatches += 1;
+                }
+                if outcome != "ok" {
+                    let (kind, id, slot) = match &check {
+                        Check::Transaction { signature, slot, .. } => ("transaction", signature, slot),
+                        Check::Account { pubkey, slot } => ("account", pubkey, slot),
+                    };
+                    println!(
+                        "{}",
+                        json!({ "outcome": outcome, "kind": kind, "id": id, "slot": slot, "details": details })
+                    );
+                }
+            }
+            message = stream.next() => {
+                let Some(message) = message else {
+                    anyhow::bail!("stream closed with {} checks pending", pending.len());
+                };
+                let check = match message?.update_oneof {
+                    Some(UpdateOneof::Ping(_)) => {
+                        subscribe_tx
+                            .send(SubscribeRequest {
+                                ping: Some(SubscribeRequestPing { id: 1 }),
+                                ..Default::default()
+                            })
+                            .await?;
+                        continue;
+                    }
+                    Some(UpdateOneof::Account(msg)) => {
+                        let Some(account) = msg.account else { continue };
+                        let pubkey = bs58::encode(&account.pubkey).into_string();
+                        let version = AccountVersion {
+                            slot: msg.slot,
+                            lamports: account.lamports,
+                            owner: account.owner,
+                            executable: account.executable,
+                            data: account.data,
+                            checks: 0,
+                        };
+                        let check = sample(verify, sampled, pending.len());
+                        match accounts.get_mut(&pubkey) {
+                            Some(tracked) if version.slot >= tracked.slot => {
+                                *tracked = AccountVersion { checks: tracked.checks, ..version };
+                            }
+                            Some(_) => {}
+                            None if check => {
+                                accounts.insert(pubkey.clone(), version);
+                            }
+                            None => continue,
+                        }
+                        if !check {
+                            continue;
+                        }
+                        accounts.get_mut(&pubkey).expect("tracked above").checks += 1;
+                        Check::Account { pubkey, slot: msg.slot }
+                    }
+                    Some(UpdateOneof::Transaction(msg)) => {
+                        let Some(tx) = msg.transaction else { continue };
+                        if !sample(verify, sampled, pending.len()) {
+                            continue;
+                        }
+                        let meta = tx.meta.unwrap_or_default();
+                        Check::Transaction {
+                            signature: Signature::try_from(tx.signature.as_slice())
+                                .map_or_else(|_| bs58::encode(&tx.signature).into_string(), |signature| signature.to_string()),
+                            slot: msg.slot,
+                            failed: meta.err.is_some(),
+                            fee: meta.fee,
+                            attempt: 0,
+                        }
+                    }
+                    Some(_) => continue,
+                    None => anyhow::bail!("update not found in the message"),
+                };
+                sampled += 1;
+                pending.push(schedule(check, delay));
+            }
+        }
+    }
+
+    let total = outcomes.values().sum::<u64>();
+    let mut data = vec![("Checked".to_owned(), total.to_string())];
+    data.extend(
+        outcomes
+            .iter()
+            .map(|(outcome, count)| ((*outcome).to_owned(), count.to_string())),
+    );
+    print_query_result("RPC Comparison", &data);
+    anyhow::ensure!(
+        mismatches == 0,
+        "{mismatches} of {total} checks don't match RPC"
+    );
+    Ok(())
+}
+
+/// Whether to check one more update
+fn sample(verify: &ActionVerify, sampled: usize, pending: usize) -> bool {
+    sampled < verify.checks && pending < MAX_PENDING && rand::random_bool(verify.sample_rate)
+}
+
+fn commitment_name(commitment: CommitmentLevel) -> &'static str {
+    match commitment {
+        CommitmentLevel::Processed => "processed",
+        CommitmentLevel::Confirmed => "confirmed",
+        CommitmentLevel::Finalized => "finalized",
+    }
+}
+
+fn compare_transaction(check: &Check, response: &Value) -> (&'static str, Value) {
+    let Check::Transaction {
+        slot, failed, fee, ..
+    } = check
+    else {
+        unreachable!("transaction check");
+    };
+    if response.is_null() {
+        return ("missing", Value::Null);
+    }
+    let rpc_slot = response["slot"].as_u64();
+    let rpc_failed = !response["meta"]["err"].is_null();
+    let rpc_fee = response["meta"]["fee"].as_u64();
+    if rpc_slot != Some(*slot) {
+        ("slotMismatch", json!({ "stream": slot, "rpc": rpc_slot }))
+    } else if rpc_failed != *failed {
+        (
+            "statusMismatch",
+            json!({ "streamFailed": failed, "rpcErr": response["meta"]["err"] }),
+        )
+    } else if rpc_fee != Some(*fee) {
+        ("feeMismatch", json!({ "stream": fee, "rpc": rpc_fee }))
+    } else {
+        ("ok", Value::Null)
+    }
+}
+
+fn compare_account(
+    version: &AccountVersion,
+    response: &Value,
+    data_slices: &[SubscribeRequestAccountsDataSlice],
+) -> (&'static str, Value) {
+    let rpc_slot = response["context"]["slot"].as_u64().unwrap_or_default();
+    if rpc_slot < version.slot {
+        return (
+            "rpcBehind",
+            json!({ "stream": version.slot, "rpc": rpc_slot }),
+        );
+    }
+    let account = &response["value"];
+    if account.is_null() {
+        return if version.lamports == 0 {
+            ("ok", Value::Null)
+        } else {
+            ("missing", json!({ "streamLamports": version.lamports }))
+        };
+    }
+    let lamports = account["lamports"].as_u64();
+    let owner = account["owner"]
+        .as_str()
+        .and_then(|owner| owner.parse::<Pubkey>().ok());
+    let data = account["data"][0]
+        .as_str()
+        .and_then(|data| STANDARD.decode(data).ok())
+        .map(|data| {
+            if data_slices.is_empty() {
+                return data;
+            }
+            data_slices
+                .iter()
+                .flat_map(|slice| {
+                    let start = (slice.offset as usize).min(data.len());
+                    let end = (start + slice.length as usize).min(data.len());
+                    data[start..end].to_vec()
+                })
+                .collect()
+        });
+    let details = |stream: Value, rpc: Value| json!({ "stream": stream, "rpc": rpc, "streamSlot": version.slot, "rpcSlot": rpc_slot });
+    if lamports != Some(version.lamports) {
+        (
+            "lamportsMismatch",
+            details(json!(version.lamports), json!(lamports)),
+        )
+    } else if owner.map(|owner| owner.to_bytes().to_vec()) != Some(version.owner.clone()) {
+        (
+            "ownerMismatch",
+            details(
+                json!(bs58::encode(&version.owner).into_string()),
+                json!(owner.map(|owner| owner.to_string())),
+            ),
+        )
+    } else if account["executable"].as_bool() != Some(version.executable) {
+        (
+            "executableMismatch",
+            details(json!(version.executable), account["executable"].clone()),
+        )
+    } else if data.as_ref() != Some(&version.data) {
+        (
+            "dataMismatch",
+            details(
+                json!(version.data.len()),
+                json!(data.map(|data| data.len())),
+            ),
+        )
+    } else {
+        ("ok", Value::Null)
+    }
+}
@@ -0,0 +1,52 @@
+//! The `cuLimit` and `cuPriceMicroLamports` fields of transaction updates, read from their
+//! `SetComputeUnitLimit` and `SetComputeUnitPrice` instructions so consumers don't have to parse
+//! them.
+//!
+//! A field is `null` when the transaction doesn't set it: the runtime then allows 200k compute
+//! units per instruction (1.4M at most) and no priority fee is paid.
+
+use {solana_pubkey::Pubkey, yellowstone_grpc_proto::prelude::Message};
+
+const COMPUTE_BUDGET_PROGRAM: Pubkey =
+    Pubkey::from_str_const("ComputeBudget111111111111111111111111111111");
+
+const SET_COMPUTE_UNIT_LIMIT: u8 = 2;
+const SET_COMPUTE_UNIT_PRICE: u8 = 3;
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ComputeBudget {
+    pub limit: Option<u32>,
+    /// In micro-lamports per compute unit
+    pub price: Option<u64>,
+}
+
+/// Compute budget set by the top-level instructions of a transaction message
+pub fn from_message(message: Option<&Message>) -> ComputeBudget {
+    let Some(message) = message else {
+        return ComputeBudget::default();
+    };
+    parse(message.instructions.iter().filter_map(|ix| {
+        let program = message.account_keys.get(ix.program_id_index as usize)?;
+        Some((program.as_slice(), ix.data.as_slice()))
+    }))
+}
+
+/// Compute budget set by top-level instructions, given as program id and data
+pub fn parse<'a>(instructions: impl IntoIterator<Item = (&'a [u8], &'a [u8])>) -> ComputeBudget {
+    let mut budget = ComputeBudget::default();
+    for (program, data) in instructions {
+        if program != COMPUTE_BUDGET_PROGRAM.as_ref() {
+            continue;
+        }
+        match data.split_first() {
+            Some((&SET_COMPUTE_UNIT_LIMIT, units)) => {
+                budget.limit = units.try_into().ok().map(u32::from_le_bytes);
+            }
+            Some((&SET_COMPUTE_UNIT_PRICE, price)) => {
+                budget.price = price.try_into().ok().map(u64::from_le_bytes);
+            }
+            _ => {}
+        }
+    }
+    budget
+}
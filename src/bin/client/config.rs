@@ -0,0 +1,135 @@
+//! Every flag as an environment variable, to configure the client entirely through a container's
+//! environment, and `--print-config` to show the configuration in effect.
+//!
+//! A flag `--accounts-owner` is read from `SOLANA_INDEXER_ACCOUNTS_OWNER` when it isn't given on
+//! the command line; repeatable flags take `;` separated values, switches `true`/`false`. The
+//! subcommand itself is still an argument, e.g. a container's `CMD ["subscribe"]`. Flags win over
+//! the environment, which wins over a `--profile` and the legacy `GRPC_ENDPOINT`, `X_TOKEN` and
+//! `RPC_URL` variables.
+
+use {
+    crate::{Args, secrets},
+    clap::{
+        ArgAction, ArgMatches, Command, CommandFactory, FromArgMatches, ValueEnum,
+        parser::ValueSource,
+    },
+    std::sync::OnceLock,
+};
+
+const ENV_PREFIX: &str = "SOLANA_INDEXER_";
+
+/// Printed as `<redacted>` unless they're secret references
+const SECRETS: [&str; 2] = ["x_token", "encryption_key"];
+
+static MATCHES: OnceLock<ArgMatches> = OnceLock::new();
+
+/// Parse the command line and the environment, exiting with usage on errors
+pub fn parse() -> Args {
+    let matches = command().get_matches();
+    let args = Args::from_arg_matches(&matches)
+        .unwrap_or_else(|error| error.format(&mut command()).exit());
+    MATCHES.set(matches).expect("arguments are parsed once");
+    args
+}
+
+fn command() -> Command {
+    with_env(Args::command())
+}
+
+fn with_env(command: Command) -> Command {
+    command
+        .mut_args(|arg| {
+            let switch = matches!(arg.get_action(), ArgAction::SetTrue | ArgAction::SetFalse);
+            if arg.is_positional() || !(arg.get_action().takes_values() || switch) {
+                return arg;
+            }
+            let name = format!("{ENV_PREFIX}{}", arg.get_id().as_str().to_ascii_uppercase());
+            match arg.get_action() {
+                ArgAction::Append => arg.env(name).value_delimiter(';'),
+                _ => arg.env(name),
+            }
+        })
+        .mut_subcommands(with_env)
+}
+
+/// Print the configuration in effect as `NAME=value` lines by where the values come from
+pub fn print(args: &Args) {
+    let matches = MATCHES.get().expect("arguments parsed with config::parse");
+    let command = command();
+    let mut commands = vec![(&command, matches)];
+    if let Some((name, matches)) = matches.subcommand()
+        && let Some(subcommand) = command.find_subcommand(name)
+    {
+        println!("# subcommand: {name}");
+        commands.push((subcommand, matches));
+    }
+
+    let mut sources: [(&str, Vec<String>); 4] = [
+        ("command line", vec![]),
+        ("environment", vec![]),
+        ("profile or .env", vec![]),
+        ("defaults", vec![]),
+    ];
+    for (command, matches) in commands {
+        for arg in command.get_arguments() {
+            let Some(name) = arg.get_env() else {
+                continue;
+            };
+            let id = arg.get_id().as_str();
+            let value = matches.try_get_raw(id).ok().flatten().map(|values| {
+                values
+                    .map(|value| value.to_string_lossy().into_owned())
+                    .collect::<Vec<_>>()
+                    .join(";")
+            });
+            let merged = merged(args, id);
+            let (source, value) = match (value, merged) {
+                (value, Some(merged)) if value.as_ref() != Some(&merged) => (2, merged),
+                (Some(value), _) => match matches.value_source(id) {
+                    Some(ValueSource::CommandLine) => (0, value),
+                    Some(ValueSource::EnvVariable) => (1, value),
+                    _ => (3, value),
+                },
+                (None, _) => continue,
+            };
+            let value = if SECRETS.contains(&id) && !secrets::is_reference(&value) {
+                "<redacted>".to_owned()
+            } else {
+                value
+            };
+            sources[source]
+                .1
+                .push(format!("{}={value}", name.to_string_lossy()));
+        }
+    }
+    for (source, lines) in sources {
+        if !lines.is_empty() {
+            println!("# {source}");
+            for line in lines {
+                println!("{line}");
+            }
+        }
+    }
+}
+
+/// Values `run` merges from a profile or `.env` after parsing
+fn merged(args: &Args, id: &str) -> Option<String> {
+    match id {
+        "endpoint" => Some(args.endpoint.clone()),
+        "x_token" => Some(args.x_token.clone()),
+        "x_token_file" => args
+            .x_token_file
+            .as_ref()
+            .map(|path| path.display().to_string()),
+        "rpc_url" => Some(args.rpc_url.clone()),
+        "auth_scheme" => possible_value(&args.auth_scheme),
+        "commitment" => args.commitment.as_ref().and_then(possible_value),
+        _ => None,
+    }
+}
+
+fn possible_value(value: &impl ValueEnum) -> Option<String> {
+    value
+        .to_possible_value()
+        .map(|value| value.get_name().to_owned())
+}
@@ -0,0 +1,82 @@
+//! Local HTTP control API enabled with `--control-addr`.
+//!
+//! - `GET /metrics`: Prometheus metrics
+//! - `GET /pressure`: pressure score and its inputs as JSON, e.g. for a KEDA `metrics-api` scaler
+//! - `GET /account/<pubkey>`: latest indexed version of an account
+
+use {
+    crate::{metrics::Metrics, state::StateStore},
+    anyhow::Context,
+    axum::{
+        Json, Router,
+        extract::{Path, State},
+        http::StatusCode,
+        routing::get,
+    },
+    serde_json::{Value, json},
+    std::{net::SocketAddr, sync::Arc},
+    tokio::net::TcpListener,
+    tracing::{error, info},
+};
+
+#[derive(Clone)]
+struct ControlState {
+    metrics: Arc<Metrics>,
+    state: Arc<StateStore>,
+}
+
+/// Bind the control API and serve it in the background
+pub async fn spawn(
+    addr: SocketAddr,
+    metrics: Arc<Metrics>,
+    state: Arc<StateStore>,
+) -> anyhow::Result<()> {
+    let app = Router::new()
+        .route("/metrics", get(get_metrics))
+        .route("/pressure", get(get_pressure))
+        .route("/account/{pubkey}", get(get_account))
+        .with_state(ControlState { metrics, state });
+
+    let listener = TcpListener::bind(addr)
+        .await
+        .with_context(|| format!("failed to bind control API on {addr}"))?;
+    info!("control API listening on http://{addr}");
+    tokio::spawn(async move {
+        if let Err(error) = axum::serve(listener, app).await {
+            error!("control API failed: {error}");
+        }
+    });
+    Ok(())
+}
+
+async fn get_metrics(State(state): State<ControlState>) -> String {
+    let mut text = state.metrics.snapshot().to_prometheus();
+    text.push_str(&format!(
+        "# HELP indexer_state_accounts Accounts held in the state store\n\
+         # TYPE indexer_state_accounts gauge\n\
+         indexer_state_accounts {}\n\
+         # HELP indexer_state_bytes Approximate memory held by the state store\n\
+         # TYPE indexer_state_bytes gauge\n\
+         indexer_state_bytes {}\n",
+        state.state.len(),
+        state.state.bytes(),
+    ));
+    text
+}
+
+async fn get_pressure(State(state): State<ControlState>) -> Json<Value> {
+    Json(state.metrics.snapshot().to_json())
+}
+
+async fn get_account(
+    State(state): State<ControlState>,
+    Path(pubkey): Path<String>,
+) -> (StatusCode, Json<Value>) {
+    match state.state.get(&pubkey) {
+        Some(account) => (StatusCode::OK, Json(account)),
+        None => (
+            StatusCode::NOT_FOUND,
+            Json(json!({ "error": format!("account {pubkey} is not indexed") })),
+        ),
+    }
+}
@@ -0,0 +1,82 @@
+//! `--encryption-key`: AES-256-GCM encryption of `--out-file` lines and `--capture` frames, so
+//! archives can sit on shared storage.
+//!
+//! Every payload is sealed on its own with a random nonce, stored as `nonce || ciphertext ||
+//! tag`. Out-file lines hold the sealed line in base64, so files stay line-delimited and
+//! rotate as before; capture frames are sealed records. Slot indexes are left in the clear.
+
+use {
+    crate::secrets,
+    anyhow::Context,
+    base64::{Engine, engine::general_purpose::STANDARD},
+    ring::{
+        aead::{AES_256_GCM, Aad, LessSafeKey, NONCE_LEN, Nonce, UnboundKey},
+        rand::{SecureRandom, SystemRandom},
+    },
+};
+
+pub struct Cipher {
+    key: LessSafeKey,
+    random: SystemRandom,
+}
+
+impl Cipher {
+    /// Key from 64 hex characters, or a secret reference to them
+    pub async fn resolve(key: &str) -> anyhow::Result<Self> {
+        let key = hex::decode(secrets::resolve(key).await?.trim())
+            .context("encryption key is not hex")?;
+        let key = UnboundKey::new(&AES_256_GCM, &key)
+            .ok()
+            .context("encryption key must be 32 bytes (64 hex characters)")?;
+        Ok(Self {
+            key: LessSafeKey::new(key),
+            random: SystemRandom::new(),
+        })
+    }
+
+    pub fn seal(&self, plaintext: &[u8]) -> anyhow::Result<Vec<u8>> {
+        let mut nonce = [0; NONCE_LEN];
+        self.random
+            .fill(&mut nonce)
+            .ok()
+            .context("failed to generate a nonce")?;
+        let mut sealed = Vec::with_capacity(NONCE_LEN + plaintext.len() + AES_256_GCM.tag_len());
+        sealed.extend_from_slice(&nonce);
+        let mut in_out = plaintext.to_vec();
+        self.key
+            .seal_in_place_append_tag(
+                Nonce::assume_unique_for_key(nonce),
+                Aad::empty(),
+                &mut in_out,
+            )
+            .ok()
+            .context("failed to encrypt")?;
+        sealed.extend_from_slice(&in_out);
+        Ok(sealed)
+    }
+
+    pub fn open(&self, sealed: &[u8]) -> anyhow::Result<Vec<u8>> {
+        anyhow::ensure!(sealed.len() >= NONCE_LEN, "encrypted payload is truncated");
+        let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+        let nonce = Nonce::try_assume_unique_for_key(nonce).expect("nonce length");
+        let mut in_out = ciphertext.to_vec();
+        let plaintext = self
+            .key
+            .open_in_place(nonce, Aad::empty(), &mut in_out)
+            .ok()
+            .context("failed to decrypt, wrong key or corrupted payload")?;
+        Ok(plaintext.to_vec())
+    }
+
+    /// Seal a line of text, base64-encoded so it stays a line
+    pub fn seal_line(&self, line: &str) -> anyhow::Result<String> {
+        Ok(STANDARD.encode(self.seal(line.as_bytes())?))
+    }
+
+    pub fn open_line(&self, line: &str) -> anyhow::Result<String> {
+        let sealed = STANDARD
+            .decode(line.trim())
+            .context("encrypted line is not base64")?;
+        String::from_utf8(self.open(&sealed)?).context("decrypted line is not UTF-8")
+    }
+}
@@ -0,0 +1,245 @@
+//! `--daemon`, `--pid-file` and `--log-file`: run unattended.
+//!
+//! `--daemon` detaches from the terminal (Unix only, Windows services use the WinSW wrapper in
+//! `packaging/windows`): stdin, stdout and stderr go to `/dev/null`, so updates should go to
+//! `--out-file` or a `--sink`, and the working directory is kept for relative paths.
+//! `--pid-file` is written once detached and removed at exit, a pid file of a running process
+//! is refused. `--log-file` replaces stderr logging, rotating the file at `--log-max-size` and
+//! keeping `--log-keep` numbered archives (`client.log.1` is the newest).
+//!
+//! SIGHUP reopens the log file after an external rotation, SIGTERM shuts down like Ctrl-C:
+//! the session summary is written and the process exits with 0.
+
+use {
+    crate::Args,
+    anyhow::Context,
+    std::{
+        fs::{self, File, OpenOptions},
+        io::{self, Write},
+        path::{Path, PathBuf},
+        sync::{Arc, Mutex},
+    },
+    tracing::info,
+};
+
+/// What has to live until exit
+pub struct Daemon {
+    _pid_file: Option<PidFile>,
+    log_file: Option<LogFile>,
+}
+
+/// Detach, write the pid file and open the log file. Forking is only sound while the process has
+/// a single thread, so this runs before the runtime starts.
+pub fn start(args: &Args) -> anyhow::Result<Daemon> {
+    if args.daemon {
+        detach()?;
+    }
+    let pid_file = args.pid_file.as_deref().map(PidFile::create).transpose()?;
+    let log_file = args
+        .log_file
+        .as_deref()
+        .map(|path| LogFile::open(path, args.log_max_size, args.log_keep))
+        .transpose()?;
+    Ok(Daemon {
+        _pid_file: pid_file,
+        log_file,
+    })
+}
+
+impl Daemon {
+    /// Reopen the log file on SIGHUP
+    pub fn spawn_reload(&self) {
+        #[cfg(unix)]
+        if let Some(log_file) = self.log_file.clone() {
+            tokio::spawn(async move {
+                use tokio::signal::unix::{SignalKind, signal};
+                let Ok(mut hangup) = signal(SignalKind::hangup()) else {
+                    tracing::warn!("failed to listen for SIGHUP");
+                    return;
+                };
+                while hangup.recv().await.is_some() {
+                    match log_file.reopen() {
+                        Ok(()) => info!("log file reopened"),
+                        Err(error) => eprintln!("failed to reopen the log file: {error:#}"),
+                    }
+                }
+            });
+        }
+    }
+
+    /// Where logs go instead of stderr
+    pub fn log_file(&self) -> Option<LogFile> {
+        self.log_file.clone()
+    }
+
+    /// Whether errors have to be logged, stderr isn't read
+    pub const fn logs_to_file(&self) -> bool {
+        self.log_file.is_some()
+    }
+}
+
+/// Resolves on SIGTERM, never on platforms without it
+pub async fn terminated() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{SignalKind, signal};
+        if let Ok(mut terminate) = signal(SignalKind::terminate()) {
+            terminate.recv().await;
+            return;
+        }
+    }
+    std::future::pending().await
+}
+
+#[cfg(unix)]
+fn detach() -> anyhow::Result<()> {
+    use std::os::fd::AsRawFd;
+
+    // Fork twice with a new session in between, so the daemon can't reacquire a terminal
+    for step in 0..2 {
+        match unsafe { libc::fork() } {
+            -1 => return Err(io::Error::last_os_error()).context("failed to fork"),
+            0 => {}
+            // The parent exits without running destructors or flushing stdio twice
+            _ => unsafe { libc::_exit(0) },
+        }
+        if step == 0 && unsafe { libc::setsid() } == -1 {
+            return Err(io::Error::last_os_error()).context("failed to start a new session");
+        }
+    }
+    let null = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open("/dev/null")
+        .context("failed to open /dev/null")?;
+    for fd in 0..=2 {
+        if unsafe { libc::dup2(null.as_raw_fd(), fd) } == -1 {
+            return Err(io::Error::last_os_error()).context("failed to redirect stdio");
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn detach() -> anyhow::Result<()> {
+    anyhow::bail!(
+        "--daemon is only supported on Unix, install a Windows service with \
+         packaging/windows/install-service.ps1 instead"
+    )
+}
+
+struct PidFile(PathBuf);
+
+impl PidFile {
+    fn create(path: &Path) -> anyhow::Result<Self> {
+        if let Some(pid) = fs::read_to_string(path)
+            .ok()
+            .and_then(|pid| pid.trim().parse::<u32>().ok())
+        {
+            anyhow::ensure!(
+                !is_running(pid),
+                "{} belongs to running process {pid}",
+                path.display()
+            );
+        }
+        fs::write(path, format!("{}\n", std::process::id()))
+            .with_context(|| format!("failed to write {}", path.display()))?;
+        Ok(Self(path.to_owned()))
+    }
+}
+
+impl Drop for PidFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.0);
+    }
+}
+
+#[cfg(unix)]
+fn is_running(pid: u32) -> bool {
+    i32::try_from(pid).is_ok_and(|pid| unsafe { libc::kill(pid, 0) } == 0)
+}
+
+#[cfg(not(unix))]
+fn is_running(_pid: u32) -> bool {
+    false
+}
+
+/// Log file rotated by size, shared by the logger and the SIGHUP handler
+#[derive(Clone)]
+pub struct LogFile(Arc<Mutex<LogFileState>>);
+
+struct LogFileState {
+    path: PathBuf,
+    file: File,
+    size: u64,
+    max_size: u64,
+    keep: usize,
+}
+
+impl LogFile {
+    fn open(path: &Path, max_size: u64, keep: usize) -> anyhow::Result<Self> {
+        let file = open_append(path)?;
+        let size = file.metadata()?.len();
+        Ok(Self(Arc::new(Mutex::new(LogFileState {
+            path: path.to_owned(),
+            file,
+            size,
+            max_size,
+            keep,
+        }))))
+    }
+
+    fn reopen(&self) -> anyhow::Result<()> {
+        let mut state = self.0.lock().expect("log file lock poisoned");
+        state.file = open_append(&state.path)?;
+        state.size = state.file.metadata()?.len();
+        Ok(())
+    }
+}
+
+impl LogFileState {
+    /// Shift `<path>.<n>` archives up, dropping the oldest, and start a new file
+    fn rotate(&mut self) -> io::Result<()> {
+        let archive = |index: usize| {
+            let mut path = self.path.clone().into_os_string();
+            path.push(format!(".{index}"));
+            PathBuf::from(path)
+        };
+        if self.keep > 0 {
+            for index in (1..self.keep).rev() {
+                let from = archive(index);
+                if from.exists() {
+                    fs::rename(&from, archive(index + 1))?;
+                }
+            }
+            fs::rename(&self.path, archive(1))?;
+        }
+        self.file = File::create(&self.path)?;
+        self.size = 0;
+        Ok(())
+    }
+}
+
+impl Write for LogFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut state = self.0.lock().expect("log file lock poisoned");
+        if state.size > 0 && state.size + buf.len() as u64 > state.max_size {
+            state.rotate()?;
+        }
+        let written = state.file.write(buf)?;
+        state.size += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.lock().expect("log file lock poisoned").file.flush()
+    }
+}
+
+fn open_append(path: &Path) -> anyhow::Result<File> {
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("failed to open {}", path.display()))
+}
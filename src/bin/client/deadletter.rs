@@ -0,0 +1,58 @@
+//! `--dead-letter-dir`: keep updates that fail to decode for later inspection instead of
+//! stopping the stream on them.
+//!
+//! Every failed update is written as `<received unix ms>-<seq>.pb` (the `SubscribeUpdate`
+//! protobuf, decodable with `prost` or `protoc --decode`) next to a `.json` file with the error.
+
+use {
+    anyhow::Context,
+    serde_json::json,
+    std::{
+        fs,
+        path::PathBuf,
+        time::{SystemTime, UNIX_EPOCH},
+    },
+    tracing::warn,
+};
+
+/// An update whose decode failed, with its encoding when dead letters are kept
+pub struct DecodeFailure {
+    pub error: anyhow::Error,
+    pub raw: Option<Vec<u8>>,
+}
+
+pub struct DeadLetters {
+    dir: PathBuf,
+    seq: u64,
+}
+
+impl DeadLetters {
+    pub fn new(dir: PathBuf) -> anyhow::Result<Self> {
+        fs::create_dir_all(&dir)
+            .with_context(|| format!("failed to create dead letter directory {}", dir.display()))?;
+        Ok(Self { dir, seq: 0 })
+    }
+
+    pub fn write(&mut self, raw: &[u8], error: &anyhow::Error) -> anyhow::Result<()> {
+        let received_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis();
+        let name = format!("{received_at}-{:06}", self.seq);
+        self.seq += 1;
+
+        let path = self.dir.join(format!("{name}.pb"));
+        fs::write(&path, raw).with_context(|| format!("failed to write {}", path.display()))?;
+        let context = json!({
+            "error": format!("{error:#}"),
+            "receivedAt": received_at,
+            "bytes": raw.len(),
+        });
+        let path = self.dir.join(format!("{name}.json"));
+        fs::write(&path, serde_json::to_vec_pretty(&context)?)
+            .with_context(|| format!("failed to write {}", path.display()))?;
+
+        warn!("update failed to decode ({error:#}), written to {name}.pb");
+        Ok(())
+    }
+}
@@ -0,0 +1,120 @@
+//! `--dedup-window-slots`: drop updates received twice, e.g. resent by a provider after an
+//! internal failover.
+//!
+//! Updates are keyed like `--checkpoint-file` does, by slot, pubkey and write version for
+//! accounts and by slot and signature for transactions; updates without a key are always
+//! written. The keys of the last `--dedup-window-slots` slots up to the highest slot seen are
+//! kept, older slots are evicted and their updates pass. The window is kept across reconnects
+//! and failovers, so what a resumed subscription receives again is dropped too.
+
+use {
+    crate::checkpoint::Position,
+    serde_json::Value,
+    std::collections::{BTreeMap, HashSet},
+};
+
+pub struct Dedup {
+    window: u64,
+    slots: BTreeMap<u64, HashSet<Position>>,
+}
+
+impl Dedup {
+    pub fn new(window: u64) -> Self {
+        Self {
+            window,
+            slots: BTreeMap::new(),
+        }
+    }
+
+    /// Whether an update was received already within the window, recording it otherwise
+    pub fn is_duplicate(&mut self, kind: &str, value: &Value) -> bool {
+        let Some(position) = Position::of(kind, value).filter(Position::is_keyed) else {
+            return false;
+        };
+        let slot = position.slot();
+        let highest = self
+            .slots
+            .last_key_value()
+            .map_or(slot, |(highest, _)| slot.max(*highest));
+        let oldest = (highest + 1).saturating_sub(self.window);
+        if slot < oldest {
+            return false;
+        }
+        if !self.slots.entry(slot).or_default().insert(position) {
+            return true;
+        }
+        while self
+            .slots
+            .first_key_value()
+            .is_some_and(|(slot, _)| *slot < oldest)
+        {
+            self.slots.pop_first();
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, serde_json::json};
+
+    fn transaction(slot: u64, signature: &str) -> Value {
+        json!({"slot": slot, "signature": signature})
+    }
+
+    #[test]
+    fn duplicates() {
+        let mut dedup = Dedup::new(10);
+        assert!(!dedup.is_duplicate("transaction", &transaction(5, "a")));
+        assert!(dedup.is_duplicate("transaction", &transaction(5, "a")));
+        assert!(!dedup.is_duplicate("transaction", &transaction(5, "b")));
+        assert!(!dedup.is_duplicate("transaction", &transaction(6, "a")));
+        assert!(!dedup.is_duplicate("transactionStatus", &transaction(5, "a")));
+
+        let account = |version| json!({"slot": 5, "pubkey": "p", "writeVersion": version});
+        assert!(!dedup.is_duplicate("account", &account(1)));
+        assert!(!dedup.is_duplicate("account", &account(2)));
+        assert!(dedup.is_duplicate("account", &account(2)));
+    }
+
+    #[test]
+    fn unkeyed() {
+        let mut dedup = Dedup::new(10);
+        let update = json!({"slot": 5, "data": 1});
+        assert!(!dedup.is_duplicate("unknown", &update));
+        assert!(!dedup.is_duplicate("unknown", &update));
+        let update = json!({"signature": "a"});
+        assert!(!dedup.is_duplicate("transaction", &update));
+        assert!(!dedup.is_duplicate("transaction", &update));
+    }
+
+    #[test]
+    fn window() {
+        let mut dedup = Dedup::new(3);
+        assert!(!dedup.is_duplicate("transaction", &transaction(10, "a")));
+        assert!(!dedup.is_duplicate("transaction", &transaction(12, "b")));
+        // Slots 10..=12 are within the window
+        assert!(dedup.is_duplicate("transaction", &transaction(10, "a")));
+        assert!(!dedup.is_duplicate("transaction", &transaction(13, "c")));
+        // Slot 10 fell out of the window, its updates pass and aren't recorded
+        assert!(!dedup.is_duplicate("transaction", &transaction(10, "a")));
+        assert!(!dedup.is_duplicate("transaction", &transaction(10, "a")));
+        assert!(dedup.is_duplicate("transaction", &transaction(12, "b")));
+        assert_eq!(dedup.slots.keys().copied().collect::<Vec<_>>(), [12, 13]);
+    }
+
+    #[test]
+    fn eviction() {
+        let mut dedup = Dedup::new(2);
+        for slot in 0..100 {
+            assert!(!dedup.is_duplicate("transaction", &transaction(slot, "a")));
+        }
+        assert_eq!(dedup.slots.keys().copied().collect::<Vec<_>>(), [98, 99]);
+        // An older slot within the window evicts nothing
+        let mut dedup = Dedup::new(5);
+        assert!(!dedup.is_duplicate("transaction", &transaction(20, "a")));
+        assert!(!dedup.is_duplicate("transaction", &transaction(17, "a")));
+        assert!(dedup.is_duplicate("transaction", &transaction(17, "a")));
+        assert_eq!(dedup.slots.keys().copied().collect::<Vec<_>>(), [17, 20]);
+    }
+}
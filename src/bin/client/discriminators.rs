@@ -0,0 +1,201 @@
+//! `--instruction-stats <program[:bytes]>`: how often each instruction of a program is invoked
+//! over a sliding `--instruction-stats-window`, by discriminator, for protocol teams watching
+//! which of their instructions are hot without decoding them.
+//!
+//! The discriminator is the first bytes of the instruction data: 8 by default (the Anchor
+//! sighash), or as many as given for the program, e.g. `1` for SPL Token or `4` for the System
+//! program. Top-level and inner instructions are counted, of failed transactions too. The window
+//! slides by [`SLICES`]ths: an `instructionStats` event is written as each slice ends, and the
+//! counts are shown by `/metrics` and `/pressure`. Past [`MAX_DISCRIMINATORS`] per program,
+//! new discriminators are counted as `other`.
+
+use {
+    crate::instructions,
+    serde_json::{Value, json},
+    solana_pubkey::Pubkey,
+    std::{
+        collections::{BTreeMap, HashMap, HashSet, VecDeque},
+        time::{Duration, Instant},
+    },
+    tracing::debug,
+    yellowstone_grpc_proto::prelude::{SubscribeRequest, SubscribeRequestFilterTransactions},
+};
+
+/// Transactions filter added to the request when transactions aren't subscribed already
+pub const FILTER: &str = "instruction-stats";
+
+/// Slices of the window, an event is written as each ends
+pub const SLICES: u32 = 6;
+
+/// Distinct discriminators counted per program
+const MAX_DISCRIMINATORS: usize = 256;
+
+/// Discriminator length of programs given without one, the Anchor sighash
+const DEFAULT_BYTES: usize = 8;
+
+/// Parse `--instruction-stats`, format: `program[:bytes]`
+pub fn parse_program(value: &str) -> Result<(Pubkey, usize), String> {
+    let (program, bytes) = match value.split_once(':') {
+        Some((program, bytes)) => (
+            program,
+            bytes
+                .parse::<usize>()
+                .ok()
+                .filter(|bytes| *bytes > 0)
+                .ok_or_else(|| format!("invalid discriminator length `{bytes}`"))?,
+        ),
+        None => (value, DEFAULT_BYTES),
+    };
+    let program = program
+        .parse()
+        .map_err(|_| format!("invalid program id `{program}`"))?;
+    Ok((program, bytes))
+}
+
+/// Invocations of an instruction over the window
+#[derive(Debug, Clone)]
+pub struct Sample {
+    pub program: String,
+    pub discriminator: String,
+    pub invocations: u64,
+    pub failed: u64,
+}
+
+#[derive(Default, Clone, Copy)]
+struct Count {
+    invocations: u64,
+    failed: u64,
+}
+
+type Counts = HashMap<(Pubkey, String), Count>;
+
+pub struct InstructionStats {
+    /// Discriminator length by program
+    programs: HashMap<Pubkey, usize>,
+    slice: Duration,
+    started_at: Instant,
+    /// Counts of the last slices, oldest first
+    slices: VecDeque<Counts>,
+    current: Counts,
+    discriminators: HashMap<Pubkey, HashSet<String>>,
+}
+
+impl InstructionStats {
+    /// Count the instructions of `programs`, subscribing to their transactions if needed
+    pub fn new(
+        programs: &[(Pubkey, usize)],
+        window: Duration,
+        request: &mut SubscribeRequest,
+    ) -> Self {
+        if request.transactions.is_empty() {
+            request.transactions.insert(
+                FILTER.to_owned(),
+                SubscribeRequestFilterTransactions {
+                    vote: Some(false),
+                    account_include: programs
+                        .iter()
+                        .map(|(program, _)| program.to_string())
+                        .collect(),
+                    ..Default::default()
+                },
+            );
+        }
+        Self {
+            programs: programs.iter().copied().collect(),
+            slice: window / SLICES,
+            started_at: Instant::now(),
+            slices: VecDeque::new(),
+            current: HashMap::new(),
+            discriminators: HashMap::new(),
+        }
+    }
+
+    pub fn observe(&mut self, kind: &str, value: &Value) {
+        if kind != "transaction" {
+            return;
+        }
+        let instructions = match instructions::parse_all(value) {
+            Ok(instructions) => instructions,
+            Err(error) => {
+                debug!("failed to parse transaction instructions: {error:#}");
+                return;
+            }
+        };
+        let failed = !value["tx"]["meta"]["err"].is_null();
+        for ix in instructions {
+            let Some(bytes) = self.programs.get(&ix.program_id) else {
+                continue;
+            };
+            let mut discriminator = hex::encode(&ix.data[..ix.data.len().min(*bytes)]);
+            let known = self.discriminators.entry(ix.program_id).or_default();
+            if !known.contains(&discriminator) {
+                if known.len() < MAX_DISCRIMINATORS {
+                    known.insert(discriminator.clone());
+                } else {
+                    discriminator = "other".to_owned();
+                }
+            }
+            let count = self
+                .current
+                .entry((ix.program_id, discriminator))
+                .or_default();
+            count.invocations += 1;
+            count.failed += u64::from(failed);
+        }
+    }
+
+    /// The counts over the window as a slice ends, for the event and the metrics
+    pub fn due(&mut self) -> Option<(Value, Vec<Sample>)> {
+        if self.started_at.elapsed() < self.slice {
+            return None;
+        }
+        self.started_at = Instant::now();
+        self.slices.push_back(std::mem::take(&mut self.current));
+        if self.slices.len() > SLICES as usize {
+            self.slices.pop_front();
+        }
+
+        let mut totals = BTreeMap::<(String, String), Count>::new();
+        for ((program, discriminator), count) in self.slices.iter().flatten() {
+            let total = totals
+                .entry((program.to_string(), discriminator.clone()))
+                .or_default();
+            total.invocations += count.invocations;
+            total.failed += count.failed;
+        }
+        let mut samples = totals
+            .into_iter()
+            .map(|((program, discriminator), count)| Sample {
+                program,
+                discriminator,
+                invocations: count.invocations,
+                failed: count.failed,
+            })
+            .collect::<Vec<_>>();
+        samples.sort_by(|a, b| {
+            a.program
+                .cmp(&b.program)
+                .then(b.invocations.cmp(&a.invocations))
+        });
+
+        let mut programs = BTreeMap::<&str, Vec<Value>>::new();
+        for sample in &samples {
+            programs.entry(&sample.program).or_default().push(json!({
+                "discriminator": sample.discriminator,
+                "invocations": sample.invocations,
+                "failed": sample.failed,
+            }));
+        }
+        let programs = programs
+            .into_iter()
+            .map(|(program, instructions)| {
+                json!({ "programId": program, "instructions": instructions })
+            })
+            .collect::<Vec<_>>();
+        let event = json!({
+            "windowSecs": (self.slice * self.slices.len() as u32).as_secs_f64(),
+            "programs": programs,
+        });
+        Some((event, samples))
+    }
+}
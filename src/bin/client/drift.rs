@@ -0,0 +1,89 @@
+//! `--schema-drift-learn-secs`: alert when an account layout shows up that wasn't seen while
+//! learning, e.g. after a program upgrade changed account sizes or discriminators.
+//!
+//! A layout is the data length and the first 8 bytes (the Anchor discriminator) per owner
+//! program. Each new layout is reported once, with sample data.
+
+use {
+    crate::print_update,
+    tracing::warn,
+    serde_json::{Value, json},
+    std::{
+        collections::{HashMap, HashSet},
+        time::{Duration, Instant, SystemTime},
+    },
+};
+
+const DISCRIMINATOR_LEN: usize = 8;
+/// Bytes of account data included in an alert
+const SAMPLE_LEN: usize = 64;
+/// Known layouts listed in an alert
+const KNOWN_LAYOUTS_LIMIT: usize = 8;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct Layout {
+    len: usize,
+    discriminator: String,
+}
+
+pub struct DriftDetector {
+    learn_until: Instant,
+    layouts: HashMap<String, HashSet<Layout>>,
+}
+
+impl DriftDetector {
+    pub fn new(learn: Duration) -> Self {
+        Self {
+            learn_until: Instant::now() + learn,
+            layouts: HashMap::new(),
+        }
+    }
+
+    pub fn observe(&mut self, value: &Value) {
+        let (Some(owner), Some(data)) = (
+            value.get("owner").and_then(Value::as_str),
+            value.get("data").and_then(Value::as_str),
+        ) else {
+            return;
+        };
+        // Data is hex encoded
+        let layout = Layout {
+            len: data.len() / 2,
+            discriminator: data[..data.len().min(DISCRIMINATOR_LEN * 2)].to_owned(),
+        };
+
+        let learning = Instant::now() < self.learn_until;
+        let known = self.layouts.entry(owner.to_owned()).or_default();
+        if known.contains(&layout) {
+            return;
+        }
+        if !learning {
+            warn!(
+                "schema drift: {owner} account {} has unknown layout ({} bytes, discriminator {})",
+                value.get("pubkey").and_then(serde_json::Value::as_str).unwrap_or_default(),
+                layout.len,
+                layout.discriminator,
+            );
+            let alert = json!({
+                "owner": owner,
+                "pubkey": value.get("pubkey"),
+                "slot": value.get("slot"),
+                "dataLen": layout.len,
+                "discriminator": layout.discriminator,
+                "knownLayouts": known
+                    .iter()
+                    .take(KNOWN_LAYOUTS_LIMIT)
+                    .map(|layout| json!({"dataLen": layout.len, "discriminator": layout.discriminator}))
+                    .collect::<Vec<_>>(),
+                "sample": &data[..data.len().min(SAMPLE_LEN * 2)],
+            });
+            print_update(
+                "schemaDrift",
+                SystemTime::now(),
+                &["schema-drift".to_owned()],
+                alert,
+            );
+        }
+        known.insert(layout);
+    }
+}
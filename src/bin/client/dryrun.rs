@@ -0,0 +1,121 @@
+//! `subscribe --dry-run`: print the subscribe request as JSON and exit without connecting, to
+//! debug how flags compose into filters and to share a subscription.
+//!
+//! The request is printed as sent, including the filters features add on their own (events,
+//! watermarks, commit boundaries, ...) and the `--checkpoint-file` resume slot, in the format of
+//! `--export-request`. `cost` counts filters and the keys they match on, which is
+//! what providers limit and price subscriptions by.
+
+use {
+    crate::{
+        ActionSubscribe, aggregate, checkpoint, fees, mints, network, programs, reassemble,
+        request, slotbuffer::SlotBuffer, votes, watchlist, watermark,
+    },
+    serde_json::{Value, json},
+    std::time::Duration,
+    yellowstone_grpc_proto::prelude::SubscribeRequest,
+};
+
+pub fn run(args: &ActionSubscribe, mut request: SubscribeRequest) -> anyhow::Result<()> {
+    add_feature_filters(args, &mut request)?;
+    let output = json!({
+        "request": request::to_json(&request),
+        "cost": cost(&request),
+    });
+    println!("{}", serde_json::to_string_pretty(&output)?);
+    Ok(())
+}
+
+/// The filters `geyser_subscribe` adds for features, raw frame subscriptions (latency, stats,
+/// captures) are sent as built
+fn add_feature_filters(
+    args: &ActionSubscribe,
+    request: &mut SubscribeRequest,
+) -> anyhow::Result<()> {
+    if args.latency || args.capture.is_some() || (args.stats && !args.verify_encoding) {
+        return Ok(());
+    }
+    if let Some(slot) = args
+        .checkpoint_file
+        .clone()
+        .map(checkpoint::Checkpoint::load)
+        .transpose()?
+        .as_ref()
+        .and_then(checkpoint::Checkpoint::slot)
+    {
+        request.from_slot = request.from_slot.max(Some(slot));
+    }
+    if let Some(interval) = args.watermark_interval {
+        watermark::Watermark::new(interval, request);
+    }
+    if let Some(boundary) = args.commit_boundary {
+        SlotBuffer::<()>::new(boundary.into(), request);
+    }
+    if let Some(commitment) = args.out_file_commitment {
+        SlotBuffer::<()>::new(commitment.into(), request);
+    }
+    if args.reassemble_blocks {
+        reassemble::Reassembler::new(request)?;
+    }
+    if let Some(interval) = args.vote_stats_interval_secs {
+        votes::VoteStats::new(Duration::from_secs(interval), request);
+    }
+    if let Some(aggregation) = args.aggregate {
+        aggregate::Aggregator::new(aggregation, args.window, args.top, request);
+    }
+    if args.fee_stats {
+        fees::FeeStats::new(request);
+    }
+    if args.program_changes {
+        programs::subscribe(request);
+    }
+    if args.new_mints {
+        mints::subscribe(request);
+    }
+    if args.network_stats {
+        network::NetworkStats::subscribe(request);
+    }
+    if let Some(path) = &args.watchlist {
+        watchlist::Watchlist::load(path)?.subscribe(request);
+    }
+    Ok(())
+}
+
+fn cost(request: &SubscribeRequest) -> Value {
+    let filters = json!({
+        "accounts": request.accounts.len(),
+        "slots": request.slots.len(),
+        "transactions": request.transactions.len(),
+        "transactionsStatus": request.transactions_status.len(),
+        "blocks": request.blocks.len(),
+        "blocksMeta": request.blocks_meta.len(),
+        "entry": request.entry.len(),
+    });
+    let total = request.accounts.len()
+        + request.slots.len()
+        + request.transactions.len()
+        + request.transactions_status.len()
+        + request.blocks.len()
+        + request.blocks_meta.len()
+        + request.entry.len();
+    let transaction_keys = request
+        .transactions
+        .values()
+        .chain(request.transactions_status.values())
+        .map(|filter| {
+            filter.account_include.len()
+                + filter.account_exclude.len()
+                + filter.account_required.len()
+        })
+        .sum::<usize>();
+    json!({
+        "filters": filters,
+        "totalFilters": total,
+        "accountKeys": request.accounts.values().map(|filter| filter.account.len()).sum::<usize>(),
+        "ownerKeys": request.accounts.values().map(|filter| filter.owner.len()).sum::<usize>(),
+        "accountDataFilters": request.accounts.values().map(|filter| filter.filters.len()).sum::<usize>(),
+        "transactionAccountKeys": transaction_keys,
+        "blockAccountKeys": request.blocks.values().map(|filter| filter.account_include.len()).sum::<usize>(),
+        "accountsDataSlices": request.accounts_data_slice.len(),
+    })
+}
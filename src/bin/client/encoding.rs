@@ -0,0 +1,77 @@
+//! `--data-encoding` and `--max-data-bytes`: how account data is printed, written to
+//! `--out-file` and sinked.
+//!
+//! Updates are decoded with hex data, which client side filters, enrichments and the state
+//! store work on; the data is re-encoded (or dropped with `none`) only on the way out. Truncated
+//! or dropped data gets a `dataLen` with its full length in bytes.
+
+use {
+    base64::{Engine, engine::general_purpose::STANDARD},
+    clap::ValueEnum,
+    serde_json::{Value, json},
+};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum DataEncoding {
+    /// As decoded
+    #[default]
+    Hex,
+    Base64,
+    Base58,
+    /// Drop account data, to save storage
+    None,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DataOutput {
+    pub encoding: DataEncoding,
+    pub max_bytes: Option<usize>,
+}
+
+impl DataOutput {
+    /// Whether updates are written as decoded
+    pub fn is_identity(&self) -> bool {
+        self.encoding == DataEncoding::Hex && self.max_bytes.is_none()
+    }
+
+    /// Re-encode the data of an account update, or of the accounts of a block or block chunk
+    pub fn apply(&self, kind: &str, value: &mut Value) {
+        let accounts = match kind {
+            "account" => return self.apply_account(value),
+            "block" => value.get_mut("accounts"),
+            "blockChunk" if value["part"] == "accounts" => value.get_mut("items"),
+            _ => None,
+        };
+        if let Some(accounts) = accounts.and_then(Value::as_array_mut) {
+            accounts
+                .iter_mut()
+                .for_each(|account| self.apply_account(account));
+        }
+    }
+
+    fn apply_account(&self, account: &mut Value) {
+        let Some(object) = account.as_object_mut() else {
+            return;
+        };
+        let Some(data) = object
+            .get("data")
+            .and_then(Value::as_str)
+            .and_then(|data| hex::decode(data).ok())
+        else {
+            return;
+        };
+        let len = data.len();
+        let kept = &data[..self.max_bytes.map_or(len, |max| max.min(len))];
+        match self.encoding {
+            DataEncoding::Hex => object.insert("data".to_owned(), json!(hex::encode(kept))),
+            DataEncoding::Base64 => object.insert("data".to_owned(), json!(STANDARD.encode(kept))),
+            DataEncoding::Base58 => {
+                object.insert("data".to_owned(), json!(bs58::encode(kept).into_string()))
+            }
+            DataEncoding::None => object.remove("data"),
+        };
+        if self.encoding == DataEncoding::None || kept.len() < len {
+            object.insert("dataLen".to_owned(), json!(len));
+        }
+    }
+}
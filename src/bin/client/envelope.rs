@@ -0,0 +1,62 @@
+//! `--envelope debezium`: account changes as Debezium-style change events, one JSON object per
+//! line, so CDC tooling (Materialize, Flink, Kafka Connect sinks) can consume them directly.
+//! Other updates, events and watermarks are printed as the JSON lines `--out-file` writes, so
+//! stdout stays one JSON object per line.
+
+use {
+    crate::schema,
+    serde_json::{Value, json},
+    std::{
+        io::{self, Write},
+        time::{SystemTime, UNIX_EPOCH},
+    },
+};
+
+/// Build a change event, `before` is the previously indexed version of the account
+pub fn debezium(before: Option<Value>, after: Value, created_at: SystemTime, snapshot: bool) -> Value {
+    // Closed accounts are streamed once more with zero lamports
+    let deleted = after.get("lamports").and_then(Value::as_u64) == Some(0);
+    let op = match (&before, snapshot, deleted) {
+        (_, true, _) => "r",
+        (_, false, true) => "d",
+        (None, false, false) => "c",
+        (Some(_), false, false) => "u",
+    };
+    let source = json!({
+        "version": env!("CARGO_PKG_VERSION"),
+        "connector": "yellowstone-grpc",
+        "name": "solana",
+        "ts_ms": unix_millis(created_at),
+        "snapshot": snapshot,
+        "slot": after.get("slot"),
+        "writeVersion": after.get("writeVersion"),
+        "txnSignature": after.get("txnSignature"),
+    });
+    json!({
+        "before": before,
+        "after": if deleted { Value::Null } else { after },
+        "source": source,
+        "op": op,
+        "ts_ms": unix_millis(SystemTime::now()),
+    })
+}
+
+pub fn print_envelope(envelope: &Value) {
+    let mut stdout = io::stdout().lock();
+    let _ = writeln!(stdout, "{envelope}");
+    let _ = stdout.flush();
+}
+
+/// Print an update or event which isn't an account change
+pub fn print_line(line: &schema::Line) {
+    let mut stdout = io::stdout().lock();
+    let _ = serde_json::to_writer(&mut stdout, line);
+    let _ = writeln!(stdout);
+    let _ = stdout.flush();
+}
+
+fn unix_millis(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
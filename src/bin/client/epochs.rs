@@ -0,0 +1,119 @@
+//! `--epoch-events`: synthetic `epochEnd`/`epochStart` events when the stream crosses an epoch
+//! boundary, so sinks can partition by epoch and downstream jobs can trigger on boundaries.
+//!
+//! The epoch of a slot comes from the epoch schedule fetched at startup. `epochEnd` is written
+//! as soon as a slot of a later epoch is seen; `epochStart` once the epoch info and the active
+//! stake of the new epoch were fetched over JSON-RPC, or without them if that failed.
+
+use {
+    crate::rpc::RpcClient,
+    anyhow::Context,
+    serde_json::{Value, json},
+    solana_epoch_schedule::EpochSchedule,
+    std::sync::{Arc, Mutex},
+    tokio::sync::mpsc,
+    tracing::warn,
+    yellowstone_grpc_proto::prelude::CommitmentLevel,
+};
+
+pub const FILTER: &str = "epoch-events";
+
+pub struct EpochEvents {
+    rpc: Arc<RpcClient>,
+    commitment: CommitmentLevel,
+    schedule: EpochSchedule,
+    /// Latest epoch seen, kept across reconnects
+    epoch: Mutex<Option<u64>>,
+    started_tx: mpsc::UnboundedSender<Value>,
+    started_rx: Mutex<mpsc::UnboundedReceiver<Value>>,
+}
+
+impl EpochEvents {
+    pub async fn load(rpc: RpcClient, commitment: CommitmentLevel) -> anyhow::Result<Self> {
+        let schedule = rpc.epoch_schedule().await?;
+        let (started_tx, started_rx) = mpsc::unbounded_channel();
+        Ok(Self {
+            rpc: Arc::new(rpc),
+            commitment,
+            schedule,
+            epoch: Mutex::new(None),
+            started_tx,
+            started_rx: Mutex::new(started_rx),
+        })
+    }
+
+    /// Take the slot of an update, returns the `epochEnd` event when it starts a new epoch
+    pub fn observe(&self, slot: u64) -> Option<Value> {
+        let epoch = self.schedule.get_epoch(slot);
+        let mut latest = self.epoch.lock().expect("epoch lock poisoned");
+        let previous = *latest;
+        *latest = Some(previous.map_or(epoch, |previous| previous.max(epoch)));
+        drop(latest);
+        let previous = previous.filter(|previous| *previous < epoch)?;
+
+        let event = json!({
+            "epoch": previous,
+            "firstSlot": self.schedule.get_first_slot_in_epoch(previous),
+            "lastSlot": self.schedule.get_last_slot_in_epoch(previous),
+            "nextEpochSlot": slot,
+        });
+        let mut started = json!({
+            "epoch": epoch,
+            "firstSlot": self.schedule.get_first_slot_in_epoch(epoch),
+            "lastSlot": self.schedule.get_last_slot_in_epoch(epoch),
+            "slotsInEpoch": self.schedule.get_slots_in_epoch(epoch),
+            "observedSlot": slot,
+        });
+        let rpc = Arc::clone(&self.rpc);
+        let commitment = self.commitment;
+        let started_tx = self.started_tx.clone();
+        tokio::spawn(async move {
+            if let Err(error) = enrich(&rpc, commitment, &mut started).await {
+                warn!("failed to fetch info of epoch {epoch}: {error:#}");
+            }
+            let _ = started_tx.send(started);
+        });
+        Some(event)
+    }
+
+    /// `epochStart` events whose epoch info arrived
+    pub fn started(&self) -> Vec<Value> {
+        let mut started_rx = self.started_rx.lock().expect("epoch events lock poisoned");
+        let mut events = vec![];
+        while let Ok(event) = started_rx.try_recv() {
+            events.push(event);
+        }
+        events
+    }
+}
+
+/// Add the epoch info and the active stake of the current epoch
+async fn enrich(
+    rpc: &RpcClient,
+    commitment: CommitmentLevel,
+    event: &mut Value,
+) -> anyhow::Result<()> {
+    let config = json!([{ "commitment": commitment.as_str_name().to_lowercase() }]);
+    let info = rpc.call("getEpochInfo", config.clone()).await?;
+    event["blockHeight"] = info["blockHeight"].clone();
+    event["transactionCount"] = info["transactionCount"].clone();
+
+    let vote_accounts = rpc.call("getVoteAccounts", config).await?;
+    let current = vote_accounts["current"]
+        .as_array()
+        .context("invalid getVoteAccounts response")?;
+    let delinquent = vote_accounts["delinquent"]
+        .as_array()
+        .context("invalid getVoteAccounts response")?;
+    let stake = |accounts: &[Value]| {
+        accounts
+            .iter()
+            .filter_map(|account| account["activatedStake"].as_u64())
+            .sum::<u64>()
+    };
+    event["activeStake"] = json!(stake(current));
+    event["delinquentStake"] = json!(stake(delinquent));
+    event["validators"] = json!(current.len());
+    event["delinquentValidators"] = json!(delinquent.len());
+    Ok(())
+}
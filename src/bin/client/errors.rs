@@ -0,0 +1,215 @@
+//! `--classify-errors`: failed transactions and transaction statuses get an `error` object
+//! normalizing their `TransactionError`, for failure analysis without knowing its JSON shapes.
+//!
+//! The `category` is `transaction` for errors of the transaction as a whole (fees, blockhash,
+//! account locks), `instruction` for runtime errors of an instruction and `custom` for the
+//! error codes returned by programs. Instruction errors have the `instructionIndex` and, for
+//! transaction updates, the `programId` of the failed top-level instruction. Custom errors have
+//! their `code`, with the `anchorError` name of the Anchor framework errors (codes below 6000):
+//! the names of codes defined by programs need their IDL, which isn't loaded. Payloads of other
+//! errors, e.g. the account index of `InsufficientFundsForRent`, are kept as `details`.
+//!
+//! Transactions and failures by error are counted per update kind, shown by `/metrics` and
+//! `/pressure`.
+
+use {
+    crate::instructions,
+    serde_json::{Value, json},
+    std::collections::BTreeMap,
+    tracing::debug,
+};
+
+/// First error code of programs using Anchor, codes below are the framework's
+const ANCHOR_USER_ERRORS: u64 = 6000;
+
+/// `error` object of a failed transaction or transaction status update
+pub fn classify(kind: &str, value: &Value) -> Option<Value> {
+    let err = match kind {
+        "transaction" => &value["tx"]["meta"]["err"],
+        "transactionStatus" => &value["err"],
+        _ => return None,
+    };
+    let (name, payload) = match err {
+        Value::Null => return None,
+        Value::String(name) => (name.as_str(), &Value::Null),
+        Value::Object(object) if object.len() == 1 => object
+            .iter()
+            .next()
+            .map(|(name, payload)| (name.as_str(), payload))?,
+        _ => return Some(json!({ "category": "transaction", "name": "Unknown", "details": err })),
+    };
+    let index = payload.get(0).and_then(Value::as_u64);
+    let (index, instruction) = match (name, index, payload.get(1)) {
+        ("InstructionError", Some(index), Some(instruction)) => (index, instruction),
+        _ => {
+            let mut error = json!({ "category": "transaction", "name": name });
+            if !payload.is_null() {
+                error["details"] = payload.clone();
+            }
+            return Some(error);
+        }
+    };
+
+    let mut error = json!({ "category": "instruction", "instructionIndex": index });
+    if kind == "transaction" {
+        match instructions::program_id(value, index as usize) {
+            Ok(program_id) => error["programId"] = json!(program_id.map(|id| id.to_string())),
+            Err(cause) => {
+                debug!("failed to resolve the program of a failed instruction: {cause:#}")
+            }
+        }
+    }
+    match instruction {
+        Value::String(name) => error["name"] = name.as_str().into(),
+        Value::Object(object) if object.len() == 1 => {
+            let (name, payload) = object.iter().next().expect("one entry");
+            error["name"] = name.as_str().into();
+            match (name.as_str(), payload.as_u64()) {
+                ("Custom", Some(code)) => {
+                    error["category"] = "custom".into();
+                    error["code"] = code.into();
+                    if let Some(anchor) = anchor_error(code) {
+                        error["anchorError"] = anchor.into();
+                    }
+                }
+                _ => error["details"] = payload.clone(),
+            }
+        }
+        _ => {
+            error["name"] = "Unknown".into();
+            error["details"] = instruction.clone();
+        }
+    }
+    Some(error)
+}
+
+/// Name of an Anchor framework error code
+fn anchor_error(code: u64) -> Option<&'static str> {
+    if code >= ANCHOR_USER_ERRORS {
+        return None;
+    }
+    Some(match code {
+        100 => "InstructionMissing",
+        101 => "InstructionFallbackNotFound",
+        102 => "InstructionDidNotDeserialize",
+        103 => "InstructionDidNotSerialize",
+        1000 => "IdlInstructionStub",
+        1001 => "IdlInstructionInvalidProgram",
+        1002 => "IdlAccountNotEmpty",
+        1500 => "EventInstructionStub",
+        2000 => "ConstraintMut",
+        2001 => "ConstraintHasOne",
+        2002 => "ConstraintSigner",
+        2003 => "ConstraintRaw",
+        2004 => "ConstraintOwner",
+        2005 => "ConstraintRentExempt",
+        2006 => "ConstraintSeeds",
+        2007 => "ConstraintExecutable",
+        2008 => "ConstraintState",
+        2009 => "ConstraintAssociated",
+        2010 => "ConstraintAssociatedInit",
+        2011 => "ConstraintClose",
+        2012 => "ConstraintAddress",
+        2013 => "ConstraintZero",
+        2014 => "ConstraintTokenMint",
+        2015 => "ConstraintTokenOwner",
+        2016 => "ConstraintMintMintAuthority",
+        2017 => "ConstraintMintFreezeAuthority",
+        2018 => "ConstraintMintDecimals",
+        2019 => "ConstraintSpace",
+        2020 => "ConstraintAccountIsNone",
+        2021 => "ConstraintTokenTokenProgram",
+        2022 => "ConstraintMintTokenProgram",
+        2023 => "ConstraintAssociatedTokenTokenProgram",
+        2500 => "RequireViolated",
+        2501 => "RequireEqViolated",
+        2502 => "RequireKeysEqViolated",
+        2503 => "RequireNeqViolated",
+        2504 => "RequireKeysNeqViolated",
+        2505 => "RequireGtViolated",
+        2506 => "RequireGteViolated",
+        3000 => "AccountDiscriminatorAlreadySet",
+        3001 => "AccountDiscriminatorNotFound",
+        3002 => "AccountDiscriminatorMismatch",
+        3003 => "AccountDidNotDeserialize",
+        3004 => "AccountDidNotSerialize",
+        3005 => "AccountNotEnoughKeys",
+        3006 => "AccountNotMutable",
+        3007 => "AccountOwnedByWrongProgram",
+        3008 => "InvalidProgramId",
+        3009 => "InvalidProgramExecutable",
+        3010 => "AccountNotSigner",
+        3011 => "AccountNotSystemOwned",
+        3012 => "AccountNotInitialized",
+        3013 => "AccountNotProgramData",
+        3014 => "AccountNotAssociatedTokenAccount",
+        3015 => "AccountSysvarMismatch",
+        3016 => "AccountReallocExceedsLimit",
+        3017 => "AccountDuplicateReallocs",
+        4100 => "DeclaredProgramIdMismatch",
+        4101 => "TryingToInitPayerAsProgramAccount",
+        4102 => "InvalidNumericConversion",
+        5000 => "Deprecated",
+        _ => return None,
+    })
+}
+
+/// Transactions and their failures by update kind
+#[derive(Debug, Clone, Default)]
+pub struct Failures {
+    /// Transactions and failed ones
+    pub transactions: BTreeMap<&'static str, (u64, u64)>,
+    /// Failed transactions by category and error name, the Anchor one of custom errors having one
+    pub errors: BTreeMap<(&'static str, String, String), u64>,
+}
+
+impl Failures {
+    pub fn record(&mut self, kind: &'static str, error: Option<&Value>) {
+        let (transactions, failed) = self.transactions.entry(kind).or_default();
+        *transactions += 1;
+        let Some(error) = error else {
+            return;
+        };
+        *failed += 1;
+        let category = error["category"].as_str().unwrap_or_default().to_owned();
+        let name = error["anchorError"]
+            .as_str()
+            .or(error["name"].as_str())
+            .unwrap_or_default()
+            .to_owned();
+        *self.errors.entry((kind, category, name)).or_default() += 1;
+    }
+
+    #[cfg(feature = "control-api")]
+    pub fn to_json(&self) -> Value {
+        self.transactions
+            .iter()
+            .map(|(kind, (transactions, failed))| {
+                let errors = self
+                    .errors
+                    .iter()
+                    .filter(|((error_kind, ..), _)| error_kind == kind)
+                    .map(|((_, category, name), count)| {
+                        json!({ "category": category, "name": name, "count": count })
+                    })
+                    .collect::<Vec<_>>();
+                let failure = json!({
+                    "transactions": transactions,
+                    "failed": failed,
+                    "failure_rate": failure_rate(*transactions, *failed),
+                    "errors": errors,
+                });
+                ((*kind).to_owned(), failure)
+            })
+            .collect::<serde_json::Map<_, _>>()
+            .into()
+    }
+}
+
+#[cfg(feature = "control-api")]
+pub fn failure_rate(transactions: u64, failed: u64) -> f64 {
+    match transactions {
+        0 => 0.0,
+        transactions => failed as f64 / transactions as f64,
+    }
+}
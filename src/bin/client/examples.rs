@@ -0,0 +1,87 @@
+//! `examples`: list and scaffold ready-to-run pipelines from `templates/`.
+
+use {
+    anyhow::Context,
+    std::{fs, path::Path},
+};
+
+struct Example {
+    name: &'static str,
+    description: &'static str,
+    /// Relative path and content of every file to scaffold
+    files: &'static [(&'static str, &'static str)],
+}
+
+macro_rules! template {
+    ($example:literal, $path:literal) => {
+        (
+            $path,
+            include_str!(concat!("../../../templates/", $example, "/", $path)),
+        )
+    };
+}
+
+const EXAMPLES: &[Example] = &[
+    Example {
+        name: "usdc-accounts-postgres",
+        description: "USDC token account changes into Postgres, with a latest-state view",
+        files: &[
+            template!("usdc-accounts-postgres", "run.sh"),
+            template!(
+                "usdc-accounts-postgres",
+                "migrations/001_usdc_account_changes.sql"
+            ),
+        ],
+    },
+    Example {
+        name: "nft-sales-discord",
+        description: "Magic Eden transactions posted to a Discord webhook",
+        files: &[template!("nft-sales-discord", "run.sh")],
+    },
+    Example {
+        name: "slot-monitor-prometheus",
+        description: "Slot progress and ingest lag metrics with Prometheus alerts",
+        files: &[
+            template!("slot-monitor-prometheus", "run.sh"),
+            template!("slot-monitor-prometheus", "prometheus.yml"),
+            template!("slot-monitor-prometheus", "alerts.yml"),
+        ],
+    },
+];
+
+pub fn run(name: Option<&str>, out: Option<&Path>) -> anyhow::Result<()> {
+    let Some(name) = name else {
+        println!("Examples, scaffold one with `examples <name> [--out <dir>]`:\n");
+        for example in EXAMPLES {
+            println!("  {:<26} {}", example.name, example.description);
+        }
+        return Ok(());
+    };
+
+    let example = EXAMPLES
+        .iter()
+        .find(|example| example.name == name)
+        .with_context(|| format!("unknown example {name}, run `examples` to list them"))?;
+    let out = out.unwrap_or_else(|| Path::new(example.name));
+
+    for (path, _) in example.files {
+        let path = out.join(path);
+        anyhow::ensure!(!path.exists(), "{} already exists", path.display());
+    }
+    for (path, content) in example.files {
+        let path = out.join(path);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&path, content)
+            .with_context(|| format!("failed to write {}", path.display()))?;
+        #[cfg(unix)]
+        if path.extension().is_some_and(|extension| extension == "sh") {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&path, fs::Permissions::from_mode(0o755))?;
+        }
+        println!("created {}", path.display());
+    }
+    println!("\nnext: {}/run.sh", out.display());
+    Ok(())
+}
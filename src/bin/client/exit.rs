@@ -0,0 +1,214 @@
+//! Stable exit codes and the `--errors-report` file, so orchestration scripts can react per
+//! failure class instead of parsing log text.
+//!
+//! | code | class |
+//! |------|-------|
+//! | 0 | success |
+//! | 1 | other |
+//! | 2 | invalid arguments (clap) |
+//! | 3 | auth failure |
+//! | 4 | filter rejected |
+//! | 5 | sink failure |
+//! | 6 | stream ended by server |
+//! | 7 | connection failure |
+//! | 8 | blockhash not valid (`is-blockhash-valid`) |
+//! | 9 | endpoint unhealthy (`health-watch --exit-on-unhealthy`) |
+
+use {
+    serde_json::json,
+    std::{
+        fmt, fs, io,
+        path::Path,
+        time::{Duration, SystemTime, UNIX_EPOCH},
+    },
+    tonic::Code,
+    yellowstone_grpc_client::GeyserGrpcBuilderError,
+};
+
+/// The server closed the subscription without an error
+#[derive(Debug)]
+pub struct StreamEnded;
+
+impl fmt::Display for StreamEnded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("stream ended by server")
+    }
+}
+
+impl std::error::Error for StreamEnded {}
+
+/// No message arrived within `--stall-timeout`
+#[derive(Debug)]
+pub struct Stalled {
+    pub timeout: Duration,
+    /// Last slot received, the subscription resumes from it
+    pub slot: Option<u64>,
+}
+
+impl fmt::Display for Stalled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "no message for {}s, the stream stalled", self.timeout.as_secs())
+    }
+}
+
+impl std::error::Error for Stalled {}
+
+/// A `--sink` delivery still failed after `--sink-retries`
+#[derive(Debug)]
+pub struct SinkFailed {
+    pub sink: String,
+    pub attempts: u32,
+}
+
+impl fmt::Display for SinkFailed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "sink `{}` failed {} times", self.sink, self.attempts)
+    }
+}
+
+impl std::error::Error for SinkFailed {}
+
+/// `is-blockhash-valid` answered that the blockhash isn't valid
+#[derive(Debug)]
+pub struct BlockhashInvalid;
+
+impl fmt::Display for BlockhashInvalid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("blockhash is not valid")
+    }
+}
+
+impl std::error::Error for BlockhashInvalid {}
+
+/// `health-watch --exit-on-unhealthy` saw `--unhealthy-threshold` failed checks in a row
+#[derive(Debug)]
+pub struct Unhealthy {
+    pub failures: u32,
+    pub reason: String,
+}
+
+impl fmt::Display for Unhealthy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "endpoint unhealthy after {} failed checks: {}",
+            self.failures, self.reason
+        )
+    }
+}
+
+impl std::error::Error for Unhealthy {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorClass {
+    Auth,
+    FilterRejected,
+    Sink,
+    StreamEnded,
+    Connection,
+    BlockhashInvalid,
+    Unhealthy,
+    Other,
+}
+
+impl ErrorClass {
+    pub fn of(error: &anyhow::Error) -> Self {
+        for cause in error.chain() {
+            if let Some(status) = cause.downcast_ref::<tonic::Status>() {
+                return match status.code() {
+                    Code::Unauthenticated | Code::PermissionDenied => Self::Auth,
+                    Code::InvalidArgument => Self::FilterRejected,
+                    Code::Unavailable | Code::DeadlineExceeded | Code::Cancelled => {
+                        Self::Connection
+                    }
+                    _ => Self::Other,
+                };
+            }
+            if let Some(GeyserGrpcBuilderError::MetadataValueError(_)) = cause.downcast_ref() {
+                return Self::Auth;
+            }
+            if cause.is::<tonic::transport::Error>() {
+                return Self::Connection;
+            }
+            if cause.is::<Stalled>() {
+                return Self::Connection;
+            }
+            if cause.is::<StreamEnded>() {
+                return Self::StreamEnded;
+            }
+            if cause.is::<SinkFailed>() {
+                return Self::Sink;
+            }
+            if cause.is::<BlockhashInvalid>() {
+                return Self::BlockhashInvalid;
+            }
+            if cause.is::<Unhealthy>() {
+                return Self::Unhealthy;
+            }
+            if let Some(error) = cause.downcast_ref::<io::Error>()
+                && matches!(
+                    error.kind(),
+                    io::ErrorKind::BrokenPipe | io::ErrorKind::WriteZero
+                )
+            {
+                return Self::Sink;
+            }
+        }
+        Self::Other
+    }
+
+    pub const fn exit_code(self) -> u8 {
+        match self {
+            Self::Other => 1,
+            Self::Auth => 3,
+            Self::FilterRejected => 4,
+            Self::Sink => 5,
+            Self::StreamEnded => 6,
+            Self::Connection => 7,
+            Self::BlockhashInvalid => 8,
+            Self::Unhealthy => 9,
+        }
+    }
+
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Auth => "auth_failure",
+            Self::FilterRejected => "filter_rejected",
+            Self::Sink => "sink_failure",
+            Self::StreamEnded => "stream_ended",
+            Self::Connection => "connection_failure",
+            Self::BlockhashInvalid => "blockhash_invalid",
+            Self::Unhealthy => "unhealthy",
+            Self::Other => "other",
+        }
+    }
+
+    /// Reconnecting can't fix bad credentials or filters, and a clean end is final
+    const fn is_retryable(self) -> bool {
+        matches!(self, Self::Connection | Self::Other)
+    }
+}
+
+/// Retry classification for the reconnect loop
+pub fn backoff_error(error: anyhow::Error) -> backoff::Error<anyhow::Error> {
+    if ErrorClass::of(&error).is_retryable() {
+        backoff::Error::transient(error)
+    } else {
+        backoff::Error::Permanent(error)
+    }
+}
+
+/// Write the final error report as JSON
+pub fn write_report(path: &Path, error: &anyhow::Error, class: ErrorClass) -> io::Result<()> {
+    let report = json!({
+        "class": class.name(),
+        "exitCode": class.exit_code(),
+        "error": error.to_string(),
+        "causes": error.chain().skip(1).map(ToString::to_string).collect::<Vec<_>>(),
+        "timestamp": SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs(),
+    });
+    fs::write(path, serde_json::to_vec_pretty(&report)?)
+}
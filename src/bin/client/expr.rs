@@ -0,0 +1,330 @@
+//! `--where`: client-side post-filter on decoded updates, for conditions the Geyser filters
+//! can't express.
+//!
+//! ```text
+//! expr     := and ("||" and)*
+//! and      := unary ("&&" unary)*
+//! unary    := "!" unary | "(" expr ")" | operand (op operand)?
+//! op       := "==" | "!=" | "<" | "<=" | ">" | ">="
+//! operand  := number | "string" | 'string' | true | false | null | path | word
+//! ```
+//!
+//! Paths are dot separated fields of the decoded update (`lamports`, `tx.meta.fee`,
+//! `accountKeys.0`), `kind` is the update type. A bare word on the right of a comparison
+//! that isn't a field is a string, so `owner == Tokenkeg...` works without quotes.
+
+use {
+    anyhow::Context,
+    serde_json::Value,
+    std::{cmp::Ordering, sync::Arc},
+};
+
+#[derive(Debug, Clone)]
+pub struct Expr(Arc<Node>);
+
+#[derive(Debug)]
+enum Node {
+    Or(Box<Node>, Box<Node>),
+    And(Box<Node>, Box<Node>),
+    Not(Box<Node>),
+    Compare(Operand, Op, Operand),
+    Truthy(Operand),
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Debug)]
+enum Operand {
+    /// Number literal, the source text is kept to compare against string fields
+    Number(f64, String),
+    String(String),
+    Bool(bool),
+    Null,
+    /// Field path, or a string if nothing is found at it
+    Word(String),
+}
+
+#[derive(Debug, PartialEq)]
+enum Token {
+    Open,
+    Close,
+    And,
+    Or,
+    Not,
+    Op(&'static str),
+    String(String),
+    Word(String),
+}
+
+/// Resolved operand
+enum Val<'a> {
+    Number(f64, Option<&'a str>),
+    String(String),
+    Bool(bool),
+    Null,
+}
+
+impl Expr {
+    pub fn parse(source: &str) -> anyhow::Result<Self> {
+        let tokens = tokenize(source)?;
+        let mut parser = Parser { tokens, pos: 0 };
+        let node = parser.or()?;
+        if let Some(token) = parser.tokens.get(parser.pos) {
+            anyhow::bail!("unexpected {token:?} in --where expression");
+        }
+        Ok(Self(Arc::new(node)))
+    }
+
+    pub fn matches(&self, kind: &str, value: &Value) -> bool {
+        self.0.eval(kind, value)
+    }
+}
+
+fn tokenize(source: &str) -> anyhow::Result<Vec<Token>> {
+    let mut tokens = vec![];
+    let mut chars = source.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        let next = chars.peek().map(|(_, c)| *c);
+        tokens.push(match (c, next) {
+            (c, _) if c.is_whitespace() => continue,
+            ('(', _) => Token::Open,
+            (')', _) => Token::Close,
+            ('&', Some('&')) => {
+                chars.next();
+                Token::And
+            }
+            ('|', Some('|')) => {
+                chars.next();
+                Token::Or
+            }
+            ('=', Some('=')) | ('!', Some('=')) | ('<', Some('=')) | ('>', Some('=')) => {
+                chars.next();
+                Token::Op(match c {
+                    '=' => "==",
+                    '!' => "!=",
+                    '<' => "<=",
+                    _ => ">=",
+                })
+            }
+            ('!', _) => Token::Not,
+            ('<', _) => Token::Op("<"),
+            ('>', _) => Token::Op(">"),
+            ('"' | '\'', _) => {
+                let mut string = String::new();
+                loop {
+                    match chars.next() {
+                        Some((_, end)) if end == c => break,
+                        Some((_, c)) => string.push(c),
+                        None => anyhow::bail!("unterminated string in --where expression"),
+                    }
+                }
+                Token::String(string)
+            }
+            (c, _) if is_word_char(c) => {
+                let mut end = start + c.len_utf8();
+                while let Some((index, c)) = chars.peek().copied()
+                    && is_word_char(c)
+                {
+                    end = index + c.len_utf8();
+                    chars.next();
+                }
+                Token::Word(source[start..end].to_owned())
+            }
+            (c, _) => anyhow::bail!("unexpected `{c}` in --where expression"),
+        });
+    }
+    Ok(tokens)
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '.' | '-' | '+')
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn next_if(&mut self, token: &Token) -> bool {
+        let matched = self.tokens.get(self.pos) == Some(token);
+        if matched {
+            self.pos += 1;
+        }
+        matched
+    }
+
+    fn or(&mut self) -> anyhow::Result<Node> {
+        let mut node = self.and()?;
+        while self.next_if(&Token::Or) {
+            node = Node::Or(Box::new(node), Box::new(self.and()?));
+        }
+        Ok(node)
+    }
+
+    fn and(&mut self) -> anyhow::Result<Node> {
+        let mut node = self.unary()?;
+        while self.next_if(&Token::And) {
+            node = Node::And(Box::new(node), Box::new(self.unary()?));
+        }
+        Ok(node)
+    }
+
+    fn unary(&mut self) -> anyhow::Result<Node> {
+        if self.next_if(&Token::Not) {
+            return Ok(Node::Not(Box::new(self.unary()?)));
+        }
+        if self.next_if(&Token::Open) {
+            let node = self.or()?;
+            anyhow::ensure!(
+                self.next_if(&Token::Close),
+                "missing `)` in --where expression"
+            );
+            return Ok(node);
+        }
+
+        let left = self.operand()?;
+        let op = match self.tokens.get(self.pos) {
+            Some(Token::Op(op)) => match *op {
+                "==" => Op::Eq,
+                "!=" => Op::Ne,
+                "<" => Op::Lt,
+                "<=" => Op::Le,
+                ">" => Op::Gt,
+                _ => Op::Ge,
+            },
+            _ => return Ok(Node::Truthy(left)),
+        };
+        self.pos += 1;
+        Ok(Node::Compare(left, op, self.operand()?))
+    }
+
+    fn operand(&mut self) -> anyhow::Result<Operand> {
+        let token = self
+            .tokens
+            .get(self.pos)
+            .context("unexpected end of --where expression")?;
+        self.pos += 1;
+        Ok(match token {
+            Token::String(string) => Operand::String(string.clone()),
+            Token::Word(word) => match word.as_str() {
+                "true" => Operand::Bool(true),
+                "false" => Operand::Bool(false),
+                "null" => Operand::Null,
+                _ if word.starts_with(|c: char| c.is_ascii_digit() || c == '-') => {
+                    match word.parse() {
+                        Ok(number) => Operand::Number(number, word.clone()),
+                        Err(_) => Operand::Word(word.clone()),
+                    }
+                }
+                _ => Operand::Word(word.clone()),
+            },
+            token => anyhow::bail!("expected a value, found {token:?} in --where expression"),
+        })
+    }
+}
+
+impl Node {
+    fn eval(&self, kind: &str, value: &Value) -> bool {
+        match self {
+            Self::Or(left, right) => left.eval(kind, value) || right.eval(kind, value),
+            Self::And(left, right) => left.eval(kind, value) && right.eval(kind, value),
+            Self::Not(node) => !node.eval(kind, value),
+            Self::Truthy(operand) => match operand.resolve(kind, value, false) {
+                Val::Number(number, _) => number != 0.0,
+                Val::String(string) => !string.is_empty(),
+                Val::Bool(bool) => bool,
+                Val::Null => false,
+            },
+            Self::Compare(left, op, right) => {
+                let left = left.resolve(kind, value, false);
+                let right = right.resolve(kind, value, true);
+                match compare(&left, &right) {
+                    Some(ordering) => match op {
+                        Op::Eq => ordering == Ordering::Equal,
+                        Op::Ne => ordering != Ordering::Equal,
+                        Op::Lt => ordering == Ordering::Less,
+                        Op::Le => ordering != Ordering::Greater,
+                        Op::Gt => ordering == Ordering::Greater,
+                        Op::Ge => ordering != Ordering::Less,
+                    },
+                    // Values of different types are only unequal
+                    None => matches!(op, Op::Ne),
+                }
+            }
+        }
+    }
+}
+
+impl Operand {
+    fn resolve<'a>(&'a self, kind: &str, value: &Value, literal_fallback: bool) -> Val<'a> {
+        match self {
+            Self::Number(number, source) => Val::Number(*number, Some(source)),
+            Self::String(string) => Val::String(string.clone()),
+            Self::Bool(bool) => Val::Bool(*bool),
+            Self::Null => Val::Null,
+            Self::Word(path) => match lookup(value, path) {
+                Some(field) => Val::from_json(field),
+                None if path == "kind" => Val::String(kind.to_owned()),
+                None if literal_fallback => Val::String(path.clone()),
+                None => Val::Null,
+            },
+        }
+    }
+}
+
+impl Val<'_> {
+    fn from_json(value: &Value) -> Self {
+        match value {
+            Value::Null => Self::Null,
+            Value::Bool(bool) => Self::Bool(*bool),
+            Value::Number(number) => Self::Number(number.as_f64().unwrap_or(f64::NAN), None),
+            Value::String(string) => Self::String(string.clone()),
+            value => Self::String(value.to_string()),
+        }
+    }
+}
+
+/// Field at a dot separated `path`, array elements by index
+pub fn lookup<'a>(value: &'a Value, path: &str) -> Option<&'a Value> {
+    path.split('.').try_fold(value, |value, segment| match value {
+        Value::Array(items) => items.get(segment.parse::<usize>().ok()?),
+        value => value.get(segment),
+    })
+}
+
+fn compare(left: &Val, right: &Val) -> Option<Ordering> {
+    match (left, right) {
+        (Val::Number(left, _), Val::Number(right, _)) => left.partial_cmp(right),
+        (Val::String(string), Val::Number(number, source)) => {
+            compare_string_number(string, *number, *source)
+        }
+        (Val::Number(number, source), Val::String(string)) => {
+            compare_string_number(string, *number, *source).map(Ordering::reverse)
+        }
+        (Val::String(left), Val::String(right)) => Some(left.cmp(right)),
+        (Val::Bool(left), Val::Bool(right)) => Some(left.cmp(right)),
+        (Val::Null, Val::Null) => Some(Ordering::Equal),
+        _ => None,
+    }
+}
+
+/// Numeric strings (e.g. token amounts) compare as numbers, others against the literal's text
+/// (e.g. the all-digits system program id)
+fn compare_string_number(string: &str, number: f64, source: Option<&str>) -> Option<Ordering> {
+    if source == Some(string) {
+        return Some(Ordering::Equal);
+    }
+    match string.parse::<f64>() {
+        Ok(parsed) => parsed.partial_cmp(&number),
+        Err(_) => source.map(|source| string.cmp(source)),
+    }
+}
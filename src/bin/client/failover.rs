@@ -0,0 +1,143 @@
+//! `--failover-endpoint`: an ordered list of endpoints, `--endpoint` first.
+//!
+//! When the active endpoint fails (connection or stream error, `--stall-timeout`) the client
+//! moves to the next one right away instead of backing off on a dead endpoint; the usual
+//! backoff only applies once every endpoint failed in turn. While a fallback is active
+//! the preferred endpoints are health checked every `--failback-interval`, and the
+//! subscription is moved back to the first one serving again.
+
+use {
+    crate::Args,
+    backoff::{ExponentialBackoff, backoff::Backoff},
+    std::{
+        sync::{
+            Arc,
+            atomic::{AtomicBool, Ordering},
+        },
+        time::Duration,
+    },
+    tokio::sync::watch,
+    tonic_health::pb::health_check_response::ServingStatus,
+    tracing::{info, warn},
+};
+
+pub struct Failover {
+    endpoints: Vec<String>,
+    active: watch::Sender<usize>,
+    /// Set when the active endpoint changed, the next connection attempt isn't delayed
+    switched: AtomicBool,
+}
+
+impl Failover {
+    pub fn new(primary: String, fallbacks: Vec<String>) -> Self {
+        Self {
+            endpoints: [primary].into_iter().chain(fallbacks).collect(),
+            active: watch::Sender::new(0),
+            switched: AtomicBool::new(false),
+        }
+    }
+
+    /// Index and URL of the endpoint to connect to
+    pub fn active(&self) -> (usize, &str) {
+        let index = *self.active.borrow();
+        (index, &self.endpoints[index])
+    }
+
+    /// Move on from a failed endpoint, unless another one was selected meanwhile
+    pub fn failed(&self, index: usize) {
+        if self.endpoints.len() < 2 {
+            return;
+        }
+        let next = (index + 1) % self.endpoints.len();
+        let moved = self.active.send_if_modified(|active| {
+            let moved = *active == index;
+            if moved {
+                *active = next;
+            }
+            moved
+        });
+        if moved {
+            warn!(
+                "{} failed, failing over to {}",
+                self.endpoints[index], self.endpoints[next]
+            );
+            // Back to the first endpoint means all of them failed, that round is backed off
+            if next != 0 {
+                self.switched.store(true, Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// Resolves once another endpoint than `index` is selected by failback
+    pub async fn replaced(&self, index: usize) {
+        let mut active = self.active.subscribe();
+        let _ = active.wait_for(|active| *active != index).await;
+    }
+
+    /// Health check the endpoints preferred over the active one, switching back to the first
+    /// one serving
+    pub fn spawn_failback(self: &Arc<Self>, args: Args, interval: Duration) {
+        if self.endpoints.len() < 2 {
+            return;
+        }
+        let failover = Arc::clone(self);
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            ticker.tick().await;
+            loop {
+                ticker.tick().await;
+                let (active, _) = failover.active();
+                for index in 0..active {
+                    let endpoint = &failover.endpoints[index];
+                    if !is_serving(&args, endpoint).await {
+                        continue;
+                    }
+                    info!("{endpoint} is serving again, failing back");
+                    failover.switched.store(true, Ordering::Relaxed);
+                    failover.active.send_replace(index);
+                    break;
+                }
+            }
+        });
+    }
+}
+
+async fn is_serving(args: &Args, endpoint: &str) -> bool {
+    let mut args = args.clone();
+    args.endpoint = endpoint.to_owned();
+    match args.connect().await {
+        Ok(mut client) => client
+            .health_check()
+            .await
+            .is_ok_and(|response| response.status() == ServingStatus::Serving),
+        Err(_) => false,
+    }
+}
+
+/// Exponential backoff that retries immediately after a failover
+pub struct FailoverBackoff {
+    inner: ExponentialBackoff,
+    failover: Arc<Failover>,
+}
+
+impl FailoverBackoff {
+    pub fn new(failover: Arc<Failover>) -> Self {
+        Self {
+            inner: ExponentialBackoff::default(),
+            failover,
+        }
+    }
+}
+
+impl Backoff for FailoverBackoff {
+    fn reset(&mut self) {
+        self.inner.reset();
+    }
+
+    fn next_backoff(&mut self) -> Option<Duration> {
+        if self.failover.switched.swap(false, Ordering::Relaxed) {
+            return Some(Duration::ZERO);
+        }
+        self.inner.next_backoff()
+    }
+}
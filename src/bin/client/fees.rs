@@ -0,0 +1,161 @@
+//! `--fee-stats`: priority fee and compute unit percentiles, for fee estimation.
+//!
+//! A `fees` event is emitted per slot once transactions of a later slot arrive. Percentiles
+//! over the last [`ROLLING_SLOTS`] slots are kept for the metrics and `/pressure` endpoints.
+//! Vote transactions are left out, they only pay the base fee.
+
+use {
+    crate::latency::percentile,
+    serde_json::{Value, json},
+    solana_transaction_status::EncodedTransaction,
+    std::collections::BTreeMap,
+    yellowstone_grpc_proto::prelude::{SubscribeRequest, SubscribeRequestFilterTransactions},
+};
+
+#[cfg(feature = "control-api")]
+use std::collections::VecDeque;
+
+/// Transactions filter added to the request when transactions aren't subscribed already
+pub const FILTER: &str = "fee-stats";
+
+/// Base fee paid per signature, the rest of the fee is the priority fee
+const LAMPORTS_PER_SIGNATURE: u64 = 5000;
+
+/// Slots the rolling percentiles are computed over (about a minute)
+#[cfg(feature = "control-api")]
+const ROLLING_SLOTS: usize = 150;
+
+#[cfg(feature = "control-api")]
+pub const QUANTILES: [(&str, f64); 4] = [("p50", 0.5), ("p75", 0.75), ("p90", 0.9), ("p99", 0.99)];
+
+/// Percentiles at [`QUANTILES`]
+#[cfg(feature = "control-api")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Quantiles {
+    pub priority_fee: [u64; QUANTILES.len()],
+    pub compute_units: [u64; QUANTILES.len()],
+    pub transactions: u64,
+}
+
+#[derive(Default)]
+struct Samples {
+    priority_fees: Vec<u64>,
+    compute_units: Vec<u64>,
+}
+
+pub struct FeeStats {
+    slots: BTreeMap<u64, Samples>,
+    /// Latest slot emitted, late transactions of it or older slots are ignored
+    emitted_slot: Option<u64>,
+    #[cfg(feature = "control-api")]
+    rolling: VecDeque<Samples>,
+}
+
+impl FeeStats {
+    /// Collect the fees of the transactions of `request`, subscribing to them if needed
+    pub fn new(request: &mut SubscribeRequest) -> Self {
+        if request.transactions.is_empty() {
+            request.transactions.insert(
+                FILTER.to_owned(),
+                SubscribeRequestFilterTransactions {
+                    vote: Some(false),
+                    ..Default::default()
+                },
+            );
+        }
+        Self {
+            slots: BTreeMap::new(),
+            emitted_slot: None,
+            #[cfg(feature = "control-api")]
+            rolling: VecDeque::new(),
+        }
+    }
+
+    /// Take an update, returns the `fees` events of the slots completed before it
+    pub fn observe(&mut self, kind: &str, value: &Value) -> Vec<Value> {
+        if kind != "transaction" || value.get("isVote").and_then(Value::as_bool) != Some(false) {
+            return vec![];
+        }
+        let Some(slot) = value.get("slot").and_then(Value::as_u64) else {
+            return vec![];
+        };
+        if self
+            .emitted_slot
+            .is_some_and(|emitted_slot| slot <= emitted_slot)
+        {
+            return vec![];
+        }
+        let meta = &value["tx"]["meta"];
+        let signatures =
+            serde_json::from_value::<EncodedTransaction>(value["tx"]["transaction"].clone())
+                .ok()
+                .and_then(|tx| tx.decode())
+                .map_or(1, |tx| tx.signatures.len() as u64);
+        let fee = meta["fee"].as_u64().unwrap_or_default();
+        let samples = self.slots.entry(slot).or_default();
+        samples
+            .priority_fees
+            .push(fee.saturating_sub(signatures * LAMPORTS_PER_SIGNATURE));
+        samples
+            .compute_units
+            .push(meta["computeUnitsConsumed"].as_u64().unwrap_or_default());
+
+        let pending = self.slots.split_off(&slot);
+        let completed = std::mem::replace(&mut self.slots, pending);
+        completed
+            .into_iter()
+            .map(|(slot, mut samples)| {
+                samples.priority_fees.sort_unstable();
+                samples.compute_units.sort_unstable();
+                let event = json!({
+                    "slot": slot,
+                    "transactions": samples.priority_fees.len(),
+                    "priorityFeeLamports": summary(&samples.priority_fees),
+                    "computeUnits": summary(&samples.compute_units),
+                });
+                self.emitted_slot = Some(slot);
+                #[cfg(feature = "control-api")]
+                {
+                    self.rolling.push_back(samples);
+                    if self.rolling.len() > ROLLING_SLOTS {
+                        self.rolling.pop_front();
+                    }
+                }
+                event
+            })
+            .collect()
+    }
+
+    /// Percentiles over the last slots, `None` before a slot completed
+    #[cfg(feature = "control-api")]
+    pub fn rolling(&self) -> Option<Quantiles> {
+        let mut priority_fees = vec![];
+        let mut compute_units = vec![];
+        for samples in &self.rolling {
+            priority_fees.extend(&samples.priority_fees);
+            compute_units.extend(&samples.compute_units);
+        }
+        if priority_fees.is_empty() {
+            return None;
+        }
+        priority_fees.sort_unstable();
+        compute_units.sort_unstable();
+        Some(Quantiles {
+            priority_fee: QUANTILES.map(|(_, q)| percentile(&priority_fees, q)),
+            compute_units: QUANTILES.map(|(_, q)| percentile(&compute_units, q)),
+            transactions: priority_fees.len() as u64,
+        })
+    }
+}
+
+fn summary(sorted: &[u64]) -> Value {
+    json!({
+        "min": sorted[0],
+        "p50": percentile(sorted, 0.50),
+        "p75": percentile(sorted, 0.75),
+        "p90": percentile(sorted, 0.90),
+        "p99": percentile(sorted, 0.99),
+        "max": sorted[sorted.len() - 1],
+        "total": sorted.iter().sum::<u64>(),
+    })
+}
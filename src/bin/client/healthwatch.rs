@@ -0,0 +1,129 @@
+//! `health-watch`: follow the gRPC health status of `--endpoint`, to back systemd or cron health
+//! checks of a provider.
+//!
+//! A status other than `SERVING`, a failed connection or a closed watch stream is a failed
+//! check; the endpoint turns unhealthy after `--unhealthy-threshold` failed checks in a row and
+//! healthy again at the next `SERVING`. Transitions are logged and, with `--webhook-url`, posted
+//! as JSON (`event`: `unhealthy` or `recovered`, `endpoint`, `reason`, `failures`,
+//! `timestamp`). With `--exit-on-unhealthy` the watch exits with code 9 once unhealthy instead.
+//! The watch is reopened `--retry-secs` after it fails.
+
+use {
+    crate::{ActionHealthWatch, Args, exit},
+    futures::StreamExt,
+    serde_json::json,
+    std::time::{Duration, SystemTime, UNIX_EPOCH},
+    tonic_health::pb::health_check_response::ServingStatus,
+    tracing::{info, warn},
+};
+
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+struct Monitor<'a> {
+    endpoint: &'a str,
+    watch: &'a ActionHealthWatch,
+    http: reqwest::Client,
+    /// Failed checks in a row
+    failures: u32,
+    unhealthy: bool,
+}
+
+pub async fn run(args: &Args, watch: &ActionHealthWatch) -> anyhow::Result<()> {
+    let mut monitor = Monitor {
+        endpoint: &args.endpoint,
+        watch,
+        http: reqwest::Client::new(),
+        failures: 0,
+        unhealthy: false,
+    };
+    loop {
+        let reason = match watch_once(args, &mut monitor).await {
+            Ok(()) => "watch stream closed".to_owned(),
+            Err(error) if error.is::<exit::Unhealthy>() => return Err(error),
+            Err(error) => format!("{error:#}"),
+        };
+        warn!("{reason}");
+        monitor.failed(reason).await?;
+        info!("retrying in {}s", watch.retry_secs);
+        tokio::time::sleep(Duration::from_secs(watch.retry_secs)).await;
+    }
+}
+
+async fn watch_once(args: &Args, monitor: &mut Monitor<'_>) -> anyhow::Result<()> {
+    let mut client = args.connect().await?;
+    let mut stream = client.health_watch().await?;
+    info!("stream opened");
+    while let Some(message) = stream.next().await {
+        let status = message?.status();
+        info!("health: {}", status.as_str_name());
+        if status == ServingStatus::Serving {
+            monitor.serving().await;
+        } else {
+            monitor
+                .failed(format!("status {}", status.as_str_name()))
+                .await?;
+        }
+    }
+    Ok(())
+}
+
+impl Monitor<'_> {
+    async fn serving(&mut self) {
+        self.failures = 0;
+        if self.unhealthy {
+            self.unhealthy = false;
+            info!("{} recovered", self.endpoint);
+            self.alert("recovered", "status SERVING").await;
+        }
+    }
+
+    /// Count a failed check, failing with [`exit::Unhealthy`] once unhealthy with
+    /// `--exit-on-unhealthy`
+    async fn failed(&mut self, reason: String) -> anyhow::Result<()> {
+        self.failures += 1;
+        if self.unhealthy || self.failures < self.watch.unhealthy_threshold {
+            return Ok(());
+        }
+        self.unhealthy = true;
+        warn!(
+            "{} unhealthy after {} failed checks: {reason}",
+            self.endpoint, self.failures
+        );
+        self.alert("unhealthy", &reason).await;
+        anyhow::ensure!(
+            !self.watch.exit_on_unhealthy,
+            exit::Unhealthy {
+                failures: self.failures,
+                reason,
+            }
+        );
+        Ok(())
+    }
+
+    async fn alert(&self, event: &str, reason: &str) {
+        let Some(url) = &self.watch.webhook_url else {
+            return;
+        };
+        let payload = json!({
+            "event": event,
+            "endpoint": self.endpoint,
+            "reason": reason,
+            "failures": self.failures,
+            "timestamp": SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+        });
+        let result = self
+            .http
+            .post(url)
+            .timeout(WEBHOOK_TIMEOUT)
+            .json(&payload)
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status);
+        if let Err(error) = result {
+            warn!("failed to post the {event} alert: {error}");
+        }
+    }
+}
@@ -0,0 +1,149 @@
+//! Instructions of a decoded transaction update, top-level and inner ones, with their
+//! accounts resolved to addresses, for the event feeds reading instructions.
+
+use {serde_json::Value, solana_pubkey::Pubkey, solana_transaction_status::EncodedTransaction};
+
+pub struct Instruction {
+    pub program_id: Pubkey,
+    pub accounts: Vec<Pubkey>,
+    pub data: Vec<u8>,
+}
+
+impl Instruction {
+    /// Address of the instruction account at `position`
+    pub fn account(&self, position: usize) -> Option<String> {
+        self.accounts.get(position).map(ToString::to_string)
+    }
+}
+
+/// `(program id index, account indexes, data)` of an instruction
+type CompiledInstruction = (u8, Vec<u8>, Vec<u8>);
+
+struct Message {
+    /// The static keys then the address lookup table ones, writable first
+    keys: Vec<Pubkey>,
+    writable: Vec<bool>,
+    /// Top-level instructions
+    instructions: Vec<CompiledInstruction>,
+}
+
+fn decode(value: &Value) -> anyhow::Result<Message> {
+    let tx = serde_json::from_value::<EncodedTransaction>(value["tx"]["transaction"].clone())?
+        .decode()
+        .ok_or_else(|| anyhow::anyhow!("failed to decode transaction"))?;
+    let meta = &value["tx"]["meta"];
+    let loaded = |kind: &str| {
+        meta["loadedAddresses"][kind]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|key| key.as_str()?.parse::<Pubkey>().ok())
+            .collect::<Vec<_>>()
+    };
+    let (loaded_writable, loaded_readonly) = (loaded("writable"), loaded("readonly"));
+
+    let static_keys = tx.message.static_account_keys();
+    let header = tx.message.header();
+    let signed = usize::from(header.num_required_signatures);
+    let signed_writable = signed.saturating_sub(usize::from(header.num_readonly_signed_accounts));
+    let unsigned_writable = static_keys
+        .len()
+        .saturating_sub(usize::from(header.num_readonly_unsigned_accounts));
+    let writable = (0..static_keys.len())
+        .map(|index| index < signed_writable || (signed..unsigned_writable).contains(&index))
+        .chain(loaded_writable.iter().map(|_| true))
+        .chain(loaded_readonly.iter().map(|_| false))
+        .collect();
+    let keys = static_keys
+        .iter()
+        .copied()
+        .chain(loaded_writable)
+        .chain(loaded_readonly)
+        .collect();
+    let instructions = tx
+        .message
+        .instructions()
+        .iter()
+        .map(|ix| (ix.program_id_index, ix.accounts.clone(), ix.data.clone()))
+        .collect();
+    Ok(Message {
+        keys,
+        writable,
+        instructions,
+    })
+}
+
+/// Account keys of a transaction update, including failed ones
+pub fn account_keys(value: &Value) -> anyhow::Result<Vec<Pubkey>> {
+    decode(value).map(|message| message.keys)
+}
+
+/// Account keys of a transaction update the transaction may write to
+pub fn writable_accounts(value: &Value) -> anyhow::Result<Vec<Pubkey>> {
+    let message = decode(value)?;
+    Ok(message
+        .keys
+        .into_iter()
+        .zip(message.writable)
+        .filter_map(|(key, writable)| writable.then_some(key))
+        .collect())
+}
+
+/// Instructions of a successful transaction update, in execution order of the top-level ones
+/// followed by inner instructions; empty for failed transactions
+pub fn parse(value: &Value) -> anyhow::Result<Vec<Instruction>> {
+    if !value["tx"]["meta"]["err"].is_null() {
+        return Ok(vec![]);
+    }
+    parse_all(value)
+}
+
+/// Instructions of a transaction update like [`parse`], including those of failed
+/// transactions: their inner instructions stop where the transaction failed
+pub fn parse_all(value: &Value) -> anyhow::Result<Vec<Instruction>> {
+    let meta = &value["tx"]["meta"];
+    let Message {
+        keys,
+        instructions: top_level,
+        ..
+    } = decode(value)?;
+    let inner = meta["innerInstructions"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .flat_map(|inner| inner["instructions"].as_array().into_iter().flatten())
+        .filter_map(|ix| {
+            let accounts = ix["accounts"]
+                .as_array()?
+                .iter()
+                .map(|index| index.as_u64().map(|index| index as u8))
+                .collect::<Option<Vec<_>>>()?;
+            let data = bs58::decode(ix["data"].as_str()?).into_vec().ok()?;
+            Some((ix["programIdIndex"].as_u64()? as u8, accounts, data))
+        });
+
+    Ok(top_level
+        .into_iter()
+        .chain(inner)
+        .filter_map(|(program_id_index, accounts, data)| {
+            Some(Instruction {
+                program_id: *keys.get(usize::from(program_id_index))?,
+                accounts: accounts
+                    .iter()
+                    .map(|index| keys.get(usize::from(*index)).copied())
+                    .collect::<Option<_>>()?,
+                data,
+            })
+        })
+        .collect())
+}
+
+/// Program of the top-level instruction at `index` of a transaction update
+pub fn program_id(value: &Value, index: usize) -> anyhow::Result<Option<Pubkey>> {
+    let message = decode(value)?;
+    Ok(message
+        .instructions
+        .get(index)
+        .and_then(|(program_id_index, ..)| message.keys.get(usize::from(*program_id_index)))
+        .copied())
+}
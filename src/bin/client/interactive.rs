@@ -0,0 +1,494 @@
+//! Interactive menus used when the client is started without subscription flags.
+
+use {
+    crate::{
+        Action, ActionSubscribe, ArgsCommitment, ErrorPolicy, InteractiveConfig, keys,
+        presets::{self, Preset},
+    },
+    clap::ValueEnum,
+    inquire::{
+        Confirm, CustomUserError, MultiSelect, Select, Text, list_option::ListOption,
+        validator::Validation,
+    },
+    std::{collections::BTreeMap, env, time::Duration},
+    tracing::warn,
+};
+
+pub async fn interactive_prompt() -> anyhow::Result<(Action, InteractiveConfig)> {
+    println!("\n🚀 Welcome to Solana Real-Time Indexer CLI\n");
+    println!("💡 Tip: Use your own gRPC endpoint in .env file for faster response!");
+    println!("   Edit .env and set GRPC_ENDPOINT and X_TOKEN with your custom endpoint.\n");
+    
+    // Load endpoint and x-token from .env or use defaults
+    let endpoint = env::var("GRPC_ENDPOINT")
+        .unwrap_or_else(|_| "https://solana-rpc.parafi.tech:10443".to_string());
+    let x_token = env::var("X_TOKEN")
+        .unwrap_or_else(|_| "10443".to_string());
+    
+    let presets = presets::load().unwrap_or_else(|error| {
+        warn!("{error:#}");
+        BTreeMap::new()
+    });
+
+    // Create hierarchical menu - separate display items from selectable items
+    // Display items include headings for visual hierarchy
+    let mut display_items = vec![
+        // Index Data section
+        "📊 INDEX DATA (Subscribe)",
+        "    ├─ Accounts",
+        "    ├─ Transactions",
+        "    ├─ Slots",
+        "    ├─ Blocks",
+        "    ├─ Entries",
+        "    ├─ Block Meta",
+        "    └─ Multiple Types",
+        "",
+        "🔍 QUERY COMMANDS",
+        "    ├─ Get Latest Blockhash",
+        "    ├─ Get Block Height",
+        "    ├─ Get Slot",
+        "    └─ Is Blockhash Valid",
+        "",
+        "❤️  HEALTH CHECK",
+        "    └─ Health Check",
+    ];
+    if !presets.is_empty() {
+        display_items.extend(["", "💾 PRESETS", "    └─ Load Preset"]);
+    }
+    
+    // Create selectable items only (no headings, no empty lines)
+    let selectable_items: Vec<String> = display_items
+        .iter()
+        .filter(|item| {
+            let trimmed = item.trim();
+            !trimmed.is_empty() 
+                && !trimmed.contains("INDEX DATA")
+                && !trimmed.contains("QUERY COMMANDS")
+                && !trimmed.contains("HEALTH CHECK")
+                && (trimmed.contains("├─") || trimmed.contains("└─"))
+        })
+        .map(|s| s.to_string())
+        .collect();
+    
+    // Print the menu structure for visual reference
+    println!("\n{}", display_items.join("\n"));
+    println!();
+    
+    let page_size = selectable_items.len(); // Get length before moving
+    let choice = Select::new(
+        "Select an option (use ↑↓ to navigate):",
+        selectable_items
+    )
+    .with_page_size(page_size.min(20)) // Show all items at once (max 20 for safety)
+    .prompt()?;
+    
+    // Parse the selection - check original choice before trimming
+    let trimmed = choice.trim();
+    
+    // Check if it's a sub-option (contains tree characters)
+    if choice.contains("├─") || choice.contains("└─") {
+        // Extract the option name after the tree character
+        // Use replace to remove the tree characters and spaces, then trim
+        let clean_option = choice
+            .replace("├─", "")
+            .replace("└─", "")
+            .trim()
+            .to_string();
+
+        if clean_option == "Load Preset" {
+            let name = Select::new("Select a preset:", presets.keys().cloned().collect())
+                .prompt()?;
+            let preset = &presets[&name];
+            let commitment = preset
+                .commitment
+                .as_deref()
+                .map(|commitment| {
+                    ArgsCommitment::from_str(commitment, true).map_err(|error| {
+                        anyhow::anyhow!("invalid commitment in preset `{name}`: {error}")
+                    })
+                })
+                .transpose()?;
+            let config = InteractiveConfig {
+                endpoint,
+                x_token,
+                commitment,
+            };
+            return Ok((subscribe_action(preset)?, config));
+        }
+        
+        // Ask about commitment level after selecting an option
+        let commitment_choice = Select::new(
+            "Select commitment level:",
+            vec![
+                "Processed (default, fastest)",
+                "Confirmed (buffered until confirmed)",
+                "Finalized (buffered until finalized)"
+            ]
+        )
+        .prompt()?;
+        
+        let commitment = match commitment_choice {
+            s if s.contains("Processed") => Some(ArgsCommitment::Processed),
+            s if s.contains("Confirmed") => Some(ArgsCommitment::Confirmed),
+            s if s.contains("Finalized") => Some(ArgsCommitment::Finalized),
+            _ => None,
+        };
+        
+        let config = InteractiveConfig {
+            endpoint,
+            x_token,
+            commitment,
+        };
+        
+        match clean_option.as_str() {
+            "Accounts" | "Transactions" | "Slots" | "Blocks" | "Entries" | "Block Meta"
+            | "Multiple Types" => {
+                let index_types = if clean_option == "Multiple Types" {
+                    MultiSelect::new(
+                        "Select the update types to subscribe to:",
+                        INDEX_TYPES.to_vec(),
+                    )
+                    .with_validator(|selected: &[ListOption<&&str>]| {
+                        Ok(if selected.is_empty() {
+                            Validation::Invalid("Select at least one update type".into())
+                        } else {
+                            Validation::Valid
+                        })
+                    })
+                    .prompt()?
+                } else {
+                    vec![clean_option.as_str()]
+                };
+                let mut preset = Preset::default();
+                for index_type in index_types {
+                    interactive_subscribe_prompt(index_type, &mut preset).await?;
+                }
+                preset.commitment = commitment
+                    .and_then(|commitment| commitment.to_possible_value())
+                    .map(|value| value.get_name().to_owned());
+                offer_save(&preset)?;
+                Ok((subscribe_action(&preset)?, config))
+            }
+            "Get Latest Blockhash" => Ok((Action::GetLatestBlockhash, config)),
+            "Get Block Height" => Ok((Action::GetBlockHeight, config)),
+            "Get Slot" => Ok((Action::GetSlot, config)),
+            "Is Blockhash Valid" => {
+                let blockhash = Text::new("Enter blockhash to validate:")
+                    .with_validator(|input: &str| validation(keys::parse_blockhash(input)))
+                    .prompt()?;
+                let blockhash = keys::parse_blockhash(&blockhash)?;
+                Ok((Action::IsBlockhashValid { blockhash }, config))
+            }
+            "Health Check" => Ok((Action::HealthCheck, config)),
+            _ => anyhow::bail!("Invalid option selected: {}", clean_option),
+        }
+    } else if trimmed.is_empty() {
+        // Empty line separator - should not be selectable, but handle it
+        anyhow::bail!("Please select a valid option from the menu.")
+    } else if trimmed.contains("INDEX DATA") || trimmed.contains("QUERY COMMANDS") || trimmed.contains("HEALTH CHECK") || trimmed.contains("PRESETS") {
+        // User selected a heading - show error
+        anyhow::bail!("Please select a specific option (indented items), not a heading.")
+    } else {
+        // Fallback - try to match anyway
+        anyhow::bail!("Please select a valid option from the menu.")
+    }
+}
+
+/// Update types of the index menu, in menu order
+const INDEX_TYPES: [&str; 6] = [
+    "Accounts",
+    "Transactions",
+    "Slots",
+    "Blocks",
+    "Entries",
+    "Block Meta",
+];
+
+/// Ask for the filters of an update type, adding it to the subscription
+async fn interactive_subscribe_prompt(
+    index_type: &str,
+    preset: &mut Preset,
+) -> anyhow::Result<()> {
+    preset.index.push(index_type.to_owned());
+    
+    match index_type {
+        "Accounts" => {
+            println!("\n📝 Account Indexing Options:");
+            
+            let account_input = Text::new("Enter account pubkey(s) to monitor (comma-separated, or press Enter for all):")
+                .with_validator(|input: &str| validation(pubkey_list(input)))
+                .prompt_skippable()?;
+            
+            if let Some(accounts) = account_input
+                && !accounts.trim().is_empty()
+            {
+                preset.accounts_account = pubkey_list(&accounts)?;
+            }
+            
+            let owner_input = Text::new("Enter owner pubkey(s) or program names like token, stake to filter by (comma-separated, or press Enter to skip):")
+                .with_validator(|input: &str| validation(program_list(input)))
+                .prompt_skippable()?;
+            
+            if let Some(owners) = owner_input
+                && !owners.trim().is_empty()
+            {
+                preset.accounts_owner = program_list(&owners)?;
+            }
+        },
+        "Transactions" => {
+            println!("\n📝 Transaction Indexing Options:");
+            
+            let include_accounts = Text::new("Enter account pubkey(s) or program names like token, ata to include in transactions (comma-separated, or press Enter to skip):")
+                .with_validator(|input: &str| validation(program_list(input)))
+                .prompt_skippable()?;
+            
+            if let Some(accounts) = include_accounts
+                && !accounts.trim().is_empty()
+            {
+                preset.transactions_account_include = program_list(&accounts)?;
+            }
+            
+            let vote_txs = Select::new(
+                "Include vote transactions?",
+                vec!["Yes", "No", "All"]
+            )
+            .prompt()?;
+            
+            preset.transactions_vote = match vote_txs {
+                "Yes" => Some(true),
+                "No" => Some(false),
+                _ => None,
+            };
+            
+            let failed_txs = Select::new(
+                "Include failed transactions?",
+                vec!["Yes", "No", "All"]
+            )
+            .prompt()?;
+            
+            preset.transactions_failed = match failed_txs {
+                "Yes" => Some(true),
+                "No" => Some(false),
+                _ => None,
+            };
+        },
+        "Slots" => {
+            println!("\n📝 Slot Indexing - Monitoring all slot updates");
+        },
+        "Blocks" => {
+            println!("\n📝 Block Indexing Options:");
+            
+            let include_txs = Select::new(
+                "Include transactions in blocks?",
+                vec!["Yes", "No"]
+            )
+            .prompt()?;
+            
+            preset.blocks_include_transactions = Some(include_txs == "Yes");
+            
+            let include_accounts = Select::new(
+                "Include accounts in blocks?",
+                vec!["Yes", "No"]
+            )
+            .prompt()?;
+            
+            preset.blocks_include_accounts = Some(include_accounts == "Yes");
+        },
+        "Entries" => {
+            println!("\n📝 Entry Indexing - Monitoring all entry updates");
+        },
+        "Block Meta" => {
+            println!("\n📝 Block Meta Indexing - Monitoring block metadata");
+        },
+        _ => {}
+    }
+    
+    Ok(())
+}
+
+/// Comma-separated pubkeys, normalized
+fn pubkey_list(input: &str) -> anyhow::Result<Vec<String>> {
+    input
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(keys::parse_pubkey)
+        .collect()
+}
+
+/// Comma-separated pubkeys or program names, normalized
+fn program_list(input: &str) -> anyhow::Result<Vec<String>> {
+    input
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(keys::parse_program)
+        .collect()
+}
+
+/// Ask again with the parse error
+fn validation<T>(result: anyhow::Result<T>) -> Result<Validation, CustomUserError> {
+    Ok(match result {
+        Ok(_) => Validation::Valid,
+        Err(error) => Validation::Invalid(error.to_string().into()),
+    })
+}
+
+/// Offer to save the answers under a name, for "Load Preset"
+fn offer_save(preset: &Preset) -> anyhow::Result<()> {
+    if !Confirm::new("Save these answers as a preset?")
+        .with_default(false)
+        .prompt()?
+    {
+        return Ok(());
+    }
+    let name = Text::new("Preset name:").prompt()?;
+    let name = name.trim();
+    anyhow::ensure!(!name.is_empty(), "a preset needs a name");
+    let path = presets::save(name, preset.clone())?;
+    println!("💾 Saved preset `{name}` to {}", path.display());
+    Ok(())
+}
+
+/// The subscription of a preset
+fn subscribe_action(preset: &Preset) -> anyhow::Result<Action> {
+    let mut subscribe_args = ActionSubscribe {
+        accounts: false,
+        accounts_nonempty_txn_signature: None,
+        accounts_account: vec![],
+        accounts_account_path: None,
+        accounts_owner: vec![],
+        accounts_memcmp: vec![],
+        accounts_datasize: None,
+        accounts_datasize_min: None,
+        accounts_datasize_max: None,
+        accounts_mode: crate::compact::AccountsMode::All,
+        accounts_window: Duration::from_secs(1),
+        accounts_token_account_state: false,
+        accounts_lamports: vec![],
+        accounts_data_slice: vec![],
+        slots: false,
+        slots_filter_by_commitment: None,
+        slots_interslot_updates: None,
+        transactions: false,
+        transactions_vote: None,
+        transactions_failed: None,
+        transactions_signature: None,
+        transactions_account_include: vec![],
+        transactions_account_exclude: vec![],
+        transactions_account_required: vec![],
+        transactions_status: false,
+        transactions_status_vote: None,
+        transactions_status_failed: None,
+        transactions_status_signature: None,
+        transactions_status_account_include: vec![],
+        transactions_status_account_exclude: vec![],
+        transactions_status_account_required: vec![],
+        entries: false,
+        blocks: false,
+        blocks_account_include: vec![],
+        blocks_include_transactions: None,
+        blocks_include_accounts: None,
+        blocks_include_entries: None,
+        blocks_meta: false,
+        from_slot: None,
+        ping: None,
+        resub: None,
+        stall_timeout: None,
+        max_messages: None,
+        max_bytes: None,
+        duration: None,
+        until_slot: None,
+        ping_interval_ms: None,
+        stats: false,
+        verify_encoding: false,
+        verify_report: None,
+        latency: false,
+        latency_interval: Duration::from_secs(10),
+        decode_workers: 1,
+        block_chunk_size: None,
+        sample: vec![],
+        max_rate: vec![],
+        commit_boundary: None,
+        dedup_window_slots: None,
+        ordered_by_slot: false,
+        max_reorder_delay_ms: 1000,
+        out_file: None,
+        arrow_file: None,
+        arrow_batch_rows: 1024,
+        sink: vec![],
+        sink_queue: 10_000,
+        sink_overflow: crate::sink::Overflow::Drop,
+        sink_retries: 3,
+        trace_ids: false,
+        out_file_commitment: None,
+        rotate: None,
+        rotate_compression: crate::outfile::Compression::None,
+        retain: vec![],
+        prune_interval: Duration::from_secs(3600),
+        capture: None,
+        encryption_key: None,
+        reassemble_blocks: false,
+        vote_stats_interval_secs: None,
+        aggregate: None,
+        window: Duration::from_secs(60),
+        top: 10,
+        fee_stats: false,
+        instruction_stats: vec![],
+        instruction_stats_window: Duration::from_secs(60),
+        classify_errors: false,
+        program_changes: false,
+        network_stats: false,
+        skip_stats: false,
+        new_mints: false,
+        jito_tips: false,
+        watchlist: None,
+        labels: false,
+        labels_csv: vec![],
+        layouts: vec![],
+        memos: false,
+        memo_regex: vec![],
+        leaders: false,
+        epoch_events: false,
+        resolve_tokens: false,
+        mint_cache: None,
+        checkpoint_file: None,
+        error_policy: ErrorPolicy::Reconnect,
+        dead_letter_dir: None,
+        slow_threshold_ms: None,
+        snapshot: false,
+        dry_run: false,
+        export_request: None,
+        request: None,
+        where_expr: None,
+        grep: None,
+        select: None,
+        data_encoding: crate::encoding::DataEncoding::Hex,
+        max_data_bytes: None,
+        transform: None,
+        script: None,
+        schema_drift_learn_secs: None,
+        envelope: None,
+        watermark_interval: None,
+    };
+    anyhow::ensure!(!preset.index.is_empty(), "no update type in preset");
+    for index in &preset.index {
+        match index.as_str() {
+            "Accounts" => subscribe_args.accounts = true,
+            "Transactions" => subscribe_args.transactions = true,
+            "Slots" => subscribe_args.slots = true,
+            "Blocks" => subscribe_args.blocks = true,
+            "Entries" => subscribe_args.entries = true,
+            "Block Meta" => subscribe_args.blocks_meta = true,
+            index => anyhow::bail!("unknown index `{index}` in preset"),
+        }
+    }
+    subscribe_args.accounts_account = preset.accounts_account.clone();
+    subscribe_args.accounts_owner = preset.accounts_owner.clone();
+    subscribe_args.transactions_account_include = preset.transactions_account_include.clone();
+    subscribe_args.transactions_vote = preset.transactions_vote;
+    subscribe_args.transactions_failed = preset.transactions_failed;
+    subscribe_args.blocks_include_transactions = preset.blocks_include_transactions;
+    subscribe_args.blocks_include_accounts = preset.blocks_include_accounts;
+    Ok(Action::Subscribe(Box::new(subscribe_args)))
+}
@@ -0,0 +1,163 @@
+//! `--jito-tips`: `jitoTip` events for transactions tipping Jito block builders, and
+//! `jitoBundle` events labeling the bundles they end, for studying block-builder economics.
+//!
+//! A tip is what the Jito tip payment accounts received in a transaction, from its balances, so
+//! tips paid through a program are caught too. The transactions referencing the tip accounts
+//! are subscribed on top of the request.
+//!
+//! Bundles aren't visible on chain: the common pattern of a searcher landing its own
+//! transactions is labeled, i.e. the tip transaction and the transactions right before it in
+//! the block with the same fee payer, up to [`BUNDLE_SIZE`]. Only transactions received are
+//! looked at, so bundles with transactions outside the request are labeled partially. A
+//! `jitoBundle` event is written per tip transaction once transactions of a later slot arrive.
+
+use {
+    crate::instructions,
+    serde_json::{Value, json},
+    solana_pubkey::Pubkey,
+    std::collections::BTreeMap,
+    tracing::debug,
+    yellowstone_grpc_proto::prelude::{SubscribeRequest, SubscribeRequestFilterTransactions},
+};
+
+/// Transactions filter added to the request, its transactions are only written as events
+pub const FILTER: &str = "jito-tips";
+
+/// Transactions of a bundle at most
+const BUNDLE_SIZE: usize = 5;
+
+/// Accounts of the Jito tip payment program receiving tips
+const TIP_ACCOUNTS: [Pubkey; 8] = [
+    Pubkey::from_str_const("96gYZGLnJYVFmbjzopPSU6QiEV5fGqZNyN9nmNhvrZU5"),
+    Pubkey::from_str_const("HFqU5x63VTqvQss8hp11i4wVV8bD44PvwucfZ2bU7gRe"),
+    Pubkey::from_str_const("Cw8CFyM9FkoMi7K7Crf6HNQqf4uEMzpKw6QNghXLvLkY"),
+    Pubkey::from_str_const("ADaUMid9yfUytqMBgopwjb2DTLSokTSzL1zt6iGPaS49"),
+    Pubkey::from_str_const("DfXygSm4jCyNCybVYYK6DwvWqjKee8pbDmJGcLWNDXjh"),
+    Pubkey::from_str_const("ADuUkR4vqLUMWXxW9gh6D6L8pMSawimctcNZ5pGwDcEt"),
+    Pubkey::from_str_const("DttWaMuVvTiduZRnguLF7jNxTgiMBZ1hyAumKUiL2KRL"),
+    Pubkey::from_str_const("3AVi9Tg9Uo68tJfuvoKvqKNWKkC5wPdSSdeBnizKZ6jT"),
+];
+
+/// Add the transactions filter for the tip accounts to `request`
+pub fn subscribe(request: &mut SubscribeRequest) {
+    request.transactions.insert(
+        FILTER.to_owned(),
+        SubscribeRequestFilterTransactions {
+            vote: Some(false),
+            failed: Some(false),
+            account_include: TIP_ACCOUNTS.iter().map(ToString::to_string).collect(),
+            ..Default::default()
+        },
+    );
+}
+
+/// A successful transaction received
+struct Received {
+    index: u64,
+    signature: Value,
+    fee_payer: Option<Pubkey>,
+    tip: u64,
+}
+
+#[derive(Default)]
+pub struct JitoTips {
+    slots: BTreeMap<u64, Vec<Received>>,
+    /// Latest slot labeled, late transactions of it or older slots are ignored
+    emitted_slot: Option<u64>,
+}
+
+impl JitoTips {
+    /// Take an update, returns its `jitoTip` event and the `jitoBundle` events of the slots
+    /// completed before it
+    pub fn observe(&mut self, kind: &str, value: &Value) -> Vec<(&'static str, Value)> {
+        let mut events = vec![];
+        if kind != "transaction" || !value["tx"]["meta"]["err"].is_null() {
+            return events;
+        }
+        let Some(slot) = value["slot"].as_u64() else {
+            return events;
+        };
+        if self.emitted_slot.is_some_and(|emitted| slot <= emitted) {
+            return events;
+        }
+        let keys = match instructions::account_keys(value) {
+            Ok(keys) => keys,
+            Err(error) => {
+                debug!("failed to parse transaction accounts: {error:#}");
+                return events;
+            }
+        };
+        let meta = &value["tx"]["meta"];
+        let balance = |field: &str, position: usize| meta[field][position].as_u64();
+        let tips = keys
+            .iter()
+            .enumerate()
+            .filter(|(_, key)| TIP_ACCOUNTS.contains(key))
+            .filter_map(|(position, key)| {
+                let tip = balance("postBalances", position)?
+                    .saturating_sub(balance("preBalances", position)?);
+                (tip > 0).then_some((key, tip))
+            })
+            .collect::<Vec<_>>();
+        let tip = tips.iter().map(|(_, tip)| tip).sum();
+        let received = Received {
+            index: value["index"].as_u64().unwrap_or_default(),
+            signature: value["signature"].clone(),
+            fee_payer: keys.first().copied(),
+            tip,
+        };
+        if tip > 0 {
+            events.push((
+                "jitoTip",
+                json!({
+                    "slot": slot,
+                    "signature": received.signature,
+                    "index": received.index,
+                    "feePayer": received.fee_payer.map(|key| key.to_string()),
+                    "tipLamports": tip,
+                    "tipAccounts": tips
+                        .iter()
+                        .map(|(key, _)| key.to_string())
+                        .collect::<Vec<_>>(),
+                }),
+            ));
+        }
+        self.slots.entry(slot).or_default().push(received);
+
+        let pending = self.slots.split_off(&slot);
+        for (slot, mut received) in std::mem::replace(&mut self.slots, pending) {
+            received.sort_by_key(|received| received.index);
+            for (position, tipped) in received.iter().enumerate() {
+                if tipped.tip == 0 {
+                    continue;
+                }
+                let mut first = position;
+                while first > 0
+                    && position - first + 1 < BUNDLE_SIZE
+                    && received[first - 1].index + 1 == received[first].index
+                    && received[first - 1].fee_payer == tipped.fee_payer
+                {
+                    first -= 1;
+                }
+                let bundle = &received[first..=position];
+                events.push((
+                    "jitoBundle",
+                    json!({
+                        "slot": slot,
+                        "feePayer": tipped.fee_payer.map(|key| key.to_string()),
+                        "tipLamports": tipped.tip,
+                        "firstIndex": bundle[0].index,
+                        "lastIndex": tipped.index,
+                        "transactions": bundle.len(),
+                        "signatures": bundle
+                            .iter()
+                            .map(|received| received.signature.clone())
+                            .collect::<Vec<_>>(),
+                    }),
+                ));
+            }
+            self.emitted_slot = Some(slot);
+        }
+        events
+    }
+}
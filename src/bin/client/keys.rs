@@ -0,0 +1,137 @@
+//! Value parsers for pubkeys, signatures and blockhashes, so a typo fails when the arguments are
+//! parsed instead of server-side or when updates are decoded.
+//!
+//! Values are normalized to base58. A pubkey can also be given as a keypair file (`.json`, as
+//! written by `solana-keygen`), standing for its public key. Owners and included transaction
+//! accounts also take the names of common programs, see [`PROGRAM_ALIASES`].
+
+use {
+    crate::{
+        sync::VOTE_PROGRAM,
+        tokens::{TOKEN_2022_PROGRAM, TOKEN_PROGRAM},
+    },
+    anyhow::Context,
+    solana_hash::Hash,
+    solana_pubkey::Pubkey,
+    solana_signature::Signature,
+    std::{fs, path::Path, str::FromStr},
+};
+
+/// Names accepted for program ids in `--accounts-owner` and `--transactions-account-include`
+pub const PROGRAM_ALIASES: [(&str, Pubkey); 7] = [
+    ("token", TOKEN_PROGRAM),
+    ("token-2022", TOKEN_2022_PROGRAM),
+    (
+        "stake",
+        Pubkey::from_str_const("Stake11111111111111111111111111111111111111"),
+    ),
+    ("vote", VOTE_PROGRAM),
+    (
+        "system",
+        Pubkey::from_str_const("11111111111111111111111111111111"),
+    ),
+    (
+        "memo",
+        Pubkey::from_str_const("MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr"),
+    ),
+    (
+        "ata",
+        Pubkey::from_str_const("ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL"),
+    ),
+];
+
+/// Parse a program alias, or a pubkey like [`parse_pubkey`]
+pub fn parse_program(value: &str) -> anyhow::Result<String> {
+    let value = value.trim();
+    if let Some((_, program)) = PROGRAM_ALIASES
+        .iter()
+        .find(|(alias, _)| alias.eq_ignore_ascii_case(value))
+    {
+        return Ok(program.to_string());
+    }
+    parse_pubkey(value).map_err(|error| {
+        // A word rather than a mistyped address
+        if value.len() < 32 && value.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+            let aliases = PROGRAM_ALIASES.map(|(alias, _)| alias).join(", ");
+            anyhow::anyhow!("unknown program `{value}`, expected a pubkey or one of {aliases}")
+        } else {
+            error
+        }
+    })
+}
+
+/// Parse a base58 pubkey or the path of a `.json` keypair file
+pub fn parse_pubkey(value: &str) -> anyhow::Result<String> {
+    let value = value.trim();
+    if value.ends_with(".json") {
+        return keypair_pubkey(Path::new(value)).map(|pubkey| pubkey.to_string());
+    }
+    Pubkey::from_str(value)
+        .map(|pubkey| pubkey.to_string())
+        .map_err(|_| invalid(value, "pubkey", 32))
+}
+
+pub fn parse_signature(value: &str) -> anyhow::Result<String> {
+    let value = value.trim();
+    Signature::from_str(value)
+        .map(|signature| signature.to_string())
+        .map_err(|_| invalid(value, "signature", 64))
+}
+
+pub fn parse_blockhash(value: &str) -> anyhow::Result<String> {
+    let value = value.trim();
+    Hash::from_str(value)
+        .map(|hash| hash.to_string())
+        .map_err(|_| invalid(value, "blockhash", 32))
+}
+
+/// Say why `value` isn't a base58 encoded `len` bytes
+fn invalid(value: &str, what: &str, len: usize) -> anyhow::Error {
+    if value.is_empty() {
+        return anyhow::anyhow!("empty {what}");
+    }
+    if let Some(c) = value.chars().find(|c| !is_base58(*c)) {
+        let hint = if matches!(c, '0' | 'O' | 'I' | 'l') {
+            " (base58 has no 0, O, I or l)"
+        } else {
+            ""
+        };
+        return anyhow::anyhow!("invalid {what} `{value}`: `{c}` isn't base58{hint}");
+    }
+    let decoded = bs58::decode(value)
+        .into_vec()
+        .map_or(0, |bytes| bytes.len());
+    let kind = match decoded {
+        32 if len != 32 => " (a pubkey or hash?)",
+        64 if len != 64 => " (a signature?)",
+        _ => "",
+    };
+    anyhow::anyhow!("invalid {what} `{value}`: {decoded} bytes instead of {len}{kind}")
+}
+
+const fn is_base58(c: char) -> bool {
+    c.is_ascii_alphanumeric() && !matches!(c, '0' | 'O' | 'I' | 'l')
+}
+
+/// Public key of a keypair file, a JSON array of the 32 byte seed and the 32 byte public key
+fn keypair_pubkey(path: &Path) -> anyhow::Result<Pubkey> {
+    let text = fs::read_to_string(path)
+        .with_context(|| format!("failed to read keypair file {}", path.display()))?;
+    let bytes = serde_json::from_str::<Vec<u8>>(&text)
+        .ok()
+        .filter(|bytes| bytes.len() == 64)
+        .with_context(|| {
+            format!(
+                "{} isn't a keypair file, expected a JSON array of 64 bytes",
+                path.display()
+            )
+        })?;
+    let (seed, public) = bytes.split_at(32);
+    ring::signature::Ed25519KeyPair::from_seed_and_public_key(seed, public).map_err(|_| {
+        anyhow::anyhow!(
+            "{}: the public key doesn't match the secret key",
+            path.display()
+        )
+    })?;
+    Ok(Pubkey::try_from(public).expect("32 bytes"))
+}
@@ -0,0 +1,95 @@
+//! `--labels`: name known addresses in updates, such as "Raydium AMM v4" for a program id or
+//! an account owner, from a dataset bundled with the client and `--labels-csv` files.
+//!
+//! Label files have an `address,label` line per address; blank lines, `#` comments and a
+//! header line are skipped. Later files override earlier ones and the bundled labels. Labeled
+//! updates get a `labels` object mapping the addresses they reference to their label.
+
+use {
+    crate::instructions,
+    anyhow::Context,
+    serde_json::Value,
+    solana_pubkey::Pubkey,
+    std::{collections::HashMap, fs, path::PathBuf},
+    tracing::{debug, info},
+};
+
+const BUNDLED: &str = include_str!("../../../data/labels.csv");
+
+pub struct Labels {
+    labels: HashMap<String, String>,
+}
+
+impl Labels {
+    pub fn load(paths: &[PathBuf]) -> anyhow::Result<Self> {
+        let mut labels = HashMap::new();
+        parse(BUNDLED, &mut labels).context("invalid bundled labels")?;
+        for path in paths {
+            let text = fs::read_to_string(path)
+                .with_context(|| format!("failed to read labels {}", path.display()))?;
+            parse(&text, &mut labels)
+                .with_context(|| format!("invalid labels {}", path.display()))?;
+        }
+        info!("labels: {} addresses", labels.len());
+        Ok(Self { labels })
+    }
+
+    /// Add the `labels` object to an update referencing labeled addresses
+    pub fn annotate(&self, kind: &str, value: &mut Value) {
+        let mut labels = serde_json::Map::new();
+        visit_addresses(kind, value, &mut |address| {
+            if let Some(label) = self.labels.get(address) {
+                labels.insert(address.to_owned(), label.clone().into());
+            }
+        });
+        if !labels.is_empty() {
+            value["labels"] = Value::Object(labels);
+        }
+    }
+}
+
+fn parse(text: &str, labels: &mut HashMap<String, String>) -> anyhow::Result<()> {
+    for (index, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (address, label) = line
+            .split_once(',')
+            .with_context(|| format!("line {}: expected address,label", index + 1))?;
+        let (address, label) = (
+            address.trim().trim_matches('"'),
+            label.trim().trim_matches('"'),
+        );
+        if address.parse::<Pubkey>().is_err() {
+            anyhow::ensure!(
+                index == 0,
+                "line {}: invalid address `{address}`",
+                index + 1
+            );
+            continue;
+        }
+        labels.insert(address.to_owned(), label.to_owned());
+    }
+    Ok(())
+}
+
+/// Call `visit` with every string of an update, and the account keys of transactions, among
+/// which are the addresses it references
+pub fn visit_addresses(kind: &str, value: &Value, visit: &mut impl FnMut(&str)) {
+    fn strings(value: &Value, visit: &mut impl FnMut(&str)) {
+        match value {
+            Value::String(string) => visit(string),
+            Value::Array(values) => values.iter().for_each(|value| strings(value, visit)),
+            Value::Object(map) => map.values().for_each(|value| strings(value, visit)),
+            _ => {}
+        }
+    }
+    strings(value, visit);
+    if kind == "transaction" {
+        match instructions::account_keys(value) {
+            Ok(keys) => keys.iter().for_each(|key| visit(&key.to_string())),
+            Err(error) => debug!("failed to decode transaction keys: {error:#}"),
+        }
+    }
+}
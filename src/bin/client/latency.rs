@@ -0,0 +1,95 @@
+//! `--latency`: delay between the server `created_at` of an update and its local receipt.
+
+use {
+    crate::{print_query_result, StatsKind},
+    std::{
+        collections::HashMap,
+        time::{Duration, Instant, SystemTime},
+    },
+};
+
+pub struct LatencyReport {
+    interval: Duration,
+    started_at: Instant,
+    /// Latencies in microseconds per update type since the last report
+    samples: HashMap<StatsKind, Vec<u64>>,
+    /// Updates created "after" they were received, a sign of clock skew
+    skewed: u64,
+}
+
+impl LatencyReport {
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            started_at: Instant::now(),
+            samples: HashMap::new(),
+            skewed: 0,
+        }
+    }
+
+    pub fn record(&mut self, kind: StatsKind, created_at: SystemTime, received_at: SystemTime) {
+        let latency = match received_at.duration_since(created_at) {
+            Ok(latency) => latency,
+            Err(_) => {
+                self.skewed += 1;
+                Duration::ZERO
+            }
+        };
+        self.samples
+            .entry(kind)
+            .or_default()
+            .push(latency.as_micros() as u64);
+
+        if self.started_at.elapsed() >= self.interval {
+            self.report();
+        }
+    }
+
+    /// Print percentiles collected since the previous report and start a new window
+    pub fn report(&mut self) {
+        let window = self.started_at.elapsed();
+        self.started_at = Instant::now();
+        if self.samples.is_empty() {
+            return;
+        }
+
+        let mut data = StatsKind::ALL
+            .iter()
+            .filter_map(|kind| {
+                let mut samples = self.samples.remove(kind)?;
+                samples.sort_unstable();
+                Some((
+                    kind.label().to_owned(),
+                    format!(
+                        "count {}, p50 {}, p95 {}, p99 {}, max {}",
+                        samples.len(),
+                        format_micros(percentile(&samples, 0.50)),
+                        format_micros(percentile(&samples, 0.95)),
+                        format_micros(percentile(&samples, 0.99)),
+                        format_micros(samples[samples.len() - 1]),
+                    ),
+                ))
+            })
+            .collect::<Vec<_>>();
+        if self.skewed > 0 {
+            data.push((
+                "clock skew".to_owned(),
+                format!("{} updates created after receipt, counted as 0", self.skewed),
+            ));
+            self.skewed = 0;
+        }
+        print_query_result(
+            &format!("Latency created_at → received (last {:.1?})", window),
+            &data,
+        );
+    }
+}
+
+/// Nearest-rank percentile of sorted, non-empty samples
+pub fn percentile(sorted: &[u64], q: f64) -> u64 {
+    sorted[((sorted.len() - 1) as f64 * q).round() as usize]
+}
+
+pub fn format_micros(micros: u64) -> String {
+    format!("{:.2?}", Duration::from_micros(micros))
+}
@@ -0,0 +1,270 @@
+//! `--layouts`: decode the accounts of programs without an Anchor IDL, e.g. native programs,
+//! from Borsh layouts described in JSON files, or YAML ones (`.yaml`/`.yml`) with the `yaml`
+//! feature.
+//!
+//! A file is a list of layouts matched by owner, a hex `discriminator` prefix of the data
+//! (empty to match any) and an optional `dataSize`; the first matching layout decodes the
+//! fields after the discriminator:
+//!
+//! ```json
+//! [{"name": "Pool", "owner": "<program>", "discriminator": "f19a6d0411b16dbc", "fields": [
+//!     {"name": "authority", "type": "pubkey"},
+//!     {"name": "fees", "type": {"array": ["u16", 4]}},
+//!     {"name": "mints", "type": {"vec": "pubkey"}},
+//!     {"name": "closed", "type": {"option": "i64"}},
+//!     {"name": "status", "type": {"enum": [{"name": "Active"}, {"name": "Paused", "fields": []}]}}
+//! ]}]
+//! ```
+//!
+//! Types are `u8` to `u128`, `i8` to `i128`, `f32`, `f64`, `bool`, `pubkey`, `string`, `bytes`
+//! (hex) and `array`, `vec`, `option`, `struct` and `enum` of them. Matching account updates
+//! get a `decoded` object with the layout name and the fields; 128-bit integers are strings.
+//! Accounts whose data doesn't fit their layout, e.g. cut by `--accounts-data-slice`, are left
+//! as is.
+
+use {
+    anyhow::Context,
+    serde::Deserialize,
+    serde_json::{Map, Value, json},
+    std::{
+        fs,
+        path::{Path, PathBuf},
+    },
+    tracing::{debug, info},
+};
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct Layout {
+    name: String,
+    owner: String,
+    #[serde(default)]
+    discriminator: String,
+    /// Decoded discriminator
+    #[serde(skip)]
+    prefix: Vec<u8>,
+    data_size: Option<usize>,
+    fields: Vec<Field>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Field {
+    name: String,
+    #[serde(rename = "type")]
+    ty: Type,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Type {
+    Primitive(Primitive),
+    Composite(Composite),
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Primitive {
+    U8,
+    U16,
+    U32,
+    U64,
+    U128,
+    I8,
+    I16,
+    I32,
+    I64,
+    I128,
+    F32,
+    F64,
+    Bool,
+    Pubkey,
+    String,
+    Bytes,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Composite {
+    Array(Box<Type>, usize),
+    Vec(Box<Type>),
+    Option(Box<Type>),
+    Struct(Vec<Field>),
+    Enum(Vec<Variant>),
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Variant {
+    name: String,
+    #[serde(default)]
+    fields: Vec<Field>,
+}
+
+/// Layouts of a file, YAML by its extension
+fn parse(path: &Path, text: &str) -> anyhow::Result<Vec<Layout>> {
+    let yaml = path
+        .extension()
+        .is_some_and(|extension| extension == "yaml" || extension == "yml");
+    if !yaml {
+        return Ok(serde_json::from_str(text)?);
+    }
+    #[cfg(feature = "yaml")]
+    return Ok(serde_yaml::from_str(text)?);
+    #[cfg(not(feature = "yaml"))]
+    anyhow::bail!("YAML layouts require the `yaml` feature");
+}
+
+pub struct Layouts {
+    layouts: Vec<Layout>,
+}
+
+impl Layouts {
+    pub fn load(paths: &[PathBuf]) -> anyhow::Result<Self> {
+        let mut layouts = vec![];
+        for path in paths {
+            let text = fs::read_to_string(path)
+                .with_context(|| format!("failed to read layouts {}", path.display()))?;
+            let mut file = parse(path, &text)
+                .with_context(|| format!("invalid layouts {}", path.display()))?;
+            for layout in &mut file {
+                layout.prefix = hex::decode(&layout.discriminator).with_context(|| {
+                    format!(
+                        "invalid discriminator of {} in {}",
+                        layout.name,
+                        path.display()
+                    )
+                })?;
+            }
+            layouts.extend(file);
+        }
+        info!("layouts: {} account layouts", layouts.len());
+        Ok(Self { layouts })
+    }
+
+    /// Add the `decoded` object to an account update matching a layout
+    pub fn annotate(&self, kind: &str, value: &mut Value) {
+        if kind != "account" {
+            return;
+        }
+        let owner = value["owner"].as_str().unwrap_or_default();
+        let Some(data) = value["data"]
+            .as_str()
+            .and_then(|data| hex::decode(data).ok())
+        else {
+            return;
+        };
+        let Some(layout) = self.layouts.iter().find(|layout| {
+            layout.owner == owner
+                && data.starts_with(&layout.prefix)
+                && layout.data_size.is_none_or(|size| size == data.len())
+        }) else {
+            return;
+        };
+        let mut reader = Reader {
+            data: &data[layout.prefix.len()..],
+        };
+        match reader.fields(&layout.fields) {
+            Ok(fields) => value["decoded"] = json!({ "layout": layout.name, "fields": fields }),
+            Err(error) => debug!(
+                "account {} doesn't fit layout {}: {error:#}",
+                value["pubkey"], layout.name
+            ),
+        }
+    }
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl Reader<'_> {
+    fn take(&mut self, len: usize) -> anyhow::Result<&[u8]> {
+        anyhow::ensure!(len <= self.data.len(), "data ends early");
+        let (taken, rest) = self.data.split_at(len);
+        self.data = rest;
+        Ok(taken)
+    }
+
+    fn array<const N: usize>(&mut self) -> anyhow::Result<[u8; N]> {
+        Ok(self.take(N)?.try_into().expect("took N bytes"))
+    }
+
+    /// Borsh length prefix of strings, vecs and bytes
+    fn len(&mut self) -> anyhow::Result<usize> {
+        Ok(u32::from_le_bytes(self.array()?) as usize)
+    }
+
+    fn fields(&mut self, fields: &[Field]) -> anyhow::Result<Map<String, Value>> {
+        fields
+            .iter()
+            .map(|field| {
+                let value = self
+                    .value(&field.ty)
+                    .with_context(|| format!("field {}", field.name))?;
+                Ok((field.name.clone(), value))
+            })
+            .collect()
+    }
+
+    fn value(&mut self, ty: &Type) -> anyhow::Result<Value> {
+        Ok(match ty {
+            Type::Primitive(primitive) => match primitive {
+                Primitive::U8 => u8::from_le_bytes(self.array()?).into(),
+                Primitive::U16 => u16::from_le_bytes(self.array()?).into(),
+                Primitive::U32 => u32::from_le_bytes(self.array()?).into(),
+                Primitive::U64 => u64::from_le_bytes(self.array()?).into(),
+                Primitive::U128 => u128::from_le_bytes(self.array()?).to_string().into(),
+                Primitive::I8 => i8::from_le_bytes(self.array()?).into(),
+                Primitive::I16 => i16::from_le_bytes(self.array()?).into(),
+                Primitive::I32 => i32::from_le_bytes(self.array()?).into(),
+                Primitive::I64 => i64::from_le_bytes(self.array()?).into(),
+                Primitive::I128 => i128::from_le_bytes(self.array()?).to_string().into(),
+                Primitive::F32 => f32::from_le_bytes(self.array()?).into(),
+                Primitive::F64 => f64::from_le_bytes(self.array()?).into(),
+                Primitive::Bool => match self.array::<1>()? {
+                    [0] => false.into(),
+                    [1] => true.into(),
+                    [byte] => anyhow::bail!("invalid bool {byte}"),
+                },
+                Primitive::Pubkey => bs58::encode(self.take(32)?).into_string().into(),
+                Primitive::String => {
+                    let len = self.len()?;
+                    String::from_utf8(self.take(len)?.to_vec())
+                        .context("invalid UTF-8")?
+                        .into()
+                }
+                Primitive::Bytes => {
+                    let len = self.len()?;
+                    hex::encode(self.take(len)?).into()
+                }
+            },
+            Type::Composite(Composite::Array(item, len)) => (0..*len)
+                .map(|_| self.value(item))
+                .collect::<anyhow::Result<_>>()?,
+            Type::Composite(Composite::Vec(item)) => {
+                let len = self.len()?;
+                (0..len)
+                    .map(|_| self.value(item))
+                    .collect::<anyhow::Result<_>>()?
+            }
+            Type::Composite(Composite::Option(item)) => match self.array::<1>()? {
+                [0] => Value::Null,
+                [1] => self.value(item)?,
+                [tag] => anyhow::bail!("invalid option tag {tag}"),
+            },
+            Type::Composite(Composite::Struct(fields)) => Value::Object(self.fields(fields)?),
+            Type::Composite(Composite::Enum(variants)) => {
+                let [tag] = self.array::<1>()?;
+                let variant = variants
+                    .get(tag as usize)
+                    .with_context(|| format!("invalid enum variant {tag}"))?;
+                if variant.fields.is_empty() {
+                    variant.name.clone().into()
+                } else {
+                    json!({ variant.name.clone(): self.fields(&variant.fields)? })
+                }
+            }
+        })
+    }
+}
@@ -0,0 +1,166 @@
+//! `--leaders`: annotate slot and block meta updates with the leader of the slot, so skipped
+//! and dead slots can be correlated with the validators producing them.
+//!
+//! Leader schedules are fetched per epoch over JSON-RPC: the current one at startup, the next
+//! one in the background once an epoch is entered. Slots of an epoch whose schedule isn't
+//! loaded (yet) are written without a leader.
+
+use {
+    crate::rpc::RpcClient,
+    anyhow::Context,
+    serde_json::{Value, json},
+    solana_epoch_schedule::EpochSchedule,
+    std::{
+        collections::HashMap,
+        sync::{Arc, Mutex},
+        time::{Duration, Instant},
+    },
+    tracing::{info, warn},
+    yellowstone_grpc_proto::prelude::CommitmentLevel,
+};
+
+/// A failed leader schedule fetch is retried after this long
+const RETRY_AFTER: Duration = Duration::from_secs(10);
+
+/// Leaders of an epoch, stored as indexes into the leader identities to stay small
+struct EpochLeaders {
+    identities: Vec<String>,
+    slots: Vec<u32>,
+}
+
+#[derive(Default)]
+struct Schedules {
+    epochs: HashMap<u64, EpochLeaders>,
+    /// Fetches in flight or failed, by epoch
+    fetching: HashMap<u64, Instant>,
+}
+
+pub struct Leaders {
+    rpc: Arc<RpcClient>,
+    commitment: CommitmentLevel,
+    schedule: EpochSchedule,
+    schedules: Arc<Mutex<Schedules>>,
+}
+
+impl Leaders {
+    /// Fetch the epoch schedule and the leader schedule of the current epoch
+    pub async fn load(rpc: RpcClient, commitment: CommitmentLevel) -> anyhow::Result<Self> {
+        let config = json!([{ "commitment": commitment.as_str_name().to_lowercase() }]);
+        let schedule = rpc.epoch_schedule().await?;
+        let slot = rpc
+            .call("getSlot", config)
+            .await?
+            .as_u64()
+            .context("invalid getSlot response")?;
+
+        let leaders = Self {
+            rpc: Arc::new(rpc),
+            commitment,
+            schedule,
+            schedules: Arc::default(),
+        };
+        let epoch = leaders.schedule.get_epoch(slot);
+        let epoch_leaders = fetch(&leaders.rpc, &leaders.schedule, commitment, epoch).await?;
+        info!("leader schedule of epoch {epoch} loaded");
+        leaders.lock().epochs.insert(epoch, epoch_leaders);
+        Ok(leaders)
+    }
+
+    /// Epoch of `slot`
+    pub fn epoch(&self, slot: u64) -> u64 {
+        self.schedule.get_epoch(slot)
+    }
+
+    /// Identity of the leader of `slot`, fetching schedules of epochs not loaded yet
+    pub fn leader(&self, slot: u64) -> Option<String> {
+        let (epoch, index) = self.schedule.get_epoch_and_slot_index(slot);
+        let mut schedules = self.lock();
+        // The next schedule is known once an epoch starts, fetch it ahead of time
+        if schedules.epochs.contains_key(&epoch) && !schedules.epochs.contains_key(&(epoch + 1)) {
+            self.spawn_fetch(&mut schedules, epoch + 1);
+        }
+        match schedules.epochs.get(&epoch) {
+            Some(leaders) => leaders
+                .slots
+                .get(index as usize)
+                .and_then(|identity| leaders.identities.get(*identity as usize))
+                .cloned(),
+            None => {
+                self.spawn_fetch(&mut schedules, epoch);
+                None
+            }
+        }
+    }
+
+    fn spawn_fetch(&self, schedules: &mut Schedules, epoch: u64) {
+        if schedules
+            .fetching
+            .get(&epoch)
+            .is_some_and(|started_at| started_at.elapsed() < RETRY_AFTER)
+        {
+            return;
+        }
+        schedules.fetching.insert(epoch, Instant::now());
+
+        let rpc = Arc::clone(&self.rpc);
+        let schedule = self.schedule.clone();
+        let commitment = self.commitment;
+        let schedules = Arc::clone(&self.schedules);
+        tokio::spawn(async move {
+            match fetch(&rpc, &schedule, commitment, epoch).await {
+                Ok(epoch_leaders) => {
+                    info!("leader schedule of epoch {epoch} loaded");
+                    let mut schedules = schedules.lock().expect("leader schedules lock poisoned");
+                    schedules.fetching.remove(&epoch);
+                    schedules.epochs.insert(epoch, epoch_leaders);
+                    // Keep the previous epoch for late updates, drop older ones
+                    schedules.epochs.retain(|loaded, _| *loaded + 1 >= epoch);
+                }
+                Err(error) => warn!("failed to fetch leader schedule of epoch {epoch}: {error:#}"),
+            }
+        });
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Schedules> {
+        self.schedules
+            .lock()
+            .expect("leader schedules lock poisoned")
+    }
+}
+
+async fn fetch(
+    rpc: &RpcClient,
+    schedule: &EpochSchedule,
+    commitment: CommitmentLevel,
+    epoch: u64,
+) -> anyhow::Result<EpochLeaders> {
+    let first_slot = schedule.get_first_slot_in_epoch(epoch);
+    let leaders = rpc
+        .call(
+            "getLeaderSchedule",
+            json!([first_slot, { "commitment": commitment.as_str_name().to_lowercase() }]),
+        )
+        .await?;
+    let leaders = leaders
+        .as_object()
+        .with_context(|| format!("no leader schedule for epoch {epoch} yet"))?;
+    let mut epoch_leaders = EpochLeaders {
+        identities: Vec::with_capacity(leaders.len()),
+        slots: vec![u32::MAX; schedule.get_slots_in_epoch(epoch) as usize],
+    };
+    for (identity, indexes) in leaders {
+        let position = epoch_leaders.identities.len() as u32;
+        epoch_leaders.identities.push(identity.clone());
+        for index in indexes
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(Value::as_u64)
+        {
+            if let Some(slot) = epoch_leaders.slots.get_mut(index as usize) {
+                *slot = position;
+            }
+        }
+    }
+    Ok(epoch_leaders)
+}
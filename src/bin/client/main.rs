@@ -0,0 +1,3797 @@
+use {
+    anyhow::Context,
+    backoff::future::retry,
+    clap::{Parser, Subcommand, ValueEnum},
+    futures::{
+        future::TryFutureExt,
+        sink::SinkExt,
+        stream::{FuturesOrdered, StreamExt},
+    },
+    indicatif::{MultiProgress, ProgressBar, ProgressStyle},
+    tracing::{Instrument, error, info, info_span, warn},
+    serde_json::{json, Value},
+    solana_hash::Hash,
+    solana_pubkey::Pubkey,
+    solana_signature::Signature,
+    solana_transaction_status::UiTransactionEncoding,
+    std::{
+        collections::{HashMap, VecDeque},
+        env,
+        fs::File,
+        io::{self, Write},
+        path::PathBuf,
+        process::ExitCode,
+        str::FromStr,
+        sync::Arc,
+        time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+    },
+    tokio::{fs, sync::Mutex, task::JoinHandle},
+    tonic::transport::{channel::ClientTlsConfig, Certificate},
+    yellowstone_grpc_client::{
+        GeyserGrpcBuilder, GeyserGrpcClient, GeyserGrpcClientError, Interceptor,
+    },
+    yellowstone_grpc_proto::{
+        convert_from,
+        geyser::SlotStatus,
+        prelude::{
+            subscribe_request_filter_accounts_filter::Filter as AccountsFilterOneof,
+            subscribe_request_filter_accounts_filter_lamports::Cmp as AccountsFilterLamports,
+            subscribe_request_filter_accounts_filter_memcmp::Data as AccountsFilterMemcmpOneof,
+            subscribe_update::UpdateOneof, CommitmentLevel, SubscribeRequest,
+            SubscribeRequestAccountsDataSlice, SubscribeRequestFilterAccounts,
+            SubscribeRequestFilterAccountsFilter, SubscribeRequestFilterAccountsFilterLamports,
+            SubscribeRequestFilterAccountsFilterMemcmp, SubscribeRequestFilterBlocks,
+            SubscribeRequestFilterBlocksMeta, SubscribeRequestFilterEntry,
+            SubscribeRequestFilterSlots, SubscribeRequestFilterTransactions, SubscribeRequestPing,
+            SubscribeUpdate, SubscribeUpdateAccountInfo, SubscribeUpdateEntry, SubscribeUpdateTransactionInfo,
+            GetBlockHeightResponse, GetLatestBlockhashResponse, GetSlotResponse,
+            IsBlockhashValidResponse,
+        },
+        prost::Message,
+    },
+};
+
+mod account;
+mod aggregate;
+#[cfg(feature = "arrow")]
+mod arrowfile;
+mod auth;
+mod bench;
+mod blockchunks;
+mod budget;
+mod capture;
+mod checkpoint;
+mod compact;
+mod compare;
+mod computebudget;
+mod config;
+#[cfg(feature = "control-api")]
+mod control;
+mod crypto;
+mod daemon;
+mod deadletter;
+mod dedup;
+mod discriminators;
+mod drift;
+mod dryrun;
+mod encoding;
+mod envelope;
+mod epochs;
+mod errors;
+mod examples;
+mod exit;
+mod expr;
+mod failover;
+mod fees;
+mod healthwatch;
+#[cfg(feature = "interactive")]
+mod interactive;
+mod instructions;
+mod jito;
+mod keys;
+mod labels;
+mod latency;
+mod layouts;
+mod leaders;
+mod memos;
+mod metrics;
+mod mints;
+mod network;
+mod outfile;
+#[cfg(feature = "parquet")]
+mod parquetfile;
+mod ping;
+#[cfg(feature = "interactive")]
+mod presets;
+mod profiles;
+mod programs;
+mod programstate;
+mod query;
+mod raw;
+mod reassemble;
+mod replay;
+mod reorder;
+mod request;
+mod resources;
+mod retention;
+mod rpc;
+mod schema;
+#[cfg(feature = "scripting")]
+mod script;
+mod secrets;
+mod select;
+mod session;
+mod sink;
+mod skips;
+mod slotbuffer;
+mod snapshot;
+mod state;
+mod style;
+mod sync;
+mod telemetry;
+mod throttle;
+mod tracecontext;
+mod tokens;
+mod topn;
+#[cfg(feature = "wasm")]
+mod transform;
+mod units;
+#[cfg(feature = "verify-encoding")]
+mod verify;
+mod votes;
+mod watchlist;
+mod watermark;
+
+type SlotsFilterMap = HashMap<String, SubscribeRequestFilterSlots>;
+type AccountFilterMap = HashMap<String, SubscribeRequestFilterAccounts>;
+type TransactionsFilterMap = HashMap<String, SubscribeRequestFilterTransactions>;
+type TransactionsStatusFilterMap = HashMap<String, SubscribeRequestFilterTransactions>;
+type EntryFilterMap = HashMap<String, SubscribeRequestFilterEntry>;
+type BlocksFilterMap = HashMap<String, SubscribeRequestFilterBlocks>;
+type BlocksMetaFilterMap = HashMap<String, SubscribeRequestFilterBlocksMeta>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Compression {
+    Gzip,
+    Zstd,
+}
+
+impl FromStr for Compression {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "gzip" => Ok(Compression::Gzip),
+            "zstd" => Ok(Compression::Zstd),
+            _ => Err(anyhow::anyhow!("Unknown compression type: {}", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Parser)]
+#[clap(author, version, about)]
+struct Args {
+    #[clap(short, long, default_value_t = String::from("https://solana-rpc.parafi.tech:10443"))]
+    /// Service endpoint
+    endpoint: String,
+
+    /// Endpoints to fail over to, in order, when the current one fails (repeatable)
+    #[clap(long)]
+    failover_endpoint: Vec<String>,
+
+    /// How often endpoints preferred over the active one are health checked to fail back, e.g. 30s or 5m
+    #[clap(long, alias = "failback-interval-secs", default_value = "30s", value_parser = units::parse_duration)]
+    failback_interval: Duration,
+
+    /// Path of a certificate authority file
+    #[clap(long)]
+    ca_certificate: Option<PathBuf>,
+
+    /// Access token, or a secret reference: `vault://<path>#<field>` / `aws-sm://<secret-id>[#<field>]`
+    #[clap(long, default_value_t = String::from("10443"))]
+    x_token: String,
+
+    /// Named endpoint/token/commitment profile from `~/.config/solana-indexer/profiles.toml`
+    #[clap(long)]
+    profile: Option<String>,
+
+    /// Print the configuration in effect as `SOLANA_INDEXER_*` variables, by source, and exit
+    #[clap(long)]
+    print_config: bool,
+
+    /// Read the access token from this file instead, re-read on every (re)connect
+    #[clap(long)]
+    x_token_file: Option<PathBuf>,
+
+    /// How the access token is sent: `x-token` header or `authorization: Bearer` header
+    #[clap(long, value_enum, default_value_t)]
+    auth_scheme: AuthScheme,
+
+    /// Apply a timeout to connecting to the uri.
+    #[clap(long)]
+    connect_timeout_ms: Option<u64>,
+
+    /// Sets the tower service default internal buffer size, default is 1024
+    #[clap(long)]
+    buffer_size: Option<usize>,
+
+    /// Sets whether to use an adaptive flow control. Uses hyper’s default otherwise.
+    #[clap(long)]
+    http2_adaptive_window: Option<bool>,
+
+    /// Set http2 KEEP_ALIVE_TIMEOUT. Uses hyper’s default otherwise.
+    #[clap(long)]
+    http2_keep_alive_interval_ms: Option<u64>,
+
+    /// Sets the max connection-level flow control for HTTP2, default is 65,535
+    #[clap(long)]
+    initial_connection_window_size: Option<u32>,
+
+    ///Sets the SETTINGS_INITIAL_WINDOW_SIZE option for HTTP2 stream-level flow control, default is 65,535
+    #[clap(long)]
+    initial_stream_window_size: Option<u32>,
+
+    ///Set http2 KEEP_ALIVE_TIMEOUT. Uses hyper’s default otherwise.
+    #[clap(long)]
+    keep_alive_timeout_ms: Option<u64>,
+
+    /// Set http2 KEEP_ALIVE_WHILE_IDLE. Uses hyper’s default otherwise.
+    #[clap(long)]
+    keep_alive_while_idle: Option<bool>,
+
+    /// Set whether TCP keepalive messages are enabled on accepted connections.
+    #[clap(long)]
+    tcp_keepalive_ms: Option<u64>,
+
+    /// Set the value of TCP_NODELAY option for accepted connections. Enabled by default.
+    #[clap(long)]
+    tcp_nodelay: Option<bool>,
+
+    /// Apply a timeout to each request.
+    #[clap(long)]
+    timeout_ms: Option<u64>,
+
+    /// Max message size before decoding, full blocks can be super large, default is 1GiB
+    #[clap(long, default_value_t = 1024 * 1024 * 1024)]
+    max_decoding_message_size: usize,
+
+    /// Commitment level: processed, confirmed or finalized
+    #[clap(long)]
+    commitment: Option<ArgsCommitment>,
+
+    #[command(subcommand)]
+    action: Option<Action>,
+
+    /// Compression default: NONE, [gzip, zstd]
+    #[clap(long)]
+    compression: Option<Compression>,
+
+    /// Serve the control API (`/metrics`, `/pressure`, `/account/<pubkey>`) on this address, e.g. `127.0.0.1:9090`
+    #[cfg(feature = "control-api")]
+    #[clap(long)]
+    control_addr: Option<std::net::SocketAddr>,
+
+    /// Solana JSON-RPC endpoint for `--snapshot` and `get-account`, plain URL or secret reference
+    #[clap(long, default_value_t = String::from("https://api.mainnet-beta.solana.com"))]
+    rpc_url: String,
+
+    /// Write a JSON report of the final error (class, exit code, causes) to this path
+    #[clap(long)]
+    errors_report: Option<PathBuf>,
+
+    /// Write the end-of-session summary of a subscription as JSON to this path
+    #[clap(long)]
+    session_report: Option<PathBuf>,
+
+    /// Color headers and field names: auto (on a terminal), always or never
+    #[clap(long, value_enum, default_value_t)]
+    color: style::ColorChoice,
+
+    /// Don't print the configuration banner and emoji
+    #[clap(long, default_value_t = false)]
+    quiet: bool,
+
+    /// Print updates on one line and query results as bare `key: value` lines, for log scrapers
+    #[clap(long, default_value_t = false)]
+    plain: bool,
+
+    /// Output of get-latest-blockhash, get-block-height, get-slot, is-blockhash-valid and ping
+    #[clap(long, value_enum, default_value_t)]
+    format: QueryFormat,
+
+    /// Lag behind `created_at` at which the pressure score reaches 1
+    #[clap(long, default_value_t = 1000)]
+    pressure_target_lag_ms: u64,
+
+    /// Detach from the terminal and run in the background (Unix only)
+    #[clap(long, default_value_t = false)]
+    daemon: bool,
+
+    /// Write the process id to this file, removed at exit
+    #[clap(long)]
+    pid_file: Option<PathBuf>,
+
+    /// Write logs to this file instead of stderr, reopened on SIGHUP
+    #[clap(long)]
+    log_file: Option<PathBuf>,
+
+    /// Rotate the log file at this size, e.g. `100MB`
+    #[clap(long, value_parser = units::parse_bytes, default_value = "100MB")]
+    log_max_size: u64,
+
+    /// Rotated log files to keep
+    #[clap(long, default_value_t = 7)]
+    log_keep: usize,
+
+    /// Export tracing spans over OTLP/gRPC to this collector, e.g. `http://localhost:4317`
+    #[clap(long)]
+    otlp_endpoint: Option<String>,
+
+    /// Spans exported with `--otlp-endpoint`, as a `RUST_LOG` directive
+    #[clap(long, default_value_t = String::from("info,client=debug"))]
+    otlp_filter: String,
+}
+
+impl Args {
+    fn get_commitment(&self) -> Option<CommitmentLevel> {
+        Some(self.commitment.unwrap_or_default().into())
+    }
+
+    #[tracing::instrument(skip_all, fields(endpoint = %self.endpoint))]
+    async fn connect(&self) -> anyhow::Result<GeyserGrpcClient<impl Interceptor + Clone>> {
+        auth::connect(self.builder().await?, self.auth_scheme).await
+    }
+
+    /// Access token from `--x-token-file` or `--x-token`, with secret references resolved
+    async fn token(&self) -> anyhow::Result<String> {
+        let token = match &self.x_token_file {
+            Some(path) => fs::read_to_string(path)
+                .await
+                .with_context(|| format!("failed to read {}", path.display()))?
+                .trim()
+                .to_owned(),
+            None => self.x_token.clone(),
+        };
+        secrets::resolve(&token).await
+    }
+
+    /// Token for the configuration summary: references are shown, values never are
+    fn token_display(&self, token: &str) -> String {
+        let source = if let Some(path) = &self.x_token_file {
+            format!("(from {})", path.display())
+        } else if let Some(profile) = &self.profile
+            && token != "10443"
+        {
+            format!("(from profile {profile})")
+        } else if env::var("X_TOKEN").is_ok() {
+            "(from .env)".to_owned()
+        } else {
+            "(default)".to_owned()
+        };
+        let value = if self.x_token_file.is_none() && secrets::is_reference(token) {
+            token.to_owned()
+        } else {
+            "********".to_owned()
+        };
+        format!("{value} {source}")
+    }
+
+    async fn builder(&self) -> anyhow::Result<GeyserGrpcBuilder> {
+        let mut tls_config = ClientTlsConfig::new().with_native_roots();
+        if let Some(path) = &self.ca_certificate {
+            let bytes = fs::read(path).await?;
+            tls_config = tls_config.ca_certificate(Certificate::from_pem(bytes));
+        }
+        let x_token = self.token().await?;
+        let mut builder = GeyserGrpcClient::build_from_shared(self.endpoint.clone())?
+            .x_token(Some(x_token))?
+            .tls_config(tls_config)?
+            .max_decoding_message_size(self.max_decoding_message_size);
+
+        if let Some(compression) = self.compression {
+            match compression {
+                Compression::Gzip => {
+                    builder = builder.accept_compressed(tonic::codec::CompressionEncoding::Gzip)
+                }
+                Compression::Zstd => {
+                    builder = builder.accept_compressed(tonic::codec::CompressionEncoding::Zstd)
+                }
+            }
+        }
+
+        if let Some(duration) = self.connect_timeout_ms {
+            builder = builder.connect_timeout(Duration::from_millis(duration));
+        }
+        if let Some(sz) = self.buffer_size {
+            builder = builder.buffer_size(sz);
+        }
+        if let Some(enabled) = self.http2_adaptive_window {
+            builder = builder.http2_adaptive_window(enabled);
+        }
+        if let Some(duration) = self.http2_keep_alive_interval_ms {
+            builder = builder.http2_keep_alive_interval(Duration::from_millis(duration));
+        }
+        if let Some(sz) = self.initial_connection_window_size {
+            builder = builder.initial_connection_window_size(sz);
+        }
+        if let Some(sz) = self.initial_stream_window_size {
+            builder = builder.initial_stream_window_size(sz);
+        }
+        if let Some(duration) = self.keep_alive_timeout_ms {
+            builder = builder.keep_alive_timeout(Duration::from_millis(duration));
+        }
+        if let Some(enabled) = self.keep_alive_while_idle {
+            builder = builder.keep_alive_while_idle(enabled);
+        }
+        if let Some(duration) = self.tcp_keepalive_ms {
+            builder = builder.tcp_keepalive(Some(Duration::from_millis(duration)));
+        }
+        if let Some(enabled) = self.tcp_nodelay {
+            builder = builder.tcp_nodelay(enabled);
+        }
+        if let Some(duration) = self.timeout_ms {
+            builder = builder.timeout(Duration::from_millis(duration));
+        }
+
+        Ok(builder)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Envelope {
+    /// Debezium-style change events (`op`, `before`, `after`, `source`) for account updates
+    Debezium,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+enum QueryFormat {
+    /// Human readable table
+    #[default]
+    Text,
+    /// The response as a JSON line
+    Json,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+enum AuthScheme {
+    /// `x-token: <token>`
+    #[default]
+    XToken,
+    /// `authorization: Bearer <token>`
+    Bearer,
+}
+
+/// What a failed message (stream error or decode failure) does to the subscription
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+enum ErrorPolicy {
+    /// Stop and exit with the error's exit code
+    Fail,
+    /// Log the message and keep reading the stream
+    Skip,
+    /// Tear down the subscription and reconnect with backoff
+    #[default]
+    Reconnect,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
+enum ArgsCommitment {
+    #[default]
+    Processed,
+    Confirmed,
+    Finalized,
+}
+
+impl From<ArgsCommitment> for CommitmentLevel {
+    fn from(commitment: ArgsCommitment) -> Self {
+        match commitment {
+            ArgsCommitment::Processed => CommitmentLevel::Processed,
+            ArgsCommitment::Confirmed => CommitmentLevel::Confirmed,
+            ArgsCommitment::Finalized => CommitmentLevel::Finalized,
+        }
+    }
+}
+
+#[cfg(feature = "interactive")]
+use interactive::interactive_prompt;
+
+#[derive(Debug, Clone)]
+struct InteractiveConfig {
+    endpoint: String,
+    x_token: String,
+    commitment: Option<ArgsCommitment>,
+}
+
+#[derive(Debug, Clone, Subcommand)]
+enum Action {
+    /// Start interactive indexing (default mode)
+    #[clap(alias = "i")]
+    Index,
+    /// Subscribe to updates (can be used with flags or interactively)
+    Subscribe(Box<ActionSubscribe>),
+    /// Compare how fast several endpoints deliver the same updates
+    Bench(Box<ActionBench>),
+    /// Compare a sample of streamed transactions and accounts with a JSON-RPC node (--rpc-url) and report mismatches
+    Verify(Box<ActionVerify>),
+    /// Backfill transactions over RPC from `--from-slot` to the tip, then follow the stream from there
+    Sync(Box<ActionSubscribe>),
+    HealthCheck,
+    /// Follow the endpoint's health status, alerting and optionally exiting when it turns unhealthy
+    HealthWatch(ActionHealthWatch),
+    SubscribeReplayInfo,
+    /// Send pings and report round-trip times and loss
+    Ping {
+        /// Pings to send
+        #[clap(long, short, default_value_t = 5, value_parser = clap::value_parser!(u32).range(1..))]
+        count: u32,
+        /// Interval between pings
+        #[clap(long, default_value_t = 1000)]
+        interval_ms: u64,
+        /// A ping without a pong after this long is lost
+        #[clap(long, default_value_t = 5000)]
+        timeout_ms: u64,
+    },
+    GetLatestBlockhash,
+    GetBlockHeight,
+    GetSlot,
+    IsBlockhashValid {
+        #[clap(long, short, value_parser = keys::parse_blockhash)]
+        blockhash: String,
+    },
+    GetVersion,
+    /// Fetch one account and print it decoded
+    GetAccount {
+        /// Account pubkey, or a keypair file
+        #[clap(value_parser = keys::parse_pubkey)]
+        pubkey: String,
+        /// Wait for the first streamed update instead of fetching over RPC
+        #[clap(long)]
+        stream: bool,
+    },
+    /// List ready-to-run pipeline examples, or scaffold one into a directory
+    Examples {
+        /// Example to scaffold
+        name: Option<String>,
+        /// Directory to scaffold into, defaults to the example name
+        #[clap(long)]
+        out: Option<PathBuf>,
+    },
+    /// Print the JSON Schema of decoded updates, of one kind or all of them
+    Schema {
+        /// Update kind, e.g. `account` or `transactionStatus`
+        kind: Option<String>,
+        /// Write `<kind>.v<version>.schema.json` files into this directory instead
+        #[clap(long)]
+        out: Option<PathBuf>,
+    },
+    /// List the frames of a --capture file, or dump them as JSON lines
+    Inspect {
+        /// Capture file
+        file: PathBuf,
+        /// Skip frames of earlier slots, seeking with the capture index
+        #[clap(long)]
+        from_slot: Option<u64>,
+        /// Stop after this many frames
+        #[clap(long)]
+        limit: Option<usize>,
+        /// Print decoded updates instead of the frame list
+        #[clap(long)]
+        dump: bool,
+        /// Key of an encrypted capture
+        #[clap(long)]
+        encryption_key: Option<String>,
+    },
+    /// Write the updates of --capture files or --out-file lines through a sink again, once per update
+    Replay {
+        /// Capture files or out-files, `.gz`/`.zst` segments are decompressed
+        #[clap(required = true)]
+        files: Vec<PathBuf>,
+        /// Sink to write to, e.g. `exec:./handler`
+        #[clap(long, value_parser = sink::parse_sink)]
+        into_sink: sink::SinkSpec,
+        /// Retries of a failed delivery before the replay stops with a sink failure
+        #[clap(long, default_value_t = 3)]
+        sink_retries: u32,
+        /// Skip updates of earlier slots
+        #[clap(long)]
+        from_slot: Option<u64>,
+        /// Key the files were written with
+        #[clap(long)]
+        encryption_key: Option<String>,
+    },
+    /// Print the lines of an --out-file written with --encryption-key, or of one of its segments
+    Decrypt {
+        /// Out-file or segment, `.gz`/`.zst` segments are decompressed
+        file: PathBuf,
+        /// Key the file was written with
+        #[clap(long)]
+        encryption_key: String,
+    },
+    /// Write the current state of every account of a program over RPC as JSON lines, or diff two such snapshots
+    Snapshot {
+        /// Program owning the accounts
+        #[clap(long, required_unless_present = "diff")]
+        owner: Option<String>,
+        /// File to write, `.gz`/`.zst` compressed or `.parquet` (needs the `parquet` feature), defaults to stdout
+        #[clap(long, conflicts_with = "diff")]
+        out: Option<PathBuf>,
+        /// Print the accounts added, removed and changed between two snapshots
+        #[clap(long, num_args = 2, value_names = ["OLD", "NEW"], conflicts_with = "owner")]
+        diff: Option<Vec<PathBuf>>,
+    },
+}
+
+#[derive(Debug, Clone, clap::Args)]
+struct ActionSubscribe {
+    /// Subscribe on accounts updates
+    #[clap(long)]
+    accounts: bool,
+
+    /// Filter by presence of field txn_signature
+    #[clap(long)]
+    accounts_nonempty_txn_signature: Option<bool>,
+
+    /// Filter by Account Pubkey
+    #[clap(long, value_parser = keys::parse_pubkey)]
+    accounts_account: Vec<String>,
+
+    /// Path to a JSON array of account addresses
+    #[clap(long)]
+    accounts_account_path: Option<String>,
+
+    /// Filter by Owner Pubkey, or a program name: token, token-2022, stake, vote, system, memo, ata
+    #[clap(long, value_parser = keys::parse_program)]
+    accounts_owner: Vec<String>,
+
+    /// Filter by Offset and Data, format: `offset,data` in base58, or `offset,base64:data` / `offset,hex:data`
+    #[clap(long)]
+    accounts_memcmp: Vec<String>,
+
+    /// Filter by Data size
+    #[clap(long)]
+    accounts_datasize: Option<u64>,
+
+    /// Drop account updates with less data than this (client-side, after `--accounts-data-slice`)
+    #[clap(long)]
+    accounts_datasize_min: Option<u64>,
+
+    /// Drop account updates with more data than this (client-side, after `--accounts-data-slice`)
+    #[clap(long)]
+    accounts_datasize_max: Option<u64>,
+
+    /// Write every account update, or only the newest of each account per --accounts-window (client-side, by slot and write version)
+    #[clap(long, value_enum, default_value_t)]
+    accounts_mode: compact::AccountsMode,
+
+    /// Window of --accounts-mode latest, e.g. 1s or 1m
+    #[clap(long, default_value = "1s", value_parser = units::parse_duration)]
+    accounts_window: Duration,
+
+    /// Filter valid token accounts
+    #[clap(long)]
+    accounts_token_account_state: bool,
+
+    /// Filter by lamports, format: `eq:42` / `ne:42` / `lt:42` / `gt:42`
+    #[clap(long)]
+    accounts_lamports: Vec<String>,
+
+    /// Receive only part of updated data account, format: `offset,size`
+    #[clap(long)]
+    accounts_data_slice: Vec<String>,
+
+    /// Subscribe on slots updates
+    #[clap(long)]
+    slots: bool,
+
+    /// Filter slots by commitment
+    #[clap(long)]
+    slots_filter_by_commitment: Option<bool>,
+
+    /// Subscribe on interslot slot updates
+    #[clap(long)]
+    slots_interslot_updates: Option<bool>,
+
+    /// Subscribe on transactions updates
+    #[clap(long)]
+    transactions: bool,
+
+    /// Filter vote transactions
+    #[clap(long)]
+    transactions_vote: Option<bool>,
+
+    /// Filter failed transactions
+    #[clap(long)]
+    transactions_failed: Option<bool>,
+
+    /// Filter by transaction signature
+    #[clap(long, value_parser = keys::parse_signature)]
+    transactions_signature: Option<String>,
+
+    /// Filter included account in transactions, or a program name: token, token-2022, stake, vote, system, memo, ata
+    #[clap(long, value_parser = keys::parse_program)]
+    transactions_account_include: Vec<String>,
+
+    /// Filter excluded account in transactions
+    #[clap(long, value_parser = keys::parse_pubkey)]
+    transactions_account_exclude: Vec<String>,
+
+    /// Filter required account in transactions
+    #[clap(long, value_parser = keys::parse_pubkey)]
+    transactions_account_required: Vec<String>,
+
+    /// Subscribe on transactions_status updates
+    #[clap(long)]
+    transactions_status: bool,
+
+    /// Filter vote transactions for transactions_status
+    #[clap(long)]
+    transactions_status_vote: Option<bool>,
+
+    /// Filter failed transactions for transactions_status
+    #[clap(long)]
+    transactions_status_failed: Option<bool>,
+
+    /// Filter by transaction signature for transactions_status
+    #[clap(long, value_parser = keys::parse_signature)]
+    transactions_status_signature: Option<String>,
+
+    /// Filter included account in transactions for transactions_status
+    #[clap(long, value_parser = keys::parse_pubkey)]
+    transactions_status_account_include: Vec<String>,
+
+    /// Filter excluded account in transactions for transactions_status
+    #[clap(long, value_parser = keys::parse_pubkey)]
+    transactions_status_account_exclude: Vec<String>,
+
+    /// Filter required account in transactions for transactions_status
+    #[clap(long, value_parser = keys::parse_pubkey)]
+    transactions_status_account_required: Vec<String>,
+
+    #[clap(long)]
+    entries: bool,
+
+    /// Subscribe on block updates
+    #[clap(long)]
+    blocks: bool,
+
+    /// Filter included account in transactions
+    #[clap(long, value_parser = keys::parse_pubkey)]
+    blocks_account_include: Vec<String>,
+
+    /// Include transactions to block message
+    #[clap(long)]
+    blocks_include_transactions: Option<bool>,
+
+    /// Include accounts to block message
+    #[clap(long)]
+    blocks_include_accounts: Option<bool>,
+
+    /// Include entries to block message
+    #[clap(long)]
+    blocks_include_entries: Option<bool>,
+
+    /// Subscribe on block meta updates (without transactions)
+    #[clap(long)]
+    blocks_meta: bool,
+
+    /// Re-send message from slot
+    #[clap(long)]
+    from_slot: Option<u64>,
+
+    /// Send ping in subscribe request
+    #[clap(long)]
+    ping: Option<i32>,
+
+    /// Resubscribe (only to slots) after
+    #[clap(long)]
+    resub: Option<usize>,
+
+    /// Reconnect (or fail over), resuming from the last slot, when no message (pings included) arrives for this many seconds
+    #[clap(long)]
+    stall_timeout: Option<u64>,
+
+    /// Stop cleanly after this many messages (pings excluded), across reconnects
+    #[clap(long)]
+    max_messages: Option<u64>,
+
+    /// Stop cleanly after receiving this many bytes, e.g. 512MB
+    #[clap(long, value_parser = units::parse_bytes)]
+    max_bytes: Option<u64>,
+
+    /// Stop cleanly after this long, e.g. 10m; `verify` stops even with checks left, `bench` runs 1m without it
+    #[clap(long, alias = "duration-secs", value_parser = units::parse_duration)]
+    duration: Option<Duration>,
+
+    /// Stop cleanly at the first update of a slot above this one, with --from-slot to capture
+    /// a slot window
+    #[clap(long)]
+    until_slot: Option<u64>,
+
+    /// Send a ping to the server at this interval, for load balancers idling out quiet subscriptions
+    #[clap(long)]
+    ping_interval_ms: Option<u64>,
+
+    /// Show total stat instead of messages, updates are counted without being decoded
+    #[clap(long, default_value_t = false)]
+    stats: bool,
+
+    /// Verify manually implemented encoding against prost
+    #[clap(long, default_value_t = false)]
+    verify_encoding: bool,
+
+    /// Write a JSON report of `--verify-encoding` (counts, mismatches, timings) to this path
+    /// at exit
+    #[clap(long, requires = "verify_encoding")]
+    verify_report: Option<PathBuf>,
+
+    /// Report created_at to receipt latency percentiles per update type instead of messages
+    #[clap(long, default_value_t = false)]
+    latency: bool,
+
+    /// Interval between `--latency` reports, e.g. 10s or 1m
+    #[clap(long, alias = "latency-interval-secs", default_value = "10s", value_parser = units::parse_duration)]
+    latency_interval: Duration,
+
+    /// Write the current state of the subscribed accounts, fetched over RPC, before streaming
+    #[clap(long, default_value_t = false)]
+    snapshot: bool,
+
+    /// Print the subscribe request as JSON with its filter counts and exit without connecting
+    #[clap(long, default_value_t = false)]
+    dry_run: bool,
+
+    /// Save the subscribe request built from flags or interactive mode as JSON
+    #[clap(long)]
+    export_request: Option<PathBuf>,
+
+    /// Subscribe with a request saved by `--export-request` (or printed by `--dry-run`) instead of the filter flags
+    #[clap(long)]
+    request: Option<PathBuf>,
+
+    /// Number of workers decoding updates in parallel, output order is preserved; with 1 updates are decoded inline
+    #[clap(long, default_value_t = 1, value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..))]
+    decode_workers: usize,
+
+    /// Decode and write full blocks as blockChunk updates of at most this many transactions, accounts or entries, then the block without them, bounding memory on large blocks
+    #[clap(long, value_parser = clap::value_parser!(u32).range(1..))]
+    block_chunk_size: Option<u32>,
+
+    /// Decode only a random fraction of an update type, format: `type=fraction`, e.g. `accounts=0.1` (repeatable)
+    #[clap(long, value_parser = throttle::parse_sample)]
+    sample: Vec<(StatsKind, f64)>,
+
+    /// Decode at most this many updates of a type per second, format: `type=count/s`, e.g. `transactions=500/s` (repeatable)
+    #[clap(long, value_parser = throttle::parse_max_rate)]
+    max_rate: Vec<(StatsKind, f64)>,
+
+    /// Hold back the updates of each slot until it reaches this commitment, then write them together
+    #[clap(long)]
+    commit_boundary: Option<ArgsCommitment>,
+
+    /// Drop updates received again within this many slots, keyed by slot, pubkey and write version or signature (counted in /metrics)
+    #[clap(long, value_parser = clap::value_parser!(u64).range(1..))]
+    dedup_window_slots: Option<u64>,
+
+    /// Write updates in non-decreasing slot order, holding them up to --max-reorder-delay-ms
+    #[clap(long)]
+    ordered_by_slot: bool,
+
+    /// Longest an update is held by --ordered-by-slot, later updates of lower slots are written out of order
+    #[clap(long, requires = "ordered_by_slot", default_value_t = 1000)]
+    max_reorder_delay_ms: u64,
+
+    /// Also write updates as JSON lines to this file
+    #[clap(long)]
+    out_file: Option<PathBuf>,
+
+    /// Also write updates as Arrow IPC record batches to this file (needs the `arrow` feature)
+    #[clap(long)]
+    arrow_file: Option<PathBuf>,
+
+    /// Rows of the record batches of --arrow-file
+    #[clap(long, requires = "arrow_file", default_value_t = 1024, value_parser = clap::value_parser!(u32).range(1..))]
+    arrow_batch_rows: u32,
+
+    /// Also stream updates as JSON lines to a handler: `exec:<command>` writes to its stdin (repeatable, each sink has its own queue)
+    #[clap(long, value_parser = sink::parse_sink)]
+    sink: Vec<sink::SinkSpec>,
+
+    /// Updates queued per --sink before --sink-overflow applies
+    #[clap(long, default_value_t = 10_000, value_parser = clap::value_parser!(u32).range(1..))]
+    sink_queue: u32,
+
+    /// What a --sink with a full queue does: drop its updates, or block the stream
+    #[clap(long, value_enum, default_value_t)]
+    sink_overflow: sink::Overflow,
+
+    /// Retries of a failed --sink delivery before the update is dropped for that sink
+    #[clap(long, default_value_t = 3)]
+    sink_retries: u32,
+
+    /// Give every update a W3C `traceparent`, written to --out-file and --sink lines and Debezium envelopes
+    #[clap(long, default_value_t = false)]
+    trace_ids: bool,
+
+    /// Write to --out-file only once a slot reaches this commitment, dropping dead slots, while stdout stays immediate
+    #[clap(long, requires = "out_file")]
+    out_file_commitment: Option<ArgsCommitment>,
+
+    /// Close --out-file into a segment once it reaches a size (`512MB`) or an age (`1h`)
+    #[clap(long, requires = "out_file", value_parser = outfile::parse_rotate)]
+    rotate: Option<outfile::Rotate>,
+
+    /// Compression of the segments closed by --rotate
+    #[clap(long, requires = "rotate", value_enum, default_value_t)]
+    rotate_compression: outfile::Compression,
+
+    /// Prune the segments closed by --rotate by update kind, format: `kind=age|latest|forever`, e.g. `transaction=7d`, `account=latest` or `*=30d` (repeatable)
+    #[clap(long, requires = "rotate", value_parser = retention::parse_retain)]
+    retain: Vec<(String, retention::Policy)>,
+
+    /// How often --retain prunes the segments, e.g. 1h
+    #[clap(long, requires = "retain", default_value = "1h", value_parser = units::parse_duration)]
+    prune_interval: Duration,
+
+    /// Record raw update frames to this file instead of printing messages, read it back with `inspect`
+    #[clap(long, conflicts_with = "latency")]
+    capture: Option<PathBuf>,
+
+    /// Encrypt --out-file lines and --capture frames with AES-256-GCM, key as 64 hex characters or secret reference
+    #[clap(long)]
+    encryption_key: Option<String>,
+
+    /// Rebuild blocks from transactions and block meta, emitting a reassembledBlock event per slot
+    #[clap(long)]
+    reassemble_blocks: bool,
+
+    /// Replace vote transactions by per-validator voting statistics (latency, missed slots) emitted at this interval, in seconds
+    #[clap(long)]
+    vote_stats_interval_secs: Option<u64>,
+
+    /// Replace transactions by statistics aggregated over --window
+    #[clap(long, value_enum)]
+    aggregate: Option<aggregate::Aggregation>,
+
+    /// Window of --aggregate, e.g. 60s or 5m
+    #[clap(long, requires = "aggregate", default_value = "60s", value_parser = units::parse_duration)]
+    window: Duration,
+
+    /// Entries per leaderboard of --aggregate top-n
+    #[clap(long, requires = "aggregate", default_value_t = 10)]
+    top: usize,
+
+    /// Emit per-slot priority fee and compute unit percentiles (fees events, also in /metrics and /pressure)
+    #[clap(long)]
+    fee_stats: bool,
+
+    /// Count the instructions of a program by discriminator over --instruction-stats-window, format: `program[:bytes]`, 8 bytes by default (repeatable, instructionStats events, also in /metrics and /pressure)
+    #[clap(long, value_parser = discriminators::parse_program)]
+    instruction_stats: Vec<(Pubkey, usize)>,
+
+    /// Sliding window of --instruction-stats, e.g. 60s or 5m
+    #[clap(long, default_value = "60s", value_parser = units::parse_duration)]
+    instruction_stats_window: Duration,
+
+    /// Annotate failed transactions and transaction statuses with a normalized error object (category, instruction, program, custom code, Anchor framework error name), failure rates also in /metrics and /pressure
+    #[clap(long)]
+    classify_errors: bool,
+
+    /// Emit programChange events for BPF Upgradeable Loader deploys, upgrades, authority changes and closes
+    #[clap(long)]
+    program_changes: bool,
+
+    /// Emit networkStats events (TPS, transactions and entries per block, block time deltas) on every block meta
+    #[clap(long)]
+    network_stats: bool,
+
+    /// Emit per-leader skip rates and block production gaps (leaderSlots events, skipStats per epoch, also in /metrics and /pressure), implies --leaders
+    #[clap(long)]
+    skip_stats: bool,
+
+    /// Emit newMint events for Token and Token-2022 InitializeMint instructions (streams all token program transactions)
+    #[clap(long)]
+    new_mints: bool,
+
+    /// Emit jitoTip events for transactions tipping Jito block builders, and jitoBundle events labeling the bundles they end (streams the transactions referencing the tip accounts)
+    #[clap(long)]
+    jito_tips: bool,
+
+    /// Subscribe to the accounts and transactions of a JSON watchlist of labeled addresses, annotating updates referencing them
+    #[clap(long)]
+    watchlist: Option<PathBuf>,
+
+    /// Annotate updates with labels of the known addresses they reference (programs, sysvars, major mints), in a labels object
+    #[clap(long)]
+    labels: bool,
+
+    /// CSV of extra address labels, one `address,label` per line, overriding the bundled ones (implies --labels)
+    #[clap(long)]
+    labels_csv: Vec<PathBuf>,
+
+    /// JSON (or YAML with the `yaml` feature) file of Borsh account layouts by owner and discriminator, matching account updates get a decoded object (repeatable)
+    #[clap(long)]
+    layouts: Vec<PathBuf>,
+
+    /// Annotate transactions with the text of their Memo program instructions, in a memo field
+    #[clap(long)]
+    memos: bool,
+
+    /// Only keep transactions whose memo matches, format: `[name=]regex`, adding `memo:<name>` to their filters (repeatable, implies --memos)
+    #[clap(long, value_parser = memos::parse_regex)]
+    memo_regex: Vec<(String, regex::Regex)>,
+
+    /// Annotate slot and block meta updates with the slot leader, from leader schedules fetched over --rpc-url
+    #[clap(long)]
+    leaders: bool,
+
+    /// Emit epochEnd/epochStart events at epoch boundaries, with epoch info and stake fetched over --rpc-url
+    #[clap(long)]
+    epoch_events: bool,
+
+    /// Annotate token account updates with their wallet, mint, decimals and symbol (mints fetched over --rpc-url, cached)
+    #[clap(long)]
+    resolve_tokens: bool,
+
+    /// Persist the mint decimals and symbols resolved by --resolve-tokens in this JSON file
+    #[clap(long, requires = "resolve_tokens")]
+    mint_cache: Option<PathBuf>,
+
+    /// Persist the last written slot and its written updates here, restarts resume from it without duplicates; with --sink an update counts as written once every sink delivered it
+    #[clap(long)]
+    checkpoint_file: Option<PathBuf>,
+
+    /// What a stream error or decode failure does: exit, skip the message, or reconnect
+    #[clap(long, value_enum, default_value_t)]
+    error_policy: ErrorPolicy,
+
+    /// Write updates that fail to decode (protobuf and error) to this directory and continue
+    #[clap(long)]
+    dead_letter_dir: Option<PathBuf>,
+
+    /// Log updates whose decode and output took longer than this, with stage timings
+    #[clap(long)]
+    slow_threshold_ms: Option<u64>,
+
+    /// Client-side post-filter on decoded updates, e.g. `lamports > 1000000 && owner == Tokenkeg...`
+    #[clap(long = "where", value_parser = expr::Expr::parse)]
+    where_expr: Option<expr::Expr>,
+
+    /// Client-side regex over the JSON of every decoded update (after enrichments like --memos), keeping only matching ones, e.g. a log line or an address
+    #[clap(long)]
+    grep: Option<regex::Regex>,
+
+    /// Only print and write these fields of every update, e.g. `.signature,.slot,.tx.meta.fee`
+    #[clap(long, value_parser = select::parse_select)]
+    select: Option<select::Select>,
+
+    /// Encoding of printed and written account data, `none` drops it
+    #[clap(long, value_enum, default_value_t)]
+    data_encoding: encoding::DataEncoding,
+
+    /// Truncate printed and written account data to this many bytes
+    #[clap(long)]
+    max_data_bytes: Option<usize>,
+
+    /// WASM module transforming (or dropping) every decoded update before it's written
+    #[clap(long)]
+    transform: Option<PathBuf>,
+
+    /// Rhai script whose `on_update(kind, update)` keeps, drops or rewrites every decoded update
+    #[clap(long)]
+    script: Option<PathBuf>,
+
+    /// Learn account layouts (size, discriminator) per program for this long, then alert on new ones, in seconds
+    #[clap(long)]
+    schema_drift_learn_secs: Option<u64>,
+
+    /// Write account updates in a change event envelope, one JSON object per line, to stdout, --out-file, --arrow-file and --sink
+    #[clap(long)]
+    envelope: Option<Envelope>,
+
+    /// Emit a watermark record (all data ≤ slot at the commitment was written) at this interval, e.g. 5s or 1m
+    #[clap(long, alias = "watermark-interval-secs", value_parser = units::parse_duration)]
+    watermark_interval: Option<Duration>,
+}
+
+#[derive(Debug, Clone, clap::Args)]
+struct ActionVerify {
+    /// Fraction of transaction and account updates to check
+    #[clap(long, default_value_t = 0.01)]
+    sample_rate: f64,
+
+    /// Stop once this many updates were checked
+    #[clap(long, default_value_t = 100)]
+    checks: usize,
+
+    /// Time given to the RPC node to catch up before an update is checked, in seconds
+    #[clap(long, default_value_t = 10)]
+    delay_secs: u64,
+
+    #[clap(flatten)]
+    subscribe: ActionSubscribe,
+}
+
+#[derive(Debug, Clone, clap::Args)]
+struct ActionHealthWatch {
+    /// Failed checks in a row (non-SERVING status, connection or stream failure) before the
+    /// endpoint is unhealthy
+    #[clap(long, default_value_t = 3, value_parser = clap::value_parser!(u32).range(1..))]
+    unhealthy_threshold: u32,
+
+    /// Exit with code 9 once the endpoint is unhealthy
+    #[clap(long, default_value_t = false)]
+    exit_on_unhealthy: bool,
+
+    /// POST a JSON alert to this URL when the endpoint turns unhealthy or recovers
+    #[clap(long)]
+    webhook_url: Option<String>,
+
+    /// Delay before reopening a failed watch, in seconds
+    #[clap(long, default_value_t = 5)]
+    retry_secs: u64,
+}
+
+#[derive(Debug, Clone, clap::Args)]
+struct ActionBench {
+    /// Endpoint to compare with `--endpoint`, format: `url` or `url,x-token` (repeatable)
+    #[clap(long, required = true)]
+    compare: Vec<String>,
+
+    /// Interval between reports, in seconds
+    #[clap(long, default_value_t = 10)]
+    report_interval_secs: u64,
+
+    /// Time to wait for an update from every endpoint before counting it as missed, in seconds
+    #[clap(long, default_value_t = 30)]
+    window_secs: u64,
+
+    #[clap(flatten)]
+    subscribe: ActionSubscribe,
+}
+
+impl Action {
+    async fn get_subscribe_request(
+        &self,
+        commitment: Option<CommitmentLevel>,
+    ) -> anyhow::Result<Option<SubscribeRequest>> {
+        Ok(match self {
+            Self::Subscribe(args) if args.request.is_some() => {
+                let path = args.request.as_deref().expect("checked above");
+                let mut request = tokio::task::block_in_place(|| request::load(path))?;
+                // A request saved without a commitment uses `--commitment`
+                if request.commitment.is_none() {
+                    request.commitment = commitment.map(|x| x as i32);
+                }
+                Some(request)
+            }
+            Self::Subscribe(args) => {
+                let mut accounts: AccountFilterMap = HashMap::new();
+                if args.accounts {
+                    let mut accounts_account = args.accounts_account.clone();
+                    if let Some(path) = args.accounts_account_path.clone() {
+                        let accounts = tokio::task::block_in_place(move || {
+                            let file = File::open(path)?;
+                            let accounts: Vec<String> = serde_json::from_reader(file)?;
+                            accounts
+                                .iter()
+                                .map(|account| keys::parse_pubkey(account))
+                                .collect::<anyhow::Result<Vec<_>>>()
+                        })?;
+                        accounts_account.extend(accounts);
+                    }
+
+                    let mut filters = vec![];
+                    for filter in args.accounts_memcmp.iter() {
+                        match filter.split_once(',') {
+                            Some((offset, data)) => {
+                                filters.push(SubscribeRequestFilterAccountsFilter {
+                                    filter: Some(AccountsFilterOneof::Memcmp(
+                                        SubscribeRequestFilterAccountsFilterMemcmp {
+                                            offset: offset
+                                                .parse()
+                                                .map_err(|_| anyhow::anyhow!("invalid offset"))?,
+                                            data: Some(parse_memcmp_data(data.trim())?),
+                                        },
+                                    )),
+                                });
+                            }
+                            _ => anyhow::bail!("invalid memcmp"),
+                        }
+                    }
+                    if let Some(datasize) = args.accounts_datasize {
+                        filters.push(SubscribeRequestFilterAccountsFilter {
+                            filter: Some(AccountsFilterOneof::Datasize(datasize)),
+                        });
+                    }
+                    if args.accounts_token_account_state {
+                        filters.push(SubscribeRequestFilterAccountsFilter {
+                            filter: Some(AccountsFilterOneof::TokenAccountState(true)),
+                        });
+                    }
+                    for filter in args.accounts_lamports.iter() {
+                        match filter.split_once(':') {
+                            Some((cmp, value)) => {
+                                let Ok(value) = value.parse() else {
+                                    anyhow::bail!("invalid lamports value: {value}");
+                                };
+                                filters.push(SubscribeRequestFilterAccountsFilter {
+                                    filter: Some(AccountsFilterOneof::Lamports(
+                                        SubscribeRequestFilterAccountsFilterLamports {
+                                            cmp: Some(match cmp {
+                                                "eq" => AccountsFilterLamports::Eq(value),
+                                                "ne" => AccountsFilterLamports::Ne(value),
+                                                "lt" => AccountsFilterLamports::Lt(value),
+                                                "gt" => AccountsFilterLamports::Gt(value),
+                                                _ => {
+                                                    anyhow::bail!("invalid lamports filter: {cmp}")
+                                                }
+                                            }),
+                                        },
+                                    )),
+                                });
+                            }
+                            _ => anyhow::bail!("invalid lamports"),
+                        }
+                    }
+
+                    accounts.insert(
+                        "client".to_owned(),
+                        SubscribeRequestFilterAccounts {
+                            account: accounts_account,
+                            owner: args.accounts_owner.clone(),
+                            filters,
+                            nonempty_txn_signature: args.accounts_nonempty_txn_signature,
+                        },
+                    );
+                }
+
+                let mut slots: SlotsFilterMap = HashMap::new();
+                if args.slots {
+                    // If commitment level is set, enable filtering by commitment for slots
+                    let filter_by_commitment = args.slots_filter_by_commitment
+                        .or_else(|| if commitment.is_some() { Some(true) } else { None });
+                    
+                    slots.insert(
+                        "client".to_owned(),
+                        SubscribeRequestFilterSlots {
+                            filter_by_commitment,
+                            interslot_updates: args.slots_interslot_updates,
+                        },
+                    );
+                }
+
+                let mut transactions: TransactionsFilterMap = HashMap::new();
+                if args.transactions {
+                    transactions.insert(
+                        "client".to_string(),
+                        SubscribeRequestFilterTransactions {
+                            vote: args.transactions_vote,
+                            failed: args.transactions_failed,
+                            signature: args.transactions_signature.clone(),
+                            account_include: args.transactions_account_include.clone(),
+                            account_exclude: args.transactions_account_exclude.clone(),
+                            account_required: args.transactions_account_required.clone(),
+                        },
+                    );
+                }
+
+                let mut transactions_status: TransactionsStatusFilterMap = HashMap::new();
+                if args.transactions_status {
+                    transactions_status.insert(
+                        "client".to_string(),
+                        SubscribeRequestFilterTransactions {
+                            vote: args.transactions_status_vote,
+                            failed: args.transactions_status_failed,
+                            signature: args.transactions_status_signature.clone(),
+                            account_include: args.transactions_status_account_include.clone(),
+                            account_exclude: args.transactions_status_account_exclude.clone(),
+                            account_required: args.transactions_status_account_required.clone(),
+                        },
+                    );
+                }
+
+                let mut entries: EntryFilterMap = HashMap::new();
+                if args.entries {
+                    entries.insert("client".to_owned(), SubscribeRequestFilterEntry {});
+                }
+
+                let mut blocks: BlocksFilterMap = HashMap::new();
+                if args.blocks {
+                    blocks.insert(
+                        "client".to_owned(),
+                        SubscribeRequestFilterBlocks {
+                            account_include: args.blocks_account_include.clone(),
+                            include_transactions: args.blocks_include_transactions,
+                            include_accounts: args.blocks_include_accounts,
+                            include_entries: args.blocks_include_entries,
+                        },
+                    );
+                }
+
+                let mut blocks_meta: BlocksMetaFilterMap = HashMap::new();
+                if args.blocks_meta {
+                    blocks_meta.insert("client".to_owned(), SubscribeRequestFilterBlocksMeta {});
+                }
+
+                let mut accounts_data_slice = Vec::new();
+                for data_slice in args.accounts_data_slice.iter() {
+                    match data_slice.split_once(',') {
+                        Some((offset, length)) => match (offset.parse(), length.parse()) {
+                            (Ok(offset), Ok(length)) => {
+                                accounts_data_slice
+                                    .push(SubscribeRequestAccountsDataSlice { offset, length });
+                            }
+                            _ => anyhow::bail!("invalid data_slice"),
+                        },
+                        _ => anyhow::bail!("invalid data_slice"),
+                    }
+                }
+
+                let ping = args.ping.map(|id| SubscribeRequestPing { id });
+
+                Some(SubscribeRequest {
+                    slots,
+                    accounts,
+                    transactions,
+                    transactions_status,
+                    entry: entries,
+                    blocks,
+                    blocks_meta,
+                    commitment: commitment.map(|x| x as i32),
+                    accounts_data_slice,
+                    ping,
+                    from_slot: args.from_slot,
+                })
+            }
+            _ => None,
+        })
+    }
+}
+
+/// Memcmp data from `--accounts-memcmp`: base58 by default, `base64:` or `hex:` prefixed
+fn parse_memcmp_data(data: &str) -> anyhow::Result<AccountsFilterMemcmpOneof> {
+    Ok(if let Some(data) = data.strip_prefix("base64:") {
+        base64::Engine::decode(&base64::engine::general_purpose::STANDARD, data)
+            .context("invalid memcmp base64")?;
+        AccountsFilterMemcmpOneof::Base64(data.to_owned())
+    } else if let Some(data) = data.strip_prefix("hex:") {
+        AccountsFilterMemcmpOneof::Bytes(hex::decode(data).context("invalid memcmp hex")?)
+    } else {
+        let data = data.strip_prefix("base58:").unwrap_or(data);
+        bs58::decode(data).into_vec().context("invalid memcmp base58")?;
+        AccountsFilterMemcmpOneof::Base58(data.to_owned())
+    })
+}
+
+
+fn main() -> ExitCode {
+    // Load .env file if it exists
+    let _ = dotenv::dotenv();
+
+    let args = config::parse();
+    let daemon = match daemon::start(&args) {
+        Ok(daemon) => daemon,
+        Err(error) => {
+            eprintln!("Error: {error:#}");
+            return ExitCode::FAILURE;
+        }
+    };
+    style::init(args.color, args.quiet, args.plain);
+    tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .expect("failed to build the tokio runtime")
+        .block_on(run_main(args, daemon))
+}
+
+async fn run_main(args: Args, daemon: daemon::Daemon) -> ExitCode {
+    let telemetry = match telemetry::init(&args, daemon.log_file()) {
+        Ok(telemetry) => telemetry,
+        Err(error) => {
+            eprintln!("Error: {error:#}");
+            return ExitCode::FAILURE;
+        }
+    };
+    daemon.spawn_reload();
+    let exit_code = run_session(args, &daemon).await;
+    telemetry.shutdown().await;
+    exit_code
+}
+
+async fn run_session(args: Args, daemon: &daemon::Daemon) -> ExitCode {
+    let errors_report = args.errors_report.clone();
+    let session_report = args.session_report.clone();
+    let session = Arc::new(session::Session::default());
+    let result = tokio::select! {
+        result = run(args, Arc::clone(&session)) => result,
+        _ = tokio::signal::ctrl_c() => {
+            if let Err(error) = session.finish(session_report.as_deref()) {
+                eprintln!("{error:#}");
+            }
+            return ExitCode::from(130);
+        }
+        _ = daemon::terminated() => {
+            info!("terminated");
+            if let Err(error) = session.finish(session_report.as_deref()) {
+                eprintln!("{error:#}");
+            }
+            return ExitCode::SUCCESS;
+        }
+    };
+    if let Err(error) = session.finish(session_report.as_deref()) {
+        eprintln!("{error:#}");
+    }
+    let Err(error) = result else {
+        return ExitCode::SUCCESS;
+    };
+
+    let class = exit::ErrorClass::of(&error);
+    eprintln!("Error: {error:?}");
+    if daemon.logs_to_file() {
+        error!("{error:?}");
+    }
+    if let Some(path) = errors_report
+        && let Err(report_error) = exit::write_report(&path, &error, class)
+    {
+        eprintln!("failed to write {}: {report_error}", path.display());
+    }
+    ExitCode::from(class.exit_code())
+}
+
+async fn run(mut args: Args, session: Arc<session::Session>) -> anyhow::Result<()> {
+
+    if let Some(Action::Examples { name, out }) = &args.action {
+        return examples::run(name.as_deref(), out.as_deref());
+    }
+    if let Some(Action::Schema { kind, out }) = &args.action {
+        return schema::run(kind.as_deref(), out.as_deref());
+    }
+    if let Some(Action::Inspect {
+        file,
+        from_slot,
+        limit,
+        dump,
+        encryption_key,
+    }) = &args.action
+    {
+        let cipher = match encryption_key {
+            Some(key) => Some(crypto::Cipher::resolve(key).await?),
+            None => None,
+        };
+        return capture::inspect(file, *from_slot, *limit, *dump, cipher.as_ref());
+    }
+    if let Some(Action::Replay {
+        files,
+        into_sink,
+        sink_retries,
+        from_slot,
+        encryption_key,
+    }) = &args.action
+    {
+        let cipher = match encryption_key {
+            Some(key) => Some(crypto::Cipher::resolve(key).await?),
+            None => None,
+        };
+        return tokio::task::block_in_place(|| {
+            replay::run(files, into_sink, *sink_retries, *from_slot, cipher.as_ref())
+        });
+    }
+    if let Some(Action::Decrypt {
+        file,
+        encryption_key,
+    }) = &args.action
+    {
+        return outfile::decrypt(file, &crypto::Cipher::resolve(encryption_key).await?);
+    }
+    if let Some(Action::Snapshot {
+        diff: Some(files), ..
+    }) = &args.action
+        && let [old, new] = files.as_slice()
+    {
+        return programstate::diff(old, new);
+    }
+
+    // Profile values replace defaults only, explicit flags win. Applied before .env so a
+    // selected profile isn't overridden by the local environment.
+    if let Some(name) = &args.profile {
+        let profile = profiles::load(name)?;
+        if let Some(endpoint) = profile.endpoint
+            && args.endpoint == "https://solana-rpc.parafi.tech:10443"
+        {
+            args.endpoint = endpoint;
+        }
+        if args.x_token == "10443" && args.x_token_file.is_none() {
+            if let Some(x_token) = profile.x_token {
+                args.x_token = x_token;
+            }
+            args.x_token_file = profile.x_token_file;
+        }
+        if let Some(scheme) = profile.auth_scheme
+            && args.auth_scheme == AuthScheme::default()
+        {
+            args.auth_scheme = AuthScheme::from_str(&scheme, true)
+                .map_err(|error| anyhow::anyhow!("profile `{name}`: {error}"))?;
+        }
+        if let Some(commitment) = profile.commitment
+            && args.commitment.is_none()
+        {
+            args.commitment = Some(
+                ArgsCommitment::from_str(&commitment, true)
+                    .map_err(|error| anyhow::anyhow!("profile `{name}`: {error}"))?,
+            );
+        }
+        if let Some(rpc_url) = profile.rpc_url
+            && args.rpc_url == "https://api.mainnet-beta.solana.com"
+        {
+            args.rpc_url = rpc_url;
+        }
+    }
+    
+    // Override with .env values if CLI args use defaults
+    if args.endpoint == "https://solana-rpc.parafi.tech:10443"
+        && let Ok(env_endpoint) = env::var("GRPC_ENDPOINT")
+    {
+        args.endpoint = env_endpoint;
+    }
+    if args.x_token == "10443"
+        && let Ok(env_token) = env::var("X_TOKEN")
+    {
+        args.x_token = env_token;
+    }
+    if args.rpc_url == "https://api.mainnet-beta.solana.com"
+        && let Ok(env_rpc_url) = env::var("RPC_URL")
+    {
+        args.rpc_url = env_rpc_url;
+    }
+    
+    if args.print_config {
+        config::print(&args);
+        return Ok(());
+    }
+
+    // Default to Index (interactive mode) if no action specified
+    // Note: This requires the subcommand to be optional, which clap supports
+    if args.action.is_none() {
+        args.action = Some(Action::Index);
+    }
+    
+    // Handle Index action (interactive mode)
+    let mut interactive_config: Option<InteractiveConfig> = None;
+    if matches!(args.action, Some(Action::Index)) {
+        let (interactive_action, config) = interactive_prompt().await?;
+        args.action = Some(interactive_action);
+        interactive_config = Some(config);
+    }
+    // Check if Subscribe action has no flags set (also run interactive)
+    else if let Some(Action::Subscribe(subscribe_args)) = &args.action {
+        // Check if all subscription flags are false/empty (default state = interactive mode)
+        let is_empty = !subscribe_args.accounts 
+            && !subscribe_args.slots 
+            && !subscribe_args.transactions 
+            && !subscribe_args.transactions_status
+            && !subscribe_args.entries
+            && !subscribe_args.blocks
+            && !subscribe_args.blocks_meta
+            && subscribe_args.accounts_account.is_empty()
+            && subscribe_args.accounts_owner.is_empty()
+            && subscribe_args.transactions_account_include.is_empty()
+            && !subscribe_args.program_changes
+            && !subscribe_args.network_stats
+            && !subscribe_args.skip_stats
+            && !subscribe_args.new_mints
+            && !subscribe_args.jito_tips
+            && subscribe_args.watchlist.is_none()
+            && subscribe_args.aggregate.is_none()
+            && subscribe_args.request.is_none();
+        
+        if is_empty {
+            let export_request = subscribe_args.export_request.clone();
+            // Run interactive mode
+            println!(
+                "{}No subscription options provided. Starting interactive mode...\n",
+                style::get().icon("🎯")
+            );
+            let (mut interactive_action, config) = interactive_prompt().await?;
+            if let Action::Subscribe(subscribe_args) = &mut interactive_action {
+                subscribe_args.export_request = export_request;
+            }
+            args.action = Some(interactive_action);
+            interactive_config = Some(config);
+        }
+    }
+    
+    if let Some(action @ Action::Subscribe(subscribe_args)) = &args.action
+        && let Some(path) = &subscribe_args.export_request
+    {
+        let commitment = interactive_config
+            .as_ref()
+            .map_or(args.commitment, |config| config.commitment);
+        let request = action
+            .get_subscribe_request(Some(commitment.unwrap_or_default().into()))
+            .await?
+            .context("expect subscribe action")?;
+        request::export(path, &request)?;
+        info!("request saved to {}", path.display());
+    }
+
+    if let Some(action @ Action::Subscribe(subscribe_args)) = &args.action
+        && subscribe_args.dry_run
+    {
+        let commitment = interactive_config
+            .as_ref()
+            .map_or(args.commitment, |config| config.commitment);
+        let request = action
+            .get_subscribe_request(Some(commitment.unwrap_or_default().into()))
+            .await?
+            .context("expect subscribe action")?;
+        return dryrun::run(subscribe_args, request);
+    }
+
+    // Apply interactive config if provided
+    if let Some(config) = interactive_config {
+        if style::get().banner() {
+            print_configuration(&args, &config.endpoint, &config.x_token, config.commitment);
+        }
+        
+        // The menus don't know about profiles, keep the profile's connection
+        if args.profile.is_none() {
+            args.endpoint = config.endpoint;
+            args.x_token = config.x_token;
+        }
+        args.commitment = config.commitment;
+    } else {
+        // Show config even when not in interactive mode, but keep --envelope output JSON lines
+        let enveloped = matches!(
+            &args.action,
+            Some(Action::Subscribe(subscribe_args) | Action::Sync(subscribe_args))
+                if subscribe_args.envelope.is_some()
+        );
+        if style::get().banner() && !enveloped {
+            print_configuration(&args, &args.endpoint, &args.x_token, args.commitment);
+        }
+    }
+
+    if let Some(Action::GetAccount {
+        pubkey,
+        stream: false,
+    }) = &args.action
+    {
+        let rpc = rpc::RpcClient::new(secrets::resolve(&args.rpc_url).await?);
+        let commitment = args.get_commitment().unwrap_or_default();
+        return account::print(&account::fetch(&rpc, pubkey, commitment).await?);
+    }
+    if let Some(Action::Snapshot { owner, out, .. }) = &args.action {
+        let owner = owner.as_deref().context("--owner is required")?;
+        let rpc = rpc::RpcClient::new(secrets::resolve(&args.rpc_url).await?);
+        let commitment = args.get_commitment().unwrap_or_default();
+        return programstate::snapshot(&rpc, owner, commitment, out.as_deref()).await;
+    }
+
+    // Bench manages its own connection per endpoint
+    if let Some(Action::Bench(bench_args)) = &args.action {
+        let request = Action::Subscribe(Box::new(bench_args.subscribe.clone()))
+            .get_subscribe_request(args.get_commitment())
+            .await?
+            .context("expect subscribe action")?;
+        return bench::run(&args, bench_args, request).await;
+    }
+    if let Some(Action::Verify(verify_args)) = &args.action {
+        let request = Action::Subscribe(Box::new(verify_args.subscribe.clone()))
+            .get_subscribe_request(args.get_commitment())
+            .await?
+            .context("expect subscribe action")?;
+        return compare::run(&args, verify_args, request).await;
+    }
+    if let Some(Action::HealthWatch(watch_args)) = &args.action {
+        return healthwatch::run(&args, watch_args).await;
+    }
+
+    // Latest account versions for `/account/<pubkey>` and change event `before` images
+    let envelope = match &args.action {
+        Some(Action::Subscribe(subscribe_args) | Action::Sync(subscribe_args)) => {
+            subscribe_args.envelope
+        }
+        _ => None,
+    };
+    // Under a container memory limit, keep the state store and a single message within a
+    // share of it instead of running into the OOM killer
+    let memory_limit = resources::cgroup_memory_limit();
+    if let Some(limit) = memory_limit {
+        info!("cgroup memory limit: {} MiB", limit / (1024 * 1024));
+        let max_message_size = usize::try_from(limit / 4).unwrap_or(usize::MAX);
+        if args.max_decoding_message_size > max_message_size {
+            info!("max decoding message size lowered to {max_message_size} bytes");
+            args.max_decoding_message_size = max_message_size;
+        }
+    }
+    #[cfg(feature = "control-api")]
+    let serve_control = args.control_addr.is_some();
+    #[cfg(not(feature = "control-api"))]
+    let serve_control = false;
+    let state = (serve_control || envelope.is_some())
+        .then(|| Arc::new(state::StateStore::new(memory_limit.map(|limit| limit / 4))));
+
+    // Sync continues as a subscription replaying from the slot after the backfill, with the
+    // account state snapshotted at the handoff
+    if let Some(Action::Sync(sync_args)) = &args.action {
+        let from_slot = sync_args.from_slot.context("sync requires --from-slot")?;
+        let request = Action::Subscribe(sync_args.clone())
+            .get_subscribe_request(args.get_commitment())
+            .await?
+            .context("expect subscribe action")?;
+        let rpc = rpc::RpcClient::new(secrets::resolve(&args.rpc_url).await?);
+        let commitment = args.get_commitment().unwrap_or_default();
+        let handoff = sync::backfill(&rpc, &request, from_slot, commitment).await?;
+
+        let mut subscribe_args = sync_args.clone();
+        subscribe_args.from_slot = Some(handoff + 1);
+        subscribe_args.snapshot |= !request.accounts.is_empty();
+        args.action = Some(Action::Subscribe(subscribe_args));
+    }
+
+    if let Some(action @ Action::Subscribe(subscribe_args)) = &args.action
+        && subscribe_args.snapshot
+    {
+        let request = action
+            .get_subscribe_request(args.get_commitment())
+            .await?
+            .context("expect subscribe action")?;
+        let rpc = rpc::RpcClient::new(secrets::resolve(&args.rpc_url).await?);
+        let commitment = args.get_commitment().unwrap_or_default();
+        snapshot::bootstrap(&rpc, &request, commitment, state.as_deref(), envelope).await?;
+    }
+
+    let lookups = match &args.action {
+        Some(Action::Subscribe(subscribe_args)) => RpcLookups::load(&args, subscribe_args).await?,
+        _ => RpcLookups::default(),
+    };
+    // Kept across reconnects and failovers, after which updates are received again
+    let dedup = match &args.action {
+        Some(Action::Subscribe(subscribe_args)) => subscribe_args
+            .dedup_window_slots
+            .map(|window| Arc::new(std::sync::Mutex::new(dedup::Dedup::new(window)))),
+        _ => None,
+    };
+
+    if let Some(Action::Subscribe(subscribe_args)) = &args.action {
+        if let (Some(from_slot), Some(until_slot)) =
+            (subscribe_args.from_slot, subscribe_args.until_slot)
+        {
+            anyhow::ensure!(
+                from_slot <= until_slot,
+                "--until-slot {until_slot} is below --from-slot {from_slot}"
+            );
+        }
+        // Updates are held until their slot reaches these commitments, lower ones are never streamed
+        anyhow::ensure!(
+            subscribe_args
+                .commit_boundary
+                .is_none_or(|boundary| boundary >= args.commitment.unwrap_or_default()),
+            "--commit-boundary is below --commitment, the held updates would never be written"
+        );
+        anyhow::ensure!(
+            subscribe_args
+                .out_file_commitment
+                .is_none_or(|commitment| commitment >= args.commitment.unwrap_or_default()),
+            "--out-file-commitment is below --commitment, the held updates would never be written"
+        );
+        session.start(budget::Budget {
+            max_messages: subscribe_args.max_messages,
+            max_bytes: subscribe_args.max_bytes,
+            duration: subscribe_args.duration,
+            until_slot: subscribe_args.until_slot,
+        });
+    }
+    let metrics = Arc::new(metrics::Metrics::new(
+        Duration::from_millis(args.pressure_target_lag_ms),
+        memory_limit,
+    ));
+    #[cfg(feature = "control-api")]
+    if let (Some(addr), Some(state)) = (args.control_addr, &state) {
+        control::spawn(addr, Arc::clone(&metrics), Arc::clone(state)).await?;
+    }
+
+    let zero_attempts = Arc::new(Mutex::new(true));
+    let failover = Arc::new(failover::Failover::new(
+        args.endpoint.clone(),
+        args.failover_endpoint.clone(),
+    ));
+    failover.spawn_failback(args.clone(), args.failback_interval);
+    // Slot a stalled subscription resumes from
+    let resume_slot = Arc::new(std::sync::Mutex::new(None::<u64>));
+
+    // The default exponential backoff strategy intervals:
+    // [500ms, 750ms, 1.125s, 1.6875s, 2.53125s, 3.796875s, 5.6953125s,
+    // 8.5s, 12.8s, 19.2s, 28.8s, 43.2s, 64.8s, 97s, ... ]
+    // Failing over to the next endpoint is immediate.
+    retry(failover::FailoverBackoff::new(Arc::clone(&failover)), move || {
+        let mut args = args.clone();
+        let zero_attempts = Arc::clone(&zero_attempts);
+        let metrics = Arc::clone(&metrics);
+        let state = state.clone();
+        let lookups = lookups.clone();
+        let dedup = dedup.clone();
+        let failover = Arc::clone(&failover);
+        let (endpoint_index, endpoint) = failover.active();
+        args.endpoint = endpoint.to_owned();
+        let resume_slot = Arc::clone(&resume_slot);
+        let session = Arc::clone(&session);
+        // Records the outcome of the attempt, which `session` is moved into
+        let report = Arc::clone(&session);
+        let resumed = resume_slot.lock().expect("resume slot lock poisoned").take();
+        if let (Some(slot), Some(Action::Subscribe(subscribe_args))) = (resumed, &mut args.action) {
+            info!("resuming from slot {slot}");
+            subscribe_args.from_slot = subscribe_args.from_slot.max(Some(slot));
+        }
+
+        let attempt = async move {
+            let mut zero_attempts = zero_attempts.lock().await;
+            if *zero_attempts {
+                *zero_attempts = false;
+            } else {
+                info!("Retry to connect to the server");
+            }
+            drop(zero_attempts);
+
+            let commitment = args.get_commitment();
+
+            // Plain stats, latency and captures don't need decoded updates, inspect raw frames instead
+            if let Some(action @ Action::Subscribe(subscribe_args)) = args.action.as_ref()
+                && (subscribe_args.latency
+                    || subscribe_args.capture.is_some()
+                    || (subscribe_args.stats && !subscribe_args.verify_encoding))
+            {
+                let request = action
+                    .get_subscribe_request(commitment)
+                    .await
+                    .map_err(backoff::Error::Permanent)?
+                    .ok_or_else(|| backoff::Error::Permanent(anyhow::anyhow!(
+                        "expect subscribe action"
+                    )))?;
+                let builder = args.builder().await.map_err(exit::backoff_error)?;
+                let client = raw::RawGeyserClient::connect(builder, args.auth_scheme)
+                    .await
+                    .map_err(exit::backoff_error)?;
+                info!("Connected");
+                let resub = subscribe_args.resub.unwrap_or(0);
+                let stall_timeout = subscribe_args.stall_timeout.map(Duration::from_secs);
+                let result = if let Some(path) = &subscribe_args.capture {
+                    let cipher = match &subscribe_args.encryption_key {
+                        Some(key) => Some(
+                            crypto::Cipher::resolve(key)
+                                .await
+                                .map_err(backoff::Error::Permanent)?,
+                        ),
+                        None => None,
+                    };
+                    raw::geyser_subscribe_capture(client, request, resub, stall_timeout, &session, path, cipher)
+                        .await
+                } else if subscribe_args.latency {
+                    let interval = subscribe_args.latency_interval;
+                    raw::geyser_subscribe_latency(client, request, resub, stall_timeout, &session, interval)
+                        .await
+                } else {
+                    raw::geyser_subscribe_stats(client, request, resub, stall_timeout, &session, &metrics)
+                        .await
+                };
+                return result.map_err(exit::backoff_error);
+            }
+
+            let mut client = args.connect().await.map_err(exit::backoff_error)?;
+            info!("Connected");
+
+            let result = match args.action.as_ref() {
+                Some(Action::Index) => {
+                    // This should never happen as we convert Index to Subscribe above
+                    return Err(backoff::Error::Permanent(anyhow::anyhow!(
+                        "Index action should have been converted to Subscribe"
+                    )));
+                }
+                Some(Action::GetAccount { pubkey, stream: true }) => account::first_update(
+                    client,
+                    pubkey,
+                    commitment.unwrap_or_default(),
+                )
+                .await
+                .and_then(|value| account::print(&value))
+                .map_err(exit::backoff_error),
+                Some(
+                    Action::Bench(_)
+                    | Action::Verify(_)
+                    | Action::HealthWatch(_)
+                    | Action::Sync(_)
+                    | Action::Examples { .. }
+                    | Action::Schema { .. }
+                    | Action::Inspect { .. }
+                    | Action::Replay { .. }
+                    | Action::Decrypt { .. }
+                    | Action::Snapshot { .. }
+                    | Action::GetAccount { stream: false, .. },
+                ) => {
+                    return Err(backoff::Error::Permanent(anyhow::anyhow!(
+                        "action is handled before connecting"
+                    )));
+                }
+                Some(Action::HealthCheck) => {
+                    let response = client
+                    .health_check()
+                    .await
+                        .map_err(anyhow::Error::new)?;
+                    print_health_check(&response);
+                    Ok(())
+                }
+                    .map_err(exit::backoff_error),
+                Some(action @ Action::Subscribe(subscribe_args)) => {
+                    let request = action
+                        .get_subscribe_request(commitment)
+                        .await
+                        .map_err(backoff::Error::Permanent)?
+                        .ok_or_else(|| backoff::Error::Permanent(anyhow::anyhow!(
+                            "expect subscribe action"
+                        )))?;
+
+                    geyser_subscribe(
+                        client,
+                        request,
+                        subscribe_args,
+                        &metrics,
+                        &session,
+                        state.as_deref(),
+                        &lookups,
+                        dedup.as_deref(),
+                    )
+                    .await
+                        .map_err(|error| match subscribe_args.error_policy {
+                            ErrorPolicy::Fail => backoff::Error::Permanent(error),
+                            ErrorPolicy::Skip | ErrorPolicy::Reconnect => {
+                                exit::backoff_error(error)
+                            }
+                        })
+                }
+                Some(Action::SubscribeReplayInfo) => client
+                    .subscribe_replay_info()
+                    .await
+                    .map_err(anyhow::Error::new)
+                    .map(|response| info!("response: {response:?}"))
+                    .map_err(exit::backoff_error),
+                // Lost pings are reported, not retried
+                Some(Action::Ping {
+                    count,
+                    interval_ms,
+                    timeout_ms,
+                }) => ping::run(
+                    client,
+                    *count,
+                    Duration::from_millis(*interval_ms),
+                    Duration::from_millis(*timeout_ms),
+                    args.format,
+                )
+                .await
+                .map_err(backoff::Error::Permanent),
+                Some(Action::GetLatestBlockhash) => {
+                    let response = client
+                    .get_latest_blockhash(commitment)
+                    .await
+                        .map_err(anyhow::Error::new)?;
+                    print_latest_blockhash(&response, args.format);
+                    Ok(())
+                }
+                    .map_err(exit::backoff_error),
+                Some(Action::GetBlockHeight) => {
+                    let response = client
+                    .get_block_height(commitment)
+                    .await
+                        .map_err(anyhow::Error::new)?;
+                    print_block_height(&response, args.format);
+                    Ok(())
+                }
+                    .map_err(exit::backoff_error),
+                Some(Action::GetSlot) => {
+                    let response = client
+                    .get_slot(commitment)
+                    .await
+                        .map_err(anyhow::Error::new)?;
+                    print_slot(&response, args.format);
+                    Ok(())
+                }
+                    .map_err(exit::backoff_error),
+                Some(Action::IsBlockhashValid { blockhash }) => {
+                    let response = client
+                    .is_blockhash_valid(blockhash.clone(), commitment)
+                    .await
+                        .map_err(anyhow::Error::new)?;
+                    print_blockhash_valid(&response, args.format);
+                    if response.valid {
+                        Ok(())
+                    } else {
+                        Err(exit::BlockhashInvalid.into())
+                    }
+                }
+                    .map_err(exit::backoff_error),
+                Some(Action::GetVersion) => client
+                    .get_version()
+                    .await
+                    .map_err(anyhow::Error::new)
+                    .map(|response| info!("response: {response:?}"))
+                    .map_err(exit::backoff_error),
+                None => {
+                    // This should never happen as we set default to Index above
+                    return Err(backoff::Error::Permanent(anyhow::anyhow!(
+                        "No action specified"
+                    )));
+                }
+            };
+
+            result?;
+
+            Ok::<(), backoff::Error<anyhow::Error>>(())
+        }
+        .inspect_err(|error| error!("failed to connect: {error}"));
+
+        async move {
+            // `--duration` may be spent while reconnecting
+            if let Some(reason) = report.budget_spent() {
+                info!("{reason}, stopping");
+                return Ok(());
+            }
+            report.record_attempt();
+            let result = tokio::select! {
+                result = attempt => result,
+                () = failover.replaced(endpoint_index) => Err(backoff::Error::transient(
+                    anyhow::anyhow!("switching endpoints"),
+                )),
+            };
+            if let Err(backoff::Error::Transient { err, .. } | backoff::Error::Permanent(err)) =
+                &result
+            {
+                report.record_error(exit::ErrorClass::of(err));
+            }
+            if let Err(backoff::Error::Transient { err, .. }) = &result {
+                // Keep resuming while connecting fails, a stream that ran resumes only on a stall
+                let stalled = err.chain().find_map(|cause| cause.downcast_ref::<exit::Stalled>());
+                let resume = match stalled {
+                    Some(stalled) => stalled.slot.max(resumed),
+                    None if err.chain().any(|cause| cause.is::<tonic::transport::Error>()) => {
+                        resumed
+                    }
+                    None => None,
+                };
+                *resume_slot.lock().expect("resume slot lock poisoned") = resume;
+                failover.failed(endpoint_index);
+            }
+            result
+        }
+    })
+    .await
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn geyser_subscribe(
+    mut client: GeyserGrpcClient<impl Interceptor>,
+    mut request: SubscribeRequest,
+    args: &ActionSubscribe,
+    metrics: &metrics::Metrics,
+    session: &session::Session,
+    state: Option<&state::StateStore>,
+    lookups: &RpcLookups,
+    dedup: Option<&std::sync::Mutex<dedup::Dedup>>,
+) -> anyhow::Result<()> {
+    let resub = args.resub.unwrap_or(0);
+    let stall_timeout = args.stall_timeout.map(Duration::from_secs);
+    let mut ping_interval = args.ping_interval_ms.map(|interval| {
+        let period = Duration::from_millis(interval);
+        let mut ping_interval =
+            tokio::time::interval_at(tokio::time::Instant::now() + period, period);
+        ping_interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        ping_interval
+    });
+    let mut ping_id = 0;
+    let stats = args.stats;
+    let decode_workers = args.decode_workers;
+    let datasize = args.accounts_datasize_min.unwrap_or(0)
+        ..=args.accounts_datasize_max.unwrap_or(u64::MAX);
+    anyhow::ensure!(
+        !datasize.is_empty(),
+        "--accounts-datasize-min is above --accounts-datasize-max"
+    );
+    let mut throttle = throttle::Throttle::new(&args.sample, &args.max_rate);
+    let cipher = match &args.encryption_key {
+        Some(key) => Some(Arc::new(crypto::Cipher::resolve(key).await?)),
+        None => None,
+    };
+    // Loaded on every (re)connect so a reconnect resumes from what was written
+    let checkpoint = args
+        .checkpoint_file
+        .clone()
+        .map(checkpoint::Checkpoint::load)
+        .transpose()?;
+    if let Some(slot) = checkpoint.as_ref().and_then(checkpoint::Checkpoint::slot) {
+        request.from_slot = request.from_slot.max(Some(slot));
+    }
+    let mut output = UpdateOutput {
+        slow_threshold: args.slow_threshold_ms.map(Duration::from_millis),
+        metrics,
+        session,
+        state,
+        where_expr: args.where_expr.as_ref(),
+        grep: args.grep.as_ref(),
+        select: args.select.as_ref(),
+        data_output: encoding::DataOutput {
+            encoding: args.data_encoding,
+            max_bytes: args.max_data_bytes,
+        },
+        #[cfg(feature = "wasm")]
+        transform: args
+            .transform
+            .as_deref()
+            .map(transform::Transform::load)
+            .transpose()?,
+        #[cfg(feature = "scripting")]
+        script: args
+            .script
+            .as_deref()
+            .map(script::Script::load)
+            .transpose()?,
+        drift: args
+            .schema_drift_learn_secs
+            .map(|secs| drift::DriftDetector::new(Duration::from_secs(secs))),
+        envelope: args.envelope,
+        watermark: args
+            .watermark_interval
+            .map(|interval| watermark::Watermark::new(interval, &mut request)),
+        memcmp: memcmp_checks(&request)?,
+        from_slot: request.from_slot,
+        last_slot: None,
+        dead_letters: args
+            .dead_letter_dir
+            .clone()
+            .map(deadletter::DeadLetters::new)
+            .transpose()?,
+        error_policy: args.error_policy,
+        checkpoint,
+        undelivered: VecDeque::new(),
+        dedup,
+        slot_buffer: args
+            .commit_boundary
+            .map(|boundary| slotbuffer::SlotBuffer::new(boundary.into(), &mut request)),
+        compactor: (args.accounts_mode == compact::AccountsMode::Latest)
+            .then(|| compact::Compactor::new(args.accounts_window)),
+        reorder: args
+            .ordered_by_slot
+            .then(|| reorder::Reorder::new(Duration::from_millis(args.max_reorder_delay_ms))),
+        out_file: args
+            .out_file
+            .as_deref()
+            .map(|path| {
+                let commitment = args.out_file_commitment.map(Into::into);
+                let rotate = args.rotate.map(|rotate| (rotate, args.rotate_compression));
+                let retention = (!args.retain.is_empty())
+                    .then(|| retention::Retention::new(args.retain.clone(), args.prune_interval));
+                outfile::OutFile::open(path, commitment, rotate, retention, cipher, &mut request)
+            })
+            .transpose()?,
+        #[cfg(feature = "arrow")]
+        arrow_file: args
+            .arrow_file
+            .as_deref()
+            .map(|path| arrowfile::ArrowFile::create(path, args.arrow_batch_rows as usize))
+            .transpose()?,
+        sinks: (!args.sink.is_empty())
+            .then(|| {
+                let options = sink::Options {
+                    queue: args.sink_queue as usize,
+                    overflow: args.sink_overflow,
+                    retries: args.sink_retries,
+                };
+                sink::Sinks::open(&args.sink, &options)
+            })
+            .transpose()?,
+        reassembler: args
+            .reassemble_blocks
+            .then(|| reassemble::Reassembler::new(&mut request))
+            .transpose()?,
+        vote_stats: args
+            .vote_stats_interval_secs
+            .map(|interval| votes::VoteStats::new(Duration::from_secs(interval), &mut request)),
+        aggregator: args
+            .aggregate
+            .map(|aggregation| aggregate::Aggregator::new(aggregation, args.window, args.top, &mut request)),
+        fee_stats: args
+            .fee_stats
+            .then(|| fees::FeeStats::new(&mut request)),
+        instruction_stats: (!args.instruction_stats.is_empty()).then(|| {
+            discriminators::InstructionStats::new(
+                &args.instruction_stats,
+                args.instruction_stats_window,
+                &mut request,
+            )
+        }),
+        classify_errors: args.classify_errors,
+        program_changes: args.program_changes,
+        network: network::NetworkStats::default(),
+        network_stats: args.network_stats,
+        skip_stats: args
+            .skip_stats
+            .then(|| lookups.leaders.clone().map(skips::SkipStats::new))
+            .flatten(),
+        new_mints: args.new_mints,
+        jito_tips: args.jito_tips.then(jito::JitoTips::default),
+        watchlist: args
+            .watchlist
+            .as_deref()
+            .map(watchlist::Watchlist::load)
+            .transpose()?,
+        labels: (args.labels || !args.labels_csv.is_empty())
+            .then(|| labels::Labels::load(&args.labels_csv))
+            .transpose()?,
+        layouts: (!args.layouts.is_empty())
+            .then(|| layouts::Layouts::load(&args.layouts))
+            .transpose()?,
+        memos: (args.memos || !args.memo_regex.is_empty())
+            .then(|| memos::Memos::new(args.memo_regex.clone())),
+        lookups,
+    };
+    if let Some(sinks) = &output.sinks {
+        metrics.set_sinks(sinks.stats());
+    }
+    // Added last, other features only add their filters when transactions aren't subscribed
+    if args.program_changes {
+        programs::subscribe(&mut request);
+    }
+    if args.new_mints {
+        mints::subscribe(&mut request);
+    }
+    if args.jito_tips {
+        jito::subscribe(&mut request);
+    }
+    if args.network_stats {
+        network::NetworkStats::subscribe(&mut request);
+    }
+    if args.skip_stats {
+        skips::SkipStats::subscribe(&mut request);
+    }
+    if let Some(watchlist) = &output.watchlist {
+        watchlist.subscribe(&mut request);
+    }
+    let keep_raw = output.dead_letters.is_some();
+
+    let mut progress = StatsProgress::new()?;
+    #[cfg(feature = "verify-encoding")]
+    let mut verifier = args
+        .verify_encoding
+        .then(|| {
+            verify::EncodingVerifier::new(
+                &progress.multi,
+                &session.encoding,
+                args.verify_report.as_deref(),
+            )
+        })
+        .transpose()?;
+    #[cfg(not(feature = "verify-encoding"))]
+    anyhow::ensure!(
+        !args.verify_encoding,
+        "--verify-encoding requires the `verify-encoding` feature"
+    );
+    #[cfg(not(feature = "wasm"))]
+    anyhow::ensure!(
+        args.transform.is_none(),
+        "--transform requires the `wasm` feature"
+    );
+    #[cfg(not(feature = "scripting"))]
+    anyhow::ensure!(
+        args.script.is_none(),
+        "--script requires the `scripting` feature"
+    );
+    #[cfg(not(feature = "arrow"))]
+    anyhow::ensure!(
+        args.arrow_file.is_none(),
+        "--arrow-file requires the `arrow` feature"
+    );
+
+    let (mut subscribe_tx, mut stream) = client
+        .subscribe_with_request(Some(request))
+        .instrument(info_span!("subscribe"))
+        .await?;
+
+    // Updates are decoded on the blocking pool, `FuturesOrdered` yields them back in the
+    // order they were received so the output order matches the stream order
+    let mut pending: FuturesOrdered<
+        JoinHandle<Result<DecodedUpdate, deadletter::DecodeFailure>>,
+    > = FuturesOrdered::new();
+
+    info!("stream opened");
+    let idle = tokio::time::sleep(stall_timeout.unwrap_or_default());
+    tokio::pin!(idle);
+    let deadline = session.deadline();
+    let until_deadline =
+        tokio::time::sleep_until(deadline.map_or_else(tokio::time::Instant::now, Into::into));
+    tokio::pin!(until_deadline);
+    // Held updates are due on time on a quiet stream too
+    let mut due_interval = output.due_period().map(|period| {
+        let mut due_interval = tokio::time::interval_at(tokio::time::Instant::now() + period, period);
+        due_interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        due_interval
+    });
+    let mut counter = 0;
+    let mut stream_error = None;
+    let mut stalled = false;
+    let mut budget_spent = false;
+    loop {
+        if let Some(reason) = session.budget_spent() {
+            info!("{reason}, stopping");
+            budget_spent = true;
+            break;
+        }
+        metrics.set_queue(pending.len(), decode_workers);
+        let message = tokio::select! {
+            message = stream.next(), if pending.len() < decode_workers => {
+                if let Some(stall_timeout) = stall_timeout {
+                    idle.as_mut().reset(tokio::time::Instant::now() + stall_timeout);
+                }
+                match message {
+                    Some(message) => message,
+                    None => break,
+                }
+            }
+            Some(decoded) = pending.next(), if !pending.is_empty() => {
+                // Decoding backpressure pauses reads, that isn't a silent stream
+                if let Some(stall_timeout) = stall_timeout {
+                    idle.as_mut().reset(tokio::time::Instant::now() + stall_timeout);
+                }
+                output.write_decoded(decoded.context("decode worker failed")?)?;
+                continue;
+            }
+            _ = async { ping_interval.as_mut().expect("checked").tick().await }, if ping_interval.is_some() => {
+                ping_id += 1;
+                subscribe_tx
+                    .send(SubscribeRequest {
+                        ping: Some(SubscribeRequestPing { id: ping_id }),
+                        ..Default::default()
+                    })
+                    .await?;
+                continue;
+            }
+            _ = async { due_interval.as_mut().expect("checked").tick().await }, if due_interval.is_some() => {
+                output.write_due()?;
+                continue;
+            }
+            () = &mut idle, if stall_timeout.is_some() => {
+                stalled = true;
+                break;
+            }
+            // The budget check above stops the subscription
+            () = &mut until_deadline, if deadline.is_some() => continue,
+        };
+
+        match message {
+            Ok(msg) => {
+                if let Some(update) = &msg.update_oneof {
+                    let slot = session::update_slot(update);
+                    if let Some(reason) = session.budget_past(slot) {
+                        info!("{reason}, stopping");
+                        budget_spent = true;
+                        break;
+                    }
+                    session.record_update(StatsKind::from_update(update), msg.encoded_len() as u64, slot);
+                }
+                if stats {
+                    let encoded_len = msg.encoded_len() as u64;
+                    let Some(update) = msg.update_oneof.as_ref() else {
+                        progress.multi.println("update not found in the message")?;
+                        break;
+                    };
+                    progress.inc(StatsKind::from_update(update), encoded_len);
+                    if let UpdateOneof::BlockMeta(meta) = update {
+                        let snapshot = output.network.observe(
+                            meta.slot,
+                            meta.parent_slot,
+                            meta.block_time.map(|time| time.timestamp),
+                            meta.executed_transaction_count,
+                            meta.entries_count,
+                        );
+                        output.metrics.set_network(snapshot);
+                        progress.set_network(&snapshot)?;
+                    }
+
+                    #[cfg(feature = "verify-encoding")]
+                    if let Some(verifier) = &mut verifier {
+                        verifier.verify(msg, &progress.multi).await?;
+                    }
+
+                    continue;
+                }
+
+                match msg.update_oneof {
+                    Some(UpdateOneof::Ping(_)) => {
+                        // This is necessary to keep load balancers that expect client pings alive. If your load balancer doesn't
+                        // require periodic client pings then this is unnecessary
+                        subscribe_tx
+                            .send(SubscribeRequest {
+                                ping: Some(SubscribeRequestPing { id: 1 }),
+                                ..Default::default()
+                            })
+                            .await?;
+                    }
+                    Some(UpdateOneof::Pong(_)) => {}
+                    // Server filters only match an exact size, ranges are applied before decoding
+                    Some(UpdateOneof::Account(ref update))
+                        if !datasize.contains(
+                            &update
+                                .account
+                                .as_ref()
+                                .map_or(0, |account| account.data.len() as u64),
+                        ) => {}
+                    Some(ref update) if !throttle.admit(StatsKind::from_update(update)) => {}
+                    Some(UpdateOneof::Block(_)) if args.block_chunk_size.is_some() => {
+                        // Updates received before the block are written first
+                        while let Some(decoded) = pending.next().await {
+                            output.write_decoded(decoded.context("decode worker failed")?)?;
+                        }
+                        let chunk_size = args.block_chunk_size.expect("checked above") as usize;
+                        let received_at = Instant::now();
+                        tokio::task::block_in_place(|| {
+                            blockchunks::decode(msg, chunk_size, received_at, args.trace_ids, keep_raw, |decoded| {
+                                output.write_decoded(decoded)
+                            })
+                        })?;
+                    }
+                    Some(_) => {
+                        let received_at = Instant::now();
+                        let trace = args.trace_ids.then(tracecontext::UpdateTrace::new);
+                        let decode = move || {
+                            let entered = trace.as_ref().map(|trace| trace.span().enter());
+                            // Decoding consumes the update, keep its encoding for a dead letter
+                            let raw = keep_raw.then(|| msg.encode_to_vec());
+                            let decoded = decode_update(msg, received_at)
+                                .map_err(|error| deadletter::DecodeFailure { error, raw });
+                            drop(entered);
+                            decoded.map(|decoded| DecodedUpdate { trace, ..decoded })
+                        };
+                        if decode_workers == 1 {
+                            // Nothing is pending with a single worker, a blocking task would only add a thread hop
+                            output.write_decoded(decode())?;
+                        } else {
+                            pending.push_back(tokio::task::spawn_blocking(decode));
+                        }
+                    }
+                    None => {
+                        let error = anyhow::anyhow!("update not found in the message");
+                        if !output.write_dead_letter(&msg.encode_to_vec(), &error)? {
+                            error!("{error}");
+                            if args.error_policy != ErrorPolicy::Skip {
+                                break;
+                            }
+                        }
+                    }
+                }
+            }
+            Err(error) if args.error_policy == ErrorPolicy::Skip => {
+                warn!("skipping stream error: {error:?}");
+            }
+            Err(error) => {
+                error!("error: {error:?}");
+                stream_error = Some(error);
+                break;
+            }
+        }
+
+        // Example to illustrate how to resubscribe/update the subscription
+        counter += 1;
+        if counter == resub {
+            let mut new_slots: SlotsFilterMap = HashMap::new();
+            new_slots.insert("client".to_owned(), SubscribeRequestFilterSlots::default());
+
+            subscribe_tx
+                .send(SubscribeRequest {
+                    slots: new_slots.clone(),
+                    accounts: HashMap::default(),
+                    transactions: HashMap::default(),
+                    transactions_status: HashMap::default(),
+                    entry: HashMap::default(),
+                    blocks: HashMap::default(),
+                    blocks_meta: HashMap::default(),
+                    commitment: None,
+                    accounts_data_slice: Vec::default(),
+                    ping: None,
+                    from_slot: None,
+                })
+                .await
+                .map_err(GeyserGrpcClientError::SubscribeSendError)?;
+        }
+    }
+
+    // Flush updates which were still being decoded when the stream ended
+    while let Some(decoded) = pending.next().await {
+        output.write_decoded(decoded.context("decode worker failed")?)?;
+    }
+    let compacted = output
+        .compactor
+        .as_mut()
+        .map(compact::Compactor::drain)
+        .unwrap_or_default();
+    for decoded in compacted {
+        output.write_ordered(decoded)?;
+    }
+    let reordered = output
+        .reorder
+        .as_mut()
+        .map(reorder::Reorder::drain)
+        .unwrap_or_default();
+    for decoded in reordered {
+        output.write(decoded)?;
+    }
+    throttle.log_dropped();
+    info!("stream closed");
+    if budget_spent {
+        return Ok(());
+    }
+    Err(match stream_error {
+        Some(status) => anyhow::Error::new(status),
+        None if stalled => exit::Stalled {
+            timeout: stall_timeout.unwrap_or_default(),
+            slot: output.last_slot,
+        }
+        .into(),
+        None => exit::StreamEnded.into(),
+    })
+}
+
+/// JSON-RPC backed lookups enriching updates, loaded once and kept across reconnects
+#[derive(Clone, Default)]
+struct RpcLookups {
+    leaders: Option<Arc<leaders::Leaders>>,
+    epoch_events: Option<Arc<epochs::EpochEvents>>,
+    mints: Option<Arc<tokens::Mints>>,
+}
+
+impl RpcLookups {
+    async fn load(args: &Args, subscribe_args: &ActionSubscribe) -> anyhow::Result<Self> {
+        let mut lookups = Self::default();
+        let leaders = subscribe_args.leaders || subscribe_args.skip_stats;
+        if !(leaders || subscribe_args.epoch_events || subscribe_args.resolve_tokens) {
+            return Ok(lookups);
+        }
+        let rpc_url = secrets::resolve(&args.rpc_url).await?;
+        let commitment = args.get_commitment().unwrap_or_default();
+        if leaders {
+            let rpc = rpc::RpcClient::new(rpc_url.clone());
+            lookups.leaders = Some(Arc::new(leaders::Leaders::load(rpc, commitment).await?));
+        }
+        if subscribe_args.epoch_events {
+            let rpc = rpc::RpcClient::new(rpc_url.clone());
+            let epoch_events = epochs::EpochEvents::load(rpc, commitment).await?;
+            lookups.epoch_events = Some(Arc::new(epoch_events));
+        }
+        if subscribe_args.resolve_tokens {
+            let rpc = rpc::RpcClient::new(rpc_url);
+            let mints = tokens::Mints::new(rpc, commitment, subscribe_args.mint_cache.clone())?;
+            lookups.mints = Some(Arc::new(mints));
+        }
+        Ok(lookups)
+    }
+}
+
+struct DecodedUpdate {
+    kind: &'static str,
+    created_at: SystemTime,
+    filters: Vec<String>,
+    value: Value,
+    timings: UpdateTimings,
+    /// With `--trace-ids`
+    trace: Option<tracecontext::UpdateTrace>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct UpdateTimings {
+    received_at: Instant,
+    /// Time spent waiting for a free decode worker
+    queued: Duration,
+    decode: Duration,
+    /// CPU time of the decode stage, lower than `decode` when the worker was preempted
+    decode_cpu: Duration,
+}
+
+#[tracing::instrument(name = "decode", level = "debug", skip_all, fields(kind = tracing::field::Empty))]
+fn decode_update(msg: SubscribeUpdate, received_at: Instant) -> anyhow::Result<DecodedUpdate> {
+    let decode_started_at = Instant::now();
+    let cpu_started_at = resources::thread_cpu_time();
+    let filters = msg.filters;
+    let created_at: SystemTime = msg
+        .created_at
+        .ok_or(anyhow::anyhow!("no created_at in the message"))?
+        .try_into()
+        .context("failed to parse created_at")?;
+    let rewards = |rewards| {
+        convert_from::create_rewards_obj(rewards)
+            .map_err(|error| anyhow::anyhow!(error))
+            .and_then(|rewards| Ok(serde_json::to_value(rewards)?))
+    };
+    let (kind, value) = match msg.update_oneof {
+        Some(UpdateOneof::Account(msg)) => {
+            let account = msg
+                .account
+                .ok_or(anyhow::anyhow!("no account in the message"))?;
+            let value = serde_json::to_value(schema::Account {
+                account: create_pretty_account(account)?,
+                is_startup: msg.is_startup,
+                slot: msg.slot,
+            })?;
+            ("account", value)
+        }
+        Some(UpdateOneof::Slot(msg)) => {
+            let status = SlotStatus::try_from(msg.status)
+                .context("failed to decode commitment")?;
+            (
+                "slot",
+                serde_json::to_value(schema::Slot {
+                    slot: msg.slot,
+                    parent: msg.parent,
+                    status: status.as_str_name().to_owned(),
+                    dead_error: msg.dead_error,
+                })?,
+            )
+        }
+        Some(UpdateOneof::Transaction(msg)) => {
+            let tx = msg
+                .transaction
+                .ok_or(anyhow::anyhow!("no transaction in the message"))?;
+            let value = serde_json::to_value(schema::Transaction {
+                transaction: create_pretty_transaction(tx)?,
+                slot: msg.slot,
+            })?;
+            ("transaction", value)
+        }
+        Some(UpdateOneof::TransactionStatus(msg)) => (
+            "transactionStatus",
+            serde_json::to_value(schema::TransactionStatus {
+                slot: msg.slot,
+                signature: Signature::try_from(msg.signature.as_slice()).context("invalid signature")?.to_string(),
+                is_vote: msg.is_vote,
+                index: msg.index,
+                err: serde_json::to_value(
+                    convert_from::create_tx_error(msg.err.as_ref())
+                        .map_err(|error| anyhow::anyhow!(error))
+                        .context("invalid error")?,
+                )?,
+            })?,
+        ),
+        Some(UpdateOneof::Entry(msg)) => ("entry", serde_json::to_value(create_pretty_entry(msg)?)?),
+        Some(UpdateOneof::BlockMeta(msg)) => (
+            "blockmeta",
+            serde_json::to_value(schema::BlockMeta {
+                slot: msg.slot,
+                blockhash: msg.blockhash,
+                rewards: msg.rewards.map(rewards).transpose()?,
+                block_time: msg.block_time.map(|obj| obj.timestamp),
+                block_height: msg.block_height.map(|obj| obj.block_height),
+                parent_slot: msg.parent_slot,
+                parent_blockhash: msg.parent_blockhash,
+                executed_transaction_count: msg.executed_transaction_count,
+                entries_count: msg.entries_count,
+            })?,
+        ),
+        Some(UpdateOneof::Block(msg)) => (
+            "block",
+            serde_json::to_value(schema::Block {
+                slot: msg.slot,
+                blockhash: msg.blockhash,
+                rewards: msg.rewards.map(rewards).transpose()?,
+                block_time: msg.block_time.map(|obj| obj.timestamp),
+                block_height: msg.block_height.map(|obj| obj.block_height),
+                parent_slot: msg.parent_slot,
+                parent_blockhash: msg.parent_blockhash,
+                executed_transaction_count: msg.executed_transaction_count,
+                transactions: msg.transactions.into_iter().map(create_pretty_transaction).collect::<Result<_, _>>()?,
+                updated_account_count: msg.updated_account_count,
+                accounts: msg.accounts.into_iter().map(create_pretty_account).collect::<Result<_, _>>()?,
+                entries_count: msg.entries_count,
+                entries: msg.entries.into_iter().map(create_pretty_entry).collect::<Result<_, _>>()?,
+            })?,
+        ),
+        Some(UpdateOneof::Ping(_)) | Some(UpdateOneof::Pong(_)) | None => {
+            anyhow::bail!("update is not decodable")
+        }
+    };
+    tracing::Span::current().record("kind", kind);
+    Ok(DecodedUpdate {
+        kind,
+        created_at,
+        filters,
+        value,
+        timings: UpdateTimings {
+            received_at,
+            queued: decode_started_at.duration_since(received_at),
+            decode: decode_started_at.elapsed(),
+            decode_cpu: resources::thread_cpu_time().saturating_sub(cpu_started_at),
+        },
+        trace: None,
+    })
+}
+
+/// Everything a decoded update goes through after the decode stage
+struct UpdateOutput<'a> {
+    slow_threshold: Option<Duration>,
+    metrics: &'a metrics::Metrics,
+    session: &'a session::Session,
+    state: Option<&'a state::StateStore>,
+    where_expr: Option<&'a expr::Expr>,
+    grep: Option<&'a regex::Regex>,
+    select: Option<&'a select::Select>,
+    data_output: encoding::DataOutput,
+    #[cfg(feature = "wasm")]
+    transform: Option<transform::Transform>,
+    #[cfg(feature = "scripting")]
+    script: Option<script::Script>,
+    drift: Option<drift::DriftDetector>,
+    envelope: Option<Envelope>,
+    watermark: Option<watermark::Watermark>,
+    /// Expected `(offset, hex data)` of the memcmp filters, re-checked on account updates
+    memcmp: Vec<(usize, String)>,
+    /// Replays start at `--from-slot` (the handoff slot for `sync`), older updates are dropped
+    from_slot: Option<u64>,
+    /// Highest slot decoded, a stalled subscription resumes from it
+    last_slot: Option<u64>,
+    dead_letters: Option<deadletter::DeadLetters>,
+    error_policy: ErrorPolicy,
+    checkpoint: Option<checkpoint::Checkpoint>,
+    /// With `--sink`, positions waiting for the sinks to deliver the update queued last before
+    /// them, by its sequence number
+    undelivered: VecDeque<(u64, checkpoint::Position)>,
+    dedup: Option<&'a std::sync::Mutex<dedup::Dedup>>,
+    slot_buffer: Option<slotbuffer::SlotBuffer>,
+    compactor: Option<compact::Compactor>,
+    reorder: Option<reorder::Reorder>,
+    out_file: Option<outfile::OutFile>,
+    #[cfg(feature = "arrow")]
+    arrow_file: Option<arrowfile::ArrowFile>,
+    sinks: Option<sink::Sinks>,
+    reassembler: Option<reassemble::Reassembler>,
+    vote_stats: Option<votes::VoteStats>,
+    aggregator: Option<aggregate::Aggregator>,
+    fee_stats: Option<fees::FeeStats>,
+    instruction_stats: Option<discriminators::InstructionStats>,
+    classify_errors: bool,
+    program_changes: bool,
+    network: network::NetworkStats,
+    network_stats: bool,
+    skip_stats: Option<skips::SkipStats>,
+    new_mints: bool,
+    jito_tips: Option<jito::JitoTips>,
+    watchlist: Option<watchlist::Watchlist>,
+    labels: Option<labels::Labels>,
+    layouts: Option<layouts::Layouts>,
+    memos: Option<memos::Memos>,
+    lookups: &'a RpcLookups,
+}
+
+/// Wait for the sinks before the checkpoint is saved, so it covers what they delivered
+impl Drop for UpdateOutput<'_> {
+    fn drop(&mut self) {
+        if let Some(sinks) = &mut self.sinks {
+            sinks.close();
+        }
+        self.record_delivered();
+    }
+}
+
+/// Filters added for events only, updates matching nothing else aren't written
+const EVENT_FILTERS: [&str; 7] = [
+    fees::FILTER,
+    programs::FILTER,
+    mints::FILTER,
+    network::FILTER,
+    skips::FILTER,
+    discriminators::FILTER,
+    jito::FILTER,
+];
+
+impl UpdateOutput<'_> {
+    fn write_decoded(
+        &mut self,
+        decoded: Result<DecodedUpdate, deadletter::DecodeFailure>,
+    ) -> anyhow::Result<()> {
+        match decoded {
+            Ok(decoded) => {
+                self.session.record_decode(decoded.timings.decode);
+                if let Some(dedup) = self.dedup
+                    && dedup
+                        .lock()
+                        .expect("dedup lock poisoned")
+                        .is_duplicate(decoded.kind, &decoded.value)
+                {
+                    self.metrics.record_duplicate();
+                    return Ok(());
+                }
+                if let Some(slot) = decoded.value.get("slot").and_then(Value::as_u64) {
+                    self.last_slot = self.last_slot.max(Some(slot));
+                }
+                if decoded.kind == "slot"
+                    && let Some(out_file) = &mut self.out_file
+                {
+                    out_file.observe_slot(&decoded.value)?;
+                }
+                let ready = match &mut self.slot_buffer {
+                    Some(slot_buffer) => slot_buffer.push(decoded),
+                    None => slotbuffer::strip(decoded).into_iter().collect(),
+                };
+                for decoded in ready {
+                    let decoded = match &mut self.compactor {
+                        Some(compactor) => compactor.push(decoded),
+                        None => Some(decoded),
+                    };
+                    if let Some(decoded) = decoded {
+                        self.write_ordered(decoded)?;
+                    }
+                }
+                self.write_due()?;
+            }
+            Err(deadletter::DecodeFailure {
+                error,
+                raw: Some(raw),
+            }) if self.write_dead_letter(&raw, &error)? => {}
+            Err(failure) if self.error_policy == ErrorPolicy::Skip => {
+                warn!("skipping update which failed to decode: {:#}", failure.error);
+            }
+            Err(failure) => return Err(failure.error),
+        }
+        Ok(())
+    }
+
+    /// Write the updates held by `--accounts-mode latest` and `--ordered-by-slot` which are due
+    fn write_due(&mut self) -> anyhow::Result<()> {
+        let compacted = self
+            .compactor
+            .as_mut()
+            .map(compact::Compactor::due)
+            .unwrap_or_default();
+        for decoded in compacted {
+            self.write_ordered(decoded)?;
+        }
+        let reordered = self
+            .reorder
+            .as_mut()
+            .map(reorder::Reorder::due)
+            .unwrap_or_default();
+        for decoded in reordered {
+            self.write(decoded)?;
+        }
+        Ok(())
+    }
+
+    /// How often held updates are checked for being due while no update arrives, a tenth of
+    /// the shortest hold
+    fn due_period(&self) -> Option<Duration> {
+        let compactor = self.compactor.as_ref().map(compact::Compactor::window);
+        let reorder = self.reorder.as_ref().map(reorder::Reorder::delay);
+        compactor
+            .into_iter()
+            .chain(reorder)
+            .min()
+            .map(|hold| (hold / 10).max(Duration::from_millis(1)))
+    }
+
+    /// Keep an update that failed to decode, `false` without `--dead-letter-dir`
+    fn write_dead_letter(&mut self, raw: &[u8], error: &anyhow::Error) -> anyhow::Result<bool> {
+        let Some(dead_letters) = &mut self.dead_letters else {
+            return Ok(false);
+        };
+        dead_letters.write(raw, error)?;
+        self.metrics.record_dead_letter();
+        Ok(true)
+    }
+
+    /// Write an update, or hold it for `--ordered-by-slot`
+    fn write_ordered(&mut self, decoded: DecodedUpdate) -> anyhow::Result<()> {
+        let decoded = match &mut self.reorder {
+            Some(reorder) => reorder.push(decoded),
+            None => Some(decoded),
+        };
+        match decoded {
+            Some(decoded) => self.write(decoded),
+            None => Ok(()),
+        }
+    }
+
+    fn write(&mut self, decoded: DecodedUpdate) -> anyhow::Result<()> {
+        let decode_cpu = decoded.timings.decode_cpu;
+        let cpu_started_at = resources::thread_cpu_time();
+        let position = self
+            .checkpoint
+            .as_ref()
+            .and_then(|_| checkpoint::Position::of(decoded.kind, &decoded.value));
+        if let (Some(checkpoint), Some(position)) = (&self.checkpoint, &position)
+            && checkpoint.contains(position)
+        {
+            return Ok(());
+        }
+        self.write_update(decoded)?;
+        // Filtered out updates are recorded too, a replay filters them the same way
+        if let (Some(checkpoint), Some(position)) = (&mut self.checkpoint, position) {
+            match &self.sinks {
+                Some(sinks) if !sinks.lost() => {
+                    self.undelivered.push_back((sinks.queued(), position));
+                }
+                // The checkpoint stays before the lost update, a restart delivers it again
+                Some(_) => {}
+                None => checkpoint.record(position),
+            }
+            self.record_delivered();
+        }
+        let output_cpu = resources::thread_cpu_time().saturating_sub(cpu_started_at);
+        self.metrics.record_cpu(decode_cpu, output_cpu);
+        Ok(())
+    }
+
+    /// Record the positions of the updates every sink delivered in `--checkpoint-file`
+    fn record_delivered(&mut self) {
+        let (Some(checkpoint), Some(sinks)) = (&mut self.checkpoint, &self.sinks) else {
+            return;
+        };
+        if sinks.lost() {
+            if !self.undelivered.is_empty() {
+                match checkpoint.slot() {
+                    Some(slot) => warn!(
+                        "a sink lost an update, --checkpoint-file stays at slot {slot} so a restart delivers it again"
+                    ),
+                    None => warn!(
+                        "a sink lost an update, --checkpoint-file stays empty so a restart delivers it again"
+                    ),
+                }
+                self.undelivered.clear();
+            }
+            return;
+        }
+        let delivered = sinks.delivered();
+        while self
+            .undelivered
+            .front()
+            .is_some_and(|(sequence, _)| *sequence <= delivered)
+        {
+            let Some((_, position)) = self.undelivered.pop_front() else {
+                break;
+            };
+            let previous = checkpoint.slot();
+            checkpoint.record(position);
+            if let Some(previous) = previous
+                && checkpoint.slot() != Some(previous)
+            {
+                sinks.checkpoint(previous);
+            }
+        }
+    }
+
+    fn write_update(&mut self, decoded: DecodedUpdate) -> anyhow::Result<()> {
+        let DecodedUpdate {
+            kind,
+            created_at,
+            mut filters,
+            mut value,
+            timings,
+            trace,
+        } = decoded;
+        let _entered = trace.as_ref().map(|trace| trace.span().enter());
+        // Logs when dropped, i.e. on every way out of the output stage
+        let _slow = self.slow_threshold.map(|threshold| SlowUpdate {
+            threshold,
+            kind,
+            identity: update_identity(kind, &value),
+            timings,
+            output_started_at: Instant::now(),
+        });
+        let traceparent = trace.as_ref().map(tracecontext::UpdateTrace::traceparent);
+        self.metrics.record_update(created_at);
+        if matches!(kind, "slot" | "blockmeta")
+            && let Some(leaders) = &self.lookups.leaders
+            && let Some(slot) = value.get("slot").and_then(Value::as_u64)
+        {
+            value["leader"] = json!(leaders.leader(slot));
+        }
+        if kind == "account"
+            && let Some(mints) = &self.lookups.mints
+        {
+            mints.annotate(&mut value);
+        }
+        if let Some(labels) = &self.labels {
+            labels.annotate(kind, &mut value);
+        }
+        if let Some(layouts) = &self.layouts {
+            layouts.annotate(kind, &mut value);
+        }
+        if let Some(memos) = &self.memos {
+            memos.annotate(kind, &mut value);
+        }
+        if self.classify_errors && matches!(kind, "transaction" | "transactionStatus") {
+            let error = errors::classify(kind, &value);
+            self.metrics.record_transaction(kind, error.as_ref());
+            if let Some(error) = error {
+                value["error"] = error;
+            }
+        }
+        if let Some(watchlist) = &self.watchlist {
+            watchlist.annotate(kind, &mut value);
+        }
+        if let Some(epoch_events) = &self.lookups.epoch_events {
+            if let Some(ended) = value
+                .get("slot")
+                .and_then(Value::as_u64)
+                .and_then(|slot| epoch_events.observe(slot))
+            {
+                self.write_event("epochEnd", epochs::FILTER, ended)?;
+            }
+            for started in epoch_events.started() {
+                self.write_event("epochStart", epochs::FILTER, started)?;
+            }
+        }
+        if let Some(from_slot) = self.from_slot
+            && value
+                .get("slot")
+                .and_then(Value::as_u64)
+                .is_some_and(|slot| slot < from_slot)
+        {
+            return Ok(());
+        }
+        if let Some(watermark) = &mut self.watermark
+            && watermark.observe(kind, &filters, &value)
+        {
+            self.write_watermark()?;
+            return Ok(());
+        }
+        if kind == "blockmeta" {
+            let snapshot = self.network.observe(
+                value["slot"].as_u64().unwrap_or_default(),
+                value["parentSlot"].as_u64().unwrap_or_default(),
+                value["blockTime"].as_i64(),
+                value["executedTransactionCount"].as_u64().unwrap_or_default(),
+                value["entriesCount"].as_u64().unwrap_or_default(),
+            );
+            self.metrics.set_network(snapshot);
+            if self.network_stats {
+                self.write_event("networkStats", network::FILTER, snapshot.to_json())?;
+            }
+        }
+        if let Some(skip_stats) = &mut self.skip_stats {
+            let events = skip_stats.observe(kind, &value);
+            #[cfg(feature = "control-api")]
+            if kind == "blockmeta" {
+                self.metrics.set_skips(skip_stats.snapshot());
+            }
+            for (event, stats) in events {
+                self.write_event(event, skips::FILTER, stats)?;
+            }
+        }
+        if self.program_changes {
+            for change in programs::observe(kind, &value) {
+                self.write_event("programChange", programs::FILTER, change)?;
+            }
+        }
+        if self.new_mints {
+            for mint in mints::observe(kind, &value) {
+                self.write_event("newMint", mints::FILTER, mint)?;
+            }
+        }
+        if let Some(jito_tips) = &mut self.jito_tips {
+            for (event, tip) in jito_tips.observe(kind, &value) {
+                self.write_event(event, jito::FILTER, tip)?;
+            }
+        }
+        if let Some(fee_stats) = &mut self.fee_stats {
+            let slots = fee_stats.observe(kind, &value);
+            #[cfg(feature = "control-api")]
+            if let Some(quantiles) = fee_stats.rolling().filter(|_| !slots.is_empty()) {
+                self.metrics.set_fees(quantiles);
+            }
+            for fees in slots {
+                self.write_event("fees", fees::FILTER, fees)?;
+            }
+        }
+        if let Some(instruction_stats) = &mut self.instruction_stats {
+            instruction_stats.observe(kind, &value);
+            if let Some((stats, samples)) = instruction_stats.due() {
+                self.metrics.set_instructions(samples);
+                self.write_event("instructionStats", discriminators::FILTER, stats)?;
+            }
+        }
+        if !filters.is_empty()
+            && filters
+                .iter()
+                .all(|filter| EVENT_FILTERS.contains(&filter.as_str()))
+        {
+            self.write_watermark()?;
+            return Ok(());
+        }
+        if let Some(reassembler) = &mut self.reassembler {
+            let (internal, blocks) = reassembler.observe(kind, &filters, &value);
+            for block in blocks {
+                self.write_event("reassembledBlock", reassemble::FILTER, block)?;
+            }
+            if internal {
+                self.write_watermark()?;
+                return Ok(());
+            }
+        }
+        if let Some(vote_stats) = &mut self.vote_stats {
+            let vote = vote_stats.observe(kind, &value);
+            for stats in vote_stats.due() {
+                self.write_event("voteStats", votes::FILTER, stats)?;
+            }
+            if vote {
+                self.write_watermark()?;
+                return Ok(());
+            }
+        }
+        if let Some(aggregator) = &mut self.aggregator {
+            let aggregated = aggregator.observe(kind, &value);
+            if let Some((event, stats)) = aggregator.due() {
+                self.write_event(event, aggregate::FILTER, stats)?;
+            }
+            if aggregated {
+                self.write_watermark()?;
+                return Ok(());
+            }
+        }
+        if kind == "account"
+            && filters.iter().any(|filter| filter == "client")
+            && !self.memcmp_matches(&value)
+        {
+            // Still written when another filter matched it
+            filters.retain(|filter| filter != "client");
+            if filters.is_empty() {
+                warn!(
+                    "dropping account update {} not matching --accounts-memcmp",
+                    update_identity(kind, &value)
+                );
+                self.write_watermark()?;
+                return Ok(());
+            }
+        }
+        if let Some(where_expr) = self.where_expr
+            && !where_expr.matches(kind, &value)
+        {
+            self.write_watermark()?;
+            return Ok(());
+        }
+        if let Some(memos) = &self.memos
+            && !memos.route(kind, &value, &mut filters)
+        {
+            self.write_watermark()?;
+            return Ok(());
+        }
+        if let Some(grep) = self.grep
+            && !grep.is_match(&value.to_string())
+        {
+            self.write_watermark()?;
+            return Ok(());
+        }
+        #[cfg(feature = "wasm")]
+        if let Some(transform) = &mut self.transform {
+            match transform
+                .apply(kind, created_at, &filters, &value)
+                .context("transform failed")?
+            {
+                Some(transformed) => value = transformed,
+                None => {
+                    self.write_watermark()?;
+                    return Ok(());
+                }
+            }
+        }
+        #[cfg(feature = "scripting")]
+        if let Some(script) = &mut self.script {
+            let (verdict, alerts) = script.on_update(kind, &value)?;
+            for alert in alerts {
+                self.write_event("scriptAlert", script::FILTER, alert)?;
+            }
+            match verdict {
+                script::Verdict::Keep => {}
+                script::Verdict::Replace(replaced) => value = replaced,
+                script::Verdict::Drop => {
+                    self.write_watermark()?;
+                    return Ok(());
+                }
+            }
+        }
+        if kind == "account"
+            && let Some(drift) = &mut self.drift
+        {
+            drift.observe(&value);
+        }
+        // The state store and envelopes keep the whole update
+        let mut enveloped = false;
+        if kind == "account"
+            && let Some(state) = self.state
+        {
+            let upsert = state.upsert(&value);
+            if self.envelope == Some(Envelope::Debezium) {
+                // Older versions arriving late would move consumers backwards, drop them
+                let state::Upsert::Applied { before } = upsert else {
+                    self.write_watermark()?;
+                    return Ok(());
+                };
+                value = envelope::debezium(before, value, created_at, false);
+                // `source` is taken from the decoded update, the images are written like updates
+                for image in ["before", "after"] {
+                    if !value[image].is_null()
+                        && let Some(output) = self.output_value(kind, &value[image])
+                    {
+                        value[image] = output;
+                    }
+                }
+                if let Some(traceparent) = &traceparent {
+                    value["source"]["traceparent"] = json!(traceparent);
+                }
+                enveloped = true;
+            }
+        }
+        let output = if enveloped {
+            value
+        } else {
+            self.output_value(kind, &value).unwrap_or(value)
+        };
+        if let Some(out_file) = &mut self.out_file {
+            out_file.write(
+                kind,
+                created_at,
+                &filters,
+                &output,
+                traceparent.as_deref(),
+            )?;
+        }
+        #[cfg(feature = "arrow")]
+        if let Some(arrow_file) = &mut self.arrow_file {
+            arrow_file.write(
+                kind,
+                created_at,
+                &filters,
+                &output,
+                traceparent.as_deref(),
+            )?;
+        }
+        if let Some(sinks) = &self.sinks {
+            sinks.deliver(sink::DecodedUpdate {
+                kind: kind.to_owned(),
+                created_at,
+                filters: filters.clone(),
+                value: output.clone(),
+                traceparent: traceparent.clone(),
+            });
+        }
+
+        if enveloped {
+            envelope::print_envelope(&output);
+        } else {
+            self.print(kind, created_at, &filters, output, traceparent.as_deref());
+        }
+        self.write_watermark()
+    }
+
+    /// The update as printed and written, with `--data-encoding`, `--max-data-bytes` and
+    /// `--select` applied; `None` if it's written as decoded
+    fn output_value(&self, kind: &str, value: &Value) -> Option<Value> {
+        if self.data_output.is_identity() {
+            return self.select.map(|select| select.apply(value));
+        }
+        let mut output = value.clone();
+        self.data_output.apply(kind, &mut output);
+        Some(match self.select {
+            Some(select) => select.apply(&output),
+            None => output,
+        })
+    }
+
+    /// Write an event computed from the stream, e.g. an aggregate, to `--out-file`,
+    /// `--arrow-file`, the sinks and stdout
+    fn write_event(&mut self, kind: &str, filter: &str, value: Value) -> anyhow::Result<()> {
+        let created_at = SystemTime::now();
+        let filters = [filter.to_owned()];
+        if let Some(out_file) = &mut self.out_file {
+            out_file.write(kind, created_at, &filters, &value, None)?;
+        }
+        #[cfg(feature = "arrow")]
+        if let Some(arrow_file) = &mut self.arrow_file {
+            arrow_file.write(kind, created_at, &filters, &value, None)?;
+        }
+        if let Some(sinks) = &self.sinks {
+            sinks.deliver(sink::DecodedUpdate {
+                kind: kind.to_owned(),
+                created_at,
+                filters: filters.to_vec(),
+                value: value.clone(),
+                traceparent: None,
+            });
+        }
+        self.print(kind, created_at, &filters, value, None);
+        Ok(())
+    }
+
+    /// Print to stdout, as JSON lines next to `--envelope` change events
+    fn print(
+        &self,
+        kind: &str,
+        created_at: SystemTime,
+        filters: &[String],
+        value: Value,
+        traceparent: Option<&str>,
+    ) {
+        if self.envelope.is_some() {
+            let line = schema::Line::new(kind, created_at, filters, &value, traceparent);
+            envelope::print_line(&line);
+        } else {
+            print_update(kind, created_at, filters, value);
+        }
+    }
+
+    fn memcmp_matches(&self, value: &Value) -> bool {
+        let data = value.get("data").and_then(Value::as_str).unwrap_or_default();
+        self.memcmp.iter().all(|(offset, expected)| {
+            data.get(offset * 2..offset * 2 + expected.len()) == Some(expected.as_str())
+        })
+    }
+
+    /// Write a watermark record when one is due, like the events
+    fn write_watermark(&mut self) -> anyhow::Result<()> {
+        match self.watermark.as_mut().and_then(watermark::Watermark::due) {
+            Some(value) => self.write_event("watermark", watermark::FILTER, value),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Memcmp filters to verify client-side, servers have been seen leaking mismatching accounts.
+/// Only those of the `client` accounts filter built from the flags are checked, on updates it
+/// matched: other filters (`--watchlist`, ...) deliver accounts of their own. Data slices cut
+/// the compared bytes, so nothing is checked with them.
+fn memcmp_checks(request: &SubscribeRequest) -> anyhow::Result<Vec<(usize, String)>> {
+    let Some(filter) = request.accounts.get("client") else {
+        return Ok(vec![]);
+    };
+    if !request.accounts_data_slice.is_empty() {
+        return Ok(vec![]);
+    }
+    Ok(snapshot::memcmp_bytes(&filter.filters)?
+        .into_iter()
+        .flatten()
+        .map(|(offset, bytes)| (offset, hex::encode(bytes)))
+        .collect())
+}
+
+/// `--slow-threshold-ms` check of an update through the output stage, logging it when dropped
+struct SlowUpdate {
+    threshold: Duration,
+    kind: &'static str,
+    identity: String,
+    timings: UpdateTimings,
+    output_started_at: Instant,
+}
+
+impl Drop for SlowUpdate {
+    fn drop(&mut self) {
+        let output = self.output_started_at.elapsed();
+        let total = self.timings.received_at.elapsed();
+        if total > self.threshold {
+            warn!(
+                "slow {} update {}: total {total:.2?} (queued {:.2?}, decode {:.2?}, output {output:.2?})",
+                self.kind, self.identity, self.timings.queued, self.timings.decode
+            );
+        }
+    }
+}
+
+/// Short human readable identity of an update, used in logs
+fn update_identity(kind: &str, value: &Value) -> String {
+    let slot = value.get("slot").and_then(Value::as_u64).unwrap_or_default();
+    let key = match kind {
+        "account" => value.get("pubkey"),
+        "transaction" | "transactionStatus" => value.get("signature"),
+        "block" | "blockmeta" => value.get("blockhash"),
+        _ => None,
+    };
+    match key.and_then(Value::as_str) {
+        Some(key) => format!("{key} at slot {slot}"),
+        None => format!("at slot {slot}"),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ProgressBarTpl {
+    Msg(&'static str),
+    Total,
+    Network,
+    #[cfg(feature = "verify-encoding")]
+    Verify,
+}
+
+fn crate_progress_bar(
+    pb: &MultiProgress,
+    pb_t: ProgressBarTpl,
+) -> Result<ProgressBar, indicatif::style::TemplateError> {
+    let pb = pb.add(ProgressBar::no_length());
+    let tpl = match pb_t {
+        ProgressBarTpl::Msg(kind) => {
+            format!("{{spinner}} {kind}: {{msg}} / ~{{bytes}} (~{{bytes_per_sec}})")
+        }
+        ProgressBarTpl::Total => {
+            "{spinner} total: {msg} / ~{bytes} (~{bytes_per_sec}) in {elapsed_precise}".to_owned()
+        }
+        ProgressBarTpl::Network => "{spinner} network: {msg}".to_owned(),
+        #[cfg(feature = "verify-encoding")]
+        ProgressBarTpl::Verify => {
+            "{spinner} verify: {msg} (elapsed time, compare to prost)".to_owned()
+        }
+    };
+    pb.set_style(ProgressStyle::with_template(&tpl)?);
+    Ok(pb)
+}
+
+/// Update types tracked by separate `--stats` progress bars
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum StatsKind {
+    Accounts,
+    Slots,
+    Transactions,
+    TransactionsStatus,
+    Entries,
+    BlocksMeta,
+    Blocks,
+    PingPong,
+}
+
+impl StatsKind {
+    const ALL: [Self; 8] = [
+        Self::Accounts,
+        Self::Slots,
+        Self::Transactions,
+        Self::TransactionsStatus,
+        Self::Entries,
+        Self::BlocksMeta,
+        Self::Blocks,
+        Self::PingPong,
+    ];
+
+    const fn label(self) -> &'static str {
+        match self {
+            Self::Accounts => "accounts",
+            Self::Slots => "slots",
+            Self::Transactions => "transactions",
+            Self::TransactionsStatus => "transactions statuses",
+            Self::Entries => "entries",
+            Self::BlocksMeta => "blocks meta",
+            Self::Blocks => "blocks",
+            Self::PingPong => "ping/pong",
+        }
+    }
+
+    const fn from_update(update: &UpdateOneof) -> Self {
+        match update {
+            UpdateOneof::Account(_) => Self::Accounts,
+            UpdateOneof::Slot(_) => Self::Slots,
+            UpdateOneof::Transaction(_) => Self::Transactions,
+            UpdateOneof::TransactionStatus(_) => Self::TransactionsStatus,
+            UpdateOneof::Entry(_) => Self::Entries,
+            UpdateOneof::BlockMeta(_) => Self::BlocksMeta,
+            UpdateOneof::Block(_) => Self::Blocks,
+            UpdateOneof::Ping(_) | UpdateOneof::Pong(_) => Self::PingPong,
+        }
+    }
+
+    /// Map a `SubscribeUpdate.update_oneof` protobuf field number
+    const fn from_field_number(field: u32) -> Option<Self> {
+        Some(match field {
+            2 => Self::Accounts,
+            3 => Self::Slots,
+            4 => Self::Transactions,
+            10 => Self::TransactionsStatus,
+            8 => Self::Entries,
+            7 => Self::BlocksMeta,
+            5 => Self::Blocks,
+            6 | 9 => Self::PingPong,
+            _ => return None,
+        })
+    }
+
+    /// Subscribe flag name of the update type, `ping` for pings and pongs
+    const fn key(self) -> &'static str {
+        match self {
+            Self::Accounts => "accounts",
+            Self::Slots => "slots",
+            Self::Transactions => "transactions",
+            Self::TransactionsStatus => "transactions_status",
+            Self::Entries => "entries",
+            Self::BlocksMeta => "blocks_meta",
+            Self::Blocks => "blocks",
+            Self::PingPong => "ping",
+        }
+    }
+
+    /// Map the subscribe flag name of an update type, e.g. `transactions_status`
+    fn from_key(key: &str) -> Option<Self> {
+        Some(match key.replace('-', "_").as_str() {
+            "accounts" => Self::Accounts,
+            "slots" => Self::Slots,
+            "transactions" => Self::Transactions,
+            "transactions_status" => Self::TransactionsStatus,
+            "entries" => Self::Entries,
+            "blocks_meta" => Self::BlocksMeta,
+            "blocks" => Self::Blocks,
+            _ => return None,
+        })
+    }
+}
+
+struct StatsProgress {
+    multi: MultiProgress,
+    kinds: Vec<(u64, ProgressBar)>,
+    total: (u64, ProgressBar),
+    /// Shown once block meta is received
+    network: Option<ProgressBar>,
+}
+
+impl StatsProgress {
+    fn new() -> anyhow::Result<Self> {
+        let multi = MultiProgress::new();
+        let kinds = StatsKind::ALL
+            .iter()
+            .map(|kind| {
+                crate_progress_bar(&multi, ProgressBarTpl::Msg(kind.label())).map(|pb| (0, pb))
+            })
+            .collect::<Result<_, _>>()?;
+        let total = (0, crate_progress_bar(&multi, ProgressBarTpl::Total)?);
+        Ok(Self {
+            multi,
+            kinds,
+            total,
+            network: None,
+        })
+    }
+
+    fn set_network(&mut self, snapshot: &network::Snapshot) -> anyhow::Result<()> {
+        let pb = match &self.network {
+            Some(pb) => pb,
+            None => self
+                .network
+                .insert(crate_progress_bar(&self.multi, ProgressBarTpl::Network)?),
+        };
+        pb.set_message(format!(
+            "{:.0} TPS, {:.0} txs / {:.0} entries per block, {:.2}s block time, {} skipped slots",
+            snapshot.tps,
+            snapshot.transactions_per_block,
+            snapshot.entries_per_block,
+            snapshot.block_time_delta_secs,
+            snapshot.skipped_slots,
+        ));
+        pb.tick();
+        Ok(())
+    }
+
+    fn inc(&mut self, kind: StatsKind, encoded_len: u64) {
+        let index = StatsKind::ALL
+            .iter()
+            .position(|value| *value == kind)
+            .expect("all kinds have a progress bar");
+        for (counter, pb) in [&mut self.kinds[index], &mut self.total] {
+            *counter += 1;
+            pb.set_message(format_thousands(*counter));
+            pb.inc(encoded_len);
+        }
+    }
+}
+
+fn format_thousands(value: u64) -> String {
+    value
+        .to_string()
+        .as_bytes()
+        .rchunks(3)
+        .rev()
+        .map(std::str::from_utf8)
+        .collect::<Result<Vec<&str>, _>>()
+        .expect("invalid number")
+        .join(",")
+}
+
+fn create_pretty_account(account: SubscribeUpdateAccountInfo) -> anyhow::Result<schema::AccountInfo> {
+    Ok(schema::AccountInfo {
+        pubkey: Pubkey::try_from(account.pubkey).map_err(|_| anyhow::anyhow!("invalid account pubkey"))?.to_string(),
+        lamports: account.lamports,
+        owner: Pubkey::try_from(account.owner).map_err(|_| anyhow::anyhow!("invalid account owner"))?.to_string(),
+        executable: account.executable,
+        rent_epoch: account.rent_epoch,
+        data: hex_string(&account.data),
+        write_version: account.write_version,
+        txn_signature: account.txn_signature.map(|sig| bs58::encode(sig).into_string()),
+    })
+}
+
+/// `hex::encode` into a buffer of the final size instead of char by char
+fn hex_string(data: &[u8]) -> String {
+    let mut hex = vec![0; data.len() * 2];
+    hex::encode_to_slice(data, &mut hex).expect("buffer fits the hex");
+    String::from_utf8(hex).expect("hex is ascii")
+}
+
+fn create_pretty_transaction(tx: SubscribeUpdateTransactionInfo) -> anyhow::Result<schema::TransactionInfo> {
+    let budget = computebudget::from_message(tx.transaction.as_ref().and_then(|tx| tx.message.as_ref()));
+    Ok(schema::TransactionInfo {
+        signature: Signature::try_from(tx.signature.as_slice()).context("invalid signature")?.to_string(),
+        is_vote: tx.is_vote,
+        index: tx.index,
+        cu_limit: budget.limit,
+        cu_price_micro_lamports: budget.price,
+        tx: convert_from::create_tx_with_meta(tx)
+            .map_err(|error| anyhow::anyhow!(error))
+            .context("invalid tx with meta")?
+            .encode(UiTransactionEncoding::Base64, Some(u8::MAX), true)
+            .context("failed to encode transaction")?,
+    })
+}
+
+fn create_pretty_entry(msg: SubscribeUpdateEntry) -> anyhow::Result<schema::Entry> {
+    Ok(schema::Entry {
+        slot: msg.slot,
+        index: msg.index,
+        num_hashes: msg.num_hashes,
+        hash: Hash::new_from_array(<[u8; 32]>::try_from(msg.hash.as_slice()).context("invalid entry hash")?).to_string(),
+        executed_transaction_count: msg.executed_transaction_count,
+        starting_transaction_index: msg.starting_transaction_index,
+    })
+}
+
+fn print_update(kind: &str, created_at: SystemTime, filters: &[String], value: Value) {
+    let unix_since = created_at
+        .duration_since(UNIX_EPOCH)
+        .expect("valid system time");
+    
+    // Format timestamp
+    let timestamp = format!("{}.{:0>6}", unix_since.as_secs(), unix_since.subsec_micros());
+    
+    let style = style::get();
+    if style.plain {
+        println!(
+            "{timestamp} {} [{}] {value}",
+            style.header(kind),
+            filters.join(",")
+        );
+        io::stdout().flush().unwrap();
+        return;
+    }
+
+    // Print with nice formatting
+    println!("\n{}", "=".repeat(80));
+    println!("{}{} {}", style.icon("📦"), style.key("Update Type:"), style.header(kind.to_uppercase()));
+    println!("{}{} {}", style.icon("🔍"), style.key("Filters:"), filters.join(", "));
+    println!("{}{} {}", style.icon("⏰"), style.key("Timestamp:"), timestamp);
+    println!("{}", "-".repeat(80));
+    
+    // Print each field on a new line
+    if let Value::Object(map) = &value {
+        for (key, val) in map {
+            // Strings unquoted, anything else as compact JSON
+            match val {
+                Value::String(s) => println!("  {}: {s}", style.key(key)),
+                _ => println!("  {}: {val}", style.key(key)),
+            }
+        }
+    } else {
+        // Pretty print JSON with indentation
+        let json_str = serde_json::to_string_pretty(&value)
+            .expect("json serialization failed");
+        println!("{}", json_str);
+    }
+    
+    println!("{}", "=".repeat(80));
+    io::stdout().flush().unwrap();
+}
+
+fn print_query_result(title: &str, data: &[(String, String)]) {
+    let style = style::get();
+    if style.plain {
+        println!("{}", style.header(title));
+        for (key, value) in data {
+            println!("{}: {value}", style.key(key));
+        }
+        io::stdout().flush().unwrap();
+        return;
+    }
+
+    println!("\n{}", "=".repeat(80));
+    println!("{}{}", style.icon("🔍"), style.header(title));
+    println!("{}", "-".repeat(80));
+    
+    for (key, value) in data {
+        // Capitalize first letter of key
+        let formatted_key = if key.is_empty() {
+            key.clone()
+        } else {
+            let mut chars = key.chars();
+            match chars.next() {
+                None => String::new(),
+                Some(first) => first.to_uppercase().collect::<String>() + chars.as_str(),
+            }
+        };
+        println!("  {}: {}", style.key(formatted_key), value);
+    }
+    
+    println!("{}", "=".repeat(80));
+    io::stdout().flush().unwrap();
+}
+
+fn print_configuration(
+    args: &Args,
+    endpoint: &str,
+    x_token: &str,
+    commitment: Option<ArgsCommitment>,
+) {
+    let style = style::get();
+    println!("\n{}{}", style.icon("📋"), style.header("Configuration:"));
+    let using_custom = env::var("GRPC_ENDPOINT").is_ok();
+    println!("  Endpoint: {} {}", endpoint, if using_custom { "(from .env - custom)" } else { "(default free endpoint)" });
+    if !using_custom {
+        println!("  {}Tip: Use your own gRPC endpoint in .env for faster response!", style.icon("💡"));
+    }
+    println!("  X-Token: {}", args.token_display(x_token));
+    if let Some(commitment) = commitment {
+        println!("  Commitment Level: {:?}", commitment);
+    } else {
+        println!("  Commitment Level: Processed (default)");
+    }
+    println!();
+}
+
+fn print_health_check<T: std::fmt::Debug>(response: &T) {
+    let debug_str = format!("{:#?}", response);
+    let data = vec![
+        ("Response".to_string(), debug_str),
+    ];
+    print_query_result("Health Check Result", &data);
+}
+
+fn print_latest_blockhash(response: &GetLatestBlockhashResponse, format: QueryFormat) {
+    if format == QueryFormat::Json {
+        query::print(&query::LatestBlockhash::from(response));
+        return;
+    }
+    let data = vec![
+        ("Slot".to_string(), response.slot.to_string()),
+        ("Blockhash".to_string(), response.blockhash.clone()),
+        ("Last Valid Block Height".to_string(), response.last_valid_block_height.to_string()),
+    ];
+    print_query_result("Latest Blockhash", &data);
+}
+
+fn print_block_height(response: &GetBlockHeightResponse, format: QueryFormat) {
+    if format == QueryFormat::Json {
+        query::print(&query::BlockHeight::from(response));
+        return;
+    }
+    let data = vec![("Block Height".to_string(), response.block_height.to_string())];
+    print_query_result("Block Height", &data);
+}
+
+fn print_slot(response: &GetSlotResponse, format: QueryFormat) {
+    if format == QueryFormat::Json {
+        query::print(&query::Slot::from(response));
+        return;
+    }
+    let data = vec![("Slot".to_string(), response.slot.to_string())];
+    print_query_result("Current Slot", &data);
+}
+
+fn print_blockhash_valid(response: &IsBlockhashValidResponse, format: QueryFormat) {
+    if format == QueryFormat::Json {
+        query::print(&query::BlockhashValid::from(response));
+        return;
+    }
+    let status_emoji = if response.valid { "✅" } else { "❌" };
+    let data = vec![
+        ("Slot".to_string(), response.slot.to_string()),
+        (
+            "Valid".to_string(),
+            format!("{}{}", style::get().icon(status_emoji), response.valid),
+        ),
+    ];
+    print_query_result("Blockhash Validation", &data);
+}
+
+#[cfg(not(feature = "interactive"))]
+async fn interactive_prompt() -> anyhow::Result<(Action, InteractiveConfig)> {
+    anyhow::bail!(
+        "interactive mode requires the `interactive` feature, pass subscription flags instead"
+    )
+}
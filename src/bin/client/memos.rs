@@ -0,0 +1,83 @@
+//! `--memos`: the text of the Memo program instructions of a transaction as its `memo` field,
+//! and `--memo-regex` keeping only the transactions whose memo matches, e.g. payment references
+//! or bridge messages.
+//!
+//! Memos of inner instructions are included, several memos are joined with `; ` like the RPC
+//! `memo` field. Bytes which aren't UTF-8 are replaced. A `--memo-regex` given as
+//! `<name>=<regex>` adds `memo:<name>` to the filters of the transactions it matches (`memo`
+//! without a name), so consumers of the out-file and sinks can route them by filter. Other
+//! update kinds are kept as they are.
+
+use {crate::instructions, regex::Regex, serde_json::Value, solana_pubkey::Pubkey, tracing::debug};
+
+const MEMO_PROGRAM: Pubkey = Pubkey::from_str_const("MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr");
+
+/// Parse `--memo-regex`, format: `[name=]regex`, returns the filter name and the regex
+pub fn parse_regex(value: &str) -> Result<(String, Regex), String> {
+    let (filter, regex) = match value.split_once('=') {
+        Some((name, regex))
+            if !name.is_empty()
+                && name
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') =>
+        {
+            (format!("memo:{name}"), regex)
+        }
+        _ => ("memo".to_owned(), value),
+    };
+    let regex = Regex::new(regex).map_err(|error| format!("invalid regex `{regex}`: {error}"))?;
+    Ok((filter, regex))
+}
+
+pub struct Memos {
+    regexes: Vec<(String, Regex)>,
+}
+
+impl Memos {
+    pub fn new(regexes: Vec<(String, Regex)>) -> Self {
+        Self { regexes }
+    }
+
+    /// Add the `memo` field to a transaction update with memos
+    pub fn annotate(&self, kind: &str, value: &mut Value) {
+        if kind != "transaction" {
+            return;
+        }
+        let instructions = match instructions::parse_all(value) {
+            Ok(instructions) => instructions,
+            Err(error) => {
+                debug!("failed to parse transaction instructions: {error:#}");
+                return;
+            }
+        };
+        let memos = instructions
+            .iter()
+            .filter(|ix| ix.program_id == MEMO_PROGRAM)
+            .map(|ix| String::from_utf8_lossy(&ix.data))
+            .collect::<Vec<_>>();
+        if !memos.is_empty() {
+            value["memo"] = memos.join("; ").into();
+        }
+    }
+
+    /// Whether `--memo-regex` keeps an update, adding the filters of the regexes matching it
+    pub fn route(&self, kind: &str, value: &Value, filters: &mut Vec<String>) -> bool {
+        if kind != "transaction" || self.regexes.is_empty() {
+            return true;
+        }
+        let Some(memo) = value["memo"].as_str() else {
+            return false;
+        };
+        let mut matched = false;
+        for (filter, regex) in &self.regexes {
+            if !regex.is_match(memo) {
+                continue;
+            }
+            matched = true;
+            if !filters.contains(filter) {
+                filters.push(filter.clone());
+            }
+        }
+        matched
+    }
+}
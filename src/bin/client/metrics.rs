@@ -0,0 +1,601 @@
+//! Ingest pressure signals for autoscalers and alerting.
+//!
+//! The pressure score is the larger of decode queue utilisation and the `created_at` lag
+//! relative to `--pressure-target-lag-ms`: below 1 the consumer keeps up, above 1 it falls
+//! behind and more replicas (or workers) are needed.
+
+use {
+    crate::{discriminators, errors, network, sink::SinkStats},
+    serde_json::Value,
+    std::{
+        sync::{
+            Arc, Mutex,
+            atomic::{AtomicU64, Ordering},
+        },
+        time::{Duration, Instant, SystemTime},
+    },
+};
+
+#[cfg(feature = "control-api")]
+use {
+    crate::{fees, resources, skips},
+    serde_json::json,
+    std::fmt::Write,
+};
+
+/// Lag trend and ingest rate are computed over windows of this length
+const TREND_WINDOW: Duration = Duration::from_secs(5);
+
+pub struct Metrics {
+    #[cfg(feature = "control-api")]
+    target_lag: Duration,
+    updates: AtomicU64,
+    dead_letters: AtomicU64,
+    duplicates: AtomicU64,
+    queue_depth: AtomicU64,
+    queue_capacity: AtomicU64,
+    lag: Mutex<LagTracker>,
+    /// CPU time spent per pipeline stage, in nanoseconds
+    decode_cpu_nanos: AtomicU64,
+    output_cpu_nanos: AtomicU64,
+    #[cfg(feature = "control-api")]
+    memory_limit: Option<u64>,
+    #[cfg(feature = "control-api")]
+    fees: Mutex<Option<fees::Quantiles>>,
+    network: Mutex<Option<network::Snapshot>>,
+    #[cfg(feature = "control-api")]
+    skips: Mutex<Option<skips::Snapshot>>,
+    instructions: Mutex<Vec<discriminators::Sample>>,
+    failures: Mutex<errors::Failures>,
+    sinks: Mutex<Vec<Arc<SinkStats>>>,
+}
+
+struct LagTracker {
+    /// Exponentially weighted moving average of the lag, in milliseconds
+    ewma_ms: f64,
+    /// Change of the average lag per second over the last full window
+    trend_ms_per_sec: f64,
+    /// Updates per second over the last full window
+    rate: f64,
+    window_started_at: Instant,
+    window_ewma_ms: f64,
+    window_updates: u64,
+}
+
+impl Metrics {
+    #[cfg_attr(not(feature = "control-api"), allow(unused_variables))]
+    pub fn new(target_lag: Duration, memory_limit: Option<u64>) -> Self {
+        Self {
+            #[cfg(feature = "control-api")]
+            target_lag,
+            updates: AtomicU64::new(0),
+            dead_letters: AtomicU64::new(0),
+            duplicates: AtomicU64::new(0),
+            queue_depth: AtomicU64::new(0),
+            queue_capacity: AtomicU64::new(1),
+            lag: Mutex::new(LagTracker {
+                ewma_ms: 0.0,
+                trend_ms_per_sec: 0.0,
+                rate: 0.0,
+                window_started_at: Instant::now(),
+                window_ewma_ms: 0.0,
+                window_updates: 0,
+            }),
+            decode_cpu_nanos: AtomicU64::new(0),
+            output_cpu_nanos: AtomicU64::new(0),
+            #[cfg(feature = "control-api")]
+            memory_limit,
+            #[cfg(feature = "control-api")]
+            fees: Mutex::new(None),
+            network: Mutex::new(None),
+            #[cfg(feature = "control-api")]
+            skips: Mutex::new(None),
+            instructions: Mutex::new(vec![]),
+            failures: Mutex::new(errors::Failures::default()),
+            sinks: Mutex::new(vec![]),
+        }
+    }
+
+    /// Rolling priority fee and compute unit percentiles of `--fee-stats`
+    #[cfg(feature = "control-api")]
+    pub fn set_fees(&self, quantiles: fees::Quantiles) {
+        *self.fees.lock().expect("fees lock poisoned") = Some(quantiles);
+    }
+
+    /// Network throughput computed from block meta
+    pub fn set_network(&self, snapshot: network::Snapshot) {
+        *self.network.lock().expect("network lock poisoned") = Some(snapshot);
+    }
+
+    /// Slots produced and skipped over the current epoch, from `--skip-stats`
+    #[cfg(feature = "control-api")]
+    pub fn set_skips(&self, snapshot: skips::Snapshot) {
+        *self.skips.lock().expect("skips lock poisoned") = Some(snapshot);
+    }
+
+    /// Instruction invocations over the window of `--instruction-stats`
+    pub fn set_instructions(&self, samples: Vec<discriminators::Sample>) {
+        *self
+            .instructions
+            .lock()
+            .expect("instructions lock poisoned") = samples;
+    }
+
+    /// Transaction or transaction status update, with its `--classify-errors` error if it failed
+    pub fn record_transaction(&self, kind: &'static str, error: Option<&Value>) {
+        self.failures
+            .lock()
+            .expect("failures lock poisoned")
+            .record(kind, error);
+    }
+
+    /// Counters of the `--sink`s of the current subscription
+    pub fn set_sinks(&self, sinks: Vec<Arc<SinkStats>>) {
+        *self.sinks.lock().expect("sinks lock poisoned") = sinks;
+    }
+
+    /// CPU time spent on one update by the decode and output stages
+    pub fn record_cpu(&self, decode: Duration, output: Duration) {
+        self.decode_cpu_nanos
+            .fetch_add(decode.as_nanos() as u64, Ordering::Relaxed);
+        self.output_cpu_nanos
+            .fetch_add(output.as_nanos() as u64, Ordering::Relaxed);
+    }
+
+    /// Update that failed to decode and was written to the dead letter directory
+    pub fn record_dead_letter(&self) {
+        self.dead_letters.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Update dropped by `--dedup-window-slots` as received already
+    pub fn record_duplicate(&self) {
+        self.duplicates.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Decode queue occupancy, `capacity` is the number of decode workers
+    pub fn set_queue(&self, depth: usize, capacity: usize) {
+        self.queue_depth.store(depth as u64, Ordering::Relaxed);
+        self.queue_capacity
+            .store(capacity.max(1) as u64, Ordering::Relaxed);
+    }
+
+    /// Record an update handed to the output, lag is measured from its `created_at`
+    pub fn record_update(&self, created_at: SystemTime) {
+        let updates = self.updates.fetch_add(1, Ordering::Relaxed) + 1;
+        let lag_ms = SystemTime::now()
+            .duration_since(created_at)
+            .unwrap_or_default()
+            .as_secs_f64()
+            * 1000.0;
+
+        let mut lag = self.lag.lock().expect("lag lock poisoned");
+        lag.ewma_ms = if updates == 1 {
+            lag_ms
+        } else {
+            lag.ewma_ms * 0.9 + lag_ms * 0.1
+        };
+        let elapsed = lag.window_started_at.elapsed();
+        if elapsed >= TREND_WINDOW {
+            let secs = elapsed.as_secs_f64();
+            lag.trend_ms_per_sec = (lag.ewma_ms - lag.window_ewma_ms) / secs;
+            lag.rate = (updates - lag.window_updates) as f64 / secs;
+            lag.window_started_at = Instant::now();
+            lag.window_ewma_ms = lag.ewma_ms;
+            lag.window_updates = updates;
+        }
+    }
+
+    #[cfg(feature = "control-api")]
+    pub fn snapshot(&self) -> PressureSnapshot {
+        let lag = self.lag.lock().expect("lag lock poisoned");
+        let queue_depth = self.queue_depth.load(Ordering::Relaxed);
+        let queue_capacity = self.queue_capacity.load(Ordering::Relaxed);
+        let queue_utilisation = queue_depth as f64 / queue_capacity as f64;
+        let lag_ratio = lag.ewma_ms / (self.target_lag.as_secs_f64() * 1000.0).max(1.0);
+        PressureSnapshot {
+            updates: self.updates.load(Ordering::Relaxed),
+            dead_letters: self.dead_letters.load(Ordering::Relaxed),
+            duplicates: self.duplicates.load(Ordering::Relaxed),
+            rate: lag.rate,
+            queue_depth,
+            queue_capacity,
+            lag_ms: lag.ewma_ms,
+            lag_trend_ms_per_sec: lag.trend_ms_per_sec,
+            score: queue_utilisation.max(lag_ratio),
+            decode_cpu_secs: self.decode_cpu_nanos.load(Ordering::Relaxed) as f64 / 1e9,
+            output_cpu_secs: self.output_cpu_nanos.load(Ordering::Relaxed) as f64 / 1e9,
+            process_cpu_secs: resources::process_cpu_time().as_secs_f64(),
+            resident_memory: resources::resident_memory(),
+            memory_limit: self.memory_limit,
+            fees: *self.fees.lock().expect("fees lock poisoned"),
+            network: *self.network.lock().expect("network lock poisoned"),
+            skips: *self.skips.lock().expect("skips lock poisoned"),
+            instructions: self
+                .instructions
+                .lock()
+                .expect("instructions lock poisoned")
+                .clone(),
+            failures: self
+                .failures
+                .lock()
+                .expect("failures lock poisoned")
+                .clone(),
+            sinks: self.sinks.lock().expect("sinks lock poisoned").clone(),
+        }
+    }
+}
+
+#[cfg(feature = "control-api")]
+pub struct PressureSnapshot {
+    pub updates: u64,
+    pub dead_letters: u64,
+    pub duplicates: u64,
+    pub rate: f64,
+    pub queue_depth: u64,
+    pub queue_capacity: u64,
+    pub lag_ms: f64,
+    pub lag_trend_ms_per_sec: f64,
+    pub score: f64,
+    pub decode_cpu_secs: f64,
+    pub output_cpu_secs: f64,
+    pub process_cpu_secs: f64,
+    pub resident_memory: Option<u64>,
+    pub memory_limit: Option<u64>,
+    pub fees: Option<fees::Quantiles>,
+    pub network: Option<network::Snapshot>,
+    pub skips: Option<skips::Snapshot>,
+    pub instructions: Vec<discriminators::Sample>,
+    pub failures: errors::Failures,
+    pub sinks: Vec<Arc<SinkStats>>,
+}
+
+#[cfg(feature = "control-api")]
+impl PressureSnapshot {
+    pub fn to_json(&self) -> Value {
+        json!({
+            "score": self.score,
+            "updates": self.updates,
+            "updates_per_sec": self.rate,
+            "dead_letters": self.dead_letters,
+            "duplicates_suppressed": self.duplicates,
+            "queue_depth": self.queue_depth,
+            "queue_capacity": self.queue_capacity,
+            "lag_ms": self.lag_ms,
+            "lag_trend_ms_per_sec": self.lag_trend_ms_per_sec,
+            "resources": {
+                "decode_cpu_secs": self.decode_cpu_secs,
+                "output_cpu_secs": self.output_cpu_secs,
+                "process_cpu_secs": self.process_cpu_secs,
+                "resident_memory_bytes": self.resident_memory,
+                "memory_limit_bytes": self.memory_limit,
+            },
+            "fees": self.fees.map(|fees| json!({
+                "transactions": fees.transactions,
+                "priority_fee_lamports": quantiles_json(&fees.priority_fee),
+                "compute_units": quantiles_json(&fees.compute_units),
+            })),
+            "network": self.network.map(|network| network.to_json()),
+            "skips": self.skips.map(|skips| skips.to_json()),
+            "instructions": self.instructions.iter().map(|sample| json!({
+                "program": sample.program,
+                "discriminator": sample.discriminator,
+                "invocations": sample.invocations,
+                "failed": sample.failed,
+            })).collect::<Vec<_>>(),
+            "failures": self.failures.to_json(),
+            "sinks": self.sinks.iter().map(|sink| sink.to_json()).collect::<Vec<_>>(),
+        })
+    }
+
+    /// Prometheus text exposition format
+    pub fn to_prometheus(&self) -> String {
+        let mut metrics: Vec<(&str, &str, &str, f64)> = vec![
+            (
+                "indexer_pressure_score",
+                "gauge",
+                "Max of decode queue utilisation and lag over target, above 1 means falling behind",
+                self.score,
+            ),
+            (
+                "indexer_updates_total",
+                "counter",
+                "Updates handed to the output",
+                self.updates as f64,
+            ),
+            (
+                "indexer_dead_letters_total",
+                "counter",
+                "Updates which failed to decode, written to the dead letter directory",
+                self.dead_letters as f64,
+            ),
+            (
+                "indexer_duplicates_suppressed_total",
+                "counter",
+                "Updates dropped by --dedup-window-slots as received already",
+                self.duplicates as f64,
+            ),
+            (
+                "indexer_updates_per_second",
+                "gauge",
+                "Ingest rate over the last window",
+                self.rate,
+            ),
+            (
+                "indexer_queue_depth",
+                "gauge",
+                "Updates waiting in the decode queue",
+                self.queue_depth as f64,
+            ),
+            (
+                "indexer_queue_capacity",
+                "gauge",
+                "Decode queue capacity (decode workers)",
+                self.queue_capacity as f64,
+            ),
+            (
+                "indexer_lag_seconds",
+                "gauge",
+                "Average delay between update created_at and output",
+                self.lag_ms / 1000.0,
+            ),
+            (
+                "indexer_lag_trend_seconds_per_second",
+                "gauge",
+                "Change of the average lag per second, positive means falling behind",
+                self.lag_trend_ms_per_sec / 1000.0,
+            ),
+            (
+                "indexer_decode_cpu_seconds_total",
+                "counter",
+                "CPU time spent decoding updates",
+                self.decode_cpu_secs,
+            ),
+            (
+                "indexer_output_cpu_seconds_total",
+                "counter",
+                "CPU time spent writing updates",
+                self.output_cpu_secs,
+            ),
+            (
+                "process_cpu_seconds_total",
+                "counter",
+                "Total user and system CPU time of the process",
+                self.process_cpu_secs,
+            ),
+        ];
+        if let Some(bytes) = self.resident_memory {
+            metrics.push((
+                "process_resident_memory_bytes",
+                "gauge",
+                "Resident memory size",
+                bytes as f64,
+            ));
+        }
+        if let Some(bytes) = self.memory_limit {
+            metrics.push((
+                "indexer_memory_limit_bytes",
+                "gauge",
+                "cgroup memory limit used to size caches",
+                bytes as f64,
+            ));
+        }
+        if let Some(network) = self.network {
+            metrics.extend([
+                (
+                    "indexer_network_tps",
+                    "gauge",
+                    "Transactions per second of block time over the last blocks",
+                    network.tps,
+                ),
+                (
+                    "indexer_network_transactions_per_block",
+                    "gauge",
+                    "Average executed transactions per block over the last blocks",
+                    network.transactions_per_block,
+                ),
+                (
+                    "indexer_network_entries_per_block",
+                    "gauge",
+                    "Average entries per block over the last blocks",
+                    network.entries_per_block,
+                ),
+                (
+                    "indexer_network_block_time_delta_seconds",
+                    "gauge",
+                    "Average block time difference between consecutive blocks",
+                    network.block_time_delta_secs,
+                ),
+                (
+                    "indexer_network_block_interval_seconds",
+                    "gauge",
+                    "Average interval between block meta updates as received",
+                    network.block_interval.as_secs_f64(),
+                ),
+                (
+                    "indexer_network_skipped_slots",
+                    "gauge",
+                    "Slots skipped by leaders over the last blocks",
+                    network.skipped_slots as f64,
+                ),
+            ]);
+        }
+        if let Some(skips) = self.skips {
+            let production = skips.production;
+            metrics.extend([
+                (
+                    "indexer_epoch",
+                    "gauge",
+                    "Epoch of the last slot settled by --skip-stats",
+                    skips.epoch as f64,
+                ),
+                (
+                    "indexer_epoch_produced_slots",
+                    "gauge",
+                    "Slots with a block over the current epoch",
+                    production.produced as f64,
+                ),
+                (
+                    "indexer_epoch_skipped_slots",
+                    "gauge",
+                    "Slots skipped by leaders over the current epoch",
+                    production.skipped as f64,
+                ),
+                (
+                    "indexer_epoch_dead_slots",
+                    "gauge",
+                    "Skipped slots with a dead slot status over the current epoch",
+                    production.dead as f64,
+                ),
+                (
+                    "indexer_epoch_skip_rate",
+                    "gauge",
+                    "Ratio of slots skipped over the current epoch",
+                    production.skip_rate(),
+                ),
+                (
+                    "indexer_epoch_longest_skip_gap_slots",
+                    "gauge",
+                    "Longest run of consecutive skipped slots over the current epoch",
+                    production.longest_gap as f64,
+                ),
+                (
+                    "indexer_epoch_block_delay_seconds",
+                    "gauge",
+                    "Average time from the first slot status to block meta over the current epoch",
+                    production.block_delay().unwrap_or_default().as_secs_f64(),
+                ),
+            ]);
+        }
+        let mut text = String::new();
+        for (name, kind, help, value) in metrics {
+            let _ = writeln!(
+                text,
+                "# HELP {name} {help}\n# TYPE {name} {kind}\n{name} {value}"
+            );
+        }
+        if let Some(fees) = self.fees {
+            for (name, help, values) in [
+                (
+                    "indexer_priority_fee_lamports",
+                    "Priority fee of non-vote transactions over the last slots",
+                    fees.priority_fee,
+                ),
+                (
+                    "indexer_compute_units",
+                    "Compute units consumed by non-vote transactions over the last slots",
+                    fees.compute_units,
+                ),
+            ] {
+                let _ = writeln!(text, "# HELP {name} {help}\n# TYPE {name} summary");
+                for ((_, q), value) in fees::QUANTILES.iter().zip(values) {
+                    let _ = writeln!(text, "{name}{{quantile=\"{q}\"}} {value}");
+                }
+                let _ = writeln!(text, "{name}_count {}", fees.transactions);
+            }
+        }
+        if !self.instructions.is_empty() {
+            for (name, help, failed) in [
+                (
+                    "indexer_instruction_invocations",
+                    "Invocations of an instruction of --instruction-stats programs over the window",
+                    false,
+                ),
+                (
+                    "indexer_instruction_failed",
+                    "Invocations of an instruction in failed transactions over the window",
+                    true,
+                ),
+            ] {
+                let _ = writeln!(text, "# HELP {name} {help}\n# TYPE {name} gauge");
+                for sample in &self.instructions {
+                    let value = if failed {
+                        sample.failed
+                    } else {
+                        sample.invocations
+                    };
+                    let _ = writeln!(
+                        text,
+                        "{name}{{program=\"{}\",discriminator=\"{}\"}} {value}",
+                        sample.program, sample.discriminator
+                    );
+                }
+            }
+        }
+        if !self.failures.transactions.is_empty() {
+            for (index, (name, kind, help)) in [
+                (
+                    "indexer_transactions_classified_total",
+                    "counter",
+                    "Transactions seen by --classify-errors, by update kind",
+                ),
+                (
+                    "indexer_transactions_failed_total",
+                    "counter",
+                    "Failed transactions, by update kind",
+                ),
+                (
+                    "indexer_transaction_failure_rate",
+                    "gauge",
+                    "Ratio of failed transactions since start, by update kind",
+                ),
+            ]
+            .into_iter()
+            .enumerate()
+            {
+                let _ = writeln!(text, "# HELP {name} {help}\n# TYPE {name} {kind}");
+                for (update, (transactions, failed)) in &self.failures.transactions {
+                    let value = [
+                        *transactions as f64,
+                        *failed as f64,
+                        errors::failure_rate(*transactions, *failed),
+                    ][index];
+                    let _ = writeln!(text, "{name}{{kind=\"{update}\"}} {value}");
+                }
+            }
+            let name = "indexer_transaction_errors_total";
+            let _ = writeln!(
+                text,
+                "# HELP {name} Failed transactions by error category and name\n# TYPE {name} counter"
+            );
+            for ((update, category, error), count) in &self.failures.errors {
+                let _ = writeln!(
+                    text,
+                    "{name}{{kind=\"{update}\",category=\"{category}\",error=\"{error}\"}} {count}"
+                );
+            }
+        }
+        let samples = self
+            .sinks
+            .iter()
+            .map(|sink| (sink.name(), sink.samples()))
+            .collect::<Vec<_>>();
+        if let Some((_, first)) = samples.first() {
+            for (index, (name, help, _)) in first.iter().enumerate() {
+                let kind = if name.ends_with("_total") {
+                    "counter"
+                } else {
+                    "gauge"
+                };
+                let _ = writeln!(text, "# HELP {name} {help}\n# TYPE {name} {kind}");
+                for (sink, samples) in &samples {
+                    let sink = sink
+                        .replace('\\', "\\\\")
+                        .replace('"', "\\\"")
+                        .replace('\n', "\\n");
+                    let _ = writeln!(text, "{name}{{sink=\"{sink}\"}} {}", samples[index].2);
+                }
+            }
+        }
+        text
+    }
+}
+
+#[cfg(feature = "control-api")]
+fn quantiles_json(values: &[u64]) -> Value {
+    fees::QUANTILES
+        .iter()
+        .zip(values)
+        .map(|((label, _), value)| ((*label).to_owned(), json!(value)))
+        .collect::<serde_json::Map<_, _>>()
+        .into()
+}
@@ -0,0 +1,94 @@
+//! `--new-mints`: `newMint` events for `InitializeMint` and `InitializeMint2` instructions of
+//! the Token and Token-2022 programs, a feed of newly created tokens.
+//!
+//! Every token program transaction is streamed to find them, which is a sizeable share of the
+//! chain's transactions. Mints created through another program (launchpads, CPIs) are caught
+//! from inner instructions; failed transactions are ignored.
+
+use {
+    crate::{
+        instructions::{self, Instruction},
+        tokens::{TOKEN_2022_PROGRAM, TOKEN_PROGRAM},
+    },
+    serde_json::{Value, json},
+    solana_pubkey::Pubkey,
+    tracing::debug,
+    yellowstone_grpc_proto::prelude::{SubscribeRequest, SubscribeRequestFilterTransactions},
+};
+
+/// Transactions filter added to the request, its transactions are only written as events
+pub const FILTER: &str = "new-mints";
+
+const INITIALIZE_MINT: u8 = 0;
+const INITIALIZE_MINT2: u8 = 20;
+
+/// Add the transactions filter for token program instructions to `request`
+pub fn subscribe(request: &mut SubscribeRequest) {
+    request.transactions.insert(
+        FILTER.to_owned(),
+        SubscribeRequestFilterTransactions {
+            vote: Some(false),
+            failed: Some(false),
+            account_include: vec![TOKEN_PROGRAM.to_string(), TOKEN_2022_PROGRAM.to_string()],
+            ..Default::default()
+        },
+    );
+}
+
+/// `newMint` events of a transaction update
+pub fn observe(kind: &str, value: &Value) -> Vec<Value> {
+    if kind != "transaction" {
+        return vec![];
+    }
+    let instructions = match instructions::parse(value) {
+        Ok(instructions) => instructions,
+        Err(error) => {
+            debug!("failed to parse token instructions: {error:#}");
+            return vec![];
+        }
+    };
+    instructions
+        .iter()
+        .filter_map(new_mint)
+        .map(|mut event| {
+            event["slot"] = value["slot"].clone();
+            event["signature"] = value["signature"].clone();
+            event
+        })
+        .collect()
+}
+
+/// Event of an `InitializeMint(2)` instruction: tag, decimals, mint authority, then the freeze
+/// authority as an optional pubkey
+fn new_mint(ix: &Instruction) -> Option<Value> {
+    let program = if ix.program_id == TOKEN_PROGRAM {
+        "token"
+    } else if ix.program_id == TOKEN_2022_PROGRAM {
+        "token2022"
+    } else {
+        return None;
+    };
+    let (&tag, data) = ix.data.split_first()?;
+    if tag != INITIALIZE_MINT && tag != INITIALIZE_MINT2 {
+        return None;
+    }
+    let pubkey_at = |offset: usize| {
+        Some(
+            Pubkey::try_from(data.get(offset..offset + 32)?)
+                .ok()?
+                .to_string(),
+        )
+    };
+    let freeze_authority = match data.get(33)? {
+        0 => None,
+        1 => Some(pubkey_at(34)?),
+        _ => return None,
+    };
+    Some(json!({
+        "mint": ix.account(0)?,
+        "program": program,
+        "decimals": data.first()?,
+        "mintAuthority": pubkey_at(1)?,
+        "freezeAuthority": freeze_authority,
+    }))
+}
@@ -0,0 +1,129 @@
+//! Network throughput from block meta updates: TPS, transactions and entries per block, block
+//! time deltas and skipped slots over the last blocks. Computed whenever block meta is
+//! received, shown by `--stats` and `/metrics`; `--network-stats` writes `networkStats` events.
+
+use {
+    serde_json::{Value, json},
+    std::{
+        collections::VecDeque,
+        time::{Duration, Instant},
+    },
+    yellowstone_grpc_proto::prelude::{SubscribeRequest, SubscribeRequestFilterBlocksMeta},
+};
+
+/// Block meta filter added to the request when block meta isn't subscribed already
+pub const FILTER: &str = "network-stats";
+
+/// Blocks the statistics are computed over (about a minute)
+const WINDOW_BLOCKS: usize = 150;
+
+struct Block {
+    slot: u64,
+    block_time: Option<i64>,
+    transactions: u64,
+    entries: u64,
+    skipped: u64,
+    received_at: Instant,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Snapshot {
+    pub slot: u64,
+    /// Transactions per second of block time
+    pub tps: f64,
+    pub transactions_per_block: f64,
+    pub entries_per_block: f64,
+    /// Average block time difference between consecutive blocks, in seconds
+    pub block_time_delta_secs: f64,
+    /// Average interval between block meta updates as received
+    pub block_interval: Duration,
+    /// Slots skipped by leaders over the window
+    pub skipped_slots: u64,
+}
+
+impl Snapshot {
+    pub fn to_json(self) -> Value {
+        json!({
+            "slot": self.slot,
+            "tps": self.tps,
+            "transactionsPerBlock": self.transactions_per_block,
+            "entriesPerBlock": self.entries_per_block,
+            "blockTimeDeltaSecs": self.block_time_delta_secs,
+            "blockIntervalMs": self.block_interval.as_secs_f64() * 1000.0,
+            "skippedSlots": self.skipped_slots,
+            "windowBlocks": WINDOW_BLOCKS,
+        })
+    }
+}
+
+#[derive(Default)]
+pub struct NetworkStats {
+    blocks: VecDeque<Block>,
+}
+
+impl NetworkStats {
+    /// Subscribe to block meta for `--network-stats`, if it isn't already
+    pub fn subscribe(request: &mut SubscribeRequest) {
+        if request.blocks_meta.is_empty() {
+            request
+                .blocks_meta
+                .insert(FILTER.to_owned(), SubscribeRequestFilterBlocksMeta {});
+        }
+    }
+
+    /// Take a block meta, returns the statistics over the last blocks including it
+    pub fn observe(
+        &mut self,
+        slot: u64,
+        parent_slot: u64,
+        block_time: Option<i64>,
+        transactions: u64,
+        entries: u64,
+    ) -> Snapshot {
+        // Block meta arrives in order per commitment, a replayed or older block is ignored
+        if self.blocks.back().is_none_or(|last| slot > last.slot) {
+            self.blocks.push_back(Block {
+                slot,
+                block_time,
+                transactions,
+                entries,
+                skipped: slot.saturating_sub(parent_slot).saturating_sub(1),
+                received_at: Instant::now(),
+            });
+            if self.blocks.len() > WINDOW_BLOCKS {
+                self.blocks.pop_front();
+            }
+        }
+        self.snapshot()
+    }
+
+    fn snapshot(&self) -> Snapshot {
+        let (Some(first), Some(last)) = (self.blocks.front(), self.blocks.back()) else {
+            return Snapshot::default();
+        };
+        let count = self.blocks.len() as f64;
+        let transactions = self
+            .blocks
+            .iter()
+            .map(|block| block.transactions)
+            .sum::<u64>();
+        let entries = self.blocks.iter().map(|block| block.entries).sum::<u64>();
+        // The first block's transactions landed before the time span starts
+        let span = match (first.block_time, last.block_time) {
+            (Some(first), Some(last)) if last > first => Some((last - first) as f64),
+            _ => None,
+        };
+        let intervals = (self.blocks.len() - 1).max(1) as u32;
+        Snapshot {
+            slot: last.slot,
+            tps: span.map_or(0.0, |span| {
+                (transactions - first.transactions) as f64 / span
+            }),
+            transactions_per_block: transactions as f64 / count,
+            entries_per_block: entries as f64 / count,
+            block_time_delta_secs: span.unwrap_or_default() / f64::from(intervals),
+            block_interval: last.received_at.duration_since(first.received_at) / intervals,
+            skipped_slots: self.blocks.iter().skip(1).map(|block| block.skipped).sum(),
+        }
+    }
+}
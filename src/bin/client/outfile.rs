@@ -0,0 +1,385 @@
+//! `--out-file`: write updates as JSON lines to a file next to the display on stdout.
+//!
+//! With `--out-file-commitment` the file is the safe storage of a dual pipeline: the stream is
+//! subscribed at a low commitment (typically `processed`) and displayed right away, while the
+//! lines of each slot are staged and written only once the slot reaches the commitment, or
+//! discarded when the slot dies.
+//!
+//! With `--rotate` the file is closed once it reaches a size or age and renamed to a segment
+//! `<stem>.<closed at>.<ext>`, compressed with `--rotate-compression` in the background. Every
+//! closed segment is appended to `<file>.index` as a JSON line with its slot range.
+//!
+//! With `--encryption-key` every line is sealed, `decrypt` reads such files and segments back.
+
+use {
+    crate::{crypto::Cipher, retention::Retention, schema, slotbuffer::SlotBuffer, units},
+    anyhow::Context,
+    clap::ValueEnum,
+    serde_json::{Value, json},
+    std::{
+        fs::{self, File, OpenOptions},
+        io::{self, BufRead, BufReader, BufWriter, Write},
+        path::{Path, PathBuf},
+        sync::{Arc, mpsc},
+        thread,
+        time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+    },
+    tracing::{info, warn},
+    yellowstone_grpc_proto::prelude::{CommitmentLevel, SubscribeRequest},
+};
+
+/// When `--rotate` closes the current file
+#[derive(Debug, Clone, Copy)]
+pub enum Rotate {
+    Size(u64),
+    Age(Duration),
+}
+
+/// Parse `--rotate`: a size (`512MB`, `2GB`) or an age (`30m`, `1h`, `1d`)
+pub fn parse_rotate(value: &str) -> Result<Rotate, String> {
+    if value.ends_with(|c: char| c.is_ascii_digit()) {
+        return Err(format!("`{value}` has no unit, e.g. 512MB or 1h"));
+    }
+    if units::is_size(value) {
+        units::parse_bytes(value).map(Rotate::Size)
+    } else {
+        units::parse_duration(value).map(Rotate::Age)
+    }
+}
+
+/// Compression of closed `--rotate` segments
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum Compression {
+    /// Keep segments as plain JSON lines
+    #[default]
+    None,
+    /// `.gz`
+    Gzip,
+    /// `.zst`
+    Zstd,
+}
+
+pub struct OutFile {
+    path: PathBuf,
+    writer: BufWriter<File>,
+    stage: Option<SlotBuffer<String>>,
+    rotate: Option<(Rotate, Compression)>,
+    cipher: Option<Arc<Cipher>>,
+    written: u64,
+    opened_at: Instant,
+    /// Closed segments to compress and index, in order, on a background thread
+    segments: mpsc::Sender<PathBuf>,
+}
+
+impl OutFile {
+    /// Append to `path`, staging lines until `commitment` when given
+    pub fn open(
+        path: &Path,
+        commitment: Option<CommitmentLevel>,
+        rotate: Option<(Rotate, Compression)>,
+        retention: Option<Retention>,
+        cipher: Option<Arc<Cipher>>,
+        request: &mut SubscribeRequest,
+    ) -> anyhow::Result<Self> {
+        let (segments, closed) = mpsc::channel::<PathBuf>();
+        if let Some((_, compression)) = rotate {
+            let mut index = path.as_os_str().to_owned();
+            index.push(".index");
+            let index = PathBuf::from(index);
+            let cipher = cipher.clone();
+            thread::spawn(move || {
+                let mut pruned_at = None::<Instant>;
+                loop {
+                    if let Some(retention) = &retention
+                        && pruned_at.is_none_or(|at| at.elapsed() >= retention.interval)
+                    {
+                        match retention.prune(&index, cipher.as_deref()) {
+                            Ok(pruned) if pruned.lines > 0 => info!(
+                                "pruned {} lines, removing {} segments",
+                                pruned.lines, pruned.segments
+                            ),
+                            Ok(_) => {}
+                            Err(error) => warn!("failed to prune segments: {error:#}"),
+                        }
+                        pruned_at = Some(Instant::now());
+                    }
+                    let timeout = retention
+                        .as_ref()
+                        .map_or(Duration::MAX, |retention| retention.interval);
+                    let segment = match closed.recv_timeout(timeout) {
+                        Ok(segment) => segment,
+                        Err(mpsc::RecvTimeoutError::Timeout) => continue,
+                        Err(mpsc::RecvTimeoutError::Disconnected) => break,
+                    };
+                    match finish_segment(&segment, compression, cipher.as_deref(), &index) {
+                        Ok(name) => info!("closed segment {name}"),
+                        Err(error) => {
+                            warn!("failed to close segment {}: {error:#}", segment.display())
+                        }
+                    }
+                }
+            });
+            // A file left by a previous run is closed as a segment, its age isn't known
+            if fs::metadata(path).is_ok_and(|metadata| metadata.len() > 0) {
+                segments.send(close_segment(path)?).ok();
+            }
+        }
+        Ok(Self {
+            path: path.to_owned(),
+            writer: BufWriter::new(append(path)?),
+            stage: commitment.map(|commitment| SlotBuffer::new(commitment, request)),
+            rotate,
+            cipher,
+            written: 0,
+            opened_at: Instant::now(),
+            segments,
+        })
+    }
+
+    /// Write an update, or stage it until its slot reaches the commitment
+    pub fn write(
+        &mut self,
+        kind: &str,
+        created_at: SystemTime,
+        filters: &[String],
+        value: &Value,
+        traceparent: Option<&str>,
+    ) -> anyhow::Result<()> {
+        let line = schema::Line::new(kind, created_at, filters, value, traceparent);
+        let slot = value.get("slot").and_then(Value::as_u64);
+        if self.cipher.is_none() && (self.stage.is_none() || slot.is_none()) {
+            return self.write_line(&line);
+        }
+        let line = serde_json::to_string(&line)?;
+        let line = match &self.cipher {
+            Some(cipher) => cipher.seal_line(&line)?,
+            None => line,
+        };
+        match (&mut self.stage, slot) {
+            (Some(stage), Some(slot)) => stage.hold(slot, line),
+            _ => self.write_lines([line])?,
+        }
+        Ok(())
+    }
+
+    /// Serialize a line straight into the file, without a string in between
+    fn write_line(&mut self, line: &schema::Line) -> anyhow::Result<()> {
+        let mut counted = Counted {
+            writer: &mut self.writer,
+            count: 0,
+        };
+        serde_json::to_writer(&mut counted, line)
+            .map_err(io::Error::from)
+            .and_then(|()| counted.write_all(b"\n"))
+            .and_then(|()| counted.flush())
+            .with_context(|| format!("failed to write {}", self.path.display()))?;
+        self.written += counted.count;
+        self.rotate_if_due()
+    }
+
+    /// Take a slot status, writing the staged lines of a slot reaching the commitment
+    pub fn observe_slot(&mut self, value: &Value) -> anyhow::Result<()> {
+        let Some(stage) = &mut self.stage else {
+            return Ok(());
+        };
+        let Some(slot) = value.get("slot").and_then(Value::as_u64) else {
+            return Ok(());
+        };
+        let ready = stage.status(slot, value.get("status").and_then(Value::as_str));
+        if !ready.is_empty() {
+            self.write_lines(ready)?;
+        }
+        Ok(())
+    }
+
+    fn write_lines(&mut self, lines: impl IntoIterator<Item = String>) -> anyhow::Result<()> {
+        lines
+            .into_iter()
+            .try_for_each(|line| {
+                self.written += line.len() as u64 + 1;
+                writeln!(self.writer, "{line}")
+            })
+            .and_then(|()| self.writer.flush())
+            .with_context(|| format!("failed to write {}", self.path.display()))?;
+        self.rotate_if_due()
+    }
+
+    fn rotate_if_due(&mut self) -> anyhow::Result<()> {
+        let Some((rotate, _)) = self.rotate else {
+            return Ok(());
+        };
+        let due = match rotate {
+            Rotate::Size(size) => self.written >= size,
+            Rotate::Age(age) => self.opened_at.elapsed() >= age,
+        };
+        if !due || self.written == 0 {
+            return Ok(());
+        }
+        self.segments.send(close_segment(&self.path)?).ok();
+        self.writer = BufWriter::new(append(&self.path)?);
+        self.written = 0;
+        self.opened_at = Instant::now();
+        Ok(())
+    }
+}
+
+/// Counts the bytes going through, for `--rotate` sizes
+struct Counted<'a, W> {
+    writer: &'a mut W,
+    count: u64,
+}
+
+impl<W: Write> Write for Counted<'_, W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.writer.write(buf)?;
+        self.count += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+fn append(path: &Path) -> anyhow::Result<File> {
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("failed to open {}", path.display()))
+}
+
+/// Rename the file to a segment named after the time it's closed at
+fn close_segment(path: &Path) -> anyhow::Result<PathBuf> {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let extension = path
+        .extension()
+        .map(|extension| format!(".{}", extension.to_string_lossy()))
+        .unwrap_or_default();
+    let closed_at = chrono::Utc::now().format("%Y%m%dT%H%M%S%.3fZ");
+    // Segments closed within the same millisecond get a sequence number
+    let segment = (0..)
+        .map(|sequence| match sequence {
+            0 => format!("{stem}.{closed_at}{extension}"),
+            _ => format!("{stem}.{closed_at}-{sequence}{extension}"),
+        })
+        .map(|name| path.with_file_name(name))
+        .find(|segment| {
+            ["", ".gz", ".zst"].iter().all(|suffix| {
+                let mut compressed = segment.as_os_str().to_owned();
+                compressed.push(suffix);
+                !Path::new(&compressed).exists()
+            })
+        })
+        .expect("unbounded sequence");
+    fs::rename(path, &segment).with_context(|| format!("failed to rotate {}", path.display()))?;
+    Ok(segment)
+}
+
+/// Compress a segment while collecting its slot range, then append it to the index
+fn finish_segment(
+    segment: &Path,
+    compression: Compression,
+    cipher: Option<&Cipher>,
+    index: &Path,
+) -> anyhow::Result<String> {
+    let reader = BufReader::new(File::open(segment)?);
+    let mut target = segment.as_os_str().to_owned();
+    let mut writer: Box<dyn Write> = match compression {
+        Compression::None => Box::new(io::sink()),
+        Compression::Gzip => {
+            target.push(".gz");
+            write_segment(Path::new(&target))?
+        }
+        Compression::Zstd => {
+            target.push(".zst");
+            write_segment(Path::new(&target))?
+        }
+    };
+
+    let (mut lines, mut bytes) = (0u64, 0u64);
+    let (mut first_slot, mut last_slot) = (None::<u64>, None::<u64>);
+    for line in reader.lines() {
+        let line = line?;
+        writeln!(writer, "{line}")?;
+        lines += 1;
+        bytes += line.len() as u64 + 1;
+        let line = match cipher {
+            Some(cipher) => cipher.open_line(&line)?,
+            None => line,
+        };
+        let slot = serde_json::from_str::<Value>(&line)
+            .ok()
+            .and_then(|value| value["update"]["slot"].as_u64());
+        if let Some(slot) = slot {
+            first_slot = Some(first_slot.map_or(slot, |first| first.min(slot)));
+            last_slot = Some(last_slot.map_or(slot, |last| last.max(slot)));
+        }
+    }
+    writer.flush()?;
+    drop(writer);
+
+    let target = PathBuf::from(target);
+    if compression != Compression::None {
+        fs::remove_file(segment)?;
+    }
+    let name = match compression {
+        Compression::None => segment,
+        Compression::Gzip | Compression::Zstd => &target,
+    }
+    .file_name()
+    .unwrap_or_default()
+    .to_string_lossy()
+    .into_owned();
+    let entry = json!({
+        "segment": name,
+        "firstSlot": first_slot,
+        "lastSlot": last_slot,
+        "lines": lines,
+        "bytes": bytes,
+        "closedAt": SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64,
+    });
+    append(index)?.write_all(format!("{entry}\n").as_bytes())?;
+    Ok(name)
+}
+
+/// Read a segment, decompressed as its extension says
+pub fn read_segment(path: &Path) -> anyhow::Result<Box<dyn BufRead>> {
+    let file = File::open(path).with_context(|| format!("failed to open {}", path.display()))?;
+    Ok(
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("gz") => Box::new(BufReader::new(flate2::read::GzDecoder::new(file))),
+            Some("zst") => Box::new(BufReader::new(zstd::Decoder::new(file)?)),
+            _ => Box::new(BufReader::new(file)),
+        },
+    )
+}
+
+/// Create a segment, compressed as its extension says
+pub fn write_segment(path: &Path) -> anyhow::Result<Box<dyn Write>> {
+    let file =
+        File::create(path).with_context(|| format!("failed to create {}", path.display()))?;
+    Ok(
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("gz") => Box::new(flate2::write::GzEncoder::new(
+                file,
+                flate2::Compression::default(),
+            )),
+            Some("zst") => Box::new(zstd::Encoder::new(file, 0)?.auto_finish()),
+            _ => Box::new(BufWriter::new(file)),
+        },
+    )
+}
+
+/// `decrypt`: print the lines of an encrypted out-file or segment, compressed or not
+pub fn decrypt(path: &Path, cipher: &Cipher) -> anyhow::Result<()> {
+    let reader = read_segment(path)?;
+    let mut stdout = io::stdout().lock();
+    for (number, line) in reader.lines().enumerate() {
+        let line = cipher
+            .open_line(&line?)
+            .with_context(|| format!("line {} of {}", number + 1, path.display()))?;
+        writeln!(stdout, "{line}")?;
+    }
+    Ok(())
+}
@@ -0,0 +1,147 @@
+//! Parquet snapshots, `snapshot --owner <program> --out <file>.parquet`: a row per account with
+//! typed columns, to load program state into DataFusion, DuckDB or Spark.
+//!
+//! The columns are the fields of account updates, `pubkey`, `slot`, `lamports`, `owner`,
+//! `executable`, `rent_epoch`, `data` (bytes rather than hex), `write_version` and
+//! `txn_signature`, compressed with zstd. `snapshot --diff` reads them back like JSON snapshots.
+
+use {
+    anyhow::Context,
+    arrow_array::{
+        Array, ArrayRef, RecordBatch,
+        builder::{BinaryBuilder, BooleanBuilder, StringBuilder, UInt64Builder},
+        cast::AsArray,
+        types::UInt64Type,
+    },
+    arrow_schema::{DataType, Field, Schema},
+    parquet::{
+        arrow::{ArrowWriter, arrow_reader::ParquetRecordBatchReaderBuilder},
+        basic::{Compression, ZstdLevel},
+        file::properties::WriterProperties,
+    },
+    serde_json::{Value, json},
+    std::{collections::BTreeMap, fs::File, path::Path, sync::Arc},
+};
+
+/// Write accounts, sorted by pubkey, as decoded for the JSON snapshots
+pub fn write(path: &Path, accounts: &BTreeMap<String, Value>) -> anyhow::Result<()> {
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("pubkey", DataType::Utf8, false),
+        Field::new("slot", DataType::UInt64, false),
+        Field::new("lamports", DataType::UInt64, false),
+        Field::new("owner", DataType::Utf8, false),
+        Field::new("executable", DataType::Boolean, false),
+        Field::new("rent_epoch", DataType::UInt64, false),
+        Field::new("data", DataType::Binary, false),
+        Field::new("write_version", DataType::UInt64, false),
+        Field::new("txn_signature", DataType::Utf8, true),
+    ]));
+    let mut pubkey = StringBuilder::new();
+    let mut slot = UInt64Builder::new();
+    let mut lamports = UInt64Builder::new();
+    let mut owner = StringBuilder::new();
+    let mut executable = BooleanBuilder::new();
+    let mut rent_epoch = UInt64Builder::new();
+    let mut data = BinaryBuilder::new();
+    let mut write_version = UInt64Builder::new();
+    let mut txn_signature = StringBuilder::new();
+    let u64_field = |value: &Value, field: &str| value[field].as_u64().unwrap_or_default();
+    for (key, value) in accounts {
+        pubkey.append_value(key);
+        slot.append_value(u64_field(value, "slot"));
+        lamports.append_value(u64_field(value, "lamports"));
+        owner.append_value(value["owner"].as_str().unwrap_or_default());
+        executable.append_value(value["executable"].as_bool().unwrap_or_default());
+        rent_epoch.append_value(u64_field(value, "rentEpoch"));
+        data.append_value(
+            hex::decode(value["data"].as_str().unwrap_or_default())
+                .with_context(|| format!("invalid data of account {key}"))?,
+        );
+        write_version.append_value(u64_field(value, "writeVersion"));
+        txn_signature.append_option(value["txnSignature"].as_str());
+    }
+    let columns: Vec<ArrayRef> = vec![
+        Arc::new(pubkey.finish()),
+        Arc::new(slot.finish()),
+        Arc::new(lamports.finish()),
+        Arc::new(owner.finish()),
+        Arc::new(executable.finish()),
+        Arc::new(rent_epoch.finish()),
+        Arc::new(data.finish()),
+        Arc::new(write_version.finish()),
+        Arc::new(txn_signature.finish()),
+    ];
+    let batch = RecordBatch::try_new(Arc::clone(&schema), columns)?;
+
+    let file =
+        File::create(path).with_context(|| format!("failed to create {}", path.display()))?;
+    let properties = WriterProperties::builder()
+        .set_compression(Compression::ZSTD(ZstdLevel::default()))
+        .build();
+    let mut writer = ArrowWriter::try_new(file, schema, Some(properties))?;
+    writer.write(&batch)?;
+    writer
+        .close()
+        .with_context(|| format!("failed to write {}", path.display()))?;
+    Ok(())
+}
+
+/// Accounts of a Parquet snapshot by pubkey, as the JSON snapshots have them
+pub fn read(path: &Path) -> anyhow::Result<BTreeMap<String, Value>> {
+    let file = File::open(path).with_context(|| format!("failed to open {}", path.display()))?;
+    let reader = ParquetRecordBatchReaderBuilder::try_new(file)
+        .and_then(|builder| builder.build())
+        .with_context(|| format!("failed to read {}", path.display()))?;
+    let mut accounts = BTreeMap::new();
+    for batch in reader {
+        let batch = batch.with_context(|| format!("failed to read {}", path.display()))?;
+        let column = |name: &str| {
+            batch
+                .column_by_name(name)
+                .with_context(|| format!("no `{name}` column in {}", path.display()))
+        };
+        let invalid = |name: &str| format!("invalid `{name}` column in {}", path.display());
+        let pubkey = column("pubkey")?
+            .as_string_opt::<i32>()
+            .with_context(|| invalid("pubkey"))?;
+        let slot = column("slot")?
+            .as_primitive_opt::<UInt64Type>()
+            .with_context(|| invalid("slot"))?;
+        let lamports = column("lamports")?
+            .as_primitive_opt::<UInt64Type>()
+            .with_context(|| invalid("lamports"))?;
+        let owner = column("owner")?
+            .as_string_opt::<i32>()
+            .with_context(|| invalid("owner"))?;
+        let executable = column("executable")?
+            .as_boolean_opt()
+            .with_context(|| invalid("executable"))?;
+        let rent_epoch = column("rent_epoch")?
+            .as_primitive_opt::<UInt64Type>()
+            .with_context(|| invalid("rent_epoch"))?;
+        let data = column("data")?
+            .as_binary_opt::<i32>()
+            .with_context(|| invalid("data"))?;
+        let write_version = column("write_version")?
+            .as_primitive_opt::<UInt64Type>()
+            .with_context(|| invalid("write_version"))?;
+        let txn_signature = column("txn_signature")?
+            .as_string_opt::<i32>()
+            .with_context(|| invalid("txn_signature"))?;
+        for row in 0..batch.num_rows() {
+            let value = json!({
+                "pubkey": pubkey.value(row),
+                "lamports": lamports.value(row),
+                "owner": owner.value(row),
+                "executable": executable.value(row),
+                "rentEpoch": rent_epoch.value(row),
+                "data": hex::encode(data.value(row)),
+                "writeVersion": write_version.value(row),
+                "txnSignature": (!txn_signature.is_null(row)).then(|| txn_signature.value(row)),
+                "slot": slot.value(row),
+            });
+            accounts.insert(pubkey.value(row).to_owned(), value);
+        }
+    }
+    Ok(accounts)
+}
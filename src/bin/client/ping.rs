@@ -0,0 +1,96 @@
+//! `ping`: send `--count` pings `--interval-ms` apart and report round-trip times and loss, like
+//! a network `ping`.
+//!
+//! Every ping carries its sequence number as `count`, a ping is lost when no pong arrives within
+//! `--timeout-ms`, the request fails or the pong echoes another sequence number.
+
+use {
+    crate::{
+        QueryFormat,
+        latency::{format_micros, percentile},
+        print_query_result,
+    },
+    serde_json::json,
+    std::time::{Duration, Instant},
+    yellowstone_grpc_client::{GeyserGrpcClient, Interceptor},
+};
+
+pub async fn run(
+    mut client: GeyserGrpcClient<impl Interceptor>,
+    count: u32,
+    interval: Duration,
+    timeout: Duration,
+    format: QueryFormat,
+) -> anyhow::Result<()> {
+    let mut rtts = Vec::with_capacity(count as usize);
+    let mut ticker = tokio::time::interval(interval);
+    for seq in 1..=count {
+        ticker.tick().await;
+        let started_at = Instant::now();
+        let result = tokio::time::timeout(timeout, client.ping(seq as i32)).await;
+        let rtt = started_at.elapsed();
+        let outcome = match result {
+            Ok(Ok(pong)) if pong.count == seq as i32 => {
+                rtts.push(rtt.as_micros() as u64);
+                format!("time={}", format_micros(rtt.as_micros() as u64))
+            }
+            Ok(Ok(pong)) => format!("unexpected pong count {}", pong.count),
+            Ok(Err(error)) => format!("error: {error}"),
+            Err(_) => "timeout".to_owned(),
+        };
+        if format == QueryFormat::Text {
+            println!("pong seq={seq} {outcome}");
+        }
+    }
+
+    let received = rtts.len() as u32;
+    let loss = 100.0 * f64::from(count - received) / f64::from(count);
+    rtts.sort_unstable();
+    let stats = (!rtts.is_empty()).then(|| {
+        (
+            rtts[0],
+            rtts.iter().sum::<u64>() / rtts.len() as u64,
+            percentile(&rtts, 0.95),
+            rtts[rtts.len() - 1],
+        )
+    });
+    match format {
+        QueryFormat::Json => {
+            let ms = |micros: u64| micros as f64 / 1000.0;
+            println!(
+                "{}",
+                json!({
+                    "sent": count,
+                    "received": received,
+                    "lossPercent": loss,
+                    "minMs": stats.map(|(min, ..)| ms(min)),
+                    "avgMs": stats.map(|(_, avg, ..)| ms(avg)),
+                    "p95Ms": stats.map(|(.., p95, _)| ms(p95)),
+                    "maxMs": stats.map(|(.., max)| ms(max)),
+                })
+            );
+        }
+        QueryFormat::Text => {
+            let mut data = vec![
+                ("sent".to_owned(), count.to_string()),
+                ("received".to_owned(), received.to_string()),
+                ("loss".to_owned(), format!("{loss:.1}%")),
+            ];
+            if let Some((min, avg, p95, max)) = stats {
+                data.push((
+                    "rtt".to_owned(),
+                    format!(
+                        "min {}, avg {}, p95 {}, max {}",
+                        format_micros(min),
+                        format_micros(avg),
+                        format_micros(p95),
+                        format_micros(max),
+                    ),
+                ));
+            }
+            print_query_result("Ping Statistics", &data);
+        }
+    }
+    anyhow::ensure!(received > 0, "no pong received for {count} pings");
+    Ok(())
+}
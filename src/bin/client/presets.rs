@@ -0,0 +1,73 @@
+//! Interactive presets: the answers of an interactive subscription saved under a name, so the
+//! prompts don't have to be answered again.
+//!
+//! Presets are kept next to the profiles in `$XDG_CONFIG_HOME/solana-indexer/presets.toml`,
+//! one table per preset:
+//!
+//! ```toml
+//! [usdc-accounts]
+//! index = ["Accounts", "Slots"]
+//! commitment = "confirmed"
+//! accounts_owner = ["TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA"]
+//! ```
+
+use {
+    crate::profiles,
+    anyhow::Context,
+    serde::{Deserialize, Serialize},
+    std::{collections::BTreeMap, fs, path::PathBuf},
+};
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Preset {
+    /// Menu entries of the update types, e.g. `Accounts`
+    pub index: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub commitment: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub accounts_account: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub accounts_owner: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub transactions_account_include: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transactions_vote: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transactions_failed: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub blocks_include_transactions: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub blocks_include_accounts: Option<bool>,
+}
+
+pub fn path() -> anyhow::Result<PathBuf> {
+    Ok(profiles::config_dir()?.join("presets.toml"))
+}
+
+/// Saved presets by name, none without a presets file
+pub fn load() -> anyhow::Result<BTreeMap<String, Preset>> {
+    let path = path()?;
+    let text = match fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(BTreeMap::new()),
+        Err(error) => {
+            return Err(error)
+                .with_context(|| format!("failed to read presets from {}", path.display()));
+        }
+    };
+    toml::from_str(&text).with_context(|| format!("invalid presets file {}", path.display()))
+}
+
+/// Add or replace a preset
+pub fn save(name: &str, preset: Preset) -> anyhow::Result<PathBuf> {
+    let mut presets = load()?;
+    presets.insert(name.to_owned(), preset);
+    let path = path()?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).with_context(|| format!("failed to create {}", dir.display()))?;
+    }
+    fs::write(&path, toml::to_string_pretty(&presets)?)
+        .with_context(|| format!("failed to write {}", path.display()))?;
+    Ok(path)
+}
@@ -0,0 +1,94 @@
+//! `--profile`: named connection settings, for users switching between several providers.
+//!
+//! Profiles are read from `$XDG_CONFIG_HOME/solana-indexer/profiles.toml` (by default
+//! `~/.config/solana-indexer/profiles.toml`), one table per profile:
+//!
+//! ```toml
+//! [prod-triton]
+//! endpoint = "https://example.rpcpool.com:443"
+//! x_token = "vault://secret/data/triton#x_token"
+//! commitment = "confirmed"
+//!
+//! [dev-helius]
+//! endpoint = "https://laserstream.example.com"
+//! x_token_file = "/run/secrets/helius-token"
+//! auth_scheme = "bearer"
+//! rpc_url = "https://mainnet.helius-rpc.com/?api-key=..."
+//! ```
+//!
+//! Tokens accept the same secret references as `--x-token`.
+
+use {
+    anyhow::Context,
+    std::{env, fs, path::PathBuf},
+};
+
+const KEYS: [&str; 6] = [
+    "endpoint",
+    "x_token",
+    "x_token_file",
+    "auth_scheme",
+    "commitment",
+    "rpc_url",
+];
+
+#[derive(Debug, Default)]
+pub struct Profile {
+    pub endpoint: Option<String>,
+    pub x_token: Option<String>,
+    pub x_token_file: Option<PathBuf>,
+    pub auth_scheme: Option<String>,
+    pub commitment: Option<String>,
+    pub rpc_url: Option<String>,
+}
+
+/// `$XDG_CONFIG_HOME/solana-indexer`, by default `~/.config/solana-indexer`
+pub fn config_dir() -> anyhow::Result<PathBuf> {
+    let config_dir = match env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(env::var_os("HOME").context("HOME is not set")?).join(".config"),
+    };
+    Ok(config_dir.join("solana-indexer"))
+}
+
+pub fn path() -> anyhow::Result<PathBuf> {
+    Ok(config_dir()?.join("profiles.toml"))
+}
+
+pub fn load(name: &str) -> anyhow::Result<Profile> {
+    let path = path()?;
+    let text = fs::read_to_string(&path)
+        .with_context(|| format!("failed to read profiles from {}", path.display()))?;
+    let profiles = text
+        .parse::<toml::Table>()
+        .with_context(|| format!("invalid profiles file {}", path.display()))?;
+    let table = profiles
+        .get(name)
+        .and_then(toml::Value::as_table)
+        .with_context(|| {
+            let names = profiles.keys().cloned().collect::<Vec<_>>().join(", ");
+            format!("no profile `{name}` in {} (profiles: {names})", path.display())
+        })?;
+
+    let mut profile = Profile::default();
+    for (key, value) in table {
+        anyhow::ensure!(
+            KEYS.contains(&key.as_str()),
+            "unknown key `{key}` in profile `{name}`, expected one of {}",
+            KEYS.join(", ")
+        );
+        let value = value
+            .as_str()
+            .with_context(|| format!("`{key}` in profile `{name}` must be a string"))?
+            .to_owned();
+        match key.as_str() {
+            "endpoint" => profile.endpoint = Some(value),
+            "x_token" => profile.x_token = Some(value),
+            "x_token_file" => profile.x_token_file = Some(value.into()),
+            "auth_scheme" => profile.auth_scheme = Some(value),
+            "commitment" => profile.commitment = Some(value),
+            _ => profile.rpc_url = Some(value),
+        }
+    }
+    Ok(profile)
+}
@@ -0,0 +1,141 @@
+//! `--program-changes`: `programChange` events for BPF Upgradeable Loader instructions changing
+//! a program (deploys, upgrades, extensions, authority changes and closes), for security
+//! monitoring.
+//!
+//! Instructions are found at the top level and in inner instructions, so changes made through
+//! a multisig or governance program are caught too. Failed transactions are ignored.
+
+use {
+    crate::instructions,
+    serde_json::{Value, json},
+    solana_pubkey::Pubkey,
+    tracing::debug,
+    yellowstone_grpc_proto::prelude::{SubscribeRequest, SubscribeRequestFilterTransactions},
+};
+
+/// Transactions filter added to the request, its transactions are only written as events
+pub const FILTER: &str = "program-changes";
+
+pub const BPF_LOADER_UPGRADEABLE: Pubkey =
+    Pubkey::from_str_const("BPFLoaderUpgradeab1e11111111111111111111111");
+
+/// Add the transactions filter for loader instructions to `request`
+pub fn subscribe(request: &mut SubscribeRequest) {
+    request.transactions.insert(
+        FILTER.to_owned(),
+        SubscribeRequestFilterTransactions {
+            vote: Some(false),
+            failed: Some(false),
+            account_include: vec![BPF_LOADER_UPGRADEABLE.to_string()],
+            ..Default::default()
+        },
+    );
+}
+
+/// `programChange` events of a transaction update
+pub fn observe(kind: &str, value: &Value) -> Vec<Value> {
+    if kind != "transaction" {
+        return vec![];
+    }
+    match changes(value) {
+        Ok(changes) => changes,
+        Err(error) => {
+            debug!("failed to parse loader instructions: {error:#}");
+            vec![]
+        }
+    }
+}
+
+fn changes(value: &Value) -> anyhow::Result<Vec<Value>> {
+    let mut events = vec![];
+    for ix in instructions::parse(value)? {
+        if ix.program_id != BPF_LOADER_UPGRADEABLE {
+            continue;
+        }
+        if let Some(mut event) = change(&ix.data, |position| ix.account(position)) {
+            event["slot"] = value["slot"].clone();
+            event["signature"] = value["signature"].clone();
+            events.push(event);
+        }
+    }
+    Ok(events)
+}
+
+/// Event of a loader instruction, `None` for instructions not changing a program (buffer
+/// writes) or unknown ones. `account` maps an instruction account position to its address.
+fn change(data: &[u8], account: impl Fn(usize) -> Option<String>) -> Option<Value> {
+    let tag = u32::from_le_bytes(data.get(..4)?.try_into().ok()?);
+    let u64_at = |offset: usize| {
+        Some(u64::from_le_bytes(
+            data.get(offset..offset + 8)?.try_into().ok()?,
+        ))
+    };
+    let u32_at = |offset: usize| {
+        Some(u32::from_le_bytes(
+            data.get(offset..offset + 4)?.try_into().ok()?,
+        ))
+    };
+    Some(match tag {
+        // DeployWithMaxDataLen
+        2 => json!({
+            "action": "deploy",
+            "programId": account(2),
+            "programData": account(1),
+            "buffer": account(3),
+            "authority": account(7),
+            "payer": account(0),
+            "maxDataLen": u64_at(4),
+        }),
+        // Upgrade
+        3 => json!({
+            "action": "upgrade",
+            "programId": account(1),
+            "programData": account(0),
+            "buffer": account(2),
+            "authority": account(6),
+            "spill": account(3),
+        }),
+        // SetAuthority, SetAuthorityChecked: on a buffer or a program data account, without
+        // a new authority the program becomes immutable
+        4 | 7 => json!({
+            "action": "setAuthority",
+            "account": account(0),
+            "authority": account(1),
+            "newAuthority": account(2),
+            "immutable": account(2).is_none(),
+        }),
+        // Close: a buffer or a program data account, then the program is given
+        5 => json!({
+            "action": "close",
+            "programId": account(3),
+            "account": account(0),
+            "recipient": account(1),
+            "authority": account(2),
+        }),
+        // ExtendProgram
+        6 => json!({
+            "action": "extend",
+            "programId": account(1),
+            "programData": account(0),
+            "payer": account(3),
+            "additionalBytes": u32_at(4),
+        }),
+        // Migrate to the v4 loader
+        8 => json!({
+            "action": "migrate",
+            "programId": account(1),
+            "programData": account(0),
+            "authority": account(2),
+        }),
+        // ExtendProgramChecked
+        9 => json!({
+            "action": "extend",
+            "programId": account(1),
+            "programData": account(0),
+            "authority": account(2),
+            "payer": account(4),
+            "additionalBytes": u32_at(4),
+        }),
+        _ => return None,
+    })
+}
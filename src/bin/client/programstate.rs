@@ -0,0 +1,167 @@
+//! `snapshot --owner <program>`: the current state of every account of a program as JSON lines,
+//! and `snapshot --diff <old> <new>` comparing two of them, for protocol analytics and audits.
+//!
+//! Accounts are fetched with `getProgramAccounts` at `--commitment` and written decoded like
+//! streamed account updates, with the slot of the fetch, sorted by pubkey so snapshots diff
+//! line by line too. `.gz`/`.zst` snapshots are compressed, `.parquet` ones are Parquet files
+//! with the `parquet` feature. A diff prints a line per account added, removed or changed, with
+//! the fields that changed, the slot aside.
+
+use {
+    crate::{create_pretty_account, outfile, rpc},
+    anyhow::Context,
+    serde_json::{Value, json},
+    std::{
+        collections::{BTreeMap, BTreeSet},
+        io::{self, BufRead, Write},
+        path::Path,
+    },
+    tracing::info,
+    yellowstone_grpc_proto::prelude::CommitmentLevel,
+};
+
+/// Whether a snapshot is a Parquet file, by its extension
+fn is_parquet(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|extension| extension == "parquet")
+}
+
+/// Fields which differ between snapshots of an unchanged account
+const IGNORED_FIELDS: [&str; 2] = ["slot", "writeVersion"];
+
+pub async fn snapshot(
+    rpc: &rpc::RpcClient,
+    owner: &str,
+    commitment: CommitmentLevel,
+    out: Option<&Path>,
+) -> anyhow::Result<()> {
+    let result = rpc
+        .call(
+            "getProgramAccounts",
+            json!([owner, {
+                "encoding": "base64",
+                "commitment": commitment.as_str_name().to_lowercase(),
+                "withContext": true,
+            }]),
+        )
+        .await?;
+    let slot = result["context"]["slot"]
+        .as_u64()
+        .context("no context slot in RPC response")?;
+    let mut accounts = BTreeMap::new();
+    for item in result["value"]
+        .as_array()
+        .context("invalid getProgramAccounts response")?
+    {
+        let pubkey = item["pubkey"]
+            .as_str()
+            .context("no pubkey in program account")?;
+        let account = create_pretty_account(rpc::parse_account(pubkey, &item["account"])?)?;
+        let mut value = serde_json::to_value(account)?;
+        value["slot"] = slot.into();
+        accounts.insert(pubkey.to_owned(), value);
+    }
+
+    if let Some(path) = out.filter(|path| is_parquet(path)) {
+        #[cfg(feature = "parquet")]
+        crate::parquetfile::write(path, &accounts)?;
+        #[cfg(not(feature = "parquet"))]
+        anyhow::bail!(
+            "{}: Parquet snapshots require the `parquet` feature",
+            path.display()
+        );
+    } else {
+        let mut writer = match out {
+            Some(path) => outfile::write_segment(path)?,
+            None => Box::new(io::stdout().lock()),
+        };
+        for value in accounts.values() {
+            writeln!(writer, "{value}")?;
+        }
+        writer.flush()?;
+    }
+    info!(
+        "snapshot: {} accounts of {owner} at slot {slot}",
+        accounts.len()
+    );
+    Ok(())
+}
+
+pub fn diff(old: &Path, new: &Path) -> anyhow::Result<()> {
+    let old = read(old)?;
+    let new = read(new)?;
+    let mut stdout = io::stdout().lock();
+    let (mut added, mut removed, mut changed) = (0, 0, 0);
+    for pubkey in old.keys().chain(new.keys()).collect::<BTreeSet<_>>() {
+        let line = match (old.get(pubkey), new.get(pubkey)) {
+            (None, Some(after)) => {
+                added += 1;
+                json!({ "change": "added", "pubkey": pubkey, "after": after })
+            }
+            (Some(before), None) => {
+                removed += 1;
+                json!({ "change": "removed", "pubkey": pubkey, "before": before })
+            }
+            (Some(before), Some(after)) => {
+                let fields = changed_fields(before, after);
+                if fields.is_empty() {
+                    continue;
+                }
+                changed += 1;
+                json!({
+                    "change": "changed",
+                    "pubkey": pubkey,
+                    "fields": fields,
+                    "before": before,
+                    "after": after,
+                })
+            }
+            (None, None) => continue,
+        };
+        writeln!(stdout, "{line}")?;
+    }
+    info!(
+        "diff: {added} added, {removed} removed, {changed} changed, {} unchanged",
+        new.len() - added - changed
+    );
+    Ok(())
+}
+
+/// Accounts of a snapshot by pubkey
+fn read(path: &Path) -> anyhow::Result<BTreeMap<String, Value>> {
+    if is_parquet(path) {
+        #[cfg(feature = "parquet")]
+        return crate::parquetfile::read(path);
+        #[cfg(not(feature = "parquet"))]
+        anyhow::bail!(
+            "{}: Parquet snapshots require the `parquet` feature",
+            path.display()
+        );
+    }
+    let mut accounts = BTreeMap::new();
+    for (number, line) in outfile::read_segment(path)?.lines().enumerate() {
+        let context = || format!("line {} of {}", number + 1, path.display());
+        let line = line.with_context(context)?;
+        let value = serde_json::from_str::<Value>(&line).with_context(context)?;
+        let pubkey = value["pubkey"]
+            .as_str()
+            .with_context(|| format!("no pubkey on {}", context()))?;
+        accounts.insert(pubkey.to_owned(), value);
+    }
+    Ok(accounts)
+}
+
+fn changed_fields<'a>(before: &'a Value, after: &'a Value) -> Vec<&'a str> {
+    let (Some(before), Some(after)) = (before.as_object(), after.as_object()) else {
+        return vec![];
+    };
+    before
+        .keys()
+        .chain(after.keys())
+        .map(String::as_str)
+        .filter(|field| !IGNORED_FIELDS.contains(field))
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .filter(|field| before.get(*field) != after.get(*field))
+        .collect()
+}
@@ -0,0 +1,80 @@
+//! `--format json` of the query actions: their responses as serializable structs, one JSON
+//! object per line. The generated protobuf types don't implement `Serialize`, these mirror them
+//! with camelCase field names.
+
+use {
+    serde::Serialize,
+    std::io::{self, Write},
+    yellowstone_grpc_proto::prelude::{
+        GetBlockHeightResponse, GetLatestBlockhashResponse, GetSlotResponse,
+        IsBlockhashValidResponse,
+    },
+};
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LatestBlockhash<'a> {
+    pub slot: u64,
+    pub blockhash: &'a str,
+    pub last_valid_block_height: u64,
+}
+
+impl<'a> From<&'a GetLatestBlockhashResponse> for LatestBlockhash<'a> {
+    fn from(response: &'a GetLatestBlockhashResponse) -> Self {
+        Self {
+            slot: response.slot,
+            blockhash: &response.blockhash,
+            last_valid_block_height: response.last_valid_block_height,
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockHeight {
+    pub block_height: u64,
+}
+
+impl From<&GetBlockHeightResponse> for BlockHeight {
+    fn from(response: &GetBlockHeightResponse) -> Self {
+        Self {
+            block_height: response.block_height,
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct Slot {
+    pub slot: u64,
+}
+
+impl From<&GetSlotResponse> for Slot {
+    fn from(response: &GetSlotResponse) -> Self {
+        Self {
+            slot: response.slot,
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct BlockhashValid {
+    pub slot: u64,
+    pub valid: bool,
+}
+
+impl From<&IsBlockhashValidResponse> for BlockhashValid {
+    fn from(response: &IsBlockhashValidResponse) -> Self {
+        Self {
+            slot: response.slot,
+            valid: response.valid,
+        }
+    }
+}
+
+/// Print a response on one line
+pub fn print(response: &impl Serialize) {
+    let mut stdout = io::stdout().lock();
+    let _ = serde_json::to_writer(&mut stdout, response);
+    let _ = writeln!(stdout);
+    let _ = stdout.flush();
+}
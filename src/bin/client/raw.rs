@@ -0,0 +1,413 @@
+//! Subscription over undecoded `SubscribeUpdate` frames.
+//!
+//! `--stats` and `--latency` only need the update type, size and creation time of every
+//! message, so instead of decoding full blocks and transactions with prost we walk the
+//! top-level protobuf tags of each frame and skip over the payloads.
+
+use {
+    crate::{
+        auth::AuthInterceptor, capture::CaptureWriter, crypto::Cipher, latency::LatencyReport,
+        metrics::Metrics, network::NetworkStats, session::Session,
+        AuthScheme, StatsKind, StatsProgress,
+    },
+    bytes::{Buf, Bytes},
+    futures::{channel::mpsc, stream::StreamExt},
+    tracing::{Instrument, error, info, info_span},
+    std::{
+        path::Path,
+        time::{Duration, SystemTime},
+    },
+    tonic::{
+        client::Grpc,
+        codec::{Codec, DecodeBuf, Decoder, EncodeBuf, Encoder, Streaming},
+        codegen::http::uri::PathAndQuery,
+        service::interceptor::InterceptedService,
+        transport::Channel,
+        Request, Status,
+    },
+    yellowstone_grpc_client::GeyserGrpcBuilder,
+    yellowstone_grpc_proto::{
+        prelude::{
+            SubscribeRequest, SubscribeRequestFilterSlots, SubscribeRequestPing,
+            SubscribeUpdateBlockMeta,
+        },
+        prost_types::Timestamp,
+        prost::{
+            encoding::{decode_key, decode_varint, WireType},
+            Message,
+        },
+    },
+};
+
+const SUBSCRIBE_PATH: &str = "/geyser.Geyser/Subscribe";
+
+/// Field numbers of `SubscribeUpdate.account` and `.transaction`, whose slot is field 2
+const FIELD_ACCOUNT: u32 = 2;
+const FIELD_TRANSACTION: u32 = 4;
+/// Field number of `SubscribeUpdate.ping`
+const FIELD_PING: u32 = 6;
+/// Field number of `SubscribeUpdate.block_meta`
+const FIELD_BLOCK_META: u32 = 7;
+/// Field number of `SubscribeUpdate.pong`
+const FIELD_PONG: u32 = 9;
+/// Field number of `SubscribeUpdate.created_at`
+const FIELD_CREATED_AT: u32 = 11;
+
+pub struct RawGeyserClient {
+    grpc: Grpc<InterceptedService<Channel, AuthInterceptor>>,
+}
+
+impl RawGeyserClient {
+    /// Connect with the same endpoint, token and codec settings as `GeyserGrpcClient`
+    pub async fn connect(builder: GeyserGrpcBuilder, scheme: AuthScheme) -> anyhow::Result<Self> {
+        let interceptor = AuthInterceptor::new(&builder, scheme)?;
+        let channel = builder.endpoint.connect().await?;
+
+        let mut grpc = Grpc::new(InterceptedService::new(channel, interceptor));
+        if let Some(encoding) = builder.send_compressed {
+            grpc = grpc.send_compressed(encoding);
+        }
+        if let Some(encoding) = builder.accept_compressed {
+            grpc = grpc.accept_compressed(encoding);
+        }
+        if let Some(limit) = builder.max_decoding_message_size {
+            grpc = grpc.max_decoding_message_size(limit);
+        }
+        if let Some(limit) = builder.max_encoding_message_size {
+            grpc = grpc.max_encoding_message_size(limit);
+        }
+        Ok(Self { grpc })
+    }
+
+    pub async fn subscribe_with_request(
+        &mut self,
+        request: SubscribeRequest,
+    ) -> anyhow::Result<(mpsc::UnboundedSender<SubscribeRequest>, Streaming<Bytes>)> {
+        let (subscribe_tx, subscribe_rx) = mpsc::unbounded();
+        subscribe_tx.unbounded_send(request)?;
+
+        self.grpc
+            .ready()
+            .await
+            .map_err(|error| anyhow::anyhow!(error).context("service was not ready"))?;
+        let response = self
+            .grpc
+            .streaming(
+                Request::new(subscribe_rx),
+                PathAndQuery::from_static(SUBSCRIBE_PATH),
+                RawCodec,
+            )
+            .await?;
+        Ok((subscribe_tx, response.into_inner()))
+    }
+}
+
+/// Encodes requests with prost, passes responses through as raw bytes
+#[derive(Debug, Default, Clone, Copy)]
+struct RawCodec;
+
+impl Codec for RawCodec {
+    type Encode = SubscribeRequest;
+    type Decode = Bytes;
+    type Encoder = RawCodec;
+    type Decoder = RawCodec;
+
+    fn encoder(&mut self) -> Self::Encoder {
+        *self
+    }
+
+    fn decoder(&mut self) -> Self::Decoder {
+        *self
+    }
+}
+
+impl Encoder for RawCodec {
+    type Item = SubscribeRequest;
+    type Error = Status;
+
+    fn encode(&mut self, item: Self::Item, dst: &mut EncodeBuf<'_>) -> Result<(), Self::Error> {
+        item.encode(dst)
+            .map_err(|error| Status::internal(format!("failed to encode request: {error}")))
+    }
+}
+
+impl Decoder for RawCodec {
+    type Item = Bytes;
+    type Error = Status;
+
+    fn decode(&mut self, src: &mut DecodeBuf<'_>) -> Result<Option<Self::Item>, Self::Error> {
+        Ok(Some(src.copy_to_bytes(src.remaining())))
+    }
+}
+
+/// Top-level fields of an encoded `SubscribeUpdate` needed without a full decode
+#[derive(Debug, Default, Clone, Copy)]
+pub struct FrameFields {
+    /// Field number of the `update_oneof` member
+    pub update: Option<u32>,
+    /// Slot of the `update_oneof` member, none for ping/pong
+    pub slot: Option<u64>,
+    pub created_at: Option<SystemTime>,
+    /// Block meta fields of a block meta update, for the network stats of `--stats`
+    pub block_meta: Option<BlockMetaFields>,
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct BlockMetaFields {
+    pub slot: u64,
+    pub parent_slot: u64,
+    pub block_time: Option<i64>,
+    pub executed_transaction_count: u64,
+    pub entries_count: u64,
+}
+
+pub fn parse_frame(mut frame: &[u8]) -> Option<FrameFields> {
+    let mut fields = FrameFields::default();
+    while frame.has_remaining() {
+        let (field, wire_type) = decode_key(&mut frame).ok()?;
+        let skip = match wire_type {
+            WireType::Varint => {
+                decode_varint(&mut frame).ok()?;
+                0
+            }
+            WireType::SixtyFourBit => 8,
+            WireType::ThirtyTwoBit => 4,
+            WireType::LengthDelimited => {
+                let len = decode_varint(&mut frame).ok()? as usize;
+                if frame.remaining() < len {
+                    return None;
+                }
+                if (2..=10).contains(&field) {
+                    fields.update = Some(field);
+                    fields.slot = match field {
+                        FIELD_ACCOUNT | FIELD_TRANSACTION => find_varint(&frame[..len], 2),
+                        FIELD_PING | FIELD_PONG => None,
+                        _ => find_varint(&frame[..len], 1),
+                    };
+                    // Block meta is small, decoding it fully is cheap
+                    if field == FIELD_BLOCK_META {
+                        fields.block_meta = SubscribeUpdateBlockMeta::decode(&frame[..len])
+                            .ok()
+                            .map(|meta| BlockMetaFields {
+                                slot: meta.slot,
+                                parent_slot: meta.parent_slot,
+                                block_time: meta.block_time.map(|time| time.timestamp),
+                                executed_transaction_count: meta.executed_transaction_count,
+                                entries_count: meta.entries_count,
+                            });
+                    }
+                } else if field == FIELD_CREATED_AT {
+                    fields.created_at = Timestamp::decode(&frame[..len])
+                        .ok()
+                        .and_then(|ts| SystemTime::try_from(ts).ok());
+                }
+                len
+            }
+            WireType::StartGroup | WireType::EndGroup => return None,
+        };
+        if frame.remaining() < skip {
+            return None;
+        }
+        frame.advance(skip);
+    }
+    Some(fields)
+}
+
+/// Value of a top-level varint field of an encoded message
+fn find_varint(mut message: &[u8], number: u32) -> Option<u64> {
+    while message.has_remaining() {
+        let (field, wire_type) = decode_key(&mut message).ok()?;
+        let skip = match wire_type {
+            WireType::Varint => {
+                let value = decode_varint(&mut message).ok()?;
+                if field == number {
+                    return Some(value);
+                }
+                0
+            }
+            WireType::SixtyFourBit => 8,
+            WireType::ThirtyTwoBit => 4,
+            WireType::LengthDelimited => decode_varint(&mut message).ok()? as usize,
+            WireType::StartGroup | WireType::EndGroup => return None,
+        };
+        if message.remaining() < skip {
+            return None;
+        }
+        message.advance(skip);
+    }
+    None
+}
+
+/// Undecoded update handed to the frame callback
+pub struct Frame {
+    pub kind: StatsKind,
+    pub slot: Option<u64>,
+    pub data: Bytes,
+    pub created_at: Option<SystemTime>,
+    pub block_meta: Option<BlockMetaFields>,
+    pub received_at: SystemTime,
+}
+
+/// Run a subscription, answering server pings and passing every frame to `on_frame`, failing
+/// with [`Stalled`](crate::exit::Stalled) when no frame arrives within `stall_timeout`
+async fn geyser_subscribe_frames(
+    mut client: RawGeyserClient,
+    request: SubscribeRequest,
+    resub: usize,
+    stall_timeout: Option<Duration>,
+    session: &Session,
+    mut on_frame: impl FnMut(Frame) -> anyhow::Result<()>,
+) -> anyhow::Result<()> {
+    let (subscribe_tx, mut stream) = client
+        .subscribe_with_request(request)
+        .instrument(info_span!("subscribe"))
+        .await?;
+
+    info!("stream opened");
+    let mut counter = 0;
+    let mut last_slot = None;
+    let deadline = session.deadline();
+    loop {
+        if let Some(reason) = session.budget_spent() {
+            info!("{reason}, stopping");
+            return Ok(());
+        }
+        let next = async {
+            match stall_timeout {
+                Some(timeout) => tokio::time::timeout(timeout, stream.next())
+                    .await
+                    .map_err(|_| crate::exit::Stalled { timeout, slot: last_slot }),
+                None => Ok(stream.next().await),
+            }
+        };
+        let until_deadline = async {
+            match deadline {
+                Some(deadline) => tokio::time::sleep_until(deadline.into()).await,
+                None => std::future::pending().await,
+            }
+        };
+        let message = tokio::select! {
+            message = next => message?,
+            // The budget check above stops the subscription
+            () = until_deadline => continue,
+        };
+        let Some(message) = message else {
+            break;
+        };
+        let frame = match message {
+            Ok(frame) => frame,
+            Err(error) => {
+                error!("error: {error:?}");
+                return Err(error.into());
+            }
+        };
+        let received_at = SystemTime::now();
+
+        let fields = parse_frame(&frame).unwrap_or_default();
+        last_slot = last_slot.max(fields.slot);
+        let Some(kind) = fields.update.and_then(StatsKind::from_field_number) else {
+            error!("update not found in the message");
+            break;
+        };
+        if let Some(reason) = session.budget_past(fields.slot) {
+            info!("{reason}, stopping");
+            return Ok(());
+        }
+        session.record_update(kind, frame.len() as u64, fields.slot);
+        on_frame(Frame {
+            kind,
+            slot: fields.slot,
+            data: frame,
+            created_at: fields.created_at,
+            block_meta: fields.block_meta,
+            received_at,
+        })?;
+
+        if fields.update == Some(FIELD_PING) {
+            subscribe_tx.unbounded_send(SubscribeRequest {
+                ping: Some(SubscribeRequestPing { id: 1 }),
+                ..Default::default()
+            })?;
+        }
+
+        counter += 1;
+        if counter == resub {
+            subscribe_tx.unbounded_send(SubscribeRequest {
+                slots: [("client".to_owned(), SubscribeRequestFilterSlots::default())].into(),
+                ..Default::default()
+            })?;
+        }
+    }
+    info!("stream closed");
+    Err(crate::exit::StreamEnded.into())
+}
+
+pub async fn geyser_subscribe_stats(
+    client: RawGeyserClient,
+    request: SubscribeRequest,
+    resub: usize,
+    stall_timeout: Option<Duration>,
+    session: &Session,
+    metrics: &Metrics,
+) -> anyhow::Result<()> {
+    let mut progress = StatsProgress::new()?;
+    let mut network = NetworkStats::default();
+    geyser_subscribe_frames(client, request, resub, stall_timeout, session, |frame| {
+        progress.inc(frame.kind, frame.data.len() as u64);
+        if let Some(meta) = frame.block_meta {
+            let snapshot = network.observe(
+                meta.slot,
+                meta.parent_slot,
+                meta.block_time,
+                meta.executed_transaction_count,
+                meta.entries_count,
+            );
+            metrics.set_network(snapshot);
+            progress.set_network(&snapshot)?;
+        }
+        Ok(())
+    })
+    .await
+}
+
+/// Record frames to a `--capture` file, counting them like `--stats`
+pub async fn geyser_subscribe_capture(
+    client: RawGeyserClient,
+    request: SubscribeRequest,
+    resub: usize,
+    stall_timeout: Option<Duration>,
+    session: &Session,
+    path: &Path,
+    cipher: Option<Cipher>,
+) -> anyhow::Result<()> {
+    let mut capture = CaptureWriter::open(path, cipher)?;
+    let mut progress = StatsProgress::new()?;
+    geyser_subscribe_frames(client, request, resub, stall_timeout, session, |frame| {
+        progress.inc(frame.kind, frame.data.len() as u64);
+        if frame.kind != StatsKind::PingPong {
+            capture.write_frame(&frame.data, frame.slot)?;
+        }
+        Ok(())
+    })
+    .await
+}
+
+pub async fn geyser_subscribe_latency(
+    client: RawGeyserClient,
+    request: SubscribeRequest,
+    resub: usize,
+    stall_timeout: Option<Duration>,
+    session: &Session,
+    report_interval: Duration,
+) -> anyhow::Result<()> {
+    let mut latency = LatencyReport::new(report_interval);
+    let result = geyser_subscribe_frames(client, request, resub, stall_timeout, session, |frame| {
+        if let Some(created_at) = frame.created_at {
+            latency.record(frame.kind, created_at, frame.received_at);
+        }
+        Ok(())
+    })
+    .await;
+    latency.report();
+    result
+}
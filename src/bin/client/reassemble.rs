@@ -0,0 +1,135 @@
+//! `--reassemble-blocks`: rebuild blocks from transaction and block meta updates, which is
+//! much cheaper to subscribe to than full blocks.
+//!
+//! Transactions are grouped by slot until the block meta of the slot arrives. A
+//! `reassembledBlock` event is emitted once as many transactions as the block executed were
+//! received, or marked incomplete when they didn't all arrive in time (e.g. with a transactions
+//! filter that doesn't match every transaction of the block).
+
+use {
+    serde_json::{Value, json},
+    std::collections::BTreeMap,
+    tracing::warn,
+    yellowstone_grpc_proto::prelude::{SubscribeRequest, SubscribeRequestFilterBlocksMeta},
+};
+
+/// Block meta filter added to the request when block meta isn't subscribed already
+pub const FILTER: &str = "reassemble";
+
+/// A block still missing transactions this many slots after its meta is emitted incomplete
+const WAIT_SLOTS: u64 = 32;
+
+/// Transactions of slots without block meta this far behind the latest one are dropped
+const ABANDONED_AFTER_SLOTS: u64 = 150;
+
+#[derive(Default)]
+struct PendingBlock {
+    meta: Option<Value>,
+    transactions: Vec<Value>,
+}
+
+#[derive(Default)]
+pub struct Reassembler {
+    blocks: BTreeMap<u64, PendingBlock>,
+    latest_meta_slot: u64,
+}
+
+impl Reassembler {
+    /// Reassemble the blocks of `request`, subscribing to block meta if needed
+    pub fn new(request: &mut SubscribeRequest) -> anyhow::Result<Self> {
+        anyhow::ensure!(
+            !request.transactions.is_empty(),
+            "--reassemble-blocks needs a transactions subscription (--transactions)"
+        );
+        if request.blocks_meta.is_empty() {
+            request
+                .blocks_meta
+                .insert(FILTER.to_owned(), SubscribeRequestFilterBlocksMeta {});
+        }
+        Ok(Self::default())
+    }
+
+    /// Take an update, returns whether it only exists for reassembly and shouldn't be written,
+    /// and the blocks completed by it
+    pub fn observe(&mut self, kind: &str, filters: &[String], value: &Value) -> (bool, Vec<Value>) {
+        let Some(slot) = value.get("slot").and_then(Value::as_u64) else {
+            return (false, vec![]);
+        };
+        match kind {
+            "transaction" => {
+                self.blocks
+                    .entry(slot)
+                    .or_default()
+                    .transactions
+                    .push(value.clone());
+                (false, self.complete(slot).into_iter().collect())
+            }
+            "blockmeta" => {
+                self.blocks.entry(slot).or_default().meta = Some(value.clone());
+                self.latest_meta_slot = self.latest_meta_slot.max(slot);
+                let mut blocks = self.expire();
+                blocks.extend(self.complete(slot));
+                (filters.iter().all(|filter| filter == FILTER), blocks)
+            }
+            _ => (false, vec![]),
+        }
+    }
+
+    /// The block of `slot` if its meta and all its transactions arrived
+    fn complete(&mut self, slot: u64) -> Option<Value> {
+        let block = self.blocks.get(&slot)?;
+        let meta = block.meta.as_ref()?;
+        if (block.transactions.len() as u64) < executed(meta) {
+            return None;
+        }
+        self.blocks.remove(&slot).map(|block| assemble(slot, block))
+    }
+
+    /// Blocks which waited too long for their transactions, forgetting abandoned slots
+    fn expire(&mut self) -> Vec<Value> {
+        let expired = self.latest_meta_slot.saturating_sub(WAIT_SLOTS);
+        let abandoned = self.latest_meta_slot.saturating_sub(ABANDONED_AFTER_SLOTS);
+        let pending = self.blocks.split_off(&expired);
+        let mut blocks = vec![];
+        for (slot, block) in std::mem::replace(&mut self.blocks, pending) {
+            if block.meta.is_some() {
+                blocks.push(assemble(slot, block));
+            } else if slot >= abandoned {
+                self.blocks.insert(slot, block);
+            }
+        }
+        blocks
+    }
+}
+
+fn executed(meta: &Value) -> u64 {
+    meta.get("executedTransactionCount")
+        .and_then(Value::as_u64)
+        .unwrap_or_default()
+}
+
+fn assemble(slot: u64, block: PendingBlock) -> Value {
+    let meta = block.meta.unwrap_or_default();
+    let mut transactions = block.transactions;
+    transactions.sort_by_key(|tx| tx.get("index").and_then(Value::as_u64));
+    let expected = executed(&meta);
+    let complete = transactions.len() as u64 == expected;
+    if !complete {
+        warn!(
+            "block {slot} reassembled incomplete: {} of {expected} transactions received",
+            transactions.len()
+        );
+    }
+    json!({
+        "slot": slot,
+        "blockhash": meta.get("blockhash"),
+        "parentSlot": meta.get("parentSlot"),
+        "parentBlockhash": meta.get("parentBlockhash"),
+        "blockTime": meta.get("blockTime"),
+        "blockHeight": meta.get("blockHeight"),
+        "executedTransactionCount": expected,
+        "receivedTransactionCount": transactions.len(),
+        "complete": complete,
+        "transactions": transactions,
+    })
+}
@@ -0,0 +1,77 @@
+//! `--ordered-by-slot`: write updates in non-decreasing slot order, for consumers which can't
+//! handle updates of different slots interleaved as the stream sends them.
+//!
+//! Updates are held for up to `--max-reorder-delay-ms` from their reception, then written with
+//! the held updates of lower slots, by slot and in reception order within a slot. An update of
+//! a slot below one written already, i.e. later than the delay, is written when received, out
+//! of order. Updates without a slot are never held. Held updates are checked on a timer too, so
+//! they aren't held past the delay while the stream is quiet.
+
+use {
+    crate::DecodedUpdate,
+    serde_json::Value,
+    std::{collections::BTreeMap, mem, time::Duration},
+    tracing::debug,
+};
+
+pub struct Reorder {
+    delay: Duration,
+    slots: BTreeMap<u64, Vec<DecodedUpdate>>,
+    /// Highest slot written
+    written: Option<u64>,
+}
+
+impl Reorder {
+    pub fn new(delay: Duration) -> Self {
+        Self {
+            delay,
+            slots: BTreeMap::new(),
+            written: None,
+        }
+    }
+
+    pub const fn delay(&self) -> Duration {
+        self.delay
+    }
+
+    /// Hold an update, returns the update if it can't be reordered
+    pub fn push(&mut self, update: DecodedUpdate) -> Option<DecodedUpdate> {
+        let Some(slot) = update.value.get("slot").and_then(Value::as_u64) else {
+            return Some(update);
+        };
+        if let Some(written) = self.written.filter(|written| slot < *written) {
+            debug!(
+                "{} of slot {slot} received after slot {written} was written",
+                update.kind
+            );
+            return Some(update);
+        }
+        self.slots.entry(slot).or_default().push(update);
+        None
+    }
+
+    /// The updates held longer than the delay and those of lower slots, in slot order
+    pub fn due(&mut self) -> Vec<DecodedUpdate> {
+        // The first update of a slot is its oldest
+        let due = self.slots.iter().rev().find_map(|(slot, updates)| {
+            updates
+                .first()
+                .is_some_and(|update| update.timings.received_at.elapsed() >= self.delay)
+                .then_some(*slot)
+        });
+        let Some(due) = due else {
+            return vec![];
+        };
+        let held = self.slots.split_off(&(due + 1));
+        self.written = Some(due);
+        mem::replace(&mut self.slots, held)
+            .into_values()
+            .flatten()
+            .collect()
+    }
+
+    /// The updates held, in slot order
+    pub fn drain(&mut self) -> Vec<DecodedUpdate> {
+        mem::take(&mut self.slots).into_values().flatten().collect()
+    }
+}
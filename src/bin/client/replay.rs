@@ -0,0 +1,166 @@
+//! `replay --into-sink`: write the updates of `--capture` files or `--out-file` lines through a
+//! sink again, to re-process them after a handler or its schema changed.
+//!
+//! Every update is written once. Updates are keyed like `--checkpoint-file` does, by slot and
+//! signature for transactions and by pubkey and write version for accounts, and an update whose
+//! key was written already, e.g. from overlapping segments or a capture replayed with its
+//! out-file, is skipped. A handler upserting on the same keys can then be replayed into any
+//! number of times. Updates without a key (events, pings) are written every time.
+
+use {
+    crate::{
+        capture,
+        checkpoint::Position,
+        crypto::Cipher,
+        decode_update, outfile,
+        sink::{self, DecodedUpdate, Sink, SinkSpec},
+    },
+    anyhow::Context,
+    serde::Deserialize,
+    serde_json::Value,
+    std::{
+        collections::HashSet,
+        io::BufRead,
+        path::{Path, PathBuf},
+        time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+    },
+    tokio::runtime::Handle,
+    tracing::info,
+    yellowstone_grpc_proto::{prelude::SubscribeUpdate, prost::Message},
+};
+
+/// A line of `--out-file`, see `schema::Line`
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct StoredLine {
+    kind: String,
+    created_at: u64,
+    #[serde(default)]
+    filters: Vec<String>,
+    update: Value,
+    traceparent: Option<String>,
+}
+
+struct Replay {
+    sink: Box<dyn Sink>,
+    name: String,
+    retries: u32,
+    runtime: Handle,
+    seen: HashSet<Position>,
+    written: u64,
+    skipped: u64,
+}
+
+impl Replay {
+    fn write(
+        &mut self,
+        kind: &str,
+        created_at: SystemTime,
+        filters: &[String],
+        value: &Value,
+        traceparent: Option<&str>,
+    ) -> anyhow::Result<()> {
+        if let Some(position) = Position::of(kind, value).filter(Position::is_keyed)
+            && !self.seen.insert(position)
+        {
+            self.skipped += 1;
+            return Ok(());
+        }
+        self.written += 1;
+        let update = DecodedUpdate {
+            kind: kind.to_owned(),
+            created_at,
+            filters: filters.to_vec(),
+            value: value.clone(),
+            traceparent: traceparent.map(str::to_owned),
+        };
+        self.runtime.block_on(sink::deliver(
+            self.sink.as_ref(),
+            &self.name,
+            &update,
+            self.retries,
+        ))
+    }
+
+    fn capture(
+        &mut self,
+        path: &Path,
+        from_slot: Option<u64>,
+        cipher: Option<&Cipher>,
+    ) -> anyhow::Result<()> {
+        capture::read_frames(path, from_slot, cipher, |offset, frame, _| {
+            let update = SubscribeUpdate::decode(frame.as_slice())
+                .with_context(|| format!("invalid frame at offset {offset}"))?;
+            let update = decode_update(update, Instant::now())?;
+            self.write(
+                update.kind,
+                update.created_at,
+                &update.filters,
+                &update.value,
+                None,
+            )?;
+            Ok(true)
+        })
+    }
+
+    fn lines(
+        &mut self,
+        path: &Path,
+        from_slot: Option<u64>,
+        cipher: Option<&Cipher>,
+    ) -> anyhow::Result<()> {
+        for (number, line) in outfile::read_segment(path)?.lines().enumerate() {
+            let context = || format!("line {} of {}", number + 1, path.display());
+            let line = line.with_context(context)?;
+            let line = match cipher {
+                Some(cipher) => cipher.open_line(&line).with_context(context)?,
+                None => line,
+            };
+            let line = serde_json::from_str::<StoredLine>(&line).with_context(context)?;
+            let slot = line.update.get("slot").and_then(Value::as_u64);
+            if from_slot.is_some_and(|from_slot| slot.is_none_or(|slot| slot < from_slot)) {
+                continue;
+            }
+            self.write(
+                &line.kind,
+                UNIX_EPOCH + Duration::from_millis(line.created_at),
+                &line.filters,
+                &line.update,
+                line.traceparent.as_deref(),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+pub fn run(
+    files: &[PathBuf],
+    sink: &SinkSpec,
+    retries: u32,
+    from_slot: Option<u64>,
+    cipher: Option<&Cipher>,
+) -> anyhow::Result<()> {
+    let mut replay = Replay {
+        sink: sink.open()?,
+        name: sink.to_string(),
+        retries,
+        runtime: Handle::current(),
+        seen: HashSet::new(),
+        written: 0,
+        skipped: 0,
+    };
+    for path in files {
+        if capture::is_capture(path) {
+            replay.capture(path, from_slot, cipher)?;
+        } else {
+            replay.lines(path, from_slot, cipher)?;
+        }
+        info!("replayed {}", path.display());
+    }
+    replay.runtime.block_on(replay.sink.flush())?;
+    info!(
+        "replay: {} updates written, {} already written skipped",
+        replay.written, replay.skipped
+    );
+    Ok(())
+}
@@ -0,0 +1,385 @@
+//! `--export-request` and `--request`: a subscribe request as JSON, to save a subscription built
+//! from flags or the interactive menus and replay it exactly.
+//!
+//! Field names follow the protobuf JSON mapping, filters are keyed by name so several filters
+//! of a type round-trip. Memcmp `bytes` are base64. `--dry-run` prints the same format under
+//! `request`, and `--request` also reads such a dry-run output.
+
+use {
+    anyhow::Context,
+    base64::{Engine, engine::general_purpose::STANDARD},
+    serde::{Deserialize, Serialize},
+    serde_json::Value,
+    std::{collections::BTreeMap, fs, path::Path},
+    yellowstone_grpc_proto::prelude::{
+        CommitmentLevel, SubscribeRequest, SubscribeRequestAccountsDataSlice,
+        SubscribeRequestFilterAccounts, SubscribeRequestFilterAccountsFilter,
+        SubscribeRequestFilterAccountsFilterLamports, SubscribeRequestFilterAccountsFilterMemcmp,
+        SubscribeRequestFilterBlocks, SubscribeRequestFilterBlocksMeta,
+        SubscribeRequestFilterEntry, SubscribeRequestFilterSlots,
+        SubscribeRequestFilterTransactions, SubscribeRequestPing,
+        subscribe_request_filter_accounts_filter::Filter as AccountsFilterOneof,
+        subscribe_request_filter_accounts_filter_lamports::Cmp as AccountsFilterLamports,
+        subscribe_request_filter_accounts_filter_memcmp::Data as AccountsFilterMemcmpOneof,
+    },
+};
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default, deny_unknown_fields)]
+pub struct RequestJson {
+    accounts: BTreeMap<String, AccountsJson>,
+    slots: BTreeMap<String, SlotsJson>,
+    transactions: BTreeMap<String, TransactionsJson>,
+    transactions_status: BTreeMap<String, TransactionsJson>,
+    blocks: BTreeMap<String, BlocksJson>,
+    blocks_meta: BTreeMap<String, EmptyJson>,
+    entry: BTreeMap<String, EmptyJson>,
+    /// `PROCESSED`, `CONFIRMED` or `FINALIZED`
+    commitment: Option<String>,
+    accounts_data_slice: Vec<DataSliceJson>,
+    ping: Option<PingJson>,
+    from_slot: Option<u64>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default, deny_unknown_fields)]
+struct AccountsJson {
+    account: Vec<String>,
+    owner: Vec<String>,
+    filters: Vec<AccountsFilterJson>,
+    nonempty_txn_signature: Option<bool>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+enum AccountsFilterJson {
+    Memcmp(MemcmpJson),
+    Datasize(u64),
+    TokenAccountState(bool),
+    Lamports(LamportsJson),
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct MemcmpJson {
+    offset: u64,
+    #[serde(flatten)]
+    data: MemcmpDataJson,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+enum MemcmpDataJson {
+    /// Base64
+    Bytes(String),
+    Base58(String),
+    Base64(String),
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+enum LamportsJson {
+    Eq(u64),
+    Ne(u64),
+    Lt(u64),
+    Gt(u64),
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default, deny_unknown_fields)]
+struct SlotsJson {
+    filter_by_commitment: Option<bool>,
+    interslot_updates: Option<bool>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default, deny_unknown_fields)]
+struct TransactionsJson {
+    vote: Option<bool>,
+    failed: Option<bool>,
+    signature: Option<String>,
+    account_include: Vec<String>,
+    account_exclude: Vec<String>,
+    account_required: Vec<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default, deny_unknown_fields)]
+struct BlocksJson {
+    account_include: Vec<String>,
+    include_transactions: Option<bool>,
+    include_accounts: Option<bool>,
+    include_entries: Option<bool>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct EmptyJson {}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DataSliceJson {
+    offset: u64,
+    length: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PingJson {
+    id: i32,
+}
+
+pub fn to_json(request: &SubscribeRequest) -> Value {
+    serde_json::to_value(RequestJson::from(request)).expect("request is serializable")
+}
+
+/// Write `request` to `--export-request`
+pub fn export(path: &Path, request: &SubscribeRequest) -> anyhow::Result<()> {
+    let text = serde_json::to_string_pretty(&to_json(request))?;
+    fs::write(path, text + "\n").with_context(|| format!("failed to write {}", path.display()))
+}
+
+/// Read a request written by `--export-request`, or the output of `--dry-run`
+pub fn load(path: &Path) -> anyhow::Result<SubscribeRequest> {
+    let text =
+        fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))?;
+    let mut value: Value = serde_json::from_str(&text)
+        .with_context(|| format!("{} isn't valid JSON", path.display()))?;
+    if let Some(request) = value.get_mut("request").map(Value::take) {
+        value = request;
+    }
+    serde_json::from_value::<RequestJson>(value)
+        .with_context(|| format!("{} isn't a subscribe request", path.display()))?
+        .try_into()
+        .with_context(|| format!("invalid request in {}", path.display()))
+}
+
+impl From<&SubscribeRequest> for RequestJson {
+    fn from(request: &SubscribeRequest) -> Self {
+        Self {
+            accounts: named(&request.accounts, |filter| AccountsJson {
+                account: filter.account.clone(),
+                owner: filter.owner.clone(),
+                filters: filter
+                    .filters
+                    .iter()
+                    .filter_map(|filter| filter.filter.as_ref())
+                    .filter_map(accounts_filter_json)
+                    .collect(),
+                nonempty_txn_signature: filter.nonempty_txn_signature,
+            }),
+            slots: named(&request.slots, |filter| SlotsJson {
+                filter_by_commitment: filter.filter_by_commitment,
+                interslot_updates: filter.interslot_updates,
+            }),
+            transactions: named(&request.transactions, transactions_json),
+            transactions_status: named(&request.transactions_status, transactions_json),
+            blocks: named(&request.blocks, |filter| BlocksJson {
+                account_include: filter.account_include.clone(),
+                include_transactions: filter.include_transactions,
+                include_accounts: filter.include_accounts,
+                include_entries: filter.include_entries,
+            }),
+            blocks_meta: named(&request.blocks_meta, |_| EmptyJson {}),
+            entry: named(&request.entry, |_| EmptyJson {}),
+            commitment: request
+                .commitment
+                .and_then(|commitment| CommitmentLevel::try_from(commitment).ok())
+                .map(|commitment| commitment.as_str_name().to_owned()),
+            accounts_data_slice: request
+                .accounts_data_slice
+                .iter()
+                .map(|slice| DataSliceJson {
+                    offset: slice.offset,
+                    length: slice.length,
+                })
+                .collect(),
+            ping: request.ping.map(|ping| PingJson { id: ping.id }),
+            from_slot: request.from_slot,
+        }
+    }
+}
+
+impl TryFrom<RequestJson> for SubscribeRequest {
+    type Error = anyhow::Error;
+
+    fn try_from(request: RequestJson) -> anyhow::Result<Self> {
+        let mut accounts = std::collections::HashMap::new();
+        for (name, filter) in request.accounts {
+            let filters = filter
+                .filters
+                .into_iter()
+                .map(|filter| {
+                    Ok(SubscribeRequestFilterAccountsFilter {
+                        filter: Some(accounts_filter(filter)?),
+                    })
+                })
+                .collect::<anyhow::Result<_>>()
+                .with_context(|| format!("invalid accounts filter `{name}`"))?;
+            let filter = SubscribeRequestFilterAccounts {
+                account: filter.account,
+                owner: filter.owner,
+                filters,
+                nonempty_txn_signature: filter.nonempty_txn_signature,
+            };
+            accounts.insert(name, filter);
+        }
+        let commitment = request
+            .commitment
+            .map(|commitment| {
+                CommitmentLevel::from_str_name(&commitment.to_ascii_uppercase())
+                    .with_context(|| format!("unknown commitment `{commitment}`"))
+            })
+            .transpose()?;
+        Ok(Self {
+            accounts,
+            slots: request
+                .slots
+                .into_iter()
+                .map(|(name, filter)| {
+                    let filter = SubscribeRequestFilterSlots {
+                        filter_by_commitment: filter.filter_by_commitment,
+                        interslot_updates: filter.interslot_updates,
+                    };
+                    (name, filter)
+                })
+                .collect(),
+            transactions: request
+                .transactions
+                .into_iter()
+                .map(|(name, filter)| (name, transactions_filter(filter)))
+                .collect(),
+            transactions_status: request
+                .transactions_status
+                .into_iter()
+                .map(|(name, filter)| (name, transactions_filter(filter)))
+                .collect(),
+            blocks: request
+                .blocks
+                .into_iter()
+                .map(|(name, filter)| {
+                    let filter = SubscribeRequestFilterBlocks {
+                        account_include: filter.account_include,
+                        include_transactions: filter.include_transactions,
+                        include_accounts: filter.include_accounts,
+                        include_entries: filter.include_entries,
+                    };
+                    (name, filter)
+                })
+                .collect(),
+            blocks_meta: request
+                .blocks_meta
+                .into_keys()
+                .map(|name| (name, SubscribeRequestFilterBlocksMeta {}))
+                .collect(),
+            entry: request
+                .entry
+                .into_keys()
+                .map(|name| (name, SubscribeRequestFilterEntry {}))
+                .collect(),
+            commitment: commitment.map(Into::into),
+            accounts_data_slice: request
+                .accounts_data_slice
+                .into_iter()
+                .map(|slice| SubscribeRequestAccountsDataSlice {
+                    offset: slice.offset,
+                    length: slice.length,
+                })
+                .collect(),
+            ping: request
+                .ping
+                .map(|ping| SubscribeRequestPing { id: ping.id }),
+            from_slot: request.from_slot,
+        })
+    }
+}
+
+/// Filters by name, sorted so the output is reproducible
+fn named<T, J>(
+    filters: &std::collections::HashMap<String, T>,
+    to_json: impl Fn(&T) -> J,
+) -> BTreeMap<String, J> {
+    filters
+        .iter()
+        .map(|(name, filter)| (name.clone(), to_json(filter)))
+        .collect()
+}
+
+fn accounts_filter_json(filter: &AccountsFilterOneof) -> Option<AccountsFilterJson> {
+    Some(match filter {
+        AccountsFilterOneof::Memcmp(memcmp) => AccountsFilterJson::Memcmp(MemcmpJson {
+            offset: memcmp.offset,
+            data: match memcmp.data.as_ref()? {
+                AccountsFilterMemcmpOneof::Bytes(bytes) => {
+                    MemcmpDataJson::Bytes(STANDARD.encode(bytes))
+                }
+                AccountsFilterMemcmpOneof::Base58(data) => MemcmpDataJson::Base58(data.clone()),
+                AccountsFilterMemcmpOneof::Base64(data) => MemcmpDataJson::Base64(data.clone()),
+            },
+        }),
+        AccountsFilterOneof::Datasize(size) => AccountsFilterJson::Datasize(*size),
+        AccountsFilterOneof::TokenAccountState(state) => {
+            AccountsFilterJson::TokenAccountState(*state)
+        }
+        AccountsFilterOneof::Lamports(lamports) => {
+            AccountsFilterJson::Lamports(match lamports.cmp? {
+                AccountsFilterLamports::Eq(value) => LamportsJson::Eq(value),
+                AccountsFilterLamports::Ne(value) => LamportsJson::Ne(value),
+                AccountsFilterLamports::Lt(value) => LamportsJson::Lt(value),
+                AccountsFilterLamports::Gt(value) => LamportsJson::Gt(value),
+            })
+        }
+    })
+}
+
+fn accounts_filter(filter: AccountsFilterJson) -> anyhow::Result<AccountsFilterOneof> {
+    Ok(match filter {
+        AccountsFilterJson::Memcmp(memcmp) => {
+            AccountsFilterOneof::Memcmp(SubscribeRequestFilterAccountsFilterMemcmp {
+                offset: memcmp.offset,
+                data: Some(match memcmp.data {
+                    MemcmpDataJson::Bytes(data) => AccountsFilterMemcmpOneof::Bytes(
+                        STANDARD.decode(data).context("invalid memcmp bytes")?,
+                    ),
+                    MemcmpDataJson::Base58(data) => AccountsFilterMemcmpOneof::Base58(data),
+                    MemcmpDataJson::Base64(data) => AccountsFilterMemcmpOneof::Base64(data),
+                }),
+            })
+        }
+        AccountsFilterJson::Datasize(size) => AccountsFilterOneof::Datasize(size),
+        AccountsFilterJson::TokenAccountState(state) => {
+            AccountsFilterOneof::TokenAccountState(state)
+        }
+        AccountsFilterJson::Lamports(lamports) => {
+            AccountsFilterOneof::Lamports(SubscribeRequestFilterAccountsFilterLamports {
+                cmp: Some(match lamports {
+                    LamportsJson::Eq(value) => AccountsFilterLamports::Eq(value),
+                    LamportsJson::Ne(value) => AccountsFilterLamports::Ne(value),
+                    LamportsJson::Lt(value) => AccountsFilterLamports::Lt(value),
+                    LamportsJson::Gt(value) => AccountsFilterLamports::Gt(value),
+                }),
+            })
+        }
+    })
+}
+
+fn transactions_json(filter: &SubscribeRequestFilterTransactions) -> TransactionsJson {
+    TransactionsJson {
+        vote: filter.vote,
+        failed: filter.failed,
+        signature: filter.signature.clone(),
+        account_include: filter.account_include.clone(),
+        account_exclude: filter.account_exclude.clone(),
+        account_required: filter.account_required.clone(),
+    }
+}
+
+fn transactions_filter(filter: TransactionsJson) -> SubscribeRequestFilterTransactions {
+    SubscribeRequestFilterTransactions {
+        vote: filter.vote,
+        failed: filter.failed,
+        signature: filter.signature,
+        account_include: filter.account_include,
+        account_exclude: filter.account_exclude,
+        account_required: filter.account_required,
+    }
+}
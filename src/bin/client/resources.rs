@@ -0,0 +1,61 @@
+//! CPU time and memory accounting, and the container memory limit used to size caches.
+
+use std::{fs, time::Duration};
+
+/// cgroup v1 reports "no limit" as a page-aligned `i64::MAX`
+const UNLIMITED: u64 = 1 << 60;
+
+/// CPU time consumed by the calling thread, zero where unsupported
+pub fn thread_cpu_time() -> Duration {
+    #[cfg(unix)]
+    return cpu_clock(libc::CLOCK_THREAD_CPUTIME_ID);
+    #[cfg(not(unix))]
+    Duration::ZERO
+}
+
+/// CPU time consumed by the whole process, zero where unsupported
+#[cfg(feature = "control-api")]
+pub fn process_cpu_time() -> Duration {
+    #[cfg(unix)]
+    return cpu_clock(libc::CLOCK_PROCESS_CPUTIME_ID);
+    #[cfg(not(unix))]
+    Duration::ZERO
+}
+
+#[cfg(unix)]
+fn cpu_clock(clock: libc::clockid_t) -> Duration {
+    let mut time = libc::timespec {
+        tv_sec: 0,
+        tv_nsec: 0,
+    };
+    // SAFETY: `time` is a valid, writable timespec
+    if unsafe { libc::clock_gettime(clock, &mut time) } != 0 {
+        return Duration::ZERO;
+    }
+    Duration::new(time.tv_sec as u64, time.tv_nsec as u32)
+}
+
+/// Resident set size of the process, Linux only
+#[cfg(feature = "control-api")]
+#[cfg(unix)]
+pub fn resident_memory() -> Option<u64> {
+    let statm = fs::read_to_string("/proc/self/statm").ok()?;
+    let pages: u64 = statm.split_whitespace().nth(1)?.parse().ok()?;
+    // SAFETY: sysconf has no preconditions
+    let page_size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) };
+    Some(pages * u64::try_from(page_size).ok()?)
+}
+
+#[cfg(not(unix))]
+pub fn resident_memory() -> Option<u64> {
+    None
+}
+
+/// Memory limit of the cgroup the process runs in (v2, then v1), `None` when unlimited
+pub fn cgroup_memory_limit() -> Option<u64> {
+    ["/sys/fs/cgroup/memory.max", "/sys/fs/cgroup/memory/memory.limit_in_bytes"]
+        .into_iter()
+        .find_map(|path| fs::read_to_string(path).ok())
+        .and_then(|limit| limit.trim().parse::<u64>().ok())
+        .filter(|limit| *limit < UNLIMITED)
+}
@@ -0,0 +1,239 @@
+//! `--retain`: prune the closed `--rotate` segments of `--out-file` by update kind, so a
+//! long-running indexer doesn't fill its disk, e.g. `--retain transaction=7d --retain
+//! account=latest` keeps a week of transactions and the latest state of every account.
+//!
+//! A background pass runs every `--prune-interval` on the thread closing segments. It reads the
+//! segments listed in `<file>.index` from the newest, drops the lines whose policy says so and
+//! rewrites the segments that changed, with their compression, removing the ones left empty and
+//! updating the index. Kinds without a policy, and the file being written, are kept.
+
+use {
+    crate::{crypto::Cipher, outfile, units},
+    anyhow::Context,
+    serde_json::Value,
+    std::{
+        collections::{HashMap, HashSet},
+        fs,
+        io::{BufRead, Write},
+        path::Path,
+        time::{Duration, SystemTime, UNIX_EPOCH},
+    },
+};
+
+/// What `--retain` keeps of an update kind
+#[derive(Debug, Clone, Copy)]
+pub enum Policy {
+    /// Lines created within this age
+    Age(Duration),
+    /// The latest line of every account, `account` only
+    Latest,
+    Forever,
+}
+
+/// Parse `--retain`, format: `kind=policy`, e.g. `transaction=7d`, `account=latest` or `*=30d`
+pub fn parse_retain(value: &str) -> Result<(String, Policy), String> {
+    let (kind, policy) = value
+        .split_once('=')
+        .ok_or_else(|| format!("invalid `{value}`, expected `kind=policy`, e.g. transaction=7d"))?;
+    let policy = match policy {
+        "latest" if kind == "account" => Policy::Latest,
+        "latest" => {
+            return Err(
+                "`latest` keeps the latest state of accounts, only for `account`".to_owned(),
+            );
+        }
+        "forever" => Policy::Forever,
+        age => Policy::Age(
+            units::parse_duration(age)
+                .map_err(|error| format!("{error}, or `latest` or `forever`"))?,
+        ),
+    };
+    Ok((kind.to_owned(), policy))
+}
+
+pub struct Retention {
+    policies: Vec<(String, Policy)>,
+    pub interval: Duration,
+}
+
+/// What a pruning pass removed
+#[derive(Default)]
+pub struct Pruned {
+    pub lines: u64,
+    pub segments: u64,
+}
+
+impl Retention {
+    pub fn new(policies: Vec<(String, Policy)>, interval: Duration) -> Self {
+        Self { policies, interval }
+    }
+
+    /// Policy of a kind, the last one given wins
+    fn policy(&self, kind: &str) -> Policy {
+        let find = |wanted: &str| {
+            self.policies
+                .iter()
+                .rev()
+                .find(|(kind, _)| kind == wanted)
+                .map(|(_, policy)| *policy)
+        };
+        find(kind).or_else(|| find("*")).unwrap_or(Policy::Forever)
+    }
+
+    /// Prune the segments of an index, newest first so `latest` knows the accounts seen since
+    pub fn prune(&self, index: &Path, cipher: Option<&Cipher>) -> anyhow::Result<Pruned> {
+        let text = match fs::read_to_string(index) {
+            Ok(text) => text,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => {
+                return Ok(Pruned::default());
+            }
+            Err(error) => {
+                return Err(error).with_context(|| format!("failed to read {}", index.display()));
+            }
+        };
+        let mut entries = text
+            .lines()
+            .filter(|line| !line.is_empty())
+            .map(serde_json::from_str::<Value>)
+            .collect::<Result<Vec<_>, _>>()
+            .with_context(|| format!("failed to parse {}", index.display()))?;
+        let dir = index.parent().unwrap_or(Path::new(""));
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+
+        let mut pruned = Pruned::default();
+        let mut seen = HashSet::new();
+        let mut changed = false;
+        for entry in entries.iter_mut().rev() {
+            let Some(name) = entry["segment"].as_str() else {
+                continue;
+            };
+            let segment = dir.join(name);
+            if !segment.exists() {
+                continue;
+            }
+            let keep = self
+                .keep(&segment, cipher, now, &mut seen)
+                .with_context(|| format!("failed to read {}", segment.display()))?;
+            let dropped = keep.iter().filter(|keep| !**keep).count() as u64;
+            if dropped == 0 {
+                continue;
+            }
+            pruned.lines += dropped;
+            changed = true;
+            if dropped == keep.len() as u64 {
+                fs::remove_file(&segment)
+                    .with_context(|| format!("failed to remove {}", segment.display()))?;
+                pruned.segments += 1;
+                *entry = Value::Null;
+                continue;
+            }
+            rewrite(&segment, &keep, cipher, entry)
+                .with_context(|| format!("failed to prune {}", segment.display()))?;
+        }
+
+        if changed {
+            let mut text = String::new();
+            for entry in entries.iter().filter(|entry| !entry.is_null()) {
+                text.push_str(&entry.to_string());
+                text.push('\n');
+            }
+            let temporary = index.with_extension("index.pruning");
+            fs::write(&temporary, text)
+                .and_then(|()| fs::rename(&temporary, index))
+                .with_context(|| format!("failed to write {}", index.display()))?;
+        }
+        Ok(pruned)
+    }
+
+    /// Whether to keep every line of a segment, adding its accounts to `seen`
+    fn keep(
+        &self,
+        segment: &Path,
+        cipher: Option<&Cipher>,
+        now: Duration,
+        seen: &mut HashSet<String>,
+    ) -> anyhow::Result<Vec<bool>> {
+        let mut keep = vec![];
+        // Line of the latest change of every account in the segment
+        let mut latest = HashMap::new();
+        for line in outfile::read_segment(segment)?.lines() {
+            let line = line?;
+            let line = match cipher {
+                Some(cipher) => cipher.open_line(&line)?,
+                None => line,
+            };
+            // Lines that don't parse are kept
+            let Ok(value) = serde_json::from_str::<Value>(&line) else {
+                keep.push(true);
+                continue;
+            };
+            let kind = value["kind"].as_str().unwrap_or_default();
+            keep.push(match self.policy(kind) {
+                Policy::Forever => true,
+                Policy::Age(age) => value["createdAt"]
+                    .as_u64()
+                    .is_none_or(|created_at| Duration::from_millis(created_at) + age >= now),
+                Policy::Latest => match value["update"]["pubkey"].as_str() {
+                    Some(pubkey) => {
+                        latest.insert(pubkey.to_owned(), keep.len());
+                        false
+                    }
+                    None => true,
+                },
+            });
+        }
+        for (pubkey, line) in latest {
+            if !seen.contains(&pubkey) {
+                keep[line] = true;
+                seen.insert(pubkey);
+            }
+        }
+        Ok(keep)
+    }
+}
+
+/// Rewrite a segment with the lines to keep, updating its index entry
+fn rewrite(
+    segment: &Path,
+    keep: &[bool],
+    cipher: Option<&Cipher>,
+    entry: &mut Value,
+) -> anyhow::Result<()> {
+    // Hidden, with the extension of the segment to be compressed alike
+    let name = segment.file_name().unwrap_or_default().to_string_lossy();
+    let temporary = segment.with_file_name(format!(".{name}"));
+    let mut writer = outfile::write_segment(&temporary)?;
+    let (mut lines, mut bytes) = (0u64, 0u64);
+    let (mut first_slot, mut last_slot) = (None::<u64>, None::<u64>);
+    for (line, keep) in outfile::read_segment(segment)?.lines().zip(keep) {
+        let line = line?;
+        if !keep {
+            continue;
+        }
+        writeln!(writer, "{line}")?;
+        lines += 1;
+        bytes += line.len() as u64 + 1;
+        let line = match cipher {
+            Some(cipher) => cipher.open_line(&line)?,
+            None => line,
+        };
+        let slot = serde_json::from_str::<Value>(&line)
+            .ok()
+            .and_then(|value| value["update"]["slot"].as_u64());
+        if let Some(slot) = slot {
+            first_slot = Some(first_slot.map_or(slot, |first| first.min(slot)));
+            last_slot = Some(last_slot.map_or(slot, |last| last.max(slot)));
+        }
+    }
+    writer.flush()?;
+    drop(writer);
+    fs::rename(&temporary, segment)?;
+
+    entry["firstSlot"] = first_slot.into();
+    entry["lastSlot"] = last_slot.into();
+    entry["lines"] = lines.into();
+    entry["bytes"] = bytes.into();
+    Ok(())
+}
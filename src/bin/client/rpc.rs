@@ -0,0 +1,98 @@
+//! Minimal Solana JSON-RPC client for state that the Geyser stream doesn't replay.
+
+use {
+    anyhow::Context,
+    base64::{Engine, engine::general_purpose::STANDARD},
+    serde_json::{Value, json},
+    solana_epoch_schedule::EpochSchedule,
+    solana_pubkey::Pubkey,
+    std::str::FromStr,
+    yellowstone_grpc_proto::prelude::SubscribeUpdateAccountInfo,
+};
+
+pub struct RpcClient {
+    http: reqwest::Client,
+    url: String,
+}
+
+impl RpcClient {
+    pub fn new(url: String) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            url,
+        }
+    }
+
+    pub async fn call(&self, method: &str, params: Value) -> anyhow::Result<Value> {
+        let mut response: Value = self
+            .http
+            .post(&self.url)
+            .json(&json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "method": method,
+                "params": params,
+            }))
+            .send()
+            .await
+            .with_context(|| format!("{method} request failed"))?
+            .error_for_status()?
+            .json()
+            .await
+            .with_context(|| format!("invalid {method} response"))?;
+        if let Some(error) = response.get("error") {
+            anyhow::bail!("{method} failed: {error}");
+        }
+        response
+            .get_mut("result")
+            .map(Value::take)
+            .with_context(|| format!("no result in {method} response"))
+    }
+
+    pub async fn epoch_schedule(&self) -> anyhow::Result<EpochSchedule> {
+        let schedule = self.call("getEpochSchedule", json!([])).await?;
+        let field = |name: &str| {
+            schedule
+                .get(name)
+                .with_context(|| format!("no {name} in getEpochSchedule response"))
+        };
+        Ok(EpochSchedule::custom(
+            field("slotsPerEpoch")?
+                .as_u64()
+                .context("invalid slotsPerEpoch")?,
+            field("leaderScheduleSlotOffset")?
+                .as_u64()
+                .context("invalid leaderScheduleSlotOffset")?,
+            field("warmup")?.as_bool().context("invalid warmup")?,
+        ))
+    }
+}
+
+/// Convert an RPC account (`base64` encoding) into the Geyser representation
+pub fn parse_account(pubkey: &str, account: &Value) -> anyhow::Result<SubscribeUpdateAccountInfo> {
+    let field = |name: &str| {
+        account
+            .get(name)
+            .with_context(|| format!("no {name} in account {pubkey}"))
+    };
+    let data = field("data")?
+        .get(0)
+        .and_then(Value::as_str)
+        .with_context(|| format!("account {pubkey} data is not base64"))?;
+    Ok(SubscribeUpdateAccountInfo {
+        pubkey: Pubkey::from_str(pubkey)
+            .with_context(|| format!("invalid pubkey {pubkey}"))?
+            .to_bytes()
+            .to_vec(),
+        lamports: field("lamports")?.as_u64().context("invalid lamports")?,
+        owner: Pubkey::from_str(field("owner")?.as_str().context("invalid owner")?)
+            .context("invalid owner")?
+            .to_bytes()
+            .to_vec(),
+        executable: field("executable")?.as_bool().context("invalid executable")?,
+        rent_epoch: field("rentEpoch")?.as_u64().context("invalid rentEpoch")?,
+        data: STANDARD.decode(data).context("invalid account data")?,
+        write_version: 0,
+        txn_signature: None,
+    })
+}
@@ -0,0 +1,243 @@
+//! Versioned shapes of the decoded updates, as printed and written to `--out-file`, and the
+//! `schema` action dumping their JSON Schema so consumers can generate code against them.
+//!
+//! [`VERSION`] is bumped whenever a field is removed, renamed or changes type; added fields
+//! keep the version. Out-file lines carry it as `schemaVersion`. Fields added after decoding
+//! (`leader`, `token`, ...) are enrichments and not part of the schemas.
+
+use {
+    anyhow::Context,
+    schemars::{JsonSchema, Schema, schema_for},
+    serde::Serialize,
+    serde_json::Value,
+    solana_transaction_status::EncodedTransactionWithStatusMeta,
+    std::{
+        fs,
+        path::Path,
+        time::{SystemTime, UNIX_EPOCH},
+    },
+};
+
+pub const VERSION: u32 = 1;
+
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountInfo {
+    pub pubkey: String,
+    pub lamports: u64,
+    pub owner: String,
+    pub executable: bool,
+    pub rent_epoch: u64,
+    /// Account data, hex-encoded
+    pub data: String,
+    pub write_version: u64,
+    pub txn_signature: Option<String>,
+}
+
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Account {
+    #[serde(flatten)]
+    pub account: AccountInfo,
+    pub is_startup: bool,
+    pub slot: u64,
+}
+
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Slot {
+    pub slot: u64,
+    pub parent: Option<u64>,
+    /// `SlotStatus` name, e.g. `SLOT_CONFIRMED`
+    pub status: String,
+    pub dead_error: Option<String>,
+}
+
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TransactionInfo {
+    pub signature: String,
+    pub is_vote: bool,
+    pub index: u64,
+    /// Set by a `SetComputeUnitLimit` instruction, `null` without one
+    pub cu_limit: Option<u32>,
+    /// Set by a `SetComputeUnitPrice` instruction, `null` without one
+    pub cu_price_micro_lamports: Option<u64>,
+    /// The transaction and its status meta, as `EncodedTransactionWithStatusMeta` in base64
+    #[schemars(with = "Value")]
+    pub tx: EncodedTransactionWithStatusMeta,
+}
+
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Transaction {
+    #[serde(flatten)]
+    pub transaction: TransactionInfo,
+    pub slot: u64,
+}
+
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TransactionStatus {
+    pub slot: u64,
+    pub signature: String,
+    pub is_vote: bool,
+    pub index: u64,
+    /// `TransactionError`, `null` for a successful transaction
+    pub err: Value,
+}
+
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Entry {
+    pub slot: u64,
+    pub index: u64,
+    pub num_hashes: u64,
+    pub hash: String,
+    pub executed_transaction_count: u64,
+    pub starting_transaction_index: u64,
+}
+
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockMeta {
+    pub slot: u64,
+    pub blockhash: String,
+    /// Rewards and partitions, as the RPC `getBlock` rewards
+    pub rewards: Option<Value>,
+    pub block_time: Option<i64>,
+    pub block_height: Option<u64>,
+    pub parent_slot: u64,
+    pub parent_blockhash: String,
+    pub executed_transaction_count: u64,
+    pub entries_count: u64,
+}
+
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Block {
+    pub slot: u64,
+    pub blockhash: String,
+    pub rewards: Option<Value>,
+    pub block_time: Option<i64>,
+    pub block_height: Option<u64>,
+    pub parent_slot: u64,
+    pub parent_blockhash: String,
+    pub executed_transaction_count: u64,
+    pub transactions: Vec<TransactionInfo>,
+    pub updated_account_count: u64,
+    pub accounts: Vec<AccountInfo>,
+    pub entries_count: u64,
+    pub entries: Vec<Entry>,
+}
+
+/// Transactions, accounts or entries of a block decoded with `--block-chunk-size`, the `block`
+/// update follows its chunks without them
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockChunk<T> {
+    pub slot: u64,
+    pub blockhash: String,
+    /// `transactions`, `accounts` or `entries`
+    pub part: &'static str,
+    /// Position of the first item in the part
+    pub offset: usize,
+    /// Items of the part in the whole block
+    pub total: usize,
+    /// As in the `transactions`, `accounts` or `entries` of a `block`
+    pub items: Vec<T>,
+}
+
+/// A line of `--out-file` and `--sink`, borrowing the update rather than copying it into a line
+/// value. Fields are in key order, as lines were written from sorted maps.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Line<'a> {
+    /// Milliseconds since the epoch
+    pub created_at: u64,
+    pub filters: &'a [String],
+    pub kind: &'a str,
+    pub schema_version: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub traceparent: Option<&'a str>,
+    pub update: &'a Value,
+}
+
+impl<'a> Line<'a> {
+    pub fn new(
+        kind: &'a str,
+        created_at: SystemTime,
+        filters: &'a [String],
+        update: &'a Value,
+        traceparent: Option<&'a str>,
+    ) -> Self {
+        Self {
+            created_at: created_at
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_millis() as u64,
+            filters,
+            kind,
+            schema_version: VERSION,
+            traceparent,
+            update,
+        }
+    }
+}
+
+/// Update kinds, as the `kind` of decoded updates, with their schema
+fn schemas() -> Vec<(&'static str, Schema)> {
+    vec![
+        ("account", schema_for!(Account)),
+        ("slot", schema_for!(Slot)),
+        ("transaction", schema_for!(Transaction)),
+        ("transactionStatus", schema_for!(TransactionStatus)),
+        ("entry", schema_for!(Entry)),
+        ("blockmeta", schema_for!(BlockMeta)),
+        ("block", schema_for!(Block)),
+        ("blockChunk", schema_for!(BlockChunk<Value>)),
+    ]
+}
+
+/// `schema`: print the schema of one update kind, or of all of them keyed by kind; with
+/// `out` write them as `<kind>.v<VERSION>.schema.json` instead
+pub fn run(kind: Option<&str>, out: Option<&Path>) -> anyhow::Result<()> {
+    let mut schemas = schemas();
+    for (kind, schema) in &mut schemas {
+        schema.insert("title".to_owned(), format!("{kind} v{VERSION}").into());
+        schema.insert("x-schema-version".to_owned(), VERSION.into());
+    }
+    if let Some(kind) = kind {
+        schemas.retain(|(name, _)| *name == kind);
+        anyhow::ensure!(
+            !schemas.is_empty(),
+            "unknown update kind `{kind}`, expected account, slot, transaction, \
+             transactionStatus, entry, blockmeta, block or blockChunk"
+        );
+    }
+
+    match out {
+        Some(out) => {
+            fs::create_dir_all(out)
+                .with_context(|| format!("failed to create {}", out.display()))?;
+            for (kind, schema) in schemas {
+                let path = out.join(format!("{kind}.v{VERSION}.schema.json"));
+                fs::write(&path, serde_json::to_vec_pretty(&schema)?)
+                    .with_context(|| format!("failed to write {}", path.display()))?;
+                println!("{}", path.display());
+            }
+        }
+        None if kind.is_some() => {
+            let (_, schema) = schemas.remove(0);
+            println!("{}", serde_json::to_string_pretty(&schema)?);
+        }
+        None => {
+            let schemas = schemas
+                .into_iter()
+                .map(|(kind, schema)| (kind.to_owned(), schema.to_value()))
+                .collect::<serde_json::Map<_, _>>();
+            println!("{}", serde_json::to_string_pretty(&schemas)?);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,127 @@
+//! `--script rules.rhai`: a Rhai script deciding what happens to every decoded update, a lighter
+//! alternative to `--transform` WASM modules.
+//!
+//! The script defines `fn on_update(kind, update)`, returning `false` to drop the update, a map
+//! to write instead of it, or anything else to keep it as is. `this` is a map kept across calls
+//! for state such as per-wallet counters, and `alert(message)` or `alert(message, data)` writes
+//! a `scriptAlert` event after the update:
+//!
+//! ```rhai
+//! fn on_update(kind, update) {
+//!     if kind != "account" { return; }
+//!     this[update.owner] = (this[update.owner] ?? 0) + 1;
+//!     if this[update.owner] == 1000 { alert(`1000 updates of ${update.owner} accounts`); }
+//!     update.lamports > 0
+//! }
+//! ```
+
+use {
+    anyhow::Context,
+    rhai::{AST, CallFnOptions, Dynamic, Engine, Map, Scope},
+    serde_json::{Value, json},
+    std::{
+        path::Path,
+        sync::{Arc, Mutex},
+    },
+};
+
+pub const FILTER: &str = "script";
+
+/// What the script decided for an update
+pub enum Verdict {
+    Keep,
+    Drop,
+    Replace(Value),
+}
+
+pub struct Script {
+    engine: Engine,
+    ast: AST,
+    scope: Scope<'static>,
+    /// `this` of `on_update`
+    state: Dynamic,
+    alerts: Arc<Mutex<Vec<Value>>>,
+}
+
+impl Script {
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let mut engine = Engine::new();
+        let alerts = Arc::new(Mutex::new(vec![]));
+        let push = {
+            let alerts = Arc::clone(&alerts);
+            move |message: &str, data: Value| {
+                alerts
+                    .lock()
+                    .expect("alerts lock poisoned")
+                    .push(json!({ "message": message, "data": data }));
+            }
+        };
+        let alert = push.clone();
+        engine.register_fn("alert", move |message: &str| alert(message, Value::Null));
+        engine.register_fn("alert", move |message: &str, data: Dynamic| {
+            push(
+                message,
+                rhai::serde::from_dynamic(&data).unwrap_or_default(),
+            );
+        });
+
+        let ast = engine
+            .compile_file(path.to_owned())
+            .with_context(|| format!("failed to compile {}", path.display()))?;
+        anyhow::ensure!(
+            ast.iter_functions()
+                .any(|function| function.name == "on_update" && function.params.len() == 2),
+            "{} doesn't define `fn on_update(kind, update)`",
+            path.display()
+        );
+        let mut scope = Scope::new();
+        engine
+            .run_ast_with_scope(&mut scope, &ast)
+            .with_context(|| format!("failed to run {}", path.display()))?;
+        Ok(Self {
+            engine,
+            ast,
+            scope,
+            state: Map::new().into(),
+            alerts,
+        })
+    }
+
+    /// Run `on_update`, returning its verdict and the alerts it raised, tagged with the kind
+    /// and slot of the update
+    pub fn on_update(
+        &mut self,
+        kind: &str,
+        value: &Value,
+    ) -> anyhow::Result<(Verdict, Vec<Value>)> {
+        let update = rhai::serde::to_dynamic(value)?;
+        let options = CallFnOptions::new()
+            .eval_ast(false)
+            .bind_this_ptr(&mut self.state);
+        let result = self
+            .engine
+            .call_fn_with_options::<Dynamic>(
+                options,
+                &mut self.scope,
+                &self.ast,
+                "on_update",
+                (kind.to_owned(), update),
+            )
+            .context("script `on_update` failed");
+        let mut alerts = std::mem::take(&mut *self.alerts.lock().expect("alerts lock poisoned"));
+        for alert in &mut alerts {
+            alert["kind"] = json!(kind);
+            alert["slot"] = value.get("slot").cloned().unwrap_or_default();
+        }
+        let result = result?;
+
+        let verdict = if result.is_map() {
+            Verdict::Replace(rhai::serde::from_dynamic(&result)?)
+        } else if result.as_bool() == Ok(false) {
+            Verdict::Drop
+        } else {
+            Verdict::Keep
+        };
+        Ok((verdict, alerts))
+    }
+}
@@ -0,0 +1,114 @@
+//! Secret references resolved when connecting.
+//!
+//! Anywhere a secret is accepted (e.g. `--x-token`) the value can be a reference instead
+//! of plain text:
+//!
+//! - `vault://<path>#<field>`: HashiCorp Vault KV v1 or v2 secret, read from `VAULT_ADDR`
+//!   with `VAULT_TOKEN` (and `VAULT_NAMESPACE` if set), e.g. `vault://secret/data/solana#x_token`
+//! - `aws-sm://<secret-id>[#<field>]`: AWS Secrets Manager secret fetched with the `aws` CLI
+//!   and its usual credential chain, `#<field>` picks a key of a JSON secret
+//!
+//! References are resolved on every (re)connect so rotated secrets are picked up.
+
+use {
+    anyhow::Context,
+    serde_json::Value,
+    std::env,
+    tokio::process::Command,
+};
+
+const VAULT_SCHEME: &str = "vault://";
+const AWS_SM_SCHEME: &str = "aws-sm://";
+
+/// Resolve a secret reference, plain values are returned unchanged
+pub async fn resolve(value: &str) -> anyhow::Result<String> {
+    if let Some(reference) = value.strip_prefix(VAULT_SCHEME) {
+        let (path, field) = split_field(reference);
+        resolve_vault(path, field)
+            .await
+            .with_context(|| format!("failed to read secret {VAULT_SCHEME}{path} from Vault"))
+    } else if let Some(reference) = value.strip_prefix(AWS_SM_SCHEME) {
+        let (secret_id, field) = split_field(reference);
+        resolve_aws(secret_id, field).await.with_context(|| {
+            format!("failed to read secret {AWS_SM_SCHEME}{secret_id} from AWS Secrets Manager")
+        })
+    } else {
+        Ok(value.to_owned())
+    }
+}
+
+/// Whether the value is a reference rather than the secret itself
+pub fn is_reference(value: &str) -> bool {
+    value.starts_with(VAULT_SCHEME) || value.starts_with(AWS_SM_SCHEME)
+}
+
+fn split_field(reference: &str) -> (&str, Option<&str>) {
+    match reference.split_once('#') {
+        Some((path, field)) => (path, Some(field)),
+        None => (reference, None),
+    }
+}
+
+async fn resolve_vault(path: &str, field: Option<&str>) -> anyhow::Result<String> {
+    let addr = env::var("VAULT_ADDR").context("VAULT_ADDR is not set")?;
+    let token = env::var("VAULT_TOKEN").context("VAULT_TOKEN is not set")?;
+
+    let mut request = reqwest::Client::new()
+        .get(format!("{}/v1/{}", addr.trim_end_matches('/'), path.trim_start_matches('/')))
+        .header("X-Vault-Token", token);
+    if let Ok(namespace) = env::var("VAULT_NAMESPACE") {
+        request = request.header("X-Vault-Namespace", namespace);
+    }
+    let response: Value = request.send().await?.error_for_status()?.json().await?;
+
+    // KV v2 nests the secret under `data.data`, KV v1 returns it as `data`
+    let data = response
+        .pointer("/data/data")
+        .filter(|data| data.is_object())
+        .or_else(|| response.get("data"))
+        .context("no data in Vault response")?;
+    select_field(data, field)
+}
+
+async fn resolve_aws(secret_id: &str, field: Option<&str>) -> anyhow::Result<String> {
+    let output = Command::new("aws")
+        .args(["secretsmanager", "get-secret-value", "--secret-id", secret_id])
+        .args(["--query", "SecretString", "--output", "text"])
+        .output()
+        .await
+        .context("failed to run the `aws` CLI")?;
+    anyhow::ensure!(
+        output.status.success(),
+        "aws CLI failed: {}",
+        String::from_utf8_lossy(&output.stderr).trim()
+    );
+    let secret = String::from_utf8(output.stdout)
+        .context("secret is not valid UTF-8")?
+        .trim_end()
+        .to_owned();
+
+    match field {
+        Some(field) => {
+            let data: Value = serde_json::from_str(&secret)
+                .context("secret must be a JSON object to select a field")?;
+            select_field(&data, Some(field))
+        }
+        None => Ok(secret),
+    }
+}
+
+/// Pick `field` from a secret object, or its only value when no field is given
+fn select_field(data: &Value, field: Option<&str>) -> anyhow::Result<String> {
+    let object = data.as_object().context("secret is not an object")?;
+    let value = match field {
+        Some(field) => object
+            .get(field)
+            .with_context(|| format!("secret has no field `{field}`"))?,
+        None if object.len() == 1 => object.values().next().expect("one value"),
+        None => anyhow::bail!("secret has several fields, select one with `#<field>`"),
+    };
+    value
+        .as_str()
+        .map(ToOwned::to_owned)
+        .context("secret field is not a string")
+}
@@ -0,0 +1,49 @@
+//! `--select '.signature,.slot,.tx.meta.fee'`: project every update to a few fields before it's
+//! printed, written to `--out-file` or sinked, to shrink the output of busy subscriptions.
+//!
+//! Paths are jq style, a leading `.` then dot separated fields, array elements as `[0]` or
+//! `.0`. The projection is an object keyed by the paths without the leading dot, fields missing
+//! from an update are `null` so every line has the same keys. Events computed from the stream
+//! (`fees`, `programStats`, ...) are written whole.
+
+use {
+    crate::expr,
+    serde_json::{Map, Value},
+    std::sync::Arc,
+};
+
+#[derive(Debug, Clone)]
+pub struct Select(Arc<[(String, String)]>);
+
+/// Parse `--select`: comma separated paths
+pub fn parse_select(value: &str) -> Result<Select, String> {
+    let paths = value
+        .split(',')
+        .map(|path| {
+            let path = path.trim();
+            let normalized = path
+                .strip_prefix('.')
+                .ok_or_else(|| format!("path `{path}` doesn't start with `.`"))?
+                .replace('[', ".")
+                .replace(']', "");
+            if normalized.is_empty() || normalized.split('.').any(str::is_empty) {
+                return Err(format!("invalid path `{path}`, e.g. .tx.meta.fee"));
+            }
+            Ok((path.trim_start_matches('.').to_owned(), normalized))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(Select(paths.into()))
+}
+
+impl Select {
+    pub fn apply(&self, value: &Value) -> Value {
+        self.0
+            .iter()
+            .map(|(key, path)| {
+                let field = expr::lookup(value, path).cloned().unwrap_or_default();
+                (key.clone(), field)
+            })
+            .collect::<Map<_, _>>()
+            .into()
+    }
+}
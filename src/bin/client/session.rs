@@ -0,0 +1,220 @@
+//! End-of-session report: when a subscription stops (the stream closes for good, an error is
+//! final, or Ctrl-C) a summary of the whole session is printed to stderr, and written as JSON
+//! to `--session-report` so runs can be compared in CI.
+//!
+//! Counts and bytes are of the messages received, before `--sample`, `--max-rate` and other
+//! client side filters, across reconnects.
+
+use {
+    crate::{StatsKind, budget::Budget, exit::ErrorClass, format_thousands},
+    anyhow::Context,
+    indicatif::HumanBytes,
+    serde_json::{Value, json},
+    std::{
+        collections::BTreeMap,
+        fs,
+        path::Path,
+        sync::Mutex,
+        time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+    },
+    yellowstone_grpc_proto::prelude::subscribe_update::UpdateOneof,
+};
+
+#[derive(Default)]
+pub struct Session {
+    counters: Mutex<Counters>,
+    /// `--verify-encoding` results, written to `--verify-report`
+    #[cfg(feature = "verify-encoding")]
+    pub encoding: crate::verify::Report,
+}
+
+#[derive(Default)]
+struct Counters {
+    /// Set once a subscription starts, other actions have no report
+    started: Option<(Instant, SystemTime)>,
+    /// Messages and encoded bytes, by [`StatsKind::ALL`] index
+    kinds: [(u64, u64); StatsKind::ALL.len()],
+    slots: Option<(u64, u64)>,
+    /// Connection attempts, all but the first are reconnects
+    attempts: u64,
+    errors: BTreeMap<&'static str, u64>,
+    decoded: u64,
+    decode_time: Duration,
+    budget: Budget,
+    /// Why the session stopped on its own, once the budget is spent
+    stopped: Option<String>,
+}
+
+impl Session {
+    fn counters(&self) -> std::sync::MutexGuard<'_, Counters> {
+        self.counters.lock().expect("session lock poisoned")
+    }
+
+    pub fn start(&self, budget: Budget) {
+        let mut counters = self.counters();
+        counters.started = Some((Instant::now(), SystemTime::now()));
+        counters.budget = budget;
+    }
+
+    /// Why the budget is spent, `None` while it isn't or without a subscription
+    pub fn budget_spent(&self) -> Option<String> {
+        let mut counters = self.counters();
+        let (started_at, _) = counters.started?;
+        let (messages, bytes) = StatsKind::ALL
+            .iter()
+            .zip(counters.kinds)
+            .filter(|(kind, _)| **kind != StatsKind::PingPong)
+            .fold((0, 0), |(messages, bytes), (_, (count, size))| {
+                (messages + count, bytes + size)
+            });
+        let reason = counters.budget.spent(messages, bytes, started_at.elapsed())?;
+        counters.stopped = Some(reason.clone());
+        Some(reason)
+    }
+
+    /// Why an update of `slot` ends the session, `None` if it doesn't. Such an update isn't
+    /// recorded.
+    pub fn budget_past(&self, slot: Option<u64>) -> Option<String> {
+        let mut counters = self.counters();
+        let reason = counters.budget.past(slot)?;
+        counters.stopped = Some(reason.clone());
+        Some(reason)
+    }
+
+    /// When `--duration` is spent
+    pub fn deadline(&self) -> Option<Instant> {
+        let counters = self.counters();
+        Some(counters.started?.0 + counters.budget.duration?)
+    }
+
+    pub fn record_update(&self, kind: StatsKind, bytes: u64, slot: Option<u64>) {
+        let index = StatsKind::ALL
+            .iter()
+            .position(|value| *value == kind)
+            .expect("all kinds are counted");
+        let mut counters = self.counters();
+        counters.kinds[index].0 += 1;
+        counters.kinds[index].1 += bytes;
+        if let Some(slot) = slot {
+            counters.slots = Some(
+                counters
+                    .slots
+                    .map_or((slot, slot), |(min, max)| (min.min(slot), max.max(slot))),
+            );
+        }
+    }
+
+    pub fn record_decode(&self, decode: Duration) {
+        let mut counters = self.counters();
+        counters.decoded += 1;
+        counters.decode_time += decode;
+    }
+
+    pub fn record_attempt(&self) {
+        self.counters().attempts += 1;
+    }
+
+    pub fn record_error(&self, class: ErrorClass) {
+        *self.counters().errors.entry(class.name()).or_default() += 1;
+    }
+
+    /// The report, `None` if no subscription was started
+    pub fn report(&self) -> Option<Value> {
+        let counters = self.counters();
+        let (started_at, started) = counters.started?;
+        let kinds = StatsKind::ALL
+            .iter()
+            .zip(counters.kinds)
+            .map(|(kind, (count, bytes))| {
+                (
+                    kind.key().to_owned(),
+                    json!({ "count": count, "bytes": bytes }),
+                )
+            })
+            .collect::<serde_json::Map<_, _>>();
+        let (count, bytes) = counters.kinds.iter().fold((0, 0), |(count, bytes), kind| {
+            (count + kind.0, bytes + kind.1)
+        });
+        Some(json!({
+            "startedAt": started.duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64,
+            "durationSecs": started_at.elapsed().as_secs_f64(),
+            "updates": kinds,
+            "total": { "count": count, "bytes": bytes },
+            "minSlot": counters.slots.map(|(min, _)| min),
+            "maxSlot": counters.slots.map(|(_, max)| max),
+            "reconnects": counters.attempts.saturating_sub(1),
+            "errors": counters.errors,
+            "avgDecodeMs": (counters.decoded > 0).then(|| {
+                counters.decode_time.as_secs_f64() * 1000.0 / counters.decoded as f64
+            }),
+            "stopReason": counters.stopped,
+        }))
+    }
+
+    /// Print the report to stderr and write it to `path`, if a subscription was started
+    pub fn finish(&self, path: Option<&Path>) -> anyhow::Result<()> {
+        #[cfg(feature = "verify-encoding")]
+        self.encoding.finish()?;
+        let Some(report) = self.report() else {
+            return Ok(());
+        };
+        let counters = self.counters();
+        eprintln!("session summary");
+        eprintln!(
+            "  {:<26} {:.1}s",
+            "duration",
+            report["durationSecs"].as_f64().unwrap_or_default()
+        );
+        for (kind, (count, bytes)) in StatsKind::ALL.iter().zip(counters.kinds) {
+            if count > 0 {
+                eprintln!(
+                    "  {:<26} {:>12} updates {:>12}",
+                    kind.label(),
+                    format_thousands(count),
+                    HumanBytes(bytes).to_string(),
+                );
+            }
+        }
+        let (count, bytes) = (&report["total"]["count"], &report["total"]["bytes"]);
+        eprintln!(
+            "  {:<26} {:>12} updates {:>12}",
+            "total",
+            format_thousands(count.as_u64().unwrap_or_default()),
+            HumanBytes(bytes.as_u64().unwrap_or_default()).to_string(),
+        );
+        if let Some((min, max)) = counters.slots {
+            eprintln!("  {:<26} {min}..={max}", "slots");
+        }
+        eprintln!("  {:<26} {}", "reconnects", report["reconnects"]);
+        for (class, count) in &counters.errors {
+            eprintln!("  {:<26} {count}", format!("errors {class}"));
+        }
+        if let Some(decode) = report["avgDecodeMs"].as_f64() {
+            eprintln!("  {:<26} {decode:.3} ms", "avg decode");
+        }
+        if let Some(reason) = &counters.stopped {
+            eprintln!("  {:<26} {reason}", "stopped");
+        }
+        drop(counters);
+
+        if let Some(path) = path {
+            fs::write(path, serde_json::to_vec_pretty(&report)?)
+                .with_context(|| format!("failed to write {}", path.display()))?;
+        }
+        Ok(())
+    }
+}
+
+/// Slot of an update, `None` for pings and pongs
+pub const fn update_slot(update: &UpdateOneof) -> Option<u64> {
+    Some(match update {
+        UpdateOneof::Account(update) => update.slot,
+        UpdateOneof::Slot(update) => update.slot,
+        UpdateOneof::Transaction(update) => update.slot,
+        UpdateOneof::TransactionStatus(update) => update.slot,
+        UpdateOneof::Entry(update) => update.slot,
+        UpdateOneof::BlockMeta(update) => update.slot,
+        UpdateOneof::Block(update) => update.slot,
+        UpdateOneof::Ping(_) | UpdateOneof::Pong(_) => return None,
+    })
+}
@@ -0,0 +1,542 @@
+//! `--sink`: stream updates as JSON lines (the `--out-file` format) to another destination.
+//!
+//! Destinations implement [`Sink`] of the library and are registered with
+//! [`indexing::register_sink!`] under the scheme of their `--sink` value, `<scheme>:<target>`. A
+//! destination pulling in dependencies is registered behind a feature, like `--script` and
+//! `--transform` are, or by a crate linked into the binary.
+//!
+//! Several `--sink`s run side by side, each on its own thread behind a queue of `--sink-queue`
+//! updates, so a slow or failing destination doesn't hold up the others or the stream: once its
+//! queue is full its updates are dropped (`--sink-overflow drop`) or the stream waits for it
+//! (`block`). A delivery failing is retried `--sink-retries` times before the update is dropped
+//! with a sink failure.
+//! Queue depth, lag and counts of every sink are in `/metrics` and `/pressure`.
+//!
+//! `exec:<command>` writes to the stdin of a child process, so handlers can be written in any
+//! language. The command runs through `sh -c`. Writes block while the handler doesn't keep up.
+//! When the handler exits or crashes it is restarted, with a growing delay if it keeps failing
+//! right after starting, and the retries of the update that failed to be written go to the new
+//! process; lines still buffered in the pipe are lost.
+
+pub use indexing::sink::{DecodedUpdate, Registration, Sink};
+#[cfg(feature = "control-api")]
+use serde_json::{Value, json};
+use {
+    crate::{exit::SinkFailed, schema},
+    anyhow::Context,
+    clap::ValueEnum,
+    std::{
+        fmt,
+        process::Stdio,
+        sync::{
+            Arc,
+            atomic::{AtomicBool, AtomicU64, Ordering},
+            mpsc,
+        },
+        thread,
+        time::{Duration, Instant, SystemTime},
+    },
+    tokio::{
+        io::{AsyncWriteExt, BufWriter},
+        process::{Child, ChildStdin, Command},
+        runtime::Handle,
+        sync::Mutex,
+    },
+    tracing::{Instrument, info, warn},
+};
+
+/// A handler running at least this long before exiting is restarted right away
+const HEALTHY_AFTER: Duration = Duration::from_secs(10);
+const MAX_RESTART_DELAY: Duration = Duration::from_secs(30);
+
+indexing::register_sink!(Registration {
+    scheme: "exec",
+    usage: "exec:<command>",
+    open: |command| Ok(Box::new(ExecSink::spawn(command)?)),
+});
+
+/// A `--sink` value, opened when the subscription starts
+#[derive(Debug, Clone)]
+pub struct SinkSpec {
+    registration: &'static Registration,
+    target: String,
+}
+
+impl SinkSpec {
+    pub fn open(&self) -> anyhow::Result<Box<dyn Sink>> {
+        (self.registration.open)(&self.target)
+    }
+}
+
+impl fmt::Display for SinkSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.registration.scheme, self.target)
+    }
+}
+
+/// Parse `--sink`, `<scheme>:<target>` with a registered scheme
+pub fn parse_sink(value: &str) -> Result<SinkSpec, String> {
+    let expected = || {
+        let mut usages = indexing::sink::registrations()
+            .map(|registration| registration.usage)
+            .collect::<Vec<_>>();
+        usages.sort_unstable();
+        usages.join(", ")
+    };
+    let registration = value
+        .split_once(':')
+        .and_then(|(scheme, _)| {
+            indexing::sink::registrations().find(|registration| registration.scheme == scheme)
+        })
+        .ok_or_else(|| format!("unknown sink `{value}`, expected {}", expected()))?;
+    let target = &value[registration.scheme.len() + 1..];
+    if target.trim().is_empty() {
+        return Err(format!(
+            "`{}:` needs a target, e.g. {}",
+            registration.scheme, registration.usage
+        ));
+    }
+    Ok(SinkSpec {
+        registration,
+        target: target.to_owned(),
+    })
+}
+
+/// What a sink with a full queue does
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum Overflow {
+    /// Drop the update for this sink, the stream and the other sinks go on
+    #[default]
+    Drop,
+    /// Wait for the sink, backpressuring the stream
+    Block,
+}
+
+pub struct Options {
+    pub queue: usize,
+    pub overflow: Overflow,
+    pub retries: u32,
+}
+
+enum Message {
+    /// An update shared by the queues with its sequence number, and the span delivering it in,
+    /// to keep sink spans in the update's trace
+    Update(u64, Arc<DecodedUpdate>, tracing::Span),
+    Checkpoint(u64),
+}
+
+/// Counters of a sink, shared with its thread
+pub struct SinkStats {
+    name: String,
+    queued: AtomicU64,
+    delivered: AtomicU64,
+    dropped: AtomicU64,
+    failed: AtomicU64,
+    /// Delay between `created_at` and the delivery of the last update
+    lag_ms: AtomicU64,
+    /// Whether updates were dropped since the queue was last empty, to log once
+    overflowing: AtomicBool,
+    /// Sequence number of the last update delivered
+    acked: AtomicU64,
+    /// Sequence number of the first update dropped or failed, `u64::MAX` while none was lost
+    lost_at: AtomicU64,
+}
+
+impl SinkStats {
+    #[cfg(feature = "control-api")]
+    pub fn to_json(&self) -> Value {
+        json!({
+            "sink": self.name,
+            "queue_depth": self.queued.load(Ordering::Relaxed),
+            "lag_ms": self.lag_ms.load(Ordering::Relaxed),
+            "delivered": self.delivered.load(Ordering::Relaxed),
+            "dropped": self.dropped.load(Ordering::Relaxed),
+            "failed": self.failed.load(Ordering::Relaxed),
+        })
+    }
+
+    /// Prometheus samples, `(metric, help, value)`, labeled with the sink by the caller
+    #[cfg(feature = "control-api")]
+    pub fn samples(&self) -> [(&'static str, &'static str, f64); 5] {
+        [
+            (
+                "indexer_sink_queue_depth",
+                "Updates waiting in the queue of a sink",
+                self.queued.load(Ordering::Relaxed) as f64,
+            ),
+            (
+                "indexer_sink_lag_seconds",
+                "Delay between created_at and delivery of the last update of a sink",
+                self.lag_ms.load(Ordering::Relaxed) as f64 / 1000.0,
+            ),
+            (
+                "indexer_sink_delivered_total",
+                "Updates delivered to a sink",
+                self.delivered.load(Ordering::Relaxed) as f64,
+            ),
+            (
+                "indexer_sink_dropped_total",
+                "Updates dropped because the queue of a sink was full",
+                self.dropped.load(Ordering::Relaxed) as f64,
+            ),
+            (
+                "indexer_sink_failed_total",
+                "Updates dropped after failing to be delivered to a sink",
+                self.failed.load(Ordering::Relaxed) as f64,
+            ),
+        ]
+    }
+
+    #[cfg(feature = "control-api")]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Every update up to this sequence number was delivered
+    fn delivered_through(&self) -> u64 {
+        let lost_at = self.lost_at.load(Ordering::Relaxed);
+        self.acked
+            .load(Ordering::Relaxed)
+            .min(lost_at.saturating_sub(1))
+    }
+
+    fn lose(&self, sequence: u64) {
+        self.lost_at.fetch_min(sequence, Ordering::Relaxed);
+    }
+}
+
+struct Worker {
+    queue: Option<mpsc::SyncSender<Message>>,
+    stats: Arc<SinkStats>,
+    thread: Option<thread::JoinHandle<()>>,
+}
+
+/// The `--sink`s of a subscription, each delivering on its own thread
+pub struct Sinks {
+    workers: Vec<Worker>,
+    overflow: Overflow,
+    /// Sequence number of the last update queued
+    sequence: AtomicU64,
+}
+
+impl Sinks {
+    pub fn open(specs: &[SinkSpec], options: &Options) -> anyhow::Result<Self> {
+        let runtime = Handle::current();
+        let mut workers = vec![];
+        for spec in specs {
+            let sink = spec.open()?;
+            let stats = Arc::new(SinkStats {
+                name: spec.to_string(),
+                queued: AtomicU64::new(0),
+                delivered: AtomicU64::new(0),
+                dropped: AtomicU64::new(0),
+                failed: AtomicU64::new(0),
+                lag_ms: AtomicU64::new(0),
+                overflowing: AtomicBool::new(false),
+                acked: AtomicU64::new(0),
+                lost_at: AtomicU64::new(u64::MAX),
+            });
+            let (queue, messages) = mpsc::sync_channel(options.queue);
+            let thread = thread::Builder::new()
+                .name(format!("sink-{}", workers.len()))
+                .spawn({
+                    let stats = Arc::clone(&stats);
+                    let retries = options.retries;
+                    let runtime = runtime.clone();
+                    move || run(sink.as_ref(), &runtime, &messages, &stats, retries)
+                })
+                .context("failed to start a sink thread")?;
+            workers.push(Worker {
+                queue: Some(queue),
+                stats,
+                thread: Some(thread),
+            });
+        }
+        Ok(Self {
+            workers,
+            overflow: options.overflow,
+            sequence: AtomicU64::new(0),
+        })
+    }
+
+    pub fn stats(&self) -> Vec<Arc<SinkStats>> {
+        self.workers
+            .iter()
+            .map(|worker| Arc::clone(&worker.stats))
+            .collect()
+    }
+
+    /// Queue an update for every sink, failures and full queues only affect their sink
+    pub fn deliver(&self, update: DecodedUpdate) {
+        let sequence = self.sequence.fetch_add(1, Ordering::Relaxed) + 1;
+        let update = Arc::new(update);
+        let span = tracing::Span::current();
+        for worker in &self.workers {
+            let message = Message::Update(sequence, Arc::clone(&update), span.clone());
+            self.send(worker, message);
+        }
+    }
+
+    /// Sequence number of the last update queued
+    pub fn queued(&self) -> u64 {
+        self.sequence.load(Ordering::Relaxed)
+    }
+
+    /// Every update queued up to this sequence number was delivered by every sink. It stops
+    /// before the first update a sink dropped or failed to deliver.
+    pub fn delivered(&self) -> u64 {
+        self.workers
+            .iter()
+            .map(|worker| worker.stats.delivered_through())
+            .min()
+            .unwrap_or_else(|| self.queued())
+    }
+
+    /// Whether a sink dropped or failed to deliver an update
+    pub fn lost(&self) -> bool {
+        self.workers
+            .iter()
+            .any(|worker| worker.stats.lost_at.load(Ordering::Relaxed) != u64::MAX)
+    }
+
+    /// Queue a checkpoint after the updates queued so far
+    pub fn checkpoint(&self, slot: u64) {
+        for worker in &self.workers {
+            self.send(worker, Message::Checkpoint(slot));
+        }
+    }
+
+    /// Close the queues and wait for the sinks to deliver what's queued
+    pub fn close(&mut self) {
+        for worker in &mut self.workers {
+            worker.queue.take();
+        }
+        tokio::task::block_in_place(|| {
+            for worker in &mut self.workers {
+                if let Some(thread) = worker.thread.take()
+                    && thread.join().is_err()
+                {
+                    warn!("sink `{}` panicked", worker.stats.name);
+                }
+            }
+        });
+    }
+
+    fn send(&self, worker: &Worker, message: Message) {
+        let Some(queue) = &worker.queue else {
+            return;
+        };
+        let stats = &worker.stats;
+        let sequence = match &message {
+            Message::Update(sequence, ..) => Some(*sequence),
+            Message::Checkpoint(_) => None,
+        };
+        stats.queued.fetch_add(1, Ordering::Relaxed);
+        let sent = match self.overflow {
+            Overflow::Drop => queue.try_send(message).is_ok(),
+            // Blocking a runtime worker would stall the tasks scheduled on it
+            Overflow::Block => tokio::task::block_in_place(|| queue.send(message).is_ok()),
+        };
+        if sent {
+            return;
+        }
+        stats.queued.fetch_sub(1, Ordering::Relaxed);
+        stats.dropped.fetch_add(1, Ordering::Relaxed);
+        if let Some(sequence) = sequence {
+            stats.lose(sequence);
+        }
+        if !stats.overflowing.swap(true, Ordering::Relaxed) {
+            warn!(
+                "sink `{}` doesn't keep up, dropping its updates",
+                stats.name
+            );
+        }
+    }
+}
+
+impl Drop for Sinks {
+    fn drop(&mut self) {
+        self.close();
+    }
+}
+
+/// Deliver the queued updates of a sink, retrying failures
+fn run(
+    sink: &dyn Sink,
+    runtime: &Handle,
+    messages: &mpsc::Receiver<Message>,
+    stats: &SinkStats,
+    retries: u32,
+) {
+    for message in messages {
+        if stats.queued.fetch_sub(1, Ordering::Relaxed) == 1
+            && stats.overflowing.swap(false, Ordering::Relaxed)
+        {
+            info!(
+                "sink `{}` caught up, {} updates dropped so far",
+                stats.name,
+                stats.dropped.load(Ordering::Relaxed)
+            );
+        }
+        let (sequence, update, span) = match message {
+            Message::Update(sequence, update, span) => (sequence, update, span),
+            Message::Checkpoint(slot) => {
+                if let Err(error) = runtime.block_on(sink.checkpoint(slot)) {
+                    warn!("sink `{}` failed to checkpoint: {error:#}", stats.name);
+                }
+                continue;
+            }
+        };
+        let delivered = deliver(sink, &stats.name, &update, retries).instrument(span);
+        match runtime.block_on(delivered) {
+            Ok(()) => {
+                let lag = SystemTime::now()
+                    .duration_since(update.created_at)
+                    .unwrap_or_default();
+                stats
+                    .lag_ms
+                    .store(lag.as_millis() as u64, Ordering::Relaxed);
+                stats.delivered.fetch_add(1, Ordering::Relaxed);
+                stats.acked.store(sequence, Ordering::Relaxed);
+            }
+            Err(error) => {
+                warn!("{error:#}, dropping the update");
+                stats.failed.fetch_add(1, Ordering::Relaxed);
+                stats.lose(sequence);
+            }
+        }
+    }
+    if let Err(error) = runtime.block_on(sink.flush()) {
+        warn!("sink `{}` failed to flush: {error:#}", stats.name);
+    }
+}
+
+/// Deliver an update, retrying a failure `retries` times with a growing delay
+pub async fn deliver(
+    sink: &dyn Sink,
+    name: &str,
+    update: &DecodedUpdate,
+    retries: u32,
+) -> anyhow::Result<()> {
+    let mut attempt = 0;
+    loop {
+        let Err(error) = sink.deliver(update.clone()).await else {
+            return Ok(());
+        };
+        if attempt == retries {
+            return Err(error.context(SinkFailed {
+                sink: name.to_owned(),
+                attempts: attempt + 1,
+            }));
+        }
+        warn!("sink `{name}` failed to deliver, retrying: {error:#}");
+        tokio::time::sleep(Duration::from_millis(100 << attempt.min(8))).await;
+        attempt += 1;
+    }
+}
+
+pub struct ExecSink {
+    command: String,
+    handler: Mutex<Handler>,
+}
+
+/// The running handler of an [`ExecSink`]
+struct Handler {
+    child: Option<(Child, BufWriter<ChildStdin>)>,
+    started_at: Instant,
+    restart_delay: Duration,
+}
+
+impl ExecSink {
+    pub fn spawn(command: &str) -> anyhow::Result<Self> {
+        let mut handler = Handler {
+            child: None,
+            started_at: Instant::now(),
+            restart_delay: Duration::ZERO,
+        };
+        handler.start(command)?;
+        Ok(Self {
+            command: command.to_owned(),
+            handler: Mutex::new(handler),
+        })
+    }
+}
+
+impl Handler {
+    fn start(&mut self, command: &str) -> anyhow::Result<()> {
+        let mut child = Command::new("sh")
+            .arg("-c")
+            .arg(command)
+            .stdin(Stdio::piped())
+            .spawn()
+            .with_context(|| format!("failed to start sink `{command}`"))?;
+        let stdin = child.stdin.take().context("sink stdin is not piped")?;
+        info!(
+            "sink `{command}` started (pid {})",
+            child.id().unwrap_or_default()
+        );
+        self.child = Some((child, BufWriter::new(stdin)));
+        self.started_at = Instant::now();
+        Ok(())
+    }
+
+    /// Reap the exited handler and start it again
+    async fn restart(&mut self, command: &str) -> anyhow::Result<()> {
+        if let Some((mut child, stdin)) = self.child.take() {
+            drop(stdin);
+            let status = child.wait().await?;
+            warn!("sink `{command}` exited ({status}), restarting");
+        }
+        self.restart_delay = if self.started_at.elapsed() >= HEALTHY_AFTER {
+            Duration::ZERO
+        } else {
+            (self.restart_delay * 2).clamp(Duration::from_millis(100), MAX_RESTART_DELAY)
+        };
+        tokio::time::sleep(self.restart_delay).await;
+        self.start(command)
+    }
+}
+
+#[indexing::async_trait]
+impl Sink for ExecSink {
+    #[tracing::instrument(name = "sink", level = "debug", skip_all, fields(kind = update.kind))]
+    async fn deliver(&self, update: DecodedUpdate) -> anyhow::Result<()> {
+        let mut line = serde_json::to_vec(&schema::Line::new(
+            &update.kind,
+            update.created_at,
+            &update.filters,
+            &update.value,
+            update.traceparent.as_deref(),
+        ))?;
+        line.push(b'\n');
+        let mut handler = self.handler.lock().await;
+        // Started again when delivering after a flush
+        if handler.child.is_none() {
+            handler.start(&self.command)?;
+        }
+        let (_, stdin) = handler.child.as_mut().expect("sink is started");
+        let written = match stdin.write_all(&line).await {
+            Ok(()) => stdin.flush().await,
+            Err(error) => Err(error),
+        };
+        if let Err(error) = written {
+            // The retry goes to the restarted handler
+            handler.restart(&self.command).await?;
+            return Err(anyhow::Error::new(error)
+                .context(format!("failed to write to sink `{}`", self.command)));
+        }
+        Ok(())
+    }
+
+    /// Close stdin and let the handler finish the lines it received
+    async fn flush(&self) -> anyhow::Result<()> {
+        if let Some((mut child, stdin)) = self.handler.lock().await.child.take() {
+            drop(stdin);
+            child
+                .wait()
+                .await
+                .with_context(|| format!("failed to wait for sink `{}`", self.command))?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,287 @@
+//! `--skip-stats`: per-leader skip rates and block production gaps over epochs, for operators
+//! tracking which validators miss their leader slots.
+//!
+//! Slot statuses are correlated with block meta: a slot is produced when its block meta arrives,
+//! skipped when the block of a later slot skips over it, and dead too when its slot status says
+//! so. The block delay is the time from the first status of a slot (its first shred with
+//! interslot updates) to its block meta. Slots are attributed to leaders from the `--leaders`
+//! schedules.
+//!
+//! A `leaderSlots` event is written once the consecutive slots of a leader are settled, and a
+//! `skipStats` event per leader when an epoch ends, with its totals over the epoch: one row per
+//! epoch and leader. Totals of the current epoch are shown by `/metrics` and `/pressure`.
+
+use {
+    crate::leaders::Leaders,
+    serde_json::{Value, json},
+    std::{
+        collections::{BTreeMap, BTreeSet},
+        sync::Arc,
+        time::{Duration, Instant},
+    },
+    yellowstone_grpc_proto::prelude::{
+        SubscribeRequest, SubscribeRequestFilterBlocksMeta, SubscribeRequestFilterSlots,
+    },
+};
+
+/// Slots and block meta filter added to the request when they aren't subscribed already
+pub const FILTER: &str = "skip-stats";
+
+/// Slot statuses kept for slots without block meta yet, below the highest slot seen
+const PENDING_SLOTS: u64 = 1024;
+
+/// Slots produced and skipped, by a leader or the cluster
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Production {
+    pub produced: u64,
+    pub skipped: u64,
+    /// Skipped slots with a dead slot status
+    pub dead: u64,
+    /// Longest run of consecutive skipped slots
+    pub longest_gap: u64,
+    gap: u64,
+    delay_sum: Duration,
+    delays: u32,
+}
+
+impl Production {
+    fn produce(&mut self, delay: Option<Duration>) {
+        self.produced += 1;
+        self.gap = 0;
+        if let Some(delay) = delay {
+            self.delay_sum += delay;
+            self.delays += 1;
+        }
+    }
+
+    fn skip(&mut self, dead: bool) {
+        self.skipped += 1;
+        self.dead += u64::from(dead);
+        self.gap += 1;
+        self.longest_gap = self.longest_gap.max(self.gap);
+    }
+
+    fn merge(&mut self, other: &Self) {
+        self.produced += other.produced;
+        self.skipped += other.skipped;
+        self.dead += other.dead;
+        self.longest_gap = self.longest_gap.max(other.longest_gap);
+        self.delay_sum += other.delay_sum;
+        self.delays += other.delays;
+    }
+
+    pub fn skip_rate(&self) -> f64 {
+        match self.produced + self.skipped {
+            0 => 0.0,
+            slots => self.skipped as f64 / slots as f64,
+        }
+    }
+
+    /// Average time from the first slot status to the block meta of produced slots
+    pub fn block_delay(&self) -> Option<Duration> {
+        (self.delays > 0).then(|| self.delay_sum / self.delays)
+    }
+
+    /// Add the production fields to an event
+    fn fields(&self, mut event: Value) -> Value {
+        event["slots"] = (self.produced + self.skipped).into();
+        event["produced"] = self.produced.into();
+        event["skipped"] = self.skipped.into();
+        event["dead"] = self.dead.into();
+        event["skipRate"] = self.skip_rate().into();
+        event["longestGap"] = self.longest_gap.into();
+        event["blockDelayMs"] = self
+            .block_delay()
+            .map(|delay| delay.as_secs_f64() * 1000.0)
+            .into();
+        event
+    }
+}
+
+/// Production of the cluster over the current epoch
+#[cfg(feature = "control-api")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Snapshot {
+    pub epoch: u64,
+    /// Highest slot settled
+    pub slot: u64,
+    pub production: Production,
+}
+
+#[cfg(feature = "control-api")]
+impl Snapshot {
+    pub fn to_json(self) -> Value {
+        self.production.fields(json!({
+            "epoch": self.epoch,
+            "slot": self.slot,
+        }))
+    }
+}
+
+/// Consecutive slots of a leader
+struct Run {
+    leader: Option<String>,
+    first_slot: u64,
+    last_slot: u64,
+    production: Production,
+}
+
+pub struct SkipStats {
+    leaders: Arc<Leaders>,
+    /// First status received of slots without block meta yet
+    first_seen: BTreeMap<u64, Instant>,
+    dead: BTreeSet<u64>,
+    settled: Option<u64>,
+    epoch: Option<u64>,
+    run: Option<Run>,
+    epoch_leaders: BTreeMap<String, Production>,
+    cluster: Production,
+}
+
+impl SkipStats {
+    pub fn new(leaders: Arc<Leaders>) -> Self {
+        Self {
+            leaders,
+            first_seen: BTreeMap::new(),
+            dead: BTreeSet::new(),
+            settled: None,
+            epoch: None,
+            run: None,
+            epoch_leaders: BTreeMap::new(),
+            cluster: Production::default(),
+        }
+    }
+
+    /// Subscribe to slots with interslot updates and to block meta, if they aren't already
+    pub fn subscribe(request: &mut SubscribeRequest) {
+        if request.slots.is_empty() {
+            request.slots.insert(
+                FILTER.to_owned(),
+                SubscribeRequestFilterSlots {
+                    interslot_updates: Some(true),
+                    ..Default::default()
+                },
+            );
+        }
+        if request.blocks_meta.is_empty() {
+            request
+                .blocks_meta
+                .insert(FILTER.to_owned(), SubscribeRequestFilterBlocksMeta {});
+        }
+    }
+
+    /// Take a slot or block meta update, returns the `leaderSlots` and `skipStats` events due
+    pub fn observe(&mut self, kind: &str, value: &Value) -> Vec<(&'static str, Value)> {
+        let mut events = vec![];
+        let Some(slot) = value["slot"].as_u64() else {
+            return events;
+        };
+        if self.settled.is_some_and(|settled| slot <= settled) {
+            return events;
+        }
+        match kind {
+            "slot" => {
+                self.first_seen.entry(slot).or_insert_with(Instant::now);
+                if value["status"] == "SLOT_DEAD" {
+                    self.dead.insert(slot);
+                }
+                let oldest = slot.saturating_sub(PENDING_SLOTS);
+                self.first_seen = self.first_seen.split_off(&oldest);
+                self.dead = self.dead.split_off(&oldest);
+            }
+            "blockmeta" => {
+                // Slots between the last block seen and the parent were produced on this fork,
+                // their block meta was missed, e.g. before starting
+                let parent_slot = value["parentSlot"]
+                    .as_u64()
+                    .unwrap_or(slot.saturating_sub(1));
+                let from = self
+                    .settled
+                    .map_or(parent_slot, |settled| settled.max(parent_slot))
+                    + 1;
+                for skipped in from..slot {
+                    let dead = self.dead.contains(&skipped);
+                    self.settle(skipped, &mut events, |production| production.skip(dead));
+                }
+                let delay = self.first_seen.get(&slot).map(Instant::elapsed);
+                self.settle(slot, &mut events, |production| production.produce(delay));
+                self.settled = Some(slot);
+                self.first_seen = self.first_seen.split_off(&(slot + 1));
+                self.dead = self.dead.split_off(&(slot + 1));
+            }
+            _ => {}
+        }
+        events
+    }
+
+    #[cfg(feature = "control-api")]
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            epoch: self.epoch.unwrap_or_default(),
+            slot: self.settled.unwrap_or_default(),
+            production: self.cluster,
+        }
+    }
+
+    fn settle(
+        &mut self,
+        slot: u64,
+        events: &mut Vec<(&'static str, Value)>,
+        outcome: impl Fn(&mut Production),
+    ) {
+        let epoch = self.leaders.epoch(slot);
+        if self.epoch.is_some_and(|current| current != epoch) {
+            self.end_run(events);
+            self.end_epoch(events);
+        }
+        self.epoch = Some(epoch);
+
+        let leader = self.leaders.leader(slot);
+        if self.run.as_ref().is_some_and(|run| run.leader != leader) {
+            self.end_run(events);
+        }
+        let run = self.run.get_or_insert_with(|| Run {
+            leader,
+            first_slot: slot,
+            last_slot: slot,
+            production: Production::default(),
+        });
+        run.last_slot = slot;
+        outcome(&mut run.production);
+        outcome(&mut self.cluster);
+    }
+
+    fn end_run(&mut self, events: &mut Vec<(&'static str, Value)>) {
+        let Some(run) = self.run.take() else {
+            return;
+        };
+        if let Some(leader) = &run.leader {
+            self.epoch_leaders
+                .entry(leader.clone())
+                .or_default()
+                .merge(&run.production);
+        }
+        events.push((
+            "leaderSlots",
+            run.production.fields(json!({
+                "epoch": self.epoch,
+                "leader": run.leader,
+                "firstSlot": run.first_slot,
+                "lastSlot": run.last_slot,
+            })),
+        ));
+    }
+
+    fn end_epoch(&mut self, events: &mut Vec<(&'static str, Value)>) {
+        for (leader, production) in std::mem::take(&mut self.epoch_leaders) {
+            events.push((
+                "skipStats",
+                production.fields(json!({
+                    "epoch": self.epoch,
+                    "leader": leader,
+                })),
+            ));
+        }
+        self.cluster = Production::default();
+    }
+}
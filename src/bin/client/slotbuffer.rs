@@ -0,0 +1,93 @@
+//! `--commit-boundary`: hold back the updates of a slot until the slot reaches a commitment,
+//! then write them together, so consumers never see half of a slot or a slot that was
+//! abandoned on a minority fork.
+
+use {
+    crate::DecodedUpdate,
+    serde_json::Value,
+    std::collections::BTreeMap,
+    yellowstone_grpc_proto::{
+        geyser::SlotStatus,
+        prelude::{CommitmentLevel, SubscribeRequest, SubscribeRequestFilterSlots},
+    },
+};
+
+/// Slots filter added to the request to learn slot statuses
+pub const FILTER: &str = "commit-boundary";
+
+/// Buffered slots this far behind a committed slot are on an abandoned fork and dropped
+const ABANDONED_AFTER_SLOTS: u64 = 150;
+
+pub struct SlotBuffer<T = DecodedUpdate> {
+    status: &'static str,
+    slots: BTreeMap<u64, Vec<T>>,
+}
+
+impl<T> SlotBuffer<T> {
+    /// Buffer until `boundary`, subscribing to all slot statuses
+    pub fn new(boundary: CommitmentLevel, request: &mut SubscribeRequest) -> Self {
+        request.slots.insert(
+            FILTER.to_owned(),
+            SubscribeRequestFilterSlots {
+                filter_by_commitment: Some(false),
+                interslot_updates: Some(false),
+            },
+        );
+        let status = match boundary {
+            CommitmentLevel::Processed => SlotStatus::SlotProcessed,
+            CommitmentLevel::Confirmed => SlotStatus::SlotConfirmed,
+            CommitmentLevel::Finalized => SlotStatus::SlotFinalized,
+        };
+        Self {
+            status: status.as_str_name(),
+            slots: BTreeMap::new(),
+        }
+    }
+
+    /// Hold an item until its slot reaches the boundary
+    pub fn hold(&mut self, slot: u64, item: T) {
+        self.slots.entry(slot).or_default().push(item);
+    }
+
+    /// Take a slot status, returns the items of the slot if it reached the boundary
+    pub fn status(&mut self, slot: u64, status: Option<&str>) -> Vec<T> {
+        if status == Some(self.status) {
+            let ready = self.slots.remove(&slot).unwrap_or_default();
+            let abandoned = slot.saturating_sub(ABANDONED_AFTER_SLOTS);
+            self.slots = self.slots.split_off(&abandoned);
+            ready
+        } else {
+            if status == Some(SlotStatus::SlotDead.as_str_name()) {
+                self.slots.remove(&slot);
+            }
+            vec![]
+        }
+    }
+}
+
+impl SlotBuffer {
+    /// Take an update, returns the updates ready to be written in order
+    pub fn push(&mut self, update: DecodedUpdate) -> Vec<DecodedUpdate> {
+        let Some(slot) = update.value.get("slot").and_then(Value::as_u64) else {
+            return vec![update];
+        };
+        if update.kind != "slot" {
+            self.hold(slot, update);
+            return vec![];
+        }
+
+        let status = update.value.get("status").and_then(Value::as_str);
+        let mut ready = self.status(slot, status);
+        ready.extend(strip(update));
+        ready
+    }
+}
+
+/// Remove the slots filter added for the boundary, `None` if it was the only filter matched
+pub fn strip(mut update: DecodedUpdate) -> Option<DecodedUpdate> {
+    if update.kind != "slot" || !update.filters.iter().any(|filter| filter == FILTER) {
+        return Some(update);
+    }
+    update.filters.retain(|filter| filter != FILTER);
+    (!update.filters.is_empty()).then_some(update)
+}
@@ -0,0 +1,238 @@
+//! `--snapshot`: write the current state of the subscribed accounts before streaming, so the
+//! output starts from complete state instead of only deltas.
+
+use {
+    crate::{
+        Envelope, create_pretty_account, envelope, print_update, rpc, schema,
+        state::{StateStore, Upsert},
+    },
+    anyhow::Context,
+    base64::{Engine, engine::general_purpose::STANDARD},
+    tracing::info,
+    serde_json::{Value, json},
+    std::{collections::HashSet, time::SystemTime},
+    yellowstone_grpc_proto::prelude::{
+        CommitmentLevel, SubscribeRequest, SubscribeRequestAccountsDataSlice,
+        SubscribeRequestFilterAccountsFilter, SubscribeUpdateAccountInfo,
+        subscribe_request_filter_accounts_filter::Filter as AccountsFilterOneof,
+        subscribe_request_filter_accounts_filter_lamports::Cmp as AccountsFilterLamports,
+        subscribe_request_filter_accounts_filter_memcmp::Data as AccountsFilterMemcmpOneof,
+    },
+};
+
+/// `getMultipleAccounts` limit per request
+const MULTIPLE_ACCOUNTS_CHUNK: usize = 100;
+
+/// SPL Token account size and offset of its `state` byte
+const TOKEN_ACCOUNT_LEN: usize = 165;
+const TOKEN_ACCOUNT_STATE_OFFSET: usize = 108;
+
+/// Offset and decoded bytes of a memcmp filter, `None` for other filter types
+pub type Memcmp = Option<(usize, Vec<u8>)>;
+
+pub async fn bootstrap(
+    rpc: &rpc::RpcClient,
+    request: &SubscribeRequest,
+    commitment: CommitmentLevel,
+    state: Option<&StateStore>,
+    envelope: Option<Envelope>,
+) -> anyhow::Result<()> {
+    let commitment = commitment.as_str_name().to_lowercase();
+    let mut written = 0;
+    // An account can be both listed and owned by a listed program, write it once
+    let mut seen = HashSet::new();
+    for filter in request.accounts.values() {
+        let filters = memcmp_bytes(&filter.filters)?;
+
+        for chunk in filter.account.chunks(MULTIPLE_ACCOUNTS_CHUNK) {
+            let result = rpc
+                .call(
+                    "getMultipleAccounts",
+                    json!([chunk, {"encoding": "base64", "commitment": commitment}]),
+                )
+                .await?;
+            let slot = context_slot(&result)?;
+            let accounts = result["value"]
+                .as_array()
+                .context("invalid getMultipleAccounts response")?;
+            for (pubkey, account) in chunk.iter().zip(accounts) {
+                // Accounts which don't exist (yet) are null
+                if account.is_null() || !seen.insert(pubkey.clone()) {
+                    continue;
+                }
+                let account = rpc::parse_account(pubkey, account)?;
+                written += write_account(
+                    account,
+                    slot,
+                    &filter.filters,
+                    &filters,
+                    request,
+                    state,
+                    envelope,
+                )?;
+            }
+        }
+
+        for owner in &filter.owner {
+            let result = rpc
+                .call(
+                    "getProgramAccounts",
+                    json!([owner, {
+                        "encoding": "base64",
+                        "commitment": commitment,
+                        "withContext": true,
+                        "filters": rpc_filters(&filter.filters, &filters),
+                    }]),
+                )
+                .await?;
+            let slot = context_slot(&result)?;
+            for item in result["value"]
+                .as_array()
+                .context("invalid getProgramAccounts response")?
+            {
+                let pubkey = item["pubkey"].as_str().context("no pubkey in program account")?;
+                if !seen.insert(pubkey.to_owned()) {
+                    continue;
+                }
+                let account = rpc::parse_account(pubkey, &item["account"])?;
+                written += write_account(
+                    account,
+                    slot,
+                    &filter.filters,
+                    &filters,
+                    request,
+                    state,
+                    envelope,
+                )?;
+            }
+        }
+    }
+    info!("snapshot: wrote {written} accounts");
+    Ok(())
+}
+
+fn context_slot(result: &Value) -> anyhow::Result<u64> {
+    result["context"]["slot"]
+        .as_u64()
+        .context("no context slot in RPC response")
+}
+
+/// Decode the memcmp data of every filter
+pub fn memcmp_bytes(
+    filters: &[SubscribeRequestFilterAccountsFilter],
+) -> anyhow::Result<Vec<Memcmp>> {
+    filters
+        .iter()
+        .map(|filter| {
+            let Some(AccountsFilterOneof::Memcmp(memcmp)) = &filter.filter else {
+                return Ok(None);
+            };
+            let bytes = match &memcmp.data {
+                Some(AccountsFilterMemcmpOneof::Bytes(bytes)) => bytes.clone(),
+                Some(AccountsFilterMemcmpOneof::Base58(data)) => {
+                    bs58::decode(data).into_vec().context("invalid memcmp base58")?
+                }
+                Some(AccountsFilterMemcmpOneof::Base64(data)) => {
+                    STANDARD.decode(data).context("invalid memcmp base64")?
+                }
+                None => anyhow::bail!("memcmp filter without data"),
+            };
+            Ok(Some((memcmp.offset as usize, bytes)))
+        })
+        .collect()
+}
+
+/// Filters `getProgramAccounts` can apply server-side, the rest are checked locally
+fn rpc_filters(
+    filters: &[SubscribeRequestFilterAccountsFilter],
+    memcmp: &[Memcmp],
+) -> Vec<Value> {
+    filters
+        .iter()
+        .zip(memcmp)
+        .filter_map(|(filter, memcmp)| match (&filter.filter, memcmp) {
+            (_, Some((offset, bytes))) => Some(json!({"memcmp": {
+                "offset": offset,
+                "bytes": STANDARD.encode(bytes),
+                "encoding": "base64",
+            }})),
+            (Some(AccountsFilterOneof::Datasize(size)), _) => Some(json!({"dataSize": size})),
+            (Some(AccountsFilterOneof::TokenAccountState(true)), _) => {
+                Some(json!({"dataSize": TOKEN_ACCOUNT_LEN}))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// Same semantics as the Geyser accounts filters: every filter has to match
+fn matches(
+    account: &SubscribeUpdateAccountInfo,
+    filters: &[SubscribeRequestFilterAccountsFilter],
+    memcmp: &[Memcmp],
+) -> bool {
+    filters.iter().zip(memcmp).all(|(filter, memcmp)| {
+        if let Some((offset, bytes)) = memcmp {
+            return account.data.get(*offset..offset + bytes.len()) == Some(bytes.as_slice());
+        }
+        match &filter.filter {
+            Some(AccountsFilterOneof::Datasize(size)) => account.data.len() as u64 == *size,
+            Some(AccountsFilterOneof::TokenAccountState(true)) => {
+                account.data.len() == TOKEN_ACCOUNT_LEN
+                    && account.data[TOKEN_ACCOUNT_STATE_OFFSET] != 0
+            }
+            Some(AccountsFilterOneof::Lamports(lamports)) => match lamports.cmp {
+                Some(AccountsFilterLamports::Eq(value)) => account.lamports == value,
+                Some(AccountsFilterLamports::Ne(value)) => account.lamports != value,
+                Some(AccountsFilterLamports::Lt(value)) => account.lamports < value,
+                Some(AccountsFilterLamports::Gt(value)) => account.lamports > value,
+                None => true,
+            },
+            _ => true,
+        }
+    })
+}
+
+fn slice_data(data: &[u8], slices: &[SubscribeRequestAccountsDataSlice]) -> Vec<u8> {
+    slices
+        .iter()
+        .flat_map(|slice| {
+            let start = (slice.offset as usize).min(data.len());
+            let end = (start + slice.length as usize).min(data.len());
+            &data[start..end]
+        })
+        .copied()
+        .collect()
+}
+
+fn write_account(
+    mut account: SubscribeUpdateAccountInfo,
+    slot: u64,
+    filters: &[SubscribeRequestFilterAccountsFilter],
+    memcmp: &[Memcmp],
+    request: &SubscribeRequest,
+    state: Option<&StateStore>,
+    envelope: Option<Envelope>,
+) -> anyhow::Result<usize> {
+    if !matches(&account, filters, memcmp) {
+        return Ok(0);
+    }
+    if !request.accounts_data_slice.is_empty() {
+        account.data = slice_data(&account.data, &request.accounts_data_slice);
+    }
+    let value = serde_json::to_value(schema::Account {
+        account: create_pretty_account(account)?,
+        is_startup: true,
+        slot,
+    })?;
+    let upsert = state.map(|state| state.upsert(&value));
+    if envelope == Some(Envelope::Debezium) {
+        if let Some(Upsert::Applied { before }) = upsert {
+            let event = envelope::debezium(before, value, SystemTime::now(), true);
+            envelope::print_envelope(&event);
+        }
+        return Ok(1);
+    }
+    print_update("account", SystemTime::now(), &["snapshot".to_owned()], value);
+    Ok(1)
+}
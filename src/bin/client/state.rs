@@ -0,0 +1,125 @@
+//! Latest version of every streamed account, served by the control API on `/account/<pubkey>`.
+
+use {
+    serde_json::Value,
+    std::{collections::HashMap, sync::RwLock},
+};
+
+/// Rough per-account overhead on top of its data: map entry, keys and JSON fields
+const ACCOUNT_OVERHEAD: u64 = 512;
+
+struct StoredAccount {
+    slot: u64,
+    write_version: u64,
+    value: Value,
+}
+
+impl StoredAccount {
+    fn size(&self) -> u64 {
+        let data = self.value.get("data").and_then(Value::as_str).map_or(0, str::len);
+        data as u64 + ACCOUNT_OVERHEAD
+    }
+}
+
+/// Result of [`StateStore::upsert`]
+pub enum Upsert {
+    /// A newer version is stored already
+    Stale,
+    /// Stored, with the version it replaced
+    Applied { before: Option<Value> },
+}
+
+#[derive(Default)]
+struct Accounts {
+    map: HashMap<String, StoredAccount>,
+    /// Approximate memory held by `map`
+    bytes: u64,
+}
+
+#[derive(Default)]
+pub struct StateStore {
+    accounts: RwLock<Accounts>,
+    /// Memory budget, accounts updated in the oldest slots are evicted above it
+    budget: Option<u64>,
+}
+
+impl StateStore {
+    pub fn new(budget: Option<u64>) -> Self {
+        Self {
+            accounts: RwLock::default(),
+            budget,
+        }
+    }
+
+    /// Store a decoded account update unless a newer version (by slot, then write version)
+    /// is already stored
+    pub fn upsert(&self, value: &Value) -> Upsert {
+        let Some(pubkey) = value.get("pubkey").and_then(Value::as_str) else {
+            return Upsert::Stale;
+        };
+        let slot = value.get("slot").and_then(Value::as_u64).unwrap_or_default();
+        let write_version = value
+            .get("writeVersion")
+            .and_then(Value::as_u64)
+            .unwrap_or_default();
+
+        let mut accounts = self.accounts.write().expect("state lock poisoned");
+        if let Some(stored) = accounts.map.get(pubkey)
+            && (stored.slot, stored.write_version) > (slot, write_version)
+        {
+            return Upsert::Stale;
+        }
+        let stored = StoredAccount {
+            slot,
+            write_version,
+            value: value.clone(),
+        };
+        accounts.bytes += stored.size();
+        let before = accounts.map.insert(pubkey.to_owned(), stored);
+        if let Some(before) = &before {
+            accounts.bytes -= before.size();
+        }
+        if let Some(budget) = self.budget
+            && accounts.bytes > budget
+        {
+            evict(&mut accounts, budget / 10 * 9);
+        }
+        Upsert::Applied {
+            before: before.map(|stored| stored.value),
+        }
+    }
+
+    #[cfg(feature = "control-api")]
+    pub fn get(&self, pubkey: &str) -> Option<Value> {
+        let accounts = self.accounts.read().expect("state lock poisoned");
+        accounts.map.get(pubkey).map(|stored| stored.value.clone())
+    }
+
+    #[cfg(feature = "control-api")]
+    pub fn len(&self) -> usize {
+        self.accounts.read().expect("state lock poisoned").map.len()
+    }
+
+    #[cfg(feature = "control-api")]
+    pub fn bytes(&self) -> u64 {
+        self.accounts.read().expect("state lock poisoned").bytes
+    }
+}
+
+/// Drop the accounts updated longest ago until `target` bytes are held. Evicting a tenth of
+/// the budget at once keeps the sort off the hot path.
+fn evict(accounts: &mut Accounts, target: u64) {
+    let mut by_slot = accounts
+        .map
+        .iter()
+        .map(|(pubkey, stored)| (stored.slot, stored.size(), pubkey.clone()))
+        .collect::<Vec<_>>();
+    by_slot.sort_unstable_by_key(|(slot, _, _)| *slot);
+    for (_, size, pubkey) in by_slot {
+        if accounts.bytes <= target {
+            break;
+        }
+        accounts.map.remove(&pubkey);
+        accounts.bytes -= size;
+    }
+}
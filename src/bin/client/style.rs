@@ -0,0 +1,73 @@
+//! `--color`, `--quiet` and `--plain`: how updates and query results are printed, for log
+//! scrapers and terminals without colors or emoji.
+//!
+//! - `--color` colors the headers and field names, `auto` only on a terminal (and without
+//!   `NO_COLOR`); it also applies to progress bars
+//! - `--quiet` drops the configuration banner and the emoji
+//! - `--plain` prints every update on one line, `<timestamp> <kind> [<filters>] <json>`, and
+//!   query results as bare `key: value` lines, without emoji or rulers
+//!
+//! The style is set once from the arguments and read by the print functions of every action.
+
+use {
+    clap::ValueEnum,
+    console::{StyledObject, style},
+    std::sync::OnceLock,
+};
+
+static STYLE: OnceLock<Style> = OnceLock::new();
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum ColorChoice {
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Style {
+    pub quiet: bool,
+    pub plain: bool,
+}
+
+/// Set the style of the process, the first call wins
+pub fn init(color: ColorChoice, quiet: bool, plain: bool) {
+    match color {
+        ColorChoice::Auto if std::env::var_os("NO_COLOR").is_some() => {
+            console::set_colors_enabled(false);
+        }
+        ColorChoice::Auto => {}
+        ColorChoice::Always => console::set_colors_enabled(true),
+        ColorChoice::Never => console::set_colors_enabled(false),
+    }
+    let _ = STYLE.set(Style { quiet, plain });
+}
+
+pub fn get() -> Style {
+    STYLE.get().copied().unwrap_or_default()
+}
+
+impl Style {
+    /// `icon` followed by a space, nothing when quiet or plain
+    pub fn icon(self, icon: &str) -> String {
+        if self.quiet || self.plain {
+            String::new()
+        } else {
+            format!("{icon} ")
+        }
+    }
+
+    /// Whether the configuration banner and tips are printed
+    pub const fn banner(self) -> bool {
+        !self.quiet && !self.plain
+    }
+
+    pub fn header<D>(self, text: D) -> StyledObject<D> {
+        style(text).bold().cyan()
+    }
+
+    pub fn key<D>(self, text: D) -> StyledObject<D> {
+        style(text).green()
+    }
+}
@@ -0,0 +1,218 @@
+//! `sync`: backfill transactions over RPC from `--from-slot` up to the tip, then hand off to
+//! the gRPC stream replaying from the slot after the last backfilled one.
+
+use {
+    crate::{computebudget::{self, ComputeBudget}, print_update, rpc},
+    anyhow::Context,
+    tracing::{info, warn},
+    serde_json::{Value, json},
+    solana_pubkey::Pubkey,
+    solana_transaction_status::EncodedTransaction,
+    std::{
+        collections::HashSet,
+        time::{Duration, SystemTime, UNIX_EPOCH},
+    },
+    yellowstone_grpc_proto::prelude::{
+        CommitmentLevel, SubscribeRequest, SubscribeRequestFilterTransactions,
+    },
+};
+
+/// `getBlocks` limit on the slot range per request
+const BLOCKS_RANGE: u64 = 500_000;
+
+pub const VOTE_PROGRAM: Pubkey = Pubkey::from_str_const("Vote111111111111111111111111111111111111111");
+
+/// Write the transactions of every block from `from_slot` up to the tip, following the tip
+/// until a pass finds no new blocks. Returns the last backfilled slot.
+pub async fn backfill(
+    rpc: &rpc::RpcClient,
+    request: &SubscribeRequest,
+    from_slot: u64,
+    commitment: CommitmentLevel,
+) -> anyhow::Result<u64> {
+    if !request.slots.is_empty()
+        || !request.entry.is_empty()
+        || !request.blocks.is_empty()
+        || !request.blocks_meta.is_empty()
+    {
+        warn!("sync: only transactions are backfilled, other updates start at the handoff slot");
+    }
+    // Blocks are only served from confirmed on
+    let commitment = match commitment {
+        CommitmentLevel::Processed => CommitmentLevel::Confirmed,
+        commitment => commitment,
+    }
+    .as_str_name()
+    .to_lowercase();
+
+    let mut start = from_slot;
+    let mut blocks = 0;
+    loop {
+        let tip = rpc
+            .call("getSlot", json!([{"commitment": commitment}]))
+            .await?
+            .as_u64()
+            .context("invalid getSlot response")?;
+        if tip < start {
+            break;
+        }
+        let end = tip.min(start + BLOCKS_RANGE - 1);
+        let slots = rpc
+            .call("getBlocks", json!([start, end, {"commitment": commitment}]))
+            .await?;
+        for slot in slots.as_array().context("invalid getBlocks response")? {
+            let slot = slot.as_u64().context("invalid slot in getBlocks response")?;
+            write_block(rpc, request, slot, &commitment).await?;
+            blocks += 1;
+        }
+        info!("sync: backfilled slots {start}..={end}, {blocks} blocks");
+        start = end + 1;
+    }
+    let handoff = start.saturating_sub(1);
+    info!("sync: caught up at slot {handoff}, following the stream from slot {start}");
+    Ok(handoff)
+}
+
+async fn write_block(
+    rpc: &rpc::RpcClient,
+    request: &SubscribeRequest,
+    slot: u64,
+    commitment: &str,
+) -> anyhow::Result<()> {
+    if request.transactions.is_empty() && request.transactions_status.is_empty() {
+        return Ok(());
+    }
+    let block = rpc
+        .call(
+            "getBlock",
+            json!([slot, {
+                "encoding": "base64",
+                "transactionDetails": "full",
+                "maxSupportedTransactionVersion": 0,
+                "rewards": false,
+                "commitment": commitment,
+            }]),
+        )
+        .await?;
+    let created_at = block["blockTime"]
+        .as_u64()
+        .map_or_else(SystemTime::now, |secs| {
+            UNIX_EPOCH + Duration::from_secs(secs)
+        });
+
+    let transactions = block["transactions"].as_array().map_or(&[][..], Vec::as_slice);
+    for (index, item) in transactions.iter().enumerate() {
+        let tx = BackfillTransaction::parse(item)
+            .with_context(|| format!("invalid transaction {index} in block {slot}"))?;
+
+        let filters = tx.matching(&request.transactions);
+        if !filters.is_empty() {
+            let value = json!({
+                "signature": tx.signature,
+                "isVote": tx.is_vote,
+                "index": index,
+                "cuLimit": tx.budget.limit,
+                "cuPriceMicroLamports": tx.budget.price,
+                "tx": item,
+                "slot": slot,
+            });
+            print_update("transaction", created_at, &filters, value);
+        }
+
+        let filters = tx.matching(&request.transactions_status);
+        if !filters.is_empty() {
+            let value = json!({
+                "slot": slot,
+                "signature": tx.signature,
+                "isVote": tx.is_vote,
+                "index": index,
+                "err": item["meta"]["err"],
+            });
+            print_update("transactionStatus", created_at, &filters, value);
+        }
+    }
+    Ok(())
+}
+
+/// Fields of an RPC block transaction the Geyser transaction filters look at
+struct BackfillTransaction {
+    signature: String,
+    is_vote: bool,
+    failed: bool,
+    accounts: HashSet<String>,
+    budget: ComputeBudget,
+}
+
+impl BackfillTransaction {
+    fn parse(item: &Value) -> anyhow::Result<Self> {
+        let tx = serde_json::from_value::<EncodedTransaction>(item["transaction"].clone())?
+            .decode()
+            .context("failed to decode transaction")?;
+        let keys = tx.message.static_account_keys();
+        let signature = tx.signatures.first().context("unsigned transaction")?;
+
+        // Same rule as the validator's simple vote check: a legacy message with up to two
+        // signatures and a single vote instruction
+        let instructions = tx.message.instructions();
+        let is_vote = tx.signatures.len() < 3
+            && tx.message.address_table_lookups().is_none()
+            && instructions.len() == 1
+            && keys.get(instructions[0].program_id_index as usize) == Some(&VOTE_PROGRAM);
+        let budget = computebudget::parse(instructions.iter().filter_map(|ix| {
+            let program = keys.get(ix.program_id_index as usize)?;
+            Some((program.as_ref(), ix.data.as_slice()))
+        }));
+
+        let mut accounts = keys.iter().map(Pubkey::to_string).collect::<HashSet<_>>();
+        for loaded in ["writable", "readonly"] {
+            accounts.extend(
+                item["meta"]["loadedAddresses"][loaded]
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .filter_map(Value::as_str)
+                    .map(str::to_owned),
+            );
+        }
+
+        Ok(Self {
+            signature: signature.to_string(),
+            is_vote,
+            failed: !item["meta"]["err"].is_null(),
+            accounts,
+            budget,
+        })
+    }
+
+    /// Names of the filters matching the transaction, with the Geyser plugin semantics
+    fn matching<'a>(
+        &self,
+        filters: impl IntoIterator<Item = (&'a String, &'a SubscribeRequestFilterTransactions)>,
+    ) -> Vec<String> {
+        filters
+            .into_iter()
+            .filter(|(_, filter)| {
+                filter.vote.is_none_or(|vote| vote == self.is_vote)
+                    && filter.failed.is_none_or(|failed| failed == self.failed)
+                    && filter
+                        .signature
+                        .as_ref()
+                        .is_none_or(|signature| *signature == self.signature)
+                    && (filter.account_include.is_empty()
+                        || filter
+                            .account_include
+                            .iter()
+                            .any(|account| self.accounts.contains(account)))
+                    && !filter
+                        .account_exclude
+                        .iter()
+                        .any(|account| self.accounts.contains(account))
+                    && filter
+                        .account_required
+                        .iter()
+                        .all(|account| self.accounts.contains(account))
+            })
+            .map(|(name, _)| name.clone())
+            .collect()
+    }
+}
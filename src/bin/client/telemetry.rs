@@ -0,0 +1,100 @@
+//! Logs and spans through `tracing`.
+//!
+//! Logs are filtered with `RUST_LOG` (default `info`) and written to stderr, or to `--log-file`.
+//! Connecting, subscribing, decoding and sinking run in spans (`connect`, `subscribe`, `decode`,
+//! `sink`), decode and sink spans at debug level as there is one per update. With the `otlp`
+//! feature, `--otlp-endpoint` exports the spans selected by `--otlp-filter` over OTLP/gRPC, e.g.
+//! to Jaeger or Tempo; the service name is `OTEL_SERVICE_NAME`, `solana-indexer` by default.
+
+use {
+    crate::{Args, daemon::LogFile},
+    tracing_subscriber::{
+        EnvFilter, Layer, Registry, layer::SubscriberExt, util::SubscriberInitExt,
+    },
+};
+
+type BoxedLayer = Box<dyn Layer<Registry> + Send + Sync>;
+
+/// Flushes exported spans at exit
+pub struct Telemetry {
+    #[cfg(feature = "otlp")]
+    provider: Option<opentelemetry_sdk::trace::SdkTracerProvider>,
+}
+
+/// Install the subscriber, must run inside the runtime for the OTLP exporter
+pub fn init(args: &Args, log_file: Option<LogFile>) -> anyhow::Result<Telemetry> {
+    let (otlp, telemetry) = otlp_layer(args)?;
+    let logs = tracing_subscriber::fmt::layer();
+    let logs = match log_file {
+        Some(log_file) => logs
+            .with_ansi(false)
+            .with_writer(move || log_file.clone())
+            .boxed(),
+        None => logs
+            .with_ansi(console::colors_enabled_stderr())
+            .with_writer(std::io::stderr)
+            .boxed(),
+    };
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
+    tracing_subscriber::registry()
+        .with(otlp)
+        .with(logs.with_filter(filter))
+        .try_init()?;
+    Ok(telemetry)
+}
+
+#[cfg(feature = "otlp")]
+fn otlp_layer(args: &Args) -> anyhow::Result<(Option<BoxedLayer>, Telemetry)> {
+    use {
+        opentelemetry::trace::TracerProvider,
+        opentelemetry_otlp::WithExportConfig,
+        opentelemetry_sdk::{Resource, trace::SdkTracerProvider},
+    };
+
+    let Some(endpoint) = &args.otlp_endpoint else {
+        return Ok((None, Telemetry { provider: None }));
+    };
+    let exporter = opentelemetry_otlp::SpanExporter::builder()
+        .with_tonic()
+        .with_endpoint(endpoint)
+        .build()?;
+    let service_name =
+        std::env::var("OTEL_SERVICE_NAME").unwrap_or_else(|_| "solana-indexer".to_owned());
+    let provider = SdkTracerProvider::builder()
+        .with_batch_exporter(exporter)
+        .with_resource(Resource::builder().with_service_name(service_name).build())
+        .build();
+    let layer = tracing_opentelemetry::layer()
+        .with_tracer(provider.tracer("solana-indexer"))
+        .with_filter(EnvFilter::try_new(&args.otlp_filter)?)
+        .boxed();
+    Ok((
+        Some(layer),
+        Telemetry {
+            provider: Some(provider),
+        },
+    ))
+}
+
+#[cfg(not(feature = "otlp"))]
+fn otlp_layer(args: &Args) -> anyhow::Result<(Option<BoxedLayer>, Telemetry)> {
+    anyhow::ensure!(
+        args.otlp_endpoint.is_none(),
+        "--otlp-endpoint requires the `otlp` feature"
+    );
+    Ok((None, Telemetry {}))
+}
+
+impl Telemetry {
+    /// Export the remaining spans
+    pub async fn shutdown(self) {
+        #[cfg(feature = "otlp")]
+        if let Some(provider) = self.provider {
+            // Blocks until the batch exporter has flushed
+            let result = tokio::task::spawn_blocking(move || provider.shutdown()).await;
+            if let Ok(Err(error)) = result {
+                eprintln!("failed to export spans: {error}");
+            }
+        }
+    }
+}
@@ -0,0 +1,171 @@
+//! `--sample` and `--max-rate`: drop updates per type before they are decoded, to tap a
+//! firehose subscription without decoding and writing all of it.
+
+use {
+    crate::StatsKind,
+    anyhow::Context,
+    tracing::info,
+    std::{collections::HashMap, time::Instant},
+};
+
+/// Parse `--sample`, format: `type=fraction`, e.g. `accounts=0.1`
+pub fn parse_sample(value: &str) -> anyhow::Result<(StatsKind, f64)> {
+    let (kind, fraction) = parse_kind(value)?;
+    let fraction = fraction
+        .parse::<f64>()
+        .ok()
+        .filter(|fraction| (0.0..=1.0).contains(fraction))
+        .with_context(|| format!("invalid sample fraction `{fraction}`, expected 0..=1"))?;
+    Ok((kind, fraction))
+}
+
+/// Parse `--max-rate`, format: `type=count/s`, e.g. `transactions=500/s`
+pub fn parse_max_rate(value: &str) -> anyhow::Result<(StatsKind, f64)> {
+    let (kind, rate) = parse_kind(value)?;
+    let rate = rate
+        .strip_suffix("/s")
+        .unwrap_or(rate)
+        .parse::<f64>()
+        .ok()
+        .filter(|rate| *rate > 0.0)
+        .with_context(|| format!("invalid rate `{rate}`, expected updates per second"))?;
+    Ok((kind, rate))
+}
+
+fn parse_kind(value: &str) -> anyhow::Result<(StatsKind, &str)> {
+    let (kind, rest) = value
+        .split_once('=')
+        .with_context(|| format!("invalid `{value}`, expected `type=value`"))?;
+    let kind = StatsKind::from_key(kind).with_context(|| {
+        format!("unknown update type `{kind}`, expected accounts, slots, transactions, transactions_status, entries, blocks_meta or blocks")
+    })?;
+    Ok((kind, rest))
+}
+
+/// Token bucket refilled at `rate` per second, bursts up to one second of updates
+struct Bucket {
+    rate: f64,
+    tokens: f64,
+    updated_at: Instant,
+}
+
+impl Bucket {
+    fn take(&mut self) -> bool {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.updated_at).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.rate);
+        self.updated_at = now;
+        let taken = self.tokens >= 1.0;
+        if taken {
+            self.tokens -= 1.0;
+        }
+        taken
+    }
+}
+
+#[derive(Default)]
+pub struct Throttle {
+    sample: HashMap<StatsKind, f64>,
+    buckets: HashMap<StatsKind, Bucket>,
+    dropped: HashMap<StatsKind, u64>,
+}
+
+impl Throttle {
+    pub fn new(sample: &[(StatsKind, f64)], max_rate: &[(StatsKind, f64)]) -> Self {
+        Self {
+            sample: sample.iter().copied().collect(),
+            buckets: max_rate
+                .iter()
+                .map(|&(kind, rate)| {
+                    let bucket = Bucket {
+                        rate,
+                        tokens: rate,
+                        updated_at: Instant::now(),
+                    };
+                    (kind, bucket)
+                })
+                .collect(),
+            dropped: HashMap::new(),
+        }
+    }
+
+    /// Whether an update of this type should be decoded, sampling applies before the rate limit
+    pub fn admit(&mut self, kind: StatsKind) -> bool {
+        let sampled = self
+            .sample
+            .get(&kind)
+            .is_none_or(|fraction| rand::random_bool(*fraction));
+        let admitted = sampled && self.buckets.get_mut(&kind).is_none_or(Bucket::take);
+        if !admitted {
+            *self.dropped.entry(kind).or_default() += 1;
+        }
+        admitted
+    }
+
+    pub fn log_dropped(&self) {
+        for (kind, dropped) in &self.dropped {
+            info!("dropped {dropped} {} updates by sampling/rate limit", kind.label());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sample() {
+        assert_eq!(
+            parse_sample("accounts=0.1").unwrap(),
+            (StatsKind::Accounts, 0.1)
+        );
+        assert_eq!(
+            parse_sample("blocks-meta=1").unwrap(),
+            (StatsKind::BlocksMeta, 1.0)
+        );
+        assert_eq!(
+            parse_sample("transactions=0").unwrap(),
+            (StatsKind::Transactions, 0.0)
+        );
+        assert!(parse_sample("accounts=1.5").is_err());
+        assert!(parse_sample("accounts=-0.1").is_err());
+        assert!(parse_sample("accounts=NaN").is_err());
+        assert!(parse_sample("accounts=").is_err());
+        assert!(parse_sample("accounts").is_err());
+        assert!(parse_sample("votes=0.1").is_err());
+    }
+
+    #[test]
+    fn max_rate() {
+        assert_eq!(
+            parse_max_rate("transactions=500/s").unwrap(),
+            (StatsKind::Transactions, 500.0)
+        );
+        assert_eq!(
+            parse_max_rate("entries=2.5").unwrap(),
+            (StatsKind::Entries, 2.5)
+        );
+        assert!(parse_max_rate("transactions=0/s").is_err());
+        assert!(parse_max_rate("transactions=500/m").is_err());
+        assert!(parse_max_rate("500/s").is_err());
+    }
+
+    #[test]
+    fn admit() {
+        let mut throttle = Throttle::new(&[(StatsKind::Accounts, 0.0)], &[]);
+        assert!(!throttle.admit(StatsKind::Accounts));
+        assert!(throttle.admit(StatsKind::Slots));
+
+        let mut throttle = Throttle::new(&[(StatsKind::Accounts, 1.0)], &[]);
+        assert!((0..100).all(|_| throttle.admit(StatsKind::Accounts)));
+
+        // A full bucket admits one second of updates at once
+        let mut throttle = Throttle::new(&[], &[(StatsKind::Transactions, 3.0)]);
+        assert_eq!(
+            (0..5)
+                .filter(|_| throttle.admit(StatsKind::Transactions))
+                .count(),
+            3
+        );
+    }
+}
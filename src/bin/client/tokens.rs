@@ -0,0 +1,343 @@
+//! `--resolve-tokens`: annotate token account updates with the wallet owning them and their
+//! mint, so output speaks of wallets and tokens rather than token account addresses.
+//!
+//! Wallet, mint and amount are read from the token account itself. Mint decimals and the
+//! Metaplex symbol are fetched over JSON-RPC in the background and cached: updates of a mint
+//! seen for the first time are written without them. Streamed mint accounts fill the cache too.
+//!
+//! With `--mint-cache` the cache is persisted as JSON (`{"<mint>": {"decimals": 6, "symbol":
+//! "USDC"}}`), so restarts don't refetch every mint.
+
+use {
+    crate::rpc::RpcClient,
+    anyhow::Context,
+    base64::{Engine, engine::general_purpose::STANDARD},
+    serde_json::{Value, json},
+    solana_pubkey::Pubkey,
+    std::{
+        collections::HashMap,
+        fs,
+        path::PathBuf,
+        sync::{Arc, Mutex},
+        time::{Duration, Instant},
+    },
+    tracing::{info, warn},
+    yellowstone_grpc_proto::prelude::CommitmentLevel,
+};
+
+pub const TOKEN_PROGRAM: Pubkey =
+    Pubkey::from_str_const("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
+pub const TOKEN_2022_PROGRAM: Pubkey =
+    Pubkey::from_str_const("TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb");
+const METADATA_PROGRAM: Pubkey =
+    Pubkey::from_str_const("metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s");
+
+/// Size of a mint without Token-2022 extensions
+const MINT_LEN: usize = 82;
+/// Size of a token account without Token-2022 extensions
+const ACCOUNT_LEN: usize = 165;
+/// Token-2022 account type byte following the base account, for accounts with extensions
+const ACCOUNT_TYPE_MINT: u8 = 1;
+const ACCOUNT_TYPE_ACCOUNT: u8 = 2;
+/// Offset of `decimals` in a mint
+const MINT_DECIMALS_OFFSET: usize = 44;
+
+/// The cache is cleared when it grows past this many mints
+const MAX_MINTS: usize = 100_000;
+/// A failed mint fetch is retried after this long
+const RETRY_AFTER: Duration = Duration::from_secs(30);
+/// How often the `--mint-cache` file is written while mints are added
+const SAVE_INTERVAL: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone)]
+pub struct MintInfo {
+    pub decimals: u8,
+    pub symbol: Option<String>,
+}
+
+struct Cache {
+    mints: HashMap<String, MintInfo>,
+    /// Fetches in flight or failed, by mint
+    fetching: HashMap<String, Instant>,
+    path: Option<PathBuf>,
+    dirty: bool,
+    saved_at: Instant,
+}
+
+impl Cache {
+    fn load(path: Option<PathBuf>) -> anyhow::Result<Self> {
+        let mut cache = Self {
+            mints: HashMap::new(),
+            fetching: HashMap::new(),
+            path,
+            dirty: false,
+            saved_at: Instant::now(),
+        };
+        let Some(path) = &cache.path else {
+            return Ok(cache);
+        };
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(cache),
+            Err(error) => {
+                return Err(error)
+                    .with_context(|| format!("failed to read mint cache {}", path.display()));
+            }
+        };
+        let value: Value = serde_json::from_str(&text)
+            .with_context(|| format!("invalid mint cache {}", path.display()))?;
+        for (mint, info) in value.as_object().into_iter().flatten() {
+            let Some(decimals) = info["decimals"].as_u64() else {
+                continue;
+            };
+            cache.mints.insert(
+                mint.clone(),
+                MintInfo {
+                    decimals: decimals as u8,
+                    symbol: info["symbol"].as_str().map(str::to_owned),
+                },
+            );
+        }
+        info!("mint cache: {} mints loaded", cache.mints.len());
+        Ok(cache)
+    }
+
+    fn insert(&mut self, mint: String, info: MintInfo) {
+        if self.mints.len() >= MAX_MINTS {
+            self.mints.clear();
+        }
+        self.fetching.remove(&mint);
+        self.mints.insert(mint, info);
+        self.dirty = true;
+        if self.saved_at.elapsed() >= SAVE_INTERVAL
+            && let Err(error) = self.save()
+        {
+            warn!("{error:#}");
+        }
+    }
+
+    /// Write the cache file if it changed, through a temporary file so a crash never leaves
+    /// a partial one
+    fn save(&mut self) -> anyhow::Result<()> {
+        self.saved_at = Instant::now();
+        let Some(path) = self.path.as_ref().filter(|_| self.dirty) else {
+            return Ok(());
+        };
+        let value = self
+            .mints
+            .iter()
+            .map(|(mint, info)| {
+                let info = json!({ "decimals": info.decimals, "symbol": info.symbol });
+                (mint.clone(), info)
+            })
+            .collect::<serde_json::Map<_, _>>();
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, serde_json::to_vec(&value)?)
+            .and_then(|()| fs::rename(&tmp, path))
+            .with_context(|| format!("failed to write mint cache {}", path.display()))?;
+        self.dirty = false;
+        Ok(())
+    }
+}
+
+impl Drop for Cache {
+    fn drop(&mut self) {
+        if let Err(error) = self.save() {
+            warn!("{error:#}");
+        }
+    }
+}
+
+pub struct Mints {
+    rpc: Arc<RpcClient>,
+    commitment: CommitmentLevel,
+    cache: Arc<Mutex<Cache>>,
+}
+
+impl Mints {
+    /// Resolve mints over `rpc`, caching them in `cache_path` if given
+    pub fn new(
+        rpc: RpcClient,
+        commitment: CommitmentLevel,
+        cache_path: Option<PathBuf>,
+    ) -> anyhow::Result<Self> {
+        Ok(Self {
+            rpc: Arc::new(rpc),
+            commitment,
+            cache: Arc::new(Mutex::new(Cache::load(cache_path)?)),
+        })
+    }
+
+    /// Add a `token` object to token account updates, cache the decimals of mint updates
+    pub fn annotate(&self, value: &mut Value) {
+        let Some(account) = parse_token_program_account(value) else {
+            return;
+        };
+        let (mint, wallet, amount) = match account {
+            TokenProgramAccount::Mint { decimals } => {
+                let Some(mint) = value.get("pubkey").and_then(Value::as_str) else {
+                    return;
+                };
+                let mut cache = self.cache.lock().expect("mint cache lock poisoned");
+                if cache
+                    .mints
+                    .get(mint)
+                    .is_none_or(|info| info.decimals != decimals)
+                {
+                    let symbol = cache.mints.get(mint).and_then(|info| info.symbol.clone());
+                    cache.insert(mint.to_owned(), MintInfo { decimals, symbol });
+                }
+                return;
+            }
+            TokenProgramAccount::Account {
+                mint,
+                wallet,
+                amount,
+            } => (mint.to_string(), wallet, amount),
+        };
+        let info = self.get(&mint);
+        value["token"] = json!({
+            "mint": mint,
+            "wallet": wallet.to_string(),
+            "amount": amount,
+            "decimals": info.as_ref().map(|info| info.decimals),
+            "uiAmount": info.as_ref().map(|info| amount as f64 / 10f64.powi(info.decimals.into())),
+            "uiAmountString": info.as_ref().map(|info| ui_amount_string(amount, info.decimals)),
+            "symbol": info.and_then(|info| info.symbol),
+        });
+    }
+
+    /// Cached mint info, fetching mints not cached yet
+    pub fn get(&self, mint: &str) -> Option<MintInfo> {
+        let mut cache = self.cache.lock().expect("mint cache lock poisoned");
+        if let Some(info) = cache.mints.get(mint) {
+            return Some(info.clone());
+        }
+        if cache
+            .fetching
+            .get(mint)
+            .is_some_and(|started_at| started_at.elapsed() < RETRY_AFTER)
+        {
+            return None;
+        }
+        cache.fetching.insert(mint.to_owned(), Instant::now());
+
+        let rpc = Arc::clone(&self.rpc);
+        let commitment = self.commitment;
+        let shared = Arc::clone(&self.cache);
+        let mint = mint.to_owned();
+        tokio::spawn(async move {
+            match fetch(&rpc, commitment, &mint).await {
+                Ok(info) => shared
+                    .lock()
+                    .expect("mint cache lock poisoned")
+                    .insert(mint, info),
+                Err(error) => warn!("failed to fetch mint {mint}: {error:#}"),
+            }
+        });
+        None
+    }
+}
+
+enum TokenProgramAccount {
+    Mint {
+        decimals: u8,
+    },
+    Account {
+        mint: Pubkey,
+        wallet: Pubkey,
+        amount: u64,
+    },
+}
+
+/// Mint or token account of an account update, `None` for other accounts
+fn parse_token_program_account(value: &Value) -> Option<TokenProgramAccount> {
+    let owner = value.get("owner")?.as_str()?.parse::<Pubkey>().ok()?;
+    if owner != TOKEN_PROGRAM && owner != TOKEN_2022_PROGRAM {
+        return None;
+    }
+    let data = hex::decode(value.get("data")?.as_str()?).ok()?;
+    // Token-2022 accounts with extensions are marked by their account type
+    let account_type = data.get(ACCOUNT_LEN).copied();
+    if data.len() == MINT_LEN || account_type == Some(ACCOUNT_TYPE_MINT) {
+        return Some(TokenProgramAccount::Mint {
+            decimals: *data.get(MINT_DECIMALS_OFFSET)?,
+        });
+    }
+    if data.len() != ACCOUNT_LEN && account_type != Some(ACCOUNT_TYPE_ACCOUNT) {
+        return None;
+    }
+    Some(TokenProgramAccount::Account {
+        mint: Pubkey::try_from(&data[..32]).ok()?,
+        wallet: Pubkey::try_from(&data[32..64]).ok()?,
+        amount: u64::from_le_bytes(data[64..72].try_into().ok()?),
+    })
+}
+
+/// Amount in whole tokens, without trailing zeros, as the RPC `uiAmountString`
+fn ui_amount_string(amount: u64, decimals: u8) -> String {
+    let decimals = usize::from(decimals);
+    if decimals == 0 {
+        return amount.to_string();
+    }
+    let digits = format!("{amount:0>width$}", width = decimals + 1);
+    let (whole, fraction) = digits.split_at(digits.len() - decimals);
+    let fraction = fraction.trim_end_matches('0');
+    if fraction.is_empty() {
+        whole.to_owned()
+    } else {
+        format!("{whole}.{fraction}")
+    }
+}
+
+/// Decimals of the mint and the symbol of its Metaplex metadata, if any
+async fn fetch(
+    rpc: &RpcClient,
+    commitment: CommitmentLevel,
+    mint: &str,
+) -> anyhow::Result<MintInfo> {
+    let mint_key = mint.parse::<Pubkey>().context("invalid mint")?;
+    let (metadata, _) = Pubkey::find_program_address(
+        &[b"metadata", METADATA_PROGRAM.as_ref(), mint_key.as_ref()],
+        &METADATA_PROGRAM,
+    );
+    let result = rpc
+        .call(
+            "getMultipleAccounts",
+            json!([[mint, metadata.to_string()], {
+                "encoding": "base64",
+                "commitment": commitment.as_str_name().to_lowercase(),
+            }]),
+        )
+        .await?;
+    let data = |index: usize| {
+        result["value"][index]["data"][0]
+            .as_str()
+            .and_then(|data| STANDARD.decode(data).ok())
+    };
+    let decimals = data(0)
+        .and_then(|data| data.get(MINT_DECIMALS_OFFSET).copied())
+        .with_context(|| format!("mint {mint} not found"))?;
+    Ok(MintInfo {
+        decimals,
+        symbol: data(1).and_then(|data| metadata_symbol(&data)),
+    })
+}
+
+/// Symbol of a Metaplex metadata account: key, update authority, mint, then the
+/// length-prefixed name and symbol
+fn metadata_symbol(data: &[u8]) -> Option<String> {
+    let mut offset = 1 + 32 + 32;
+    let mut string = || {
+        let len = u32::from_le_bytes(data.get(offset..offset + 4)?.try_into().ok()?) as usize;
+        let bytes = data.get(offset + 4..offset + 4 + len)?;
+        offset += 4 + len;
+        Some(
+            String::from_utf8_lossy(bytes)
+                .trim_end_matches('\0')
+                .to_owned(),
+        )
+    };
+    let _name = string()?;
+    string().filter(|symbol| !symbol.is_empty())
+}
@@ -0,0 +1,271 @@
+//! `--aggregate top-n`: periodic leaderboards of the most active accounts, the hottest programs
+//! and the largest SOL transfers over a sliding window.
+//!
+//! The window is split in [`SLICES`] slices and a leaderboard is emitted as each slice ends.
+//! Activity is counted in a count-min sketch per slice, so memory stays bounded however many
+//! distinct accounts are seen; counts may be overestimated by collisions, never
+//! underestimated. Only the keys with the highest estimates are kept as candidates.
+
+use {
+    crate::instructions,
+    serde_json::{Value, json},
+    solana_pubkey::Pubkey,
+    std::{
+        cmp::Reverse,
+        collections::{HashMap, VecDeque},
+        hash::{DefaultHasher, Hash, Hasher},
+        time::Instant,
+    },
+    tracing::debug,
+};
+
+/// Slices of the window, the leaderboard slides by one slice at a time
+pub const SLICES: u32 = 4;
+
+const SYSTEM_PROGRAM: Pubkey = Pubkey::from_str_const("11111111111111111111111111111111");
+/// `SystemInstruction::Transfer` tag
+const SYSTEM_TRANSFER: u32 = 2;
+
+const SKETCH_DEPTH: usize = 4;
+const SKETCH_WIDTH: usize = 4096;
+/// Candidates kept per slice, as a multiple of the leaderboard size
+const CANDIDATES_PER_ENTRY: usize = 8;
+
+/// Count-min sketch: `SKETCH_DEPTH` rows of counters, a key's estimate is the smallest of its
+/// counter in each row
+struct Sketch {
+    counters: Vec<u64>,
+}
+
+impl Sketch {
+    fn new() -> Self {
+        Self {
+            counters: vec![0; SKETCH_DEPTH * SKETCH_WIDTH],
+        }
+    }
+
+    fn cells(key: &Pubkey) -> impl Iterator<Item = usize> + '_ {
+        (0..SKETCH_DEPTH).map(move |row| {
+            let mut hasher = DefaultHasher::new();
+            (row, key).hash(&mut hasher);
+            row * SKETCH_WIDTH + (hasher.finish() as usize % SKETCH_WIDTH)
+        })
+    }
+
+    /// Count `key` once, returning its new estimate
+    fn add(&mut self, key: &Pubkey) -> u64 {
+        Self::cells(key)
+            .map(|cell| {
+                self.counters[cell] += 1;
+                self.counters[cell]
+            })
+            .min()
+            .unwrap_or_default()
+    }
+
+    /// Estimate of `key` over several sketches, as if they were one
+    fn estimate<'a>(sketches: impl Iterator<Item = &'a Self> + Clone, key: &Pubkey) -> u64 {
+        Self::cells(key)
+            .map(|cell| sketches.clone().map(|sketch| sketch.counters[cell]).sum())
+            .min()
+            .unwrap_or_default()
+    }
+}
+
+/// Counts of a slice with the keys most likely at the top
+struct Counts {
+    sketch: Sketch,
+    candidates: HashMap<Pubkey, u64>,
+    capacity: usize,
+}
+
+impl Counts {
+    fn new(capacity: usize) -> Self {
+        Self {
+            sketch: Sketch::new(),
+            candidates: HashMap::new(),
+            capacity,
+        }
+    }
+
+    fn add(&mut self, key: Pubkey) {
+        let estimate = self.sketch.add(&key);
+        self.candidates.insert(key, estimate);
+        if self.candidates.len() > self.capacity * 2 {
+            let mut candidates = self.candidates.drain().collect::<Vec<_>>();
+            candidates.sort_unstable_by_key(|(_, estimate)| Reverse(*estimate));
+            candidates.truncate(self.capacity);
+            self.candidates.extend(candidates);
+        }
+    }
+}
+
+struct Transfer {
+    from: String,
+    to: String,
+    lamports: u64,
+    signature: Value,
+    slot: Value,
+}
+
+struct Slice {
+    started_at: Instant,
+    accounts: Counts,
+    programs: Counts,
+    /// Largest transfers, at most `n`
+    transfers: Vec<Transfer>,
+}
+
+pub struct TopN {
+    n: usize,
+    /// Oldest slice first, the last one is being filled
+    slices: VecDeque<Slice>,
+}
+
+impl TopN {
+    pub fn new(n: usize) -> Self {
+        let mut top_n = Self {
+            n,
+            slices: VecDeque::new(),
+        };
+        top_n.slices.push_back(top_n.slice());
+        top_n
+    }
+
+    fn slice(&self) -> Slice {
+        let capacity = self.n * CANDIDATES_PER_ENTRY;
+        Slice {
+            started_at: Instant::now(),
+            accounts: Counts::new(capacity),
+            programs: Counts::new(capacity),
+            transfers: Vec::new(),
+        }
+    }
+
+    pub fn observe(&mut self, value: &Value) {
+        let slice = self.slices.back_mut().expect("a slice is being filled");
+        match instructions::writable_accounts(value) {
+            Ok(accounts) => accounts
+                .into_iter()
+                .for_each(|account| slice.accounts.add(account)),
+            Err(error) => debug!("failed to decode transaction keys: {error:#}"),
+        }
+        let instructions = match instructions::parse_all(value) {
+            Ok(instructions) => instructions,
+            Err(error) => {
+                debug!("failed to parse transaction instructions: {error:#}");
+                return;
+            }
+        };
+        let mut programs = instructions
+            .iter()
+            .map(|ix| ix.program_id)
+            .collect::<Vec<_>>();
+        programs.sort_unstable();
+        programs.dedup();
+        programs
+            .into_iter()
+            .for_each(|program| slice.programs.add(program));
+
+        if !value["tx"]["meta"]["err"].is_null() {
+            return;
+        }
+        for ix in &instructions {
+            let Some(lamports) = transfer_lamports(ix) else {
+                continue;
+            };
+            if slice.transfers.len() == self.n
+                && slice
+                    .transfers
+                    .last()
+                    .is_some_and(|smallest| smallest.lamports >= lamports)
+            {
+                continue;
+            }
+            let transfer = Transfer {
+                from: ix.account(0).unwrap_or_default(),
+                to: ix.account(1).unwrap_or_default(),
+                lamports,
+                signature: value["signature"].clone(),
+                slot: value["slot"].clone(),
+            };
+            let position = slice
+                .transfers
+                .partition_point(|other| other.lamports >= lamports);
+            slice.transfers.insert(position, transfer);
+            slice.transfers.truncate(self.n);
+        }
+    }
+
+    /// Leaderboards over the window, then start the next slice, dropping the oldest one once
+    /// the window is full
+    pub fn rotate(&mut self) -> Value {
+        let window_secs = self
+            .slices
+            .front()
+            .map_or(0.0, |slice| slice.started_at.elapsed().as_secs_f64());
+        let leaderboard = json!({
+            "windowSecs": window_secs,
+            "accounts": self.top(|slice| &slice.accounts, "address"),
+            "programs": self.top(|slice| &slice.programs, "programId"),
+            "transfers": self.top_transfers(),
+        });
+        if self.slices.len() == SLICES as usize {
+            self.slices.pop_front();
+        }
+        self.slices.push_back(self.slice());
+        leaderboard
+    }
+
+    fn top(&self, counts: impl Fn(&Slice) -> &Counts, key_name: &str) -> Vec<Value> {
+        let sketches = self.slices.iter().map(|slice| &counts(slice).sketch);
+        let mut candidates = self
+            .slices
+            .iter()
+            .flat_map(|slice| counts(slice).candidates.keys())
+            .collect::<Vec<_>>();
+        candidates.sort_unstable();
+        candidates.dedup();
+        let mut top = candidates
+            .into_iter()
+            .map(|key| (key, Sketch::estimate(sketches.clone(), key)))
+            .collect::<Vec<_>>();
+        top.sort_unstable_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        top.into_iter()
+            .take(self.n)
+            .map(|(key, count)| json!({ key_name: key.to_string(), "count": count }))
+            .collect()
+    }
+
+    fn top_transfers(&self) -> Vec<Value> {
+        let mut transfers = self
+            .slices
+            .iter()
+            .flat_map(|slice| &slice.transfers)
+            .collect::<Vec<_>>();
+        transfers.sort_by_key(|transfer| Reverse(transfer.lamports));
+        transfers
+            .into_iter()
+            .take(self.n)
+            .map(|transfer| {
+                json!({
+                    "from": transfer.from,
+                    "to": transfer.to,
+                    "lamports": transfer.lamports,
+                    "signature": transfer.signature,
+                    "slot": transfer.slot,
+                })
+            })
+            .collect()
+    }
+}
+
+/// Lamports moved by a System Program transfer instruction
+fn transfer_lamports(ix: &instructions::Instruction) -> Option<u64> {
+    if ix.program_id != SYSTEM_PROGRAM {
+        return None;
+    }
+    let tag = u32::from_le_bytes(ix.data.get(..4)?.try_into().ok()?);
+    (tag == SYSTEM_TRANSFER).then_some(())?;
+    Some(u64::from_le_bytes(ix.data.get(4..12)?.try_into().ok()?))
+}
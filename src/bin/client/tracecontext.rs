@@ -0,0 +1,51 @@
+//! `--trace-ids`: a W3C trace context for every update, from its receipt to downstream
+//! processing.
+//!
+//! Each update gets a `traceparent` (`00-<trace id>-<span id>-01`) when it's received, written
+//! with it to `--out-file` and `--sink` lines and in `source` of Debezium envelopes, so a handler
+//! can forward it as an HTTP or Kafka header or keep it in a database row. The decode and sink
+//! spans run in an `update` span; exported with `--otlp-endpoint`, the `traceparent` names that
+//! span, so downstream spans join the same trace.
+
+use tracing::Span;
+
+pub struct UpdateTrace {
+    trace_id: u128,
+    span_id: u64,
+    span: Span,
+}
+
+impl UpdateTrace {
+    pub fn new() -> Self {
+        let span = tracing::debug_span!("update");
+        #[cfg(feature = "otlp")]
+        {
+            use {
+                opentelemetry::trace::TraceContextExt, tracing_opentelemetry::OpenTelemetrySpanExt,
+            };
+            let context = span.context();
+            let span_context = context.span().span_context().clone();
+            if span_context.is_valid() {
+                return Self {
+                    trace_id: u128::from_be_bytes(span_context.trace_id().to_bytes()),
+                    span_id: u64::from_be_bytes(span_context.span_id().to_bytes()),
+                    span,
+                };
+            }
+        }
+        // All zero ids are invalid
+        Self {
+            trace_id: rand::random::<u128>().max(1),
+            span_id: rand::random::<u64>().max(1),
+            span,
+        }
+    }
+
+    pub fn traceparent(&self) -> String {
+        format!("00-{:032x}-{:016x}-01", self.trace_id, self.span_id)
+    }
+
+    pub const fn span(&self) -> &Span {
+        &self.span
+    }
+}
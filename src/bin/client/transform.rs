@@ -0,0 +1,90 @@
+//! `--transform`: run every decoded update through a WASM module before it's written, to filter
+//! or enrich updates without rebuilding the indexer.
+//!
+//! The module, binary or text format, has no imports and exports its `memory` and two
+//! functions:
+//!
+//! - `alloc(len: i32) -> i32`: a buffer of `len` bytes for the input, owned by the module
+//!   once `transform` is called
+//! - `transform(ptr: i32, len: i32) -> i64`: takes the update as JSON (`{"kind", "filters",
+//!   "createdAt", "update"}`) and returns the replacement `update` as JSON, packed as
+//!   `ptr << 32 | len`. `0` drops the update. The output is read right away, so the module
+//!   may reuse its buffer on the next call.
+
+use {
+    anyhow::Context,
+    serde_json::{Value, json},
+    std::{
+        path::Path,
+        time::{SystemTime, UNIX_EPOCH},
+    },
+    wasmtime::{Engine, Instance, Memory, Module, Store, TypedFunc},
+};
+
+pub struct Transform {
+    store: Store<()>,
+    memory: Memory,
+    alloc: TypedFunc<u32, u32>,
+    transform: TypedFunc<(u32, u32), u64>,
+}
+
+impl Transform {
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let engine = Engine::default();
+        let module = Module::from_file(&engine, path)
+            .with_context(|| format!("failed to load {}", path.display()))?;
+        let mut store = Store::new(&engine, ());
+        let instance = Instance::new(&mut store, &module, &[])
+            .context("failed to instantiate the transform, it must have no imports")?;
+        let memory = instance
+            .get_memory(&mut store, "memory")
+            .context("transform doesn't export `memory`")?;
+        let alloc = instance
+            .get_typed_func(&mut store, "alloc")
+            .context("transform doesn't export `alloc(i32) -> i32`")?;
+        let transform = instance
+            .get_typed_func(&mut store, "transform")
+            .context("transform doesn't export `transform(i32, i32) -> i64`")?;
+        Ok(Self {
+            store,
+            memory,
+            alloc,
+            transform,
+        })
+    }
+
+    /// The transformed update, `None` if the module dropped it
+    pub fn apply(
+        &mut self,
+        kind: &str,
+        created_at: SystemTime,
+        filters: &[String],
+        value: &Value,
+    ) -> anyhow::Result<Option<Value>> {
+        let input = json!({
+            "kind": kind,
+            "filters": filters,
+            "createdAt": created_at.duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64,
+            "update": value,
+        })
+        .to_string();
+        let len = u32::try_from(input.len()).context("update too large for the transform")?;
+        let ptr = self.alloc.call(&mut self.store, len)?;
+        self.memory
+            .write(&mut self.store, ptr as usize, input.as_bytes())
+            .context("transform `alloc` returned an out of bounds buffer")?;
+
+        let output = self.transform.call(&mut self.store, (ptr, len))?;
+        if output == 0 {
+            return Ok(None);
+        }
+        let (ptr, len) = ((output >> 32) as usize, output as u32 as usize);
+        let mut buffer = vec![0; len];
+        self.memory
+            .read(&self.store, ptr, &mut buffer)
+            .context("transform returned an out of bounds buffer")?;
+        serde_json::from_slice(&buffer)
+            .map(Some)
+            .context("transform returned invalid JSON")
+    }
+}
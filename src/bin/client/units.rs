@@ -0,0 +1,115 @@
+//! Sizes and durations of flags, a number with an optional unit: `512MB`, `2GB`, `30s`, `10m`,
+//! `1h` or `1d`. Units are case insensitive.
+
+use std::time::Duration;
+
+/// Parse a size: bytes, optionally with a unit (`512MB`, `2GB`)
+pub fn parse_bytes(value: &str) -> Result<u64, String> {
+    let (number, unit) = split(value)?;
+    let shift = match unit.to_ascii_uppercase().as_str() {
+        "" | "B" => 0,
+        "KB" => 10,
+        "MB" => 20,
+        "GB" => 30,
+        _ => return Err(format!("unknown unit `{unit}`, expected B, KB, MB or GB")),
+    };
+    number
+        .checked_mul(1 << shift)
+        .ok_or_else(|| format!("`{value}` is too large"))
+}
+
+/// Parse a duration: seconds, optionally with a unit (`30s`, `10m`, `2h`, `1d`)
+pub fn parse_duration(value: &str) -> Result<Duration, String> {
+    let (number, unit) = split(value)
+        .ok()
+        .filter(|(number, _)| *number > 0)
+        .ok_or_else(|| format!("invalid duration `{value}`, e.g. 60s or 10m"))?;
+    let secs = match unit.to_ascii_lowercase().as_str() {
+        "" | "s" => 1,
+        "m" => 60,
+        "h" => 3600,
+        "d" => 86400,
+        _ => return Err(format!("unknown unit `{unit}`, expected s, m, h or d")),
+    };
+    number
+        .checked_mul(secs)
+        .map(Duration::from_secs)
+        .ok_or_else(|| format!("`{value}` is too large"))
+}
+
+/// Whether a value has a size unit rather than a duration one
+pub fn is_size(value: &str) -> bool {
+    value.to_ascii_uppercase().ends_with('B')
+}
+
+/// The leading number of a value and its unit
+fn split(value: &str) -> Result<(u64, &str), String> {
+    let split = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let number = number
+        .parse::<u64>()
+        .map_err(|_| format!("`{value}` doesn't start with a number"))?;
+    Ok((number, unit))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bytes() {
+        assert_eq!(parse_bytes("0"), Ok(0));
+        assert_eq!(parse_bytes("512"), Ok(512));
+        assert_eq!(parse_bytes("512B"), Ok(512));
+        assert_eq!(parse_bytes("2KB"), Ok(2 << 10));
+        assert_eq!(parse_bytes("100mb"), Ok(100 << 20));
+        assert_eq!(parse_bytes("2Gb"), Ok(2 << 30));
+        assert!(parse_bytes("").is_err());
+        assert!(parse_bytes("MB").is_err());
+        assert!(parse_bytes("-1").is_err());
+        assert!(parse_bytes("1TB").is_err());
+        assert!(parse_bytes("1 MB").is_err());
+    }
+
+    #[test]
+    fn bytes_overflow() {
+        assert_eq!(parse_bytes(&u64::MAX.to_string()), Ok(u64::MAX));
+        assert!(parse_bytes(&format!("{}KB", u64::MAX >> 9)).is_err());
+        assert!(parse_bytes("18446744073709551616").is_err());
+    }
+
+    #[test]
+    fn durations() {
+        assert_eq!(parse_duration("30"), Ok(Duration::from_secs(30)));
+        assert_eq!(parse_duration("30s"), Ok(Duration::from_secs(30)));
+        assert_eq!(parse_duration("10m"), Ok(Duration::from_secs(600)));
+        assert_eq!(parse_duration("2H"), Ok(Duration::from_secs(7200)));
+        assert_eq!(parse_duration("1d"), Ok(Duration::from_secs(86400)));
+        assert!(parse_duration("0").is_err());
+        assert!(parse_duration("0s").is_err());
+        assert!(parse_duration("").is_err());
+        assert!(parse_duration("s").is_err());
+        assert!(parse_duration("1w").is_err());
+        assert!(parse_duration("1.5h").is_err());
+    }
+
+    #[test]
+    fn durations_overflow() {
+        assert_eq!(
+            parse_duration(&u64::MAX.to_string()),
+            Ok(Duration::from_secs(u64::MAX))
+        );
+        assert!(parse_duration(&format!("{}d", u64::MAX / 86400 + 1)).is_err());
+        assert!(parse_duration("18446744073709551616s").is_err());
+    }
+
+    #[test]
+    fn sizes() {
+        assert!(is_size("100MB"));
+        assert!(is_size("512b"));
+        assert!(!is_size("10m"));
+        assert!(!is_size("60"));
+    }
+}
@@ -0,0 +1,234 @@
+//! `--verify-encoding`: compare the plugin's hand written encoder against prost.
+//!
+//! Mismatching messages are saved to `grpc-client-verify/`. With `--verify-report` a JSON
+//! report of the whole run (messages by kind, mismatches by category, encoding time
+//! percentiles and the saved files) is written at exit, so CI can assert on it.
+
+use {
+    crate::{ProgressBarTpl, StatsKind, crate_progress_bar, latency},
+    anyhow::Context,
+    indicatif::{MultiProgress, ProgressBar},
+    serde_json::json,
+    std::{
+        collections::BTreeMap,
+        path::{Path, PathBuf},
+        sync::{Mutex, MutexGuard},
+        time::{Instant, SystemTime, UNIX_EPOCH},
+    },
+    tokio::fs,
+    yellowstone_grpc_proto::{
+        plugin::filter::message::FilteredUpdate, prelude::SubscribeUpdate, prost::Message,
+    },
+};
+
+const DUMP_DIR: &str = "grpc-client-verify";
+
+/// Encoding times kept for the percentiles, a uniform sample of longer runs
+const MAX_TIMINGS: usize = 100_000;
+
+/// Dump paths listed in the report
+const MAX_DUMPS: usize = 100;
+
+/// Results of all the verifiers of a session, across reconnects
+#[derive(Default)]
+pub struct Report {
+    state: Mutex<ReportState>,
+}
+
+#[derive(Default)]
+struct ReportState {
+    /// Set by the first verifier, a session without one has no report
+    started: Option<(Instant, SystemTime)>,
+    path: Option<PathBuf>,
+    messages: u64,
+    bytes: u64,
+    kinds: BTreeMap<&'static str, u64>,
+    /// Mismatching messages, a message can fail several categories
+    mismatched: u64,
+    mismatches: BTreeMap<&'static str, u64>,
+    conversion_errors: u64,
+    /// Per message `(prost, plugin)` encoding nanoseconds
+    timings: Vec<(u64, u64)>,
+    prost_nanos: u128,
+    plugin_nanos: u128,
+    dumps: Vec<String>,
+}
+
+impl Report {
+    fn state(&self) -> MutexGuard<'_, ReportState> {
+        self.state.lock().expect("verify report lock poisoned")
+    }
+
+    fn start(&self, path: Option<&Path>) {
+        let mut state = self.state();
+        state.started.get_or_insert((Instant::now(), SystemTime::now()));
+        if path.is_some() {
+            state.path = path.map(Path::to_owned);
+        }
+    }
+
+    /// Write the report to `--verify-report`, if set
+    pub fn finish(&self) -> anyhow::Result<()> {
+        let state = self.state();
+        let (Some((started_at, started)), Some(path)) = (state.started, &state.path) else {
+            return Ok(());
+        };
+
+        let percentiles = |nanos: &mut Vec<u64>| {
+            nanos.sort_unstable();
+            (!nanos.is_empty()).then(|| {
+                json!({
+                    "p50": latency::percentile(nanos, 0.5),
+                    "p90": latency::percentile(nanos, 0.9),
+                    "p99": latency::percentile(nanos, 0.99),
+                    "max": nanos.last(),
+                })
+            })
+        };
+        let (mut prost, mut plugin): (Vec<_>, Vec<_>) = state.timings.iter().copied().unzip();
+        let report = json!({
+            "startedAt": started.duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64,
+            "durationSecs": started_at.elapsed().as_secs_f64(),
+            "messages": state.messages,
+            "bytes": state.bytes,
+            "kinds": state.kinds,
+            "mismatchedMessages": state.mismatched,
+            "mismatches": state.mismatches,
+            "conversionErrors": state.conversion_errors,
+            "timingNanos": {
+                "prost": percentiles(&mut prost),
+                "plugin": percentiles(&mut plugin),
+                "samples": state.timings.len(),
+            },
+            "pluginVsProstPercent": (state.prost_nanos > 0)
+                .then(|| 100.0 * state.plugin_nanos as f64 / state.prost_nanos as f64),
+            "dumpDir": DUMP_DIR,
+            "dumps": state.dumps,
+            "dumpsOmitted": state.mismatched.saturating_sub(state.dumps.len() as u64),
+        });
+        std::fs::write(path, serde_json::to_vec_pretty(&report)?)
+            .with_context(|| format!("failed to write {}", path.display()))
+    }
+}
+
+impl ReportState {
+    fn record_timing(&mut self, prost: u64, plugin: u64) {
+        self.prost_nanos += u128::from(prost);
+        self.plugin_nanos += u128::from(plugin);
+        if self.timings.len() < MAX_TIMINGS {
+            self.timings.push((prost, plugin));
+        } else {
+            // Reservoir sampling: every message has the same chance to be kept
+            let index = rand::random_range(0..self.messages) as usize;
+            if let Some(timing) = self.timings.get_mut(index) {
+                *timing = (prost, plugin);
+            }
+        }
+    }
+}
+
+pub struct EncodingVerifier<'a> {
+    /// Total nanoseconds spent encoding with prost
+    prost_c: u128,
+    /// Total nanoseconds spent encoding with the plugin encoder
+    ref_c: u128,
+    pb: ProgressBar,
+    report: &'a Report,
+}
+
+impl<'a> EncodingVerifier<'a> {
+    pub fn new(
+        multi: &MultiProgress,
+        report: &'a Report,
+        report_path: Option<&Path>,
+    ) -> anyhow::Result<Self> {
+        report.start(report_path);
+        Ok(Self {
+            prost_c: 0,
+            ref_c: 0,
+            pb: crate_progress_bar(multi, ProgressBarTpl::Verify)?,
+            report,
+        })
+    }
+
+    pub async fn verify(&mut self, msg: SubscribeUpdate, multi: &MultiProgress) -> anyhow::Result<()> {
+        let kind = msg.update_oneof.as_ref().map(StatsKind::from_update);
+        let encoded_len_prost0 = msg.encoded_len();
+        let encoded_prost0 = msg.encode_to_vec();
+
+        let update = match FilteredUpdate::from_subscribe_update(msg) {
+            Ok(update) => update,
+            Err(error) => {
+                self.report.state().conversion_errors += 1;
+                return Err(anyhow::anyhow!(error))
+                    .context("failed to convert update message to filtered update");
+            }
+        };
+
+        let ts = Instant::now();
+        let msg2 = update.as_subscribe_update();
+        let encoded_len_prost = msg2.encoded_len();
+        let encoded_prost = msg2.encode_to_vec();
+        let prost_nanos = ts.elapsed().as_nanos();
+        self.prost_c += prost_nanos;
+
+        let ts = Instant::now();
+        let encoded_len_ref = update.encoded_len();
+        let encoded_ref = update.encode_to_vec();
+        let ref_nanos = ts.elapsed().as_nanos();
+        self.ref_c += ref_nanos;
+
+        self.pb.set_message(format!(
+            "{:.2?}%",
+            100f64 * (self.ref_c as f64) / (self.prost_c as f64)
+        ));
+
+        let mismatches = [
+            ("roundTripLength", encoded_len_prost0 != encoded_len_prost),
+            ("roundTripBytes", encoded_prost0 != encoded_prost),
+            ("pluginLength", encoded_len_prost != encoded_len_ref),
+            ("pluginBytes", encoded_prost != encoded_ref),
+        ]
+        .into_iter()
+        .filter_map(|(category, mismatch)| mismatch.then_some(category))
+        .collect::<Vec<_>>();
+
+        let dump = {
+            let mut state = self.report.state();
+            state.messages += 1;
+            state.bytes += encoded_len_prost0 as u64;
+            *state
+                .kinds
+                .entry(kind.map_or("unknown", |kind| kind.key()))
+                .or_default() += 1;
+            state.record_timing(prost_nanos as u64, ref_nanos as u64);
+            if !mismatches.is_empty() {
+                state.mismatched += 1;
+                for category in &mismatches {
+                    *state.mismatches.entry(category).or_default() += 1;
+                }
+            }
+            !mismatches.is_empty() && state.dumps.len() < MAX_DUMPS
+        };
+
+        if !mismatches.is_empty() {
+            let name = SystemTime::now().duration_since(UNIX_EPOCH)?.as_nanos();
+            let path = format!("{DUMP_DIR}/{name}");
+            multi.println(format!(
+                "found unmached message ({}), save to `{path}`",
+                mismatches.join(", ")
+            ))?;
+            fs::create_dir_all(DUMP_DIR)
+                .await
+                .context("failed to create dir for unmached")?;
+            fs::write(&path, encoded_prost)
+                .await
+                .context("failed to save unmached")?;
+            if dump {
+                self.report.state().dumps.push(path);
+            }
+        }
+
+        Ok(())
+    }
+}
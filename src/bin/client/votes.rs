@@ -0,0 +1,176 @@
+//! `--vote-stats-interval-secs`: per-validator voting statistics in place of raw vote
+//! transactions, for validator operators watching how their votes land.
+//!
+//! For every vote account, each interval reports the slots newly voted on, the vote latency
+//! (slots between a voted slot and the slot the vote landed in) and the missed slots: slots
+//! voted on by any validator in the interval that the account didn't vote on.
+
+use {
+    crate::sync::VOTE_PROGRAM,
+    serde_json::{Value, json},
+    solana_transaction_status::EncodedTransaction,
+    solana_vote_interface::instruction::VoteInstruction,
+    std::{
+        collections::{BTreeMap, BTreeSet, HashMap},
+        time::{Duration, Instant},
+    },
+    tracing::debug,
+    yellowstone_grpc_proto::prelude::{SubscribeRequest, SubscribeRequestFilterTransactions},
+};
+
+/// Transactions filter added to the request when transactions aren't subscribed already
+pub const FILTER: &str = "vote-stats";
+
+#[derive(Default)]
+struct ValidatorStats {
+    identity: String,
+    votes: u64,
+    failed_votes: u64,
+    voted_slots: BTreeSet<u64>,
+    latency_sum: u64,
+    latency_max: u64,
+    last_voted_hash: Option<String>,
+}
+
+pub struct VoteStats {
+    interval: Duration,
+    emitted_at: Instant,
+    /// Last slot voted on per vote account, kept across intervals to find newly voted slots
+    last_voted: HashMap<String, u64>,
+    validators: BTreeMap<String, ValidatorStats>,
+    cluster_slots: BTreeSet<u64>,
+}
+
+impl VoteStats {
+    /// Aggregate the vote transactions of `request`, subscribing to them if needed
+    pub fn new(interval: Duration, request: &mut SubscribeRequest) -> Self {
+        if request.transactions.is_empty() {
+            request.transactions.insert(
+                FILTER.to_owned(),
+                SubscribeRequestFilterTransactions {
+                    vote: Some(true),
+                    ..Default::default()
+                },
+            );
+        }
+        Self {
+            interval,
+            emitted_at: Instant::now(),
+            last_voted: HashMap::new(),
+            validators: BTreeMap::new(),
+            cluster_slots: BTreeSet::new(),
+        }
+    }
+
+    /// Take a transaction update, returns `true` for vote transactions, which aren't written
+    pub fn observe(&mut self, kind: &str, value: &Value) -> bool {
+        if kind != "transaction" || value.get("isVote").and_then(Value::as_bool) != Some(true) {
+            return false;
+        }
+        if let Err(error) = self.record(value) {
+            debug!("failed to parse vote transaction: {error:#}");
+        }
+        true
+    }
+
+    fn record(&mut self, value: &Value) -> anyhow::Result<()> {
+        let landed_slot = value["slot"].as_u64().unwrap_or_default();
+        let tx = serde_json::from_value::<EncodedTransaction>(value["tx"]["transaction"].clone())?
+            .decode()
+            .ok_or_else(|| anyhow::anyhow!("failed to decode transaction"))?;
+        let keys = tx.message.static_account_keys();
+        let Some(instruction) = tx
+            .message
+            .instructions()
+            .iter()
+            .find(|ix| keys.get(ix.program_id_index as usize) == Some(&VOTE_PROGRAM))
+        else {
+            return Ok(());
+        };
+        let vote_account = instruction
+            .accounts
+            .first()
+            .and_then(|index| keys.get(*index as usize))
+            .ok_or_else(|| anyhow::anyhow!("vote instruction without vote account"))?
+            .to_string();
+        let stats = self.validators.entry(vote_account.clone()).or_default();
+        stats.identity = keys.first().map(ToString::to_string).unwrap_or_default();
+        stats.votes += 1;
+        if !value["tx"]["meta"]["err"].is_null() {
+            stats.failed_votes += 1;
+            return Ok(());
+        }
+
+        let vote = bincode::deserialize::<VoteInstruction>(&instruction.data)?;
+        let slots = match &vote {
+            VoteInstruction::Vote(vote) | VoteInstruction::VoteSwitch(vote, _) => {
+                vote.slots.clone()
+            }
+            VoteInstruction::UpdateVoteState(update)
+            | VoteInstruction::UpdateVoteStateSwitch(update, _)
+            | VoteInstruction::CompactUpdateVoteState(update)
+            | VoteInstruction::CompactUpdateVoteStateSwitch(update, _) => update.slots(),
+            VoteInstruction::TowerSync(tower) | VoteInstruction::TowerSyncSwitch(tower, _) => {
+                tower.slots()
+            }
+            _ => return Ok(()),
+        };
+        let last_voted = self.last_voted.get(&vote_account).copied();
+        let newest = vote.last_voted_slot();
+        for slot in slots {
+            // The first vote seen of an account only counts its newest slot, not the whole tower
+            let new = match last_voted {
+                Some(last_voted) => slot > last_voted,
+                None => Some(slot) == newest,
+            };
+            if !new {
+                continue;
+            }
+            let latency = landed_slot.saturating_sub(slot);
+            stats.voted_slots.insert(slot);
+            stats.latency_sum += latency;
+            stats.latency_max = stats.latency_max.max(latency);
+            self.cluster_slots.insert(slot);
+        }
+        stats.last_voted_hash = Some(vote.hash().to_string());
+        if let Some(slot) = newest {
+            self.last_voted
+                .insert(vote_account, last_voted.unwrap_or_default().max(slot));
+        }
+        Ok(())
+    }
+
+    /// Statistics of every validator which voted, if the interval elapsed
+    pub fn due(&mut self) -> Vec<Value> {
+        if self.emitted_at.elapsed() < self.interval {
+            return vec![];
+        }
+        let window_secs = self.emitted_at.elapsed().as_secs_f64();
+        self.emitted_at = Instant::now();
+        let cluster_slots = std::mem::take(&mut self.cluster_slots);
+        std::mem::take(&mut self.validators)
+            .into_iter()
+            .map(|(vote_account, stats)| {
+                let voted = stats.voted_slots.len() as u64;
+                let missed = cluster_slots
+                    .iter()
+                    .filter(|slot| !stats.voted_slots.contains(slot))
+                    .count();
+                json!({
+                    "slot": cluster_slots.last(),
+                    "voteAccount": vote_account,
+                    "identity": stats.identity,
+                    "windowSecs": window_secs,
+                    "votes": stats.votes,
+                    "failedVotes": stats.failed_votes,
+                    "votedSlots": voted,
+                    "missedSlots": missed,
+                    "latencyAvg": (voted > 0).then(|| stats.latency_sum as f64 / voted as f64),
+                    "latencyMax": stats.latency_max,
+                    "lastVotedSlot": self.last_voted.get(&vote_account),
+                    "lastVotedHash": stats.last_voted_hash,
+                })
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,116 @@
+//! `--watchlist wallets.json`: subscribe to the accounts and transactions of a list of labeled
+//! addresses, and annotate every update referencing one of them.
+//!
+//! The file maps addresses to a label, or to a label and a group:
+//!
+//! ```json
+//! {
+//!     "9WzDXwBbmkg8ZTbNMqUxvQRAyrZzDsGYdLVL9zYtAWWM": "Binance 2",
+//!     "5Q544fKrFoe6tsEbD7S8EmxGTJYAKtTVhAW5Q5pge4j1": { "label": "Raydium", "group": "amm" }
+//! }
+//! ```
+//!
+//! Matching updates get a `watchlist` array of `{address, label, group}`. Addresses are looked
+//! up in every string of an update, and in the account keys of transactions; transactions
+//! inside blocks aren't decoded.
+
+use {
+    crate::labels,
+    anyhow::Context,
+    serde_json::{Value, json},
+    std::{
+        collections::{BTreeSet, HashMap},
+        fs,
+        path::Path,
+    },
+    tracing::info,
+    yellowstone_grpc_proto::prelude::{
+        SubscribeRequest, SubscribeRequestFilterAccounts, SubscribeRequestFilterTransactions,
+    },
+};
+
+pub const FILTER: &str = "watchlist";
+
+struct Entry {
+    label: String,
+    group: Option<String>,
+}
+
+pub struct Watchlist {
+    entries: HashMap<String, Entry>,
+}
+
+impl Watchlist {
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("failed to read watchlist {}", path.display()))?;
+        let value: HashMap<String, Value> = serde_json::from_str(&text)
+            .with_context(|| format!("invalid watchlist {}", path.display()))?;
+        let mut entries = HashMap::new();
+        for (address, entry) in value {
+            address
+                .parse::<solana_pubkey::Pubkey>()
+                .with_context(|| format!("invalid watchlist address `{address}`"))?;
+            let entry = match entry {
+                Value::String(label) => Entry { label, group: None },
+                Value::Object(entry) => Entry {
+                    label: entry
+                        .get("label")
+                        .and_then(Value::as_str)
+                        .with_context(|| format!("watchlist entry `{address}` has no label"))?
+                        .to_owned(),
+                    group: entry
+                        .get("group")
+                        .and_then(Value::as_str)
+                        .map(str::to_owned),
+                },
+                _ => anyhow::bail!("watchlist entry `{address}` must be a label or an object"),
+            };
+            entries.insert(address, entry);
+        }
+        anyhow::ensure!(!entries.is_empty(), "watchlist {} is empty", path.display());
+        info!("watchlist: {} addresses", entries.len());
+        Ok(Self { entries })
+    }
+
+    /// Add accounts and transactions filters for the watched addresses to `request`
+    pub fn subscribe(&self, request: &mut SubscribeRequest) {
+        let addresses = self.entries.keys().cloned().collect::<Vec<_>>();
+        request.accounts.insert(
+            FILTER.to_owned(),
+            SubscribeRequestFilterAccounts {
+                account: addresses.clone(),
+                ..Default::default()
+            },
+        );
+        request.transactions.insert(
+            FILTER.to_owned(),
+            SubscribeRequestFilterTransactions {
+                vote: Some(false),
+                account_include: addresses,
+                ..Default::default()
+            },
+        );
+    }
+
+    /// Add the `watchlist` array to an update referencing watched addresses
+    pub fn annotate(&self, kind: &str, value: &mut Value) {
+        let mut matches = BTreeSet::new();
+        labels::visit_addresses(kind, value, &mut |string| {
+            if let Some((address, _)) = self.entries.get_key_value(string) {
+                matches.insert(address.as_str());
+            }
+        });
+        if matches.is_empty() {
+            return;
+        }
+        let watchlist = matches
+            .into_iter()
+            .map(|address| {
+                let entry = &self.entries[address];
+                json!({ "address": address, "label": entry.label, "group": entry.group })
+            })
+            .collect::<Vec<_>>();
+        value["watchlist"] = Value::Array(watchlist);
+    }
+}
@@ -0,0 +1,84 @@
+//! `--watermark-interval`: periodic "all data ≤ slot N at commitment C delivered" records
+//! so downstream stream processors can advance event time.
+//!
+//! The server releases the updates of a slot before the slot status at the subscribed commitment,
+//! so the last slot seen with that status is a safe watermark. Records are written like the events
+//! computed from the stream: to `--out-file`, `--arrow-file`, the sinks and stdout.
+
+use {
+    serde_json::{Value, json},
+    std::time::{Duration, Instant},
+    yellowstone_grpc_proto::{
+        geyser::SlotStatus,
+        prelude::{CommitmentLevel, SubscribeRequest, SubscribeRequestFilterSlots},
+    },
+};
+
+/// Slots filter added to the request when slots aren't subscribed already
+pub const FILTER: &str = "watermark";
+
+pub struct Watermark {
+    interval: Duration,
+    commitment: CommitmentLevel,
+    status: &'static str,
+    slot: Option<u64>,
+    emitted_slot: Option<u64>,
+    emitted_at: Instant,
+}
+
+impl Watermark {
+    /// Track watermarks for `request`, subscribing to slot updates if needed
+    pub fn new(interval: Duration, request: &mut SubscribeRequest) -> Self {
+        let commitment =
+            CommitmentLevel::try_from(request.commitment.unwrap_or_default()).unwrap_or_default();
+        if request.slots.is_empty() {
+            request.slots.insert(
+                FILTER.to_owned(),
+                SubscribeRequestFilterSlots {
+                    filter_by_commitment: Some(true),
+                    interslot_updates: Some(false),
+                },
+            );
+        }
+        let status = match commitment {
+            CommitmentLevel::Processed => SlotStatus::SlotProcessed,
+            CommitmentLevel::Confirmed => SlotStatus::SlotConfirmed,
+            CommitmentLevel::Finalized => SlotStatus::SlotFinalized,
+        };
+        Self {
+            interval,
+            commitment,
+            status: status.as_str_name(),
+            slot: None,
+            emitted_slot: None,
+            emitted_at: Instant::now(),
+        }
+    }
+
+    /// Track slot updates, returns `true` for updates which only exist for the watermark
+    /// and shouldn't be written
+    pub fn observe(&mut self, kind: &str, filters: &[String], value: &Value) -> bool {
+        if kind != "slot" {
+            return false;
+        }
+        if value.get("status").and_then(Value::as_str) == Some(self.status)
+            && let Some(slot) = value.get("slot").and_then(Value::as_u64)
+        {
+            self.slot = self.slot.max(Some(slot));
+        }
+        filters.iter().all(|filter| filter == FILTER)
+    }
+
+    /// Watermark record if the interval elapsed and the watermark advanced
+    pub fn due(&mut self) -> Option<Value> {
+        if self.emitted_at.elapsed() < self.interval || self.slot <= self.emitted_slot {
+            return None;
+        }
+        self.emitted_at = Instant::now();
+        self.emitted_slot = self.slot;
+        Some(json!({
+            "slot": self.slot,
+            "commitment": self.commitment.as_str_name().to_lowercase(),
+        }))
+    }
+}
@@ -4,7 +4,7 @@ use {
     clap::{Parser, Subcommand, ValueEnum},
     futures::{future::TryFutureExt, sink::SinkExt, stream::StreamExt},
     indicatif::{MultiProgress, ProgressBar, ProgressStyle},
-    log::{error, info},
+    tracing::{error, info},
     serde_json::{json, Value},
     solana_hash::Hash,
     solana_pubkey::Pubkey,
@@ -12,7 +12,6 @@ use {
     solana_transaction_status::UiTransactionEncoding,
     std::{
         collections::HashMap,
-        env,
         fs::File,
         path::PathBuf,
         str::FromStr,
@@ -595,13 +594,13 @@ impl Action {
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-    unsafe{
-        env::set_var(
-            env_logger::DEFAULT_FILTER_ENV,
-            env::var_os(env_logger::DEFAULT_FILTER_ENV).unwrap_or_else(|| "info".into()),
-        );
-    }
-    env_logger::init();
+    tracing_subscriber::fmt()
+        .with_env_filter(
+            tracing_subscriber::EnvFilter::try_from_default_env()
+                .unwrap_or_else(|_| "info".into()),
+        )
+        .with_writer(std::io::stderr)
+        .init();
 
     let args = Args::parse();
     let zero_attempts = Arc::new(Mutex::new(true));
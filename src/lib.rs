@@ -0,0 +1,6 @@
+//! Extension points of the indexer client, for crates adding to it without forking the stream
+//! core. The `client` binary links the destinations registered here.
+
+pub mod sink;
+
+pub use {async_trait::async_trait, inventory};
@@ -0,0 +1,88 @@
+//! Destinations of `--sink`, streaming updates somewhere else than stdout and `--out-file`.
+//!
+//! A destination implements [`Sink`] and is registered under the scheme of its `--sink` value,
+//! `<scheme>:<target>`, with [`register_sink!`](crate::register_sink). Registrations are
+//! collected when the binary is linked, so a crate adding a destination only has to be a
+//! dependency of the binary built with it, e.g. behind a feature:
+//!
+//! ```ignore
+//! use indexing::sink::{DecodedUpdate, Registration, Sink};
+//!
+//! struct Webhook(String);
+//!
+//! #[indexing::async_trait]
+//! impl Sink for Webhook {
+//!     async fn deliver(&self, update: DecodedUpdate) -> anyhow::Result<()> {
+//!         todo!("post {} to {}", update.kind, self.0)
+//!     }
+//! }
+//!
+//! indexing::register_sink!(Registration {
+//!     scheme: "webhook",
+//!     usage: "webhook:<url>",
+//!     open: |url| Ok(Box::new(Webhook(url.to_owned()))),
+//! });
+//! ```
+//!
+//! Every `--sink` runs in its own task behind its own queue, a destination is called for one
+//! update at a time and may take as long as it needs.
+
+use {serde_json::Value, std::time::SystemTime};
+
+/// An update delivered to the sinks, as written to `--out-file`
+#[derive(Debug, Clone)]
+pub struct DecodedUpdate {
+    /// Update kind, e.g. `account`, or the name of an event computed from the stream
+    pub kind: String,
+    pub created_at: SystemTime,
+    /// Filters of the request matching the update
+    pub filters: Vec<String>,
+    pub value: Value,
+    /// W3C `traceparent` of the update, with `--trace-ids`
+    pub traceparent: Option<String>,
+}
+
+/// A destination of updates, written to after `--out-file` and before stdout
+#[async_trait::async_trait]
+pub trait Sink: Send + Sync {
+    /// Deliver an update; a failure is retried `--sink-retries` times before the update is
+    /// dropped for this sink
+    async fn deliver(&self, update: DecodedUpdate) -> anyhow::Result<()>;
+
+    /// Deliver what's still buffered, called when the stream ends
+    async fn flush(&self) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    /// With `--checkpoint-file`, called once every sink delivered the updates of `slot` and the
+    /// checkpoint moved past it; destinations keeping their own offsets commit them here. Not
+    /// called for the slots after an update a sink dropped or failed to deliver.
+    async fn checkpoint(&self, _slot: u64) -> anyhow::Result<()> {
+        Ok(())
+    }
+}
+
+/// A destination, by the scheme of its `--sink` values
+#[derive(Debug)]
+pub struct Registration {
+    pub scheme: &'static str,
+    /// Shown in errors, e.g. `exec:<command>`
+    pub usage: &'static str,
+    /// Open the destination from what follows `<scheme>:`
+    pub open: fn(&str) -> anyhow::Result<Box<dyn Sink>>,
+}
+
+inventory::collect!(Registration);
+
+/// Register a destination, see [`Registration`]
+#[macro_export]
+macro_rules! register_sink {
+    ($registration:expr $(,)?) => {
+        $crate::inventory::submit! { $registration }
+    };
+}
+
+/// The registered destinations, in no particular order
+pub fn registrations() -> impl Iterator<Item = &'static Registration> {
+    inventory::iter::<Registration>.into_iter()
+}
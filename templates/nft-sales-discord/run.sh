@@ -0,0 +1,16 @@
+#!/bin/sh
+# Post successful Magic Eden v2 transactions to a Discord channel.
+# Requires DISCORD_WEBHOOK_URL and curl, set CLIENT to the client binary if it isn't on PATH.
+set -eu
+
+"${CLIENT:-client}" --commitment confirmed subscribe \
+    --transactions \
+    --transactions-vote false \
+    --transactions-failed false \
+    --transactions-account-include M2mx93ekt1fmXSVkTrUL9cR9UqDmxnZzYHChRMLW7Ey \
+    | grep --line-buffered '^  signature: ' \
+    | while read -r _ signature; do
+        curl -fsS -H 'Content-Type: application/json' \
+            -d "{\"content\": \"New Magic Eden transaction: https://solscan.io/tx/${signature}\"}" \
+            "$DISCORD_WEBHOOK_URL" || echo "failed to post ${signature}" >&2
+    done
@@ -0,0 +1,6 @@
+#!/bin/sh
+# Follow slot and block meta updates and expose ingest metrics for Prometheus on :9090.
+# Set CLIENT to the client binary if it isn't on PATH.
+set -eu
+
+exec "${CLIENT:-client}" --control-addr 0.0.0.0:9090 subscribe --slots --blocks-meta > /dev/null
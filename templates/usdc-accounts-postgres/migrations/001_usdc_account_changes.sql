@@ -0,0 +1,23 @@
+-- One row per change event written by `--envelope debezium`
+CREATE TABLE IF NOT EXISTS usdc_account_changes (
+    id BIGSERIAL PRIMARY KEY,
+    event JSONB NOT NULL,
+    received_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX IF NOT EXISTS usdc_account_changes_pubkey
+    ON usdc_account_changes ((COALESCE(event->'after'->>'pubkey', event->'before'->>'pubkey')));
+
+-- Latest state of every open USDC token account
+CREATE OR REPLACE VIEW usdc_token_accounts AS
+SELECT pubkey, slot, account
+FROM (
+    SELECT DISTINCT ON (pubkey)
+        COALESCE(event->'after'->>'pubkey', event->'before'->>'pubkey') AS pubkey,
+        (event->'source'->>'slot')::BIGINT AS slot,
+        event->>'op' AS op,
+        event->'after' AS account
+    FROM usdc_account_changes
+    ORDER BY pubkey, id DESC
+) latest
+WHERE op <> 'd';
@@ -0,0 +1,16 @@
+#!/bin/sh
+# Stream USDC token account changes into Postgres as Debezium-style change events.
+# Requires DATABASE_URL and psql, set CLIENT to the client binary if it isn't on PATH.
+set -eu
+cd "$(dirname "$0")"
+
+psql "$DATABASE_URL" -f migrations/001_usdc_account_changes.sql
+
+"${CLIENT:-client}" --commitment confirmed subscribe \
+    --accounts \
+    --accounts-owner TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA \
+    --accounts-memcmp 0,EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v \
+    --accounts-datasize 165 \
+    --envelope debezium \
+    | grep --line-buffered '^{' \
+    | psql "$DATABASE_URL" -c "\copy usdc_account_changes(event) from stdin with (format csv, quote e'\x01', delimiter e'\x02')"